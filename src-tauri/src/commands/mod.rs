@@ -15,6 +15,7 @@ mod import_export;
 mod mcp;
 mod misc;
 mod model_fetch;
+mod notification;
 mod omo;
 mod openclaw;
 mod plugin;
@@ -48,6 +49,7 @@ pub use import_export::*;
 pub use mcp::*;
 pub use misc::*;
 pub use model_fetch::*;
+pub use notification::*;
 pub use omo::*;
 pub use openclaw::*;
 pub use plugin::*;
@@ -0,0 +1,40 @@
+//! 通知配置命令
+
+use crate::error::AppError;
+use crate::services::notification::{self, NotificationConfig, NotificationDelivery};
+use crate::store::AppState;
+use tauri::State;
+
+/// 获取通知配置
+#[tauri::command]
+pub fn get_notification_config(state: State<'_, AppState>) -> Result<NotificationConfig, AppError> {
+    state.db.get_notification_config()
+}
+
+/// 保存通知配置
+#[tauri::command]
+pub fn save_notification_config(
+    state: State<'_, AppState>,
+    config: NotificationConfig,
+) -> Result<(), AppError> {
+    config.validate()?;
+    state.db.set_notification_config(&config)
+}
+
+/// 发送测试通知
+///
+/// 传入 `config` 时使用未保存的配置测试，否则使用已保存的配置；忽略规则与启用开关
+#[tauri::command]
+pub async fn test_notification(
+    state: State<'_, AppState>,
+    config: Option<NotificationConfig>,
+) -> Result<Vec<NotificationDelivery>, AppError> {
+    let config = match config {
+        Some(config) => {
+            config.validate()?;
+            config
+        }
+        None => state.db.get_notification_config()?,
+    };
+    Ok(notification::send_test_notification(&config).await)
+}
@@ -3,9 +3,13 @@
 //! 提供前端调用的 API 接口
 
 use crate::error::AppError;
+use crate::proxy::capture;
+use crate::proxy::model_rules::ModelRoutingRule;
+use crate::proxy::replay::{self, ReplayFixture, ReplayReport};
 use crate::proxy::types::*;
 use crate::proxy::{CircuitBreakerConfig, CircuitBreakerStats};
 use crate::store::AppState;
+use std::path::Path;
 
 /// 启动代理服务器（仅启动服务，不接管 Live 配置）
 #[tauri::command]
@@ -63,6 +67,33 @@ pub async fn update_proxy_config(
     state.proxy_service.update_config(&config).await
 }
 
+/// 获取代理入站访问控制配置（访问令牌、来源白名单、局域网共享）
+#[tauri::command]
+pub async fn get_proxy_access_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<ProxyAccessConfig, String> {
+    state.proxy_service.get_access_config().await
+}
+
+/// 更新代理入站访问控制配置
+///
+/// 返回实际保存的配置（开启局域网共享且未设置令牌时会自动生成）
+#[tauri::command]
+pub async fn update_proxy_access_config(
+    state: tauri::State<'_, AppState>,
+    config: ProxyAccessConfig,
+) -> Result<ProxyAccessConfig, String> {
+    state.proxy_service.update_access_config(&config).await
+}
+
+/// 重新生成代理访问令牌
+#[tauri::command]
+pub async fn regenerate_proxy_access_token(
+    state: tauri::State<'_, AppState>,
+) -> Result<ProxyAccessConfig, String> {
+    state.proxy_service.regenerate_access_token().await
+}
+
 // ==================== Global & Per-App Config ====================
 
 /// 获取全局代理配置
@@ -234,6 +265,177 @@ pub async fn set_pricing_model_source(
         .map_err(|e| e.to_string())
 }
 
+/// 获取故障转移路由策略（priority / round_robin / weighted / least_latency / least_cost）
+#[tauri::command]
+pub async fn get_proxy_routing_strategy(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<RoutingStrategy, String> {
+    state
+        .db
+        .get_routing_strategy(&app_type)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 设置故障转移路由策略
+#[tauri::command]
+pub async fn set_proxy_routing_strategy(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    strategy: String,
+) -> Result<(), String> {
+    state
+        .db
+        .set_routing_strategy(&app_type, &strategy)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取重试策略
+#[tauri::command]
+pub async fn get_proxy_retry_policy(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<RetryPolicy, String> {
+    state
+        .db
+        .get_retry_policy(&app_type)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 设置重试策略（下一个请求生效）
+#[tauri::command]
+pub async fn set_proxy_retry_policy(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    policy: RetryPolicy,
+) -> Result<(), String> {
+    state
+        .db
+        .set_retry_policy(&app_type, &policy)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取模型路由规则（按匹配顺序）
+#[tauri::command]
+pub async fn get_model_routing_rules(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<ModelRoutingRule>, String> {
+    state
+        .db
+        .get_model_routing_rules()
+        .map_err(|e| e.to_string())
+}
+
+/// 新增或更新模型路由规则（下一个请求生效）
+#[tauri::command]
+pub async fn save_model_routing_rule(
+    state: tauri::State<'_, AppState>,
+    rule: ModelRoutingRule,
+) -> Result<(), String> {
+    state
+        .db
+        .save_model_routing_rule(&rule)
+        .map_err(|e| e.to_string())?;
+    state.proxy_service.reload_model_routing_rules().await;
+    Ok(())
+}
+
+/// 删除模型路由规则
+#[tauri::command]
+pub async fn delete_model_routing_rule(
+    state: tauri::State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    state
+        .db
+        .delete_model_routing_rule(&id)
+        .map_err(|e| e.to_string())?;
+    state.proxy_service.reload_model_routing_rules().await;
+    Ok(())
+}
+
+/// 按给定顺序重排模型路由规则
+#[tauri::command]
+pub async fn reorder_model_routing_rules(
+    state: tauri::State<'_, AppState>,
+    ids: Vec<String>,
+) -> Result<(), String> {
+    state
+        .db
+        .reorder_model_routing_rules(&ids)
+        .map_err(|e| e.to_string())?;
+    state.proxy_service.reload_model_routing_rules().await;
+    Ok(())
+}
+
+/// 获取生效中的请求抓包规则
+#[tauri::command]
+pub async fn get_capture_rules(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<CaptureRule>, String> {
+    state.db.get_capture_rules().map_err(|e| e.to_string())
+}
+
+/// 为应用（或其中某个供应商）开启限时请求抓包
+#[tauri::command]
+pub async fn start_request_capture(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    provider_id: Option<String>,
+    duration_minutes: u32,
+) -> Result<CaptureRule, String> {
+    let rule = capture::start_capture_rule(&state.db, &app_type, provider_id, duration_minutes)
+        .map_err(|e| e.to_string())?;
+    state.proxy_service.reload_capture_rules().await;
+    Ok(rule)
+}
+
+/// 关闭请求抓包；未指定供应商时关闭该应用下的全部抓包规则
+#[tauri::command]
+pub async fn stop_request_capture(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    provider_id: Option<String>,
+) -> Result<(), String> {
+    capture::stop_capture_rule(&state.db, &app_type, provider_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    state.proxy_service.reload_capture_rules().await;
+    Ok(())
+}
+
+/// 将历史请求（需已抓包）或 fixture 文件回放到指定供应商，返回各供应商的对比结果
+#[tauri::command]
+pub async fn replay_request(
+    state: tauri::State<'_, AppState>,
+    request_id: Option<String>,
+    fixture_path: Option<String>,
+    provider_ids: Vec<String>,
+) -> Result<ReplayReport, String> {
+    let (fixture, original) = match (request_id, fixture_path) {
+        (Some(request_id), None) => (
+            ReplayFixture::from_request_capture(&state.db, &request_id)
+                .map_err(|e| e.to_string())?,
+            state
+                .db
+                .get_request_detail(&request_id)
+                .map_err(|e| e.to_string())?,
+        ),
+        (None, Some(path)) => (
+            ReplayFixture::from_file(Path::new(&path)).map_err(|e| e.to_string())?,
+            None,
+        ),
+        _ => return Err("请指定请求 ID 或回放文件其中之一".to_string()),
+    };
+
+    replay::replay(state.db.clone(), &fixture, &provider_ids, original)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// 检查代理服务器是否正在运行
 #[tauri::command]
 pub async fn is_proxy_running(state: tauri::State<'_, AppState>) -> Result<bool, String> {
@@ -1,15 +1,37 @@
 #![allow(non_snake_case)]
 
+use crate::database::Database;
+use crate::services::session_search::{
+    index_freshness, IndexFreshness, SessionSearchHit, SessionSearchQuery,
+};
 use crate::session_manager;
+use crate::store::AppState;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::State;
 
+/// 会话列表刷新后顺带更新搜索索引
 #[tauri::command]
-pub async fn list_sessions() -> Result<Vec<session_manager::SessionMeta>, String> {
+pub async fn list_sessions(
+    state: State<'_, AppState>,
+) -> Result<Vec<session_manager::SessionMeta>, String> {
     let sessions = tauri::async_runtime::spawn_blocking(session_manager::scan_sessions)
         .await
         .map_err(|e| format!("Failed to scan sessions: {e}"))?;
+    spawn_index_refresh(state.db.clone(), Some(sessions.clone()));
     Ok(sessions)
 }
 
+/// 在后台同步会话搜索索引（已有同步在进行时跳过）
+fn spawn_index_refresh(db: Arc<Database>, sessions: Option<Vec<session_manager::SessionMeta>>) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let sessions = sessions.unwrap_or_else(session_manager::scan_sessions);
+        if let Err(e) = db.refresh_session_search_index(&sessions, false) {
+            log::warn!("[SESSION-SEARCH] 后台同步索引失败: {e}");
+        }
+    });
+}
+
 #[tauri::command]
 pub async fn get_session_messages(
     providerId: String,
@@ -24,6 +46,88 @@ pub async fn get_session_messages(
     .map_err(|e| format!("Failed to load session messages: {e}"))?
 }
 
+/// 加载结构化会话消息（工具调用参数、工具结果、思考过程与每轮用量）
+#[tauri::command]
+pub async fn get_session_structured_messages(
+    providerId: String,
+    sourcePath: String,
+) -> Result<Vec<session_manager::StructuredMessage>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::load_structured_messages(&providerId, &sourcePath)
+    })
+    .await
+    .map_err(|e| format!("Failed to load session messages: {e}"))?
+}
+
+/// 全文搜索所有会话的消息
+///
+/// 直接查询现有索引；本次运行尚未同步过时先同步一次，索引过期时在后台同步。
+#[tauri::command]
+pub async fn search_sessions(
+    state: State<'_, AppState>,
+    query: SessionSearchQuery,
+) -> Result<Vec<SessionSearchHit>, String> {
+    let db = state.db.clone();
+    if index_freshness() == IndexFreshness::Stale {
+        spawn_index_refresh(db.clone(), None);
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        if index_freshness() == IndexFreshness::Never {
+            let sessions = session_manager::scan_sessions();
+            db.refresh_session_search_index(&sessions, true)
+                .map_err(|e| e.to_string())?;
+        }
+        db.search_sessions(&query).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Failed to search sessions: {e}"))?
+}
+
+/// 导出单个会话为 Markdown / HTML / JSON 文件
+#[tauri::command]
+pub async fn export_session(
+    request: session_manager::export::SessionExportRequest,
+    options: session_manager::export::SessionExportOptions,
+    targetPath: String,
+) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::export::export_session(&request, &options, Path::new(&targetPath))
+    })
+    .await
+    .map_err(|e| format!("Failed to export session: {e}"))??;
+
+    Ok(true)
+}
+
+/// 批量导出多个会话为 zip
+#[tauri::command]
+pub async fn export_sessions_zip(
+    items: Vec<session_manager::export::SessionExportRequest>,
+    options: session_manager::export::SessionExportOptions,
+    targetPath: String,
+) -> Result<session_manager::export::SessionExportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::export::export_sessions_zip(&items, &options, Path::new(&targetPath))
+    })
+    .await
+    .map_err(|e| format!("Failed to export sessions: {e}"))?
+}
+
+/// 将会话转换为另一个 CLI 的原生格式，写入其会话目录以便直接恢复
+#[tauri::command]
+pub async fn convert_session(
+    request: session_manager::convert::SessionConvertRequest,
+    targetProviderId: String,
+) -> Result<session_manager::convert::SessionConvertReport, String> {
+    let target = session_manager::convert::ConvertTarget::from_provider_id(&targetProviderId)
+        .ok_or_else(|| format!("Unsupported conversion target: {targetProviderId}"))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::convert::convert_session(&request, target)
+    })
+    .await
+    .map_err(|e| format!("Failed to convert session: {e}"))?
+}
+
 #[tauri::command]
 pub async fn launch_session_terminal(
     command: String,
@@ -41,7 +145,8 @@ pub async fn launch_session_terminal(
     let target = match preferred.as_deref() {
         Some("iterm2") => "iterm".to_string(),
         Some(t) => t.to_string(),
-        None => "terminal".to_string(), // Default to Terminal.app on macOS
+        // Default to Terminal.app on macOS; Linux/Windows treat it as "system default"
+        None => "terminal".to_string(),
     };
 
     tauri::async_runtime::spawn_blocking(move || {
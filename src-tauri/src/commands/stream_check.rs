@@ -3,6 +3,7 @@
 use crate::app_config::AppType;
 use crate::commands::copilot::CopilotAuthState;
 use crate::error::AppError;
+use crate::proxy::health::HealthCheckConfig;
 use crate::services::stream_check::{
     HealthStatus, StreamCheckConfig, StreamCheckResult, StreamCheckService,
 };
@@ -116,26 +117,7 @@ pub async fn stream_check_all_providers(
             claude_api_format_override,
         )
         .await
-        .unwrap_or_else(|e| {
-            let (http_status, message) = match &e {
-                crate::error::AppError::HttpStatus { status, .. } => (
-                    Some(*status),
-                    StreamCheckService::classify_http_status(*status).to_string(),
-                ),
-                _ => (None, e.to_string()),
-            };
-            StreamCheckResult {
-                status: HealthStatus::Failed,
-                success: false,
-                message,
-                response_time_ms: None,
-                http_status,
-                model_used: String::new(),
-                tested_at: chrono::Utc::now().timestamp(),
-                retry_count: 0,
-                error_category: None,
-            }
-        });
+        .unwrap_or_else(|e| failed_check_result(&e));
 
         let _ = state
             .db
@@ -147,6 +129,45 @@ pub async fn stream_check_all_providers(
     Ok(results)
 }
 
+/// 获取代理后台健康检查配置
+#[tauri::command]
+pub fn get_proxy_health_check_config(
+    state: State<'_, AppState>,
+) -> Result<HealthCheckConfig, AppError> {
+    state.db.get_health_check_config()
+}
+
+/// 保存代理后台健康检查配置（运行中的检查器在下一轮调度时生效）
+#[tauri::command]
+pub fn save_proxy_health_check_config(
+    state: State<'_, AppState>,
+    config: HealthCheckConfig,
+) -> Result<(), AppError> {
+    state.db.save_health_check_config(&config)
+}
+
+/// 把检查过程中的异常转换为失败结果（批量检查 / 后台健康检查不因单个供应商中断）
+pub(crate) fn failed_check_result(e: &AppError) -> StreamCheckResult {
+    let (http_status, message) = match e {
+        AppError::HttpStatus { status, .. } => (
+            Some(*status),
+            StreamCheckService::classify_http_status(*status).to_string(),
+        ),
+        _ => (None, e.to_string()),
+    };
+    StreamCheckResult {
+        status: HealthStatus::Failed,
+        success: false,
+        message,
+        response_time_ms: None,
+        http_status,
+        model_used: String::new(),
+        tested_at: chrono::Utc::now().timestamp(),
+        retry_count: 0,
+        error_category: None,
+    }
+}
+
 /// 获取流式检查配置
 #[tauri::command]
 pub fn get_stream_check_config(state: State<'_, AppState>) -> Result<StreamCheckConfig, AppError> {
@@ -162,9 +183,9 @@ pub fn save_stream_check_config(
     state.db.save_stream_check_config(&config)
 }
 
-async fn resolve_copilot_auth_override(
+pub(crate) async fn resolve_copilot_auth_override(
     provider: &crate::provider::Provider,
-    copilot_state: &State<'_, CopilotAuthState>,
+    copilot_state: &CopilotAuthState,
 ) -> Result<Option<crate::proxy::providers::AuthInfo>, AppError> {
     let is_copilot = is_copilot_provider(provider);
 
@@ -195,9 +216,9 @@ async fn resolve_copilot_auth_override(
     )))
 }
 
-async fn resolve_copilot_base_url_override(
+pub(crate) async fn resolve_copilot_base_url_override(
     provider: &crate::provider::Provider,
-    copilot_state: &State<'_, CopilotAuthState>,
+    copilot_state: &CopilotAuthState,
 ) -> Result<Option<String>, AppError> {
     let is_copilot = is_copilot_provider(provider);
     let is_full_url = provider
@@ -238,11 +259,11 @@ fn is_copilot_provider(provider: &crate::provider::Provider) -> bool {
             .unwrap_or(false)
 }
 
-async fn resolve_claude_api_format_override(
+pub(crate) async fn resolve_claude_api_format_override(
     app_type: &AppType,
     provider: &crate::provider::Provider,
     config: &StreamCheckConfig,
-    copilot_state: &State<'_, CopilotAuthState>,
+    copilot_state: &CopilotAuthState,
     auth_override: Option<&crate::proxy::providers::AuthInfo>,
 ) -> Result<Option<String>, AppError> {
     if *app_type != AppType::Claude {
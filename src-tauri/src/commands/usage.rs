@@ -1,8 +1,18 @@
 //! 使用统计相关命令
 
 use crate::error::AppError;
+use crate::proxy::capture::{self, RequestCaptureDetail};
+use crate::proxy::usage::calculator::{ModelPricing, PricingTier};
+use crate::services::pricing_io::{
+    ModelPricingInfo, PricingFileFormat, PricingImportPreview, PricingImportResult,
+};
+use crate::services::usage_attribution::{UsageAttribution, DEFAULT_TOP_SESSIONS};
+use crate::services::usage_export::{UsageExportFormat, UsageExportSummary};
+use crate::services::usage_forecast::BurnForecast;
 use crate::services::usage_stats::*;
 use crate::store::AppState;
+use std::collections::HashMap;
+use std::path::Path;
 use tauri::State;
 
 /// 获取使用量汇总
@@ -57,6 +67,41 @@ pub fn get_model_stats(
         .get_model_stats(start_date, end_date, app_type.as_deref())
 }
 
+/// 获取按项目目录与会话归因的使用量
+///
+/// 扫描本地会话以补全会话标题与项目目录，`top_n` 为高成本会话数量（默认 10）
+#[tauri::command]
+pub async fn get_usage_attribution(
+    state: State<'_, AppState>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+    app_type: Option<String>,
+    top_n: Option<usize>,
+) -> Result<UsageAttribution, AppError> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let sessions = crate::session_manager::scan_sessions();
+        db.get_usage_attribution(
+            start_date,
+            end_date,
+            app_type.as_deref(),
+            &sessions,
+            top_n.unwrap_or(DEFAULT_TOP_SESSIONS),
+        )
+    })
+    .await
+    .map_err(|e| AppError::Message(format!("统计项目使用量失败: {e}")))?
+}
+
+/// 获取各供应商的消费速率、月末预计消费与限额触达预测
+#[tauri::command]
+pub fn get_usage_forecast(
+    state: State<'_, AppState>,
+    app_type: Option<String>,
+) -> Result<Vec<BurnForecast>, AppError> {
+    state.db.get_usage_forecast(app_type.as_deref())
+}
+
 /// 获取请求日志列表
 #[tauri::command]
 pub fn get_request_logs(
@@ -77,59 +122,98 @@ pub fn get_request_detail(
     state.db.get_request_detail(&request_id)
 }
 
+/// 获取请求关联的调试抓包（未抓包或已被清理时返回 None）
+#[tauri::command]
+pub fn get_request_capture(
+    state: State<'_, AppState>,
+    request_id: String,
+) -> Result<Option<RequestCaptureDetail>, AppError> {
+    capture::load_request_capture(&state.db, &request_id)
+}
+
 /// 获取模型定价列表
 #[tauri::command]
 pub fn get_model_pricing(state: State<'_, AppState>) -> Result<Vec<ModelPricingInfo>, AppError> {
     log::info!("获取模型定价列表");
     state.db.ensure_model_pricing_seeded()?;
 
-    let db = state.db.clone();
-    let conn = crate::database::lock_conn!(db.conn);
+    let pricing = state.db.list_model_pricing()?;
+    log::info!("成功获取 {} 条模型定价数据", pricing.len());
+    Ok(pricing)
+}
 
-    // 检查表是否存在
-    let table_exists: bool = conn
-        .query_row(
-            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='model_pricing'",
-            [],
-            |row| row.get::<_, i64>(0).map(|count| count > 0),
-        )
-        .unwrap_or(false);
+/// 预览定价文件导入（LiteLLM JSON 或 CSV），返回新增/变更/未变条目
+#[tauri::command]
+pub fn preview_pricing_import(
+    state: State<'_, AppState>,
+    file_path: String,
+) -> Result<PricingImportPreview, AppError> {
+    let (content, format) = read_pricing_file(&file_path)?;
+    state.db.preview_pricing_import(&content, format)
+}
 
-    if !table_exists {
-        log::error!("model_pricing 表不存在,可能需要重启应用以触发数据库迁移");
-        return Ok(Vec::new());
-    }
+/// 应用定价文件导入（model_ids 为空时应用全部新增与变更）
+#[tauri::command]
+pub async fn apply_pricing_import(
+    state: State<'_, AppState>,
+    file_path: String,
+    model_ids: Option<Vec<String>>,
+) -> Result<PricingImportResult, AppError> {
+    let (content, format) = read_pricing_file(&file_path)?;
+    state
+        .db
+        .apply_pricing_import(&content, format, model_ids.as_deref())
+        .await
+}
 
-    let mut stmt = conn.prepare(
-        "SELECT model_id, display_name, input_cost_per_million, output_cost_per_million,
-                cache_read_cost_per_million, cache_creation_cost_per_million
-         FROM model_pricing
-         ORDER BY display_name",
-    )?;
-
-    let rows = stmt.query_map([], |row| {
-        Ok(ModelPricingInfo {
-            model_id: row.get(0)?,
-            display_name: row.get(1)?,
-            input_cost_per_million: row.get(2)?,
-            output_cost_per_million: row.get(3)?,
-            cache_read_cost_per_million: row.get(4)?,
-            cache_creation_cost_per_million: row.get(5)?,
-        })
-    })?;
-
-    let mut pricing = Vec::new();
-    for row in rows {
-        pricing.push(row?);
-    }
+/// 导出模型定价（按扩展名选择格式：.csv 为 CSV，其余为 LiteLLM JSON）
+#[tauri::command]
+pub fn export_model_pricing(state: State<'_, AppState>, file_path: String) -> Result<(), AppError> {
+    let path = Path::new(&file_path);
+    let format = PricingFileFormat::detect(path, "");
+    let content = state.db.export_model_pricing(format)?;
+    std::fs::write(path, content).map_err(|e| AppError::io(path, e))?;
+    log::info!("已导出模型定价到 {file_path}");
+    Ok(())
+}
 
-    log::info!("成功获取 {} 条模型定价数据", pricing.len());
-    Ok(pricing)
+/// 按过滤条件导出请求日志与日汇总
+///
+/// 未指定格式时按扩展名推断（.csv / .jsonl / .db）
+#[tauri::command]
+pub async fn export_usage_data(
+    state: State<'_, AppState>,
+    filters: LogFilters,
+    file_path: String,
+    format: Option<UsageExportFormat>,
+) -> Result<UsageExportSummary, AppError> {
+    let path = std::path::PathBuf::from(&file_path);
+    let format = format
+        .or_else(|| UsageExportFormat::from_path(&path))
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!(
+                "无法识别导出格式，请使用 .csv、.jsonl 或 .db 扩展名: {file_path}"
+            ))
+        })?;
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || db.export_usage(&filters, format, &path))
+        .await
+        .map_err(|e| AppError::Message(format!("导出使用量数据失败: {e}")))?
+}
+
+fn read_pricing_file(file_path: &str) -> Result<(String, PricingFileFormat), AppError> {
+    let path = Path::new(file_path);
+    let content = std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+    let format = PricingFileFormat::detect(path, &content);
+    Ok((content, format))
 }
 
 /// 更新模型定价
+///
+/// 保存后按新价格重算该模型的历史请求日志成本
 #[tauri::command]
-pub fn update_model_pricing(
+#[allow(clippy::too_many_arguments)]
+pub async fn update_model_pricing(
     state: State<'_, AppState>,
     model_id: String,
     display_name: String,
@@ -137,27 +221,76 @@ pub fn update_model_pricing(
     output_cost: String,
     cache_read_cost: String,
     cache_creation_cost: String,
-) -> Result<(), AppError> {
-    let db = state.db.clone();
-    let conn = crate::database::lock_conn!(db.conn);
+    cache_creation_1h_cost: Option<String>,
+    reasoning_cost: Option<String>,
+    tiers: Option<Vec<PricingTier>>,
+    service_tier_multipliers: Option<HashMap<String, String>>,
+) -> Result<usize, AppError> {
+    let normalize = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+    let cache_creation_1h_cost = normalize(cache_creation_1h_cost);
+    let reasoning_cost = normalize(reasoning_cost);
+    let tiers_json = tiers
+        .filter(|t| !t.is_empty())
+        .map(|t| serde_json::to_string(&t))
+        .transpose()
+        .map_err(|e| AppError::InvalidInput(format!("分段价格序列化失败: {e}")))?;
+    let multipliers_json = service_tier_multipliers
+        .filter(|m| !m.is_empty())
+        .map(|m| serde_json::to_string(&m))
+        .transpose()
+        .map_err(|e| AppError::InvalidInput(format!("服务等级倍率序列化失败: {e}")))?;
 
-    conn.execute(
-        "INSERT OR REPLACE INTO model_pricing (
-            model_id, display_name, input_cost_per_million, output_cost_per_million,
-            cache_read_cost_per_million, cache_creation_cost_per_million
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        rusqlite::params![
-            model_id,
-            display_name,
-            input_cost,
-            output_cost,
-            cache_read_cost,
-            cache_creation_cost
-        ],
+    // 保存前校验所有价格均可解析
+    ModelPricing::from_strings(
+        &input_cost,
+        &output_cost,
+        &cache_read_cost,
+        &cache_creation_cost,
     )
-    .map_err(|e| AppError::Database(format!("更新模型定价失败: {e}")))?;
+    .map_err(|e| AppError::InvalidInput(format!("价格无效: {e}")))?
+    .with_modifiers(
+        cache_creation_1h_cost.as_deref(),
+        reasoning_cost.as_deref(),
+        tiers_json.as_deref(),
+        multipliers_json.as_deref(),
+    )
+    .map_err(AppError::InvalidInput)?;
 
-    Ok(())
+    {
+        let conn = crate::database::lock_conn!(state.db.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO model_pricing (
+                model_id, display_name, input_cost_per_million, output_cost_per_million,
+                cache_read_cost_per_million, cache_creation_cost_per_million,
+                cache_creation_1h_cost_per_million, reasoning_cost_per_million,
+                tiers, service_tier_multipliers
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            rusqlite::params![
+                model_id,
+                display_name,
+                input_cost,
+                output_cost,
+                cache_read_cost,
+                cache_creation_cost,
+                cache_creation_1h_cost,
+                reasoning_cost,
+                tiers_json,
+                multipliers_json
+            ],
+        )
+        .map_err(|e| AppError::Database(format!("更新模型定价失败: {e}")))?;
+    }
+
+    state.db.recompute_request_costs(Some(&model_id)).await
+}
+
+/// 按当前定价重算历史请求日志成本（model_id 为空时重算全部）
+#[tauri::command]
+pub async fn recompute_usage_costs(
+    state: State<'_, AppState>,
+    model_id: Option<String>,
+) -> Result<usize, AppError> {
+    state.db.recompute_request_costs(model_id.as_deref()).await
 }
 
 /// 检查 Provider 使用限额
@@ -230,15 +363,3 @@ pub fn get_usage_data_sources(
 ) -> Result<Vec<crate::services::session_usage::DataSourceSummary>, AppError> {
     crate::services::session_usage::get_data_source_breakdown(&state.db)
 }
-
-/// 模型定价信息
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ModelPricingInfo {
-    pub model_id: String,
-    pub display_name: String,
-    pub input_cost_per_million: String,
-    pub output_cost_per_million: String,
-    pub cache_read_cost_per_million: String,
-    pub cache_creation_cost_per_million: String,
-}
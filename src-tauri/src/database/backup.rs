@@ -33,6 +33,10 @@ const SYNC_PRESERVE_TABLES: &[&str] = &[
     "usage_daily_rollups",
 ];
 
+/// Prefix of local, rebuildable index tables (incl. FTS5 shadow tables) that
+/// are never exported: their virtual-table DDL cannot be replayed verbatim.
+const LOCAL_INDEX_TABLE_PREFIX: &str = "session_search_";
+
 /// A database backup entry for the UI
 #[derive(Debug, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -417,8 +421,8 @@ impl Database {
             let name: String = row.get(1).map_err(|e| AppError::Database(e.to_string()))?;
             let sql: String = row.get(3).map_err(|e| AppError::Database(e.to_string()))?;
 
-            // 跳过 SQLite 内部对象（如 sqlite_sequence）
-            if name.starts_with("sqlite_") {
+            // 跳过 SQLite 内部对象（如 sqlite_sequence）与本地可重建的索引表
+            if name.starts_with("sqlite_") || name.starts_with(LOCAL_INDEX_TABLE_PREFIX) {
                 continue;
             }
 
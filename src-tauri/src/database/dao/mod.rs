@@ -4,6 +4,7 @@
 
 pub mod failover;
 pub mod mcp;
+pub mod model_routing;
 pub mod prompts;
 pub mod providers;
 pub mod providers_seed;
@@ -0,0 +1,194 @@
+//! 模型路由规则 DAO
+//!
+//! 规则按 sort_index 顺序匹配，第一条命中的规则生效
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::model_rules::ModelRoutingRule;
+
+impl Database {
+    /// 获取所有模型路由规则（按匹配顺序）
+    pub fn get_model_routing_rules(&self) -> Result<Vec<ModelRoutingRule>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, app_type, client_format, pattern, match_type,
+                        target_provider_id, target_model, enabled, sort_index
+                 FROM model_routing_rules
+                 ORDER BY sort_index ASC, created_at ASC",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let rules = stmt
+            .query_map([], |row| {
+                Ok(ModelRoutingRule {
+                    id: row.get(0)?,
+                    app_type: row.get(1)?,
+                    client_format: row.get(2)?,
+                    pattern: row.get(3)?,
+                    match_type: row.get::<_, String>(4)?.parse().unwrap_or_default(),
+                    target_provider_id: row.get(5)?,
+                    target_model: row.get(6)?,
+                    enabled: row.get::<_, i32>(7)? != 0,
+                    sort_index: row.get(8)?,
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(rules)
+    }
+
+    /// 获取指定应用可能命中的已启用规则（含不限定应用的规则）
+    pub fn get_enabled_model_routing_rules(
+        &self,
+        app_type: &str,
+    ) -> Result<Vec<ModelRoutingRule>, AppError> {
+        Ok(self
+            .get_model_routing_rules()?
+            .into_iter()
+            .filter(|rule| rule.enabled)
+            .filter(|rule| rule.app_type.as_deref().is_none_or(|app| app == app_type))
+            .collect())
+    }
+
+    /// 新增或更新模型路由规则
+    pub fn save_model_routing_rule(&self, rule: &ModelRoutingRule) -> Result<(), AppError> {
+        rule.validate()?;
+
+        let normalize = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(String::from)
+        };
+        let now = chrono::Utc::now().timestamp();
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO model_routing_rules (
+                id, app_type, client_format, pattern, match_type,
+                target_provider_id, target_model, enabled, sort_index, created_at, updated_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)
+             ON CONFLICT(id) DO UPDATE SET
+                app_type = excluded.app_type,
+                client_format = excluded.client_format,
+                pattern = excluded.pattern,
+                match_type = excluded.match_type,
+                target_provider_id = excluded.target_provider_id,
+                target_model = excluded.target_model,
+                enabled = excluded.enabled,
+                sort_index = excluded.sort_index,
+                updated_at = excluded.updated_at",
+            rusqlite::params![
+                rule.id,
+                normalize(&rule.app_type),
+                normalize(&rule.client_format),
+                rule.pattern.trim(),
+                rule.match_type.as_str(),
+                normalize(&rule.target_provider_id),
+                normalize(&rule.target_model),
+                if rule.enabled { 1 } else { 0 },
+                rule.sort_index,
+                now,
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 删除模型路由规则
+    pub fn delete_model_routing_rule(&self, id: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute("DELETE FROM model_routing_rules WHERE id = ?1", [id])
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 按给定 ID 顺序重排规则
+    pub fn reorder_model_routing_rules(&self, ids: &[String]) -> Result<(), AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        for (index, id) in ids.iter().enumerate() {
+            tx.execute(
+                "UPDATE model_routing_rules SET sort_index = ?2 WHERE id = ?1",
+                rusqlite::params![id, index as i64],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::error::AppError;
+    use crate::proxy::model_rules::{ModelRoutingRule, RuleMatchType};
+
+    fn rule(id: &str, app_type: Option<&str>, sort_index: i64) -> ModelRoutingRule {
+        ModelRoutingRule {
+            id: id.to_string(),
+            app_type: app_type.map(String::from),
+            client_format: None,
+            pattern: "*haiku*".to_string(),
+            match_type: RuleMatchType::Glob,
+            target_provider_id: None,
+            target_model: Some("cheap-model".to_string()),
+            enabled: true,
+            sort_index,
+        }
+    }
+
+    #[test]
+    fn test_model_routing_rules_crud_and_order() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        db.save_model_routing_rule(&rule("a", Some("claude"), 1))?;
+        db.save_model_routing_rule(&rule("b", None, 0))?;
+        db.save_model_routing_rule(&rule("c", Some("codex"), 2))?;
+
+        let ids = |rules: Vec<ModelRoutingRule>| -> Vec<String> {
+            rules.into_iter().map(|r| r.id).collect()
+        };
+        assert_eq!(ids(db.get_model_routing_rules()?), vec!["b", "a", "c"]);
+        assert_eq!(
+            ids(db.get_enabled_model_routing_rules("claude")?),
+            vec!["b", "a"]
+        );
+
+        // 更新：禁用后不再参与匹配
+        let mut updated = rule("a", Some("claude"), 1);
+        updated.enabled = false;
+        db.save_model_routing_rule(&updated)?;
+        assert_eq!(
+            ids(db.get_enabled_model_routing_rules("claude")?),
+            vec!["b"]
+        );
+
+        db.reorder_model_routing_rules(&["c".to_string(), "a".to_string(), "b".to_string()])?;
+        assert_eq!(ids(db.get_model_routing_rules()?), vec!["c", "a", "b"]);
+
+        db.delete_model_routing_rule("c")?;
+        assert_eq!(ids(db.get_model_routing_rules()?), vec!["a", "b"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_rule_is_rejected() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let mut invalid = rule("x", None, 0);
+        invalid.target_model = None;
+        assert!(db.save_model_routing_rule(&invalid).is_err());
+        assert!(db.get_model_routing_rules()?.is_empty());
+        Ok(())
+    }
+}
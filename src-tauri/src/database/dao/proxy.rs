@@ -76,6 +76,72 @@ impl Database {
         Ok(())
     }
 
+    /// 获取代理入站访问控制配置
+    ///
+    /// 从 claude 行读取（三行镜像一致）
+    pub async fn get_proxy_access_config(&self) -> Result<ProxyAccessConfig, AppError> {
+        let result = {
+            let conn = lock_conn!(self.conn);
+            conn.query_row(
+                "SELECT access_token, allowed_cidrs, lan_mode
+                 FROM proxy_config WHERE app_type = 'claude'",
+                [],
+                |row| {
+                    let cidrs: String = row.get(1)?;
+                    Ok(ProxyAccessConfig {
+                        access_token: row
+                            .get::<_, Option<String>>(0)?
+                            .filter(|token| !token.trim().is_empty()),
+                        allowed_cidrs: cidrs
+                            .split([',', '\n'])
+                            .map(str::trim)
+                            .filter(|s| !s.is_empty())
+                            .map(String::from)
+                            .collect(),
+                        lan_mode: row.get::<_, i32>(2)? != 0,
+                    })
+                },
+            )
+        };
+
+        match result {
+            Ok(config) => Ok(config),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                self.init_proxy_config_rows().await?;
+                Ok(ProxyAccessConfig::default())
+            }
+            Err(e) => Err(AppError::Database(e.to_string())),
+        }
+    }
+
+    /// 更新代理入站访问控制配置（镜像写三行）
+    pub async fn update_proxy_access_config(
+        &self,
+        config: &ProxyAccessConfig,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        conn.execute(
+            "UPDATE proxy_config SET
+                access_token = ?1,
+                allowed_cidrs = ?2,
+                lan_mode = ?3,
+                updated_at = datetime('now')",
+            rusqlite::params![
+                config
+                    .access_token
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|token| !token.is_empty()),
+                config.allowed_cidrs.join(","),
+                if config.lan_mode { 1 } else { 0 },
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
     /// 获取默认成本倍率
     pub async fn get_default_cost_multiplier(&self, app_type: &str) -> Result<String, AppError> {
         let result = {
@@ -187,6 +253,163 @@ impl Database {
         Ok(())
     }
 
+    /// 获取故障转移路由策略
+    ///
+    /// 无法识别的值回落到 priority，保证路由不因脏数据中断。
+    pub async fn get_routing_strategy(&self, app_type: &str) -> Result<RoutingStrategy, AppError> {
+        let result = {
+            let conn = lock_conn!(self.conn);
+            conn.query_row(
+                "SELECT routing_strategy FROM proxy_config WHERE app_type = ?1",
+                [app_type],
+                |row| row.get::<_, String>(0),
+            )
+        };
+
+        match result {
+            Ok(value) => Ok(value.parse().unwrap_or_default()),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                self.init_proxy_config_rows().await?;
+                Ok(RoutingStrategy::default())
+            }
+            Err(e) => Err(AppError::Database(e.to_string())),
+        }
+    }
+
+    /// 设置故障转移路由策略
+    pub async fn set_routing_strategy(&self, app_type: &str, value: &str) -> Result<(), AppError> {
+        let strategy: RoutingStrategy = value.parse().map_err(|_| {
+            AppError::localized(
+                "error.invalidRoutingStrategy",
+                format!("无效路由策略: {value}"),
+                format!("Invalid routing strategy: {value}"),
+            )
+        })?;
+
+        // 确保行存在
+        self.ensure_proxy_config_row_exists(app_type)?;
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE proxy_config SET
+                routing_strategy = ?2,
+                updated_at = datetime('now')
+             WHERE app_type = ?1",
+            rusqlite::params![app_type, strategy.as_str()],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 获取重试策略
+    pub async fn get_retry_policy(&self, app_type: &str) -> Result<RetryPolicy, AppError> {
+        let result = {
+            let conn = lock_conn!(self.conn);
+            conn.query_row(
+                "SELECT retry_max_attempts, retry_base_delay_ms, retry_max_delay_ms,
+                        retry_status_codes, retry_same_provider
+                 FROM proxy_config WHERE app_type = ?1",
+                [app_type],
+                |row| {
+                    Ok(RetryPolicy {
+                        max_attempts_per_provider: row.get::<_, i64>(0)?.max(1) as u32,
+                        base_delay_ms: row.get::<_, i64>(1)?.max(0) as u64,
+                        max_delay_ms: row.get::<_, i64>(2)?.max(0) as u64,
+                        retryable_status_codes: RetryPolicy::parse_status_codes(
+                            &row.get::<_, String>(3)?,
+                        ),
+                        retry_same_provider: row.get::<_, i64>(4)? != 0,
+                    })
+                },
+            )
+        };
+
+        match result {
+            Ok(policy) => Ok(policy),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                self.init_proxy_config_rows().await?;
+                Ok(RetryPolicy::default())
+            }
+            Err(e) => Err(AppError::Database(e.to_string())),
+        }
+    }
+
+    /// 设置重试策略
+    pub async fn set_retry_policy(
+        &self,
+        app_type: &str,
+        policy: &RetryPolicy,
+    ) -> Result<(), AppError> {
+        if policy.max_attempts_per_provider == 0
+            || policy.max_attempts_per_provider > RetryPolicy::MAX_ATTEMPTS
+        {
+            return Err(AppError::localized(
+                "error.invalidRetryAttempts",
+                format!(
+                    "每个供应商的尝试次数必须在 1-{} 之间",
+                    RetryPolicy::MAX_ATTEMPTS
+                ),
+                format!(
+                    "Attempts per provider must be between 1 and {}",
+                    RetryPolicy::MAX_ATTEMPTS
+                ),
+            ));
+        }
+        if policy.base_delay_ms > policy.max_delay_ms
+            || policy.max_delay_ms > RetryPolicy::MAX_DELAY_MS
+        {
+            return Err(AppError::localized(
+                "error.invalidRetryDelay",
+                format!(
+                    "重试延迟无效：基础延迟不能大于最大延迟，且最大延迟不超过 {} 毫秒",
+                    RetryPolicy::MAX_DELAY_MS
+                ),
+                format!(
+                    "Invalid retry delay: base delay must not exceed max delay, and max delay must not exceed {} ms",
+                    RetryPolicy::MAX_DELAY_MS
+                ),
+            ));
+        }
+        if let Some(code) = policy
+            .retryable_status_codes
+            .iter()
+            .find(|code| !(400..600).contains(*code))
+        {
+            return Err(AppError::localized(
+                "error.invalidRetryStatusCode",
+                format!("无效的可重试状态码: {code}"),
+                format!("Invalid retryable status code: {code}"),
+            ));
+        }
+
+        // 确保行存在
+        self.ensure_proxy_config_row_exists(app_type)?;
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE proxy_config SET
+                retry_max_attempts = ?2,
+                retry_base_delay_ms = ?3,
+                retry_max_delay_ms = ?4,
+                retry_status_codes = ?5,
+                retry_same_provider = ?6,
+                updated_at = datetime('now')
+             WHERE app_type = ?1",
+            rusqlite::params![
+                app_type,
+                policy.max_attempts_per_provider as i64,
+                policy.base_delay_ms as i64,
+                policy.max_delay_ms as i64,
+                policy.status_codes_to_string(),
+                if policy.retry_same_provider { 1 } else { 0 },
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
     /// 获取应用级代理配置
     pub async fn get_proxy_config_for_app(
         &self,
@@ -387,7 +610,8 @@ impl Database {
             conn.query_row(
                 "SELECT listen_address, listen_port, max_retries,
                         enable_logging,
-                        streaming_first_byte_timeout, streaming_idle_timeout, non_streaming_timeout
+                        streaming_first_byte_timeout, streaming_idle_timeout, non_streaming_timeout,
+                        metrics_enabled, otlp_endpoint
                  FROM proxy_config WHERE app_type = 'claude'",
                 [],
                 |row| {
@@ -401,6 +625,8 @@ impl Database {
                         streaming_first_byte_timeout: row.get::<_, i32>(4).unwrap_or(60) as u64,
                         streaming_idle_timeout: row.get::<_, i32>(5).unwrap_or(120) as u64,
                         non_streaming_timeout: row.get::<_, i32>(6).unwrap_or(600) as u64,
+                        metrics_enabled: row.get::<_, i32>(7).unwrap_or(0) != 0,
+                        otlp_endpoint: row.get::<_, Option<String>>(8).unwrap_or(None),
                     })
                 },
             )
@@ -432,6 +658,8 @@ impl Database {
                 streaming_first_byte_timeout = ?5,
                 streaming_idle_timeout = ?6,
                 non_streaming_timeout = ?7,
+                metrics_enabled = ?8,
+                otlp_endpoint = ?9,
                 updated_at = datetime('now')",
             rusqlite::params![
                 config.listen_address,
@@ -441,6 +669,12 @@ impl Database {
                 config.streaming_first_byte_timeout as i32,
                 config.streaming_idle_timeout as i32,
                 config.non_streaming_timeout as i32,
+                if config.metrics_enabled { 1 } else { 0 },
+                config
+                    .otlp_endpoint
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|endpoint| !endpoint.is_empty()),
             ],
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
@@ -852,6 +1086,7 @@ impl Database {
 mod tests {
     use crate::database::Database;
     use crate::error::AppError;
+    use crate::proxy::types::{RetryPolicy, RoutingStrategy};
 
     #[tokio::test]
     async fn test_default_cost_multiplier_round_trip() -> Result<(), AppError> {
@@ -913,4 +1148,79 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_routing_strategy_round_trip_and_validation() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        assert_eq!(
+            db.get_routing_strategy("codex").await?,
+            RoutingStrategy::Priority
+        );
+
+        db.set_routing_strategy("codex", "least_latency").await?;
+        assert_eq!(
+            db.get_routing_strategy("codex").await?,
+            RoutingStrategy::LeastLatency
+        );
+        // 策略按 app 独立
+        assert_eq!(
+            db.get_routing_strategy("claude").await?,
+            RoutingStrategy::Priority
+        );
+
+        let err = db
+            .set_routing_strategy("codex", "random")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AppError::Localized {
+                key: "error.invalidRoutingStrategy",
+                ..
+            }
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_round_trip_and_validation() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        assert_eq!(db.get_retry_policy("claude").await?, RetryPolicy::default());
+
+        let policy = RetryPolicy {
+            max_attempts_per_provider: 3,
+            base_delay_ms: 200,
+            max_delay_ms: 5000,
+            retryable_status_codes: vec![429, 529],
+            retry_same_provider: true,
+        };
+        db.set_retry_policy("claude", &policy).await?;
+        assert_eq!(db.get_retry_policy("claude").await?, policy);
+        // 策略按 app 独立
+        assert_eq!(db.get_retry_policy("codex").await?, RetryPolicy::default());
+
+        let err = db
+            .set_retry_policy(
+                "claude",
+                &RetryPolicy {
+                    base_delay_ms: 10_000,
+                    max_delay_ms: 1000,
+                    ..RetryPolicy::default()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AppError::Localized {
+                key: "error.invalidRetryDelay",
+                ..
+            }
+        ));
+
+        Ok(())
+    }
 }
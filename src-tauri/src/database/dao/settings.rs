@@ -4,7 +4,7 @@
 
 use crate::database::{lock_conn, Database};
 use crate::error::AppError;
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
 
 impl Database {
     const LEGACY_COMMON_CONFIG_MIGRATED_KEY: &'static str = "common_config_legacy_migrated_v1";
@@ -324,4 +324,64 @@ impl Database {
             .map_err(|e| AppError::Database(format!("序列化日志配置失败: {e}")))?;
         self.set_setting("log_config", &json)
     }
+
+    // --- 通知配置 ---
+
+    /// 获取通知配置
+    pub fn get_notification_config(
+        &self,
+    ) -> Result<crate::services::notification::NotificationConfig, AppError> {
+        match self.get_setting("notification_config")? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Database(format!("解析通知配置失败: {e}"))),
+            None => Ok(crate::services::notification::NotificationConfig::default()),
+        }
+    }
+
+    /// 更新通知配置
+    pub fn set_notification_config(
+        &self,
+        config: &crate::services::notification::NotificationConfig,
+    ) -> Result<(), AppError> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Database(format!("序列化通知配置失败: {e}")))?;
+        self.set_setting("notification_config", &json)
+    }
+
+    // --- 请求抓包规则 ---
+
+    /// 获取仍在有效期内的请求抓包规则
+    pub fn get_capture_rules(&self) -> Result<Vec<crate::proxy::types::CaptureRule>, AppError> {
+        let rules: Vec<crate::proxy::types::CaptureRule> =
+            match self.get_setting("proxy_capture_rules")? {
+                Some(json) => serde_json::from_str(&json)
+                    .map_err(|e| AppError::Database(format!("解析抓包规则失败: {e}")))?,
+                None => Vec::new(),
+            };
+        let now = chrono::Utc::now().timestamp();
+        Ok(rules.into_iter().filter(|r| r.is_active(now)).collect())
+    }
+
+    /// 更新请求抓包规则
+    pub fn set_capture_rules(
+        &self,
+        rules: &[crate::proxy::types::CaptureRule],
+    ) -> Result<(), AppError> {
+        let json = serde_json::to_string(rules)
+            .map_err(|e| AppError::Database(format!("序列化抓包规则失败: {e}")))?;
+        self.set_setting("proxy_capture_rules", &json)
+    }
+
+    /// 查询请求日志关联的抓包 ID
+    pub fn get_request_capture_id(&self, request_id: &str) -> Result<Option<String>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            "SELECT capture_id FROM proxy_request_logs WHERE request_id = ?1",
+            params![request_id],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()
+        .map(Option::flatten)
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
 }
@@ -2,6 +2,7 @@
 
 use crate::database::{lock_conn, Database};
 use crate::error::AppError;
+use crate::proxy::health::HealthCheckConfig;
 use crate::services::stream_check::{StreamCheckConfig, StreamCheckResult};
 
 impl Database {
@@ -71,4 +72,20 @@ impl Database {
             .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
         self.set_setting("stream_check_config", &json)
     }
+
+    /// 获取代理后台健康检查配置
+    pub fn get_health_check_config(&self) -> Result<HealthCheckConfig, AppError> {
+        match self.get_setting("proxy_health_check_config")? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Message(format!("解析配置失败: {e}"))),
+            None => Ok(HealthCheckConfig::default()),
+        }
+    }
+
+    /// 保存代理后台健康检查配置
+    pub fn save_health_check_config(&self, config: &HealthCheckConfig) -> Result<(), AppError> {
+        let json = serde_json::to_string(&config.normalized())
+            .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
+        self.set_setting("proxy_health_check_config", &json)
+    }
 }
@@ -133,6 +133,13 @@ impl Database {
             circuit_min_requests INTEGER NOT NULL DEFAULT 10,
             default_cost_multiplier TEXT NOT NULL DEFAULT '1',
             pricing_model_source TEXT NOT NULL DEFAULT 'response',
+            access_token TEXT, allowed_cidrs TEXT NOT NULL DEFAULT '', lan_mode INTEGER NOT NULL DEFAULT 0,
+            routing_strategy TEXT NOT NULL DEFAULT 'priority',
+            retry_max_attempts INTEGER NOT NULL DEFAULT 1, retry_base_delay_ms INTEGER NOT NULL DEFAULT 500,
+            retry_max_delay_ms INTEGER NOT NULL DEFAULT 8000,
+            retry_status_codes TEXT NOT NULL DEFAULT '429,500,502,503,504,529',
+            retry_same_provider INTEGER NOT NULL DEFAULT 0,
+            metrics_enabled INTEGER NOT NULL DEFAULT 0, otlp_endpoint TEXT,
             created_at TEXT NOT NULL DEFAULT (datetime('now')), updated_at TEXT NOT NULL DEFAULT (datetime('now'))
         )", []).map_err(|e| AppError::Database(e.to_string()))?;
 
@@ -192,7 +199,9 @@ impl Database {
             duration_ms INTEGER, status_code INTEGER NOT NULL, error_message TEXT, session_id TEXT,
             provider_type TEXT, is_streaming INTEGER NOT NULL DEFAULT 0,
             cost_multiplier TEXT NOT NULL DEFAULT '1.0', created_at INTEGER NOT NULL,
-            data_source TEXT NOT NULL DEFAULT 'proxy'
+            data_source TEXT NOT NULL DEFAULT 'proxy',
+            cache_creation_1h_tokens INTEGER NOT NULL DEFAULT 0, reasoning_tokens INTEGER NOT NULL DEFAULT 0,
+            service_tier TEXT, capture_id TEXT
         )", []).map_err(|e| AppError::Database(e.to_string()))?;
 
         conn.execute("CREATE INDEX IF NOT EXISTS idx_request_logs_provider ON proxy_request_logs(provider_id, app_type)", [])
@@ -221,7 +230,9 @@ impl Database {
             model_id TEXT PRIMARY KEY, display_name TEXT NOT NULL,
             input_cost_per_million TEXT NOT NULL, output_cost_per_million TEXT NOT NULL,
             cache_read_cost_per_million TEXT NOT NULL DEFAULT '0',
-            cache_creation_cost_per_million TEXT NOT NULL DEFAULT '0'
+            cache_creation_cost_per_million TEXT NOT NULL DEFAULT '0',
+            cache_creation_1h_cost_per_million TEXT, reasoning_cost_per_million TEXT,
+            tiers TEXT, service_tier_multipliers TEXT
         )",
             [],
         )
@@ -286,6 +297,28 @@ impl Database {
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+        // 19. Model Routing Rules 表 (按模型名路由到供应商)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_routing_rules (
+                id TEXT PRIMARY KEY,
+                app_type TEXT,
+                client_format TEXT,
+                pattern TEXT NOT NULL,
+                match_type TEXT NOT NULL DEFAULT 'glob',
+                target_provider_id TEXT,
+                target_model TEXT,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                sort_index INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 20. Session Search 表 (会话全文索引，本地可重建)
+        Self::create_session_search_tables(conn)?;
+
         // 尝试添加 live_takeover_active 列到 proxy_config 表
         let _ = conn.execute(
             "ALTER TABLE proxy_config ADD COLUMN live_takeover_active INTEGER NOT NULL DEFAULT 0",
@@ -430,6 +463,51 @@ impl Database {
                         Self::migrate_v9_to_v10(conn)?;
                         Self::set_user_version(conn, 10)?;
                     }
+                    10 => {
+                        log::info!("迁移数据库从 v10 到 v11（代理入站鉴权与局域网共享）");
+                        Self::migrate_v10_to_v11(conn)?;
+                        Self::set_user_version(conn, 11)?;
+                    }
+                    11 => {
+                        log::info!("迁移数据库从 v11 到 v12（故障转移路由策略）");
+                        Self::migrate_v11_to_v12(conn)?;
+                        Self::set_user_version(conn, 12)?;
+                    }
+                    12 => {
+                        log::info!("迁移数据库从 v12 到 v13（重试策略）");
+                        Self::migrate_v12_to_v13(conn)?;
+                        Self::set_user_version(conn, 13)?;
+                    }
+                    13 => {
+                        log::info!("迁移数据库从 v13 到 v14（模型路由规则）");
+                        Self::migrate_v13_to_v14(conn)?;
+                        Self::set_user_version(conn, 14)?;
+                    }
+                    14 => {
+                        log::info!("迁移数据库从 v14 到 v15（分段与长上下文定价）");
+                        Self::migrate_v14_to_v15(conn)?;
+                        Self::set_user_version(conn, 15)?;
+                    }
+                    15 => {
+                        log::info!("迁移数据库从 v15 到 v16（Prometheus 指标开关）");
+                        Self::migrate_v15_to_v16(conn)?;
+                        Self::set_user_version(conn, 16)?;
+                    }
+                    16 => {
+                        log::info!("迁移数据库从 v16 到 v17（OTLP 链路导出）");
+                        Self::migrate_v16_to_v17(conn)?;
+                        Self::set_user_version(conn, 17)?;
+                    }
+                    17 => {
+                        log::info!("迁移数据库从 v17 到 v18（请求抓包关联）");
+                        Self::migrate_v17_to_v18(conn)?;
+                        Self::set_user_version(conn, 18)?;
+                    }
+                    18 => {
+                        log::info!("迁移数据库从 v18 到 v19（会话全文搜索索引）");
+                        Self::migrate_v18_to_v19(conn)?;
+                        Self::set_user_version(conn, 19)?;
+                    }
                     _ => {
                         return Err(AppError::Database(format!(
                             "未知的数据库版本 {version}，无法迁移到 {SCHEMA_VERSION}"
@@ -1198,6 +1276,198 @@ impl Database {
         Ok(())
     }
 
+    /// v10 -> v11 迁移：代理入站鉴权（访问令牌、CIDR 白名单、局域网共享模式）
+    fn migrate_v10_to_v11(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(conn, "proxy_config", "access_token", "TEXT")?;
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "allowed_cidrs",
+                "TEXT NOT NULL DEFAULT ''",
+            )?;
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "lan_mode",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+        }
+
+        log::info!("v10 -> v11 迁移完成：已添加代理入站鉴权配置");
+        Ok(())
+    }
+
+    /// v11 -> v12 迁移：proxy_config 增加路由策略列
+    fn migrate_v11_to_v12(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "routing_strategy",
+                "TEXT NOT NULL DEFAULT 'priority'",
+            )?;
+        }
+
+        log::info!("v11 -> v12 迁移完成：已添加故障转移路由策略");
+        Ok(())
+    }
+
+    /// v12 -> v13 迁移：proxy_config 添加重试策略列
+    fn migrate_v12_to_v13(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            for (column, definition) in [
+                ("retry_max_attempts", "INTEGER NOT NULL DEFAULT 1"),
+                ("retry_base_delay_ms", "INTEGER NOT NULL DEFAULT 500"),
+                ("retry_max_delay_ms", "INTEGER NOT NULL DEFAULT 8000"),
+                (
+                    "retry_status_codes",
+                    "TEXT NOT NULL DEFAULT '429,500,502,503,504,529'",
+                ),
+                ("retry_same_provider", "INTEGER NOT NULL DEFAULT 0"),
+            ] {
+                Self::add_column_if_missing(conn, "proxy_config", column, definition)?;
+            }
+        }
+
+        log::info!("v12 -> v13 迁移完成：已添加重试策略");
+        Ok(())
+    }
+
+    /// v13 -> v14 迁移：添加模型路由规则表
+    fn migrate_v13_to_v14(conn: &Connection) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_routing_rules (
+                id TEXT PRIMARY KEY,
+                app_type TEXT,
+                client_format TEXT,
+                pattern TEXT NOT NULL,
+                match_type TEXT NOT NULL DEFAULT 'glob',
+                target_provider_id TEXT,
+                target_model TEXT,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                sort_index INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| AppError::Database(format!("创建 model_routing_rules 表失败: {e}")))?;
+
+        log::info!("v13 -> v14 迁移完成：已添加模型路由规则表");
+        Ok(())
+    }
+
+    /// v14 -> v15 迁移：模型定价增加分段/缓存 TTL/推理/服务等级修饰项，请求日志记录对应 token
+    fn migrate_v14_to_v15(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "model_pricing")? {
+            for column in [
+                "cache_creation_1h_cost_per_million",
+                "reasoning_cost_per_million",
+                "tiers",
+                "service_tier_multipliers",
+            ] {
+                Self::add_column_if_missing(conn, "model_pricing", column, "TEXT")?;
+            }
+            Self::seed_model_pricing_modifiers(conn)?;
+        }
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            for (column, definition) in [
+                ("cache_creation_1h_tokens", "INTEGER NOT NULL DEFAULT 0"),
+                ("reasoning_tokens", "INTEGER NOT NULL DEFAULT 0"),
+                ("service_tier", "TEXT"),
+            ] {
+                Self::add_column_if_missing(conn, "proxy_request_logs", column, definition)?;
+            }
+        }
+
+        log::info!("v14 -> v15 迁移完成：已添加分段与长上下文定价");
+        Ok(())
+    }
+
+    /// v15 -> v16 迁移：proxy_config 增加 Prometheus 指标开关
+    fn migrate_v15_to_v16(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_config",
+                "metrics_enabled",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+        }
+
+        log::info!("v15 -> v16 迁移完成：已添加 Prometheus 指标开关");
+        Ok(())
+    }
+
+    /// v16 -> v17 迁移：proxy_config 增加 OTLP 链路导出端点
+    fn migrate_v16_to_v17(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_config")? {
+            Self::add_column_if_missing(conn, "proxy_config", "otlp_endpoint", "TEXT")?;
+        }
+
+        log::info!("v16 -> v17 迁移完成：已添加 OTLP 链路导出端点");
+        Ok(())
+    }
+
+    /// v17 -> v18 迁移：请求日志关联调试抓包
+    fn migrate_v17_to_v18(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(conn, "proxy_request_logs", "capture_id", "TEXT")?;
+        }
+
+        log::info!("v17 -> v18 迁移完成：请求日志已添加 capture_id");
+        Ok(())
+    }
+
+    /// v18 -> v19 迁移：会话全文搜索索引
+    fn migrate_v18_to_v19(conn: &Connection) -> Result<(), AppError> {
+        Self::create_session_search_tables(conn)?;
+
+        log::info!("v18 -> v19 迁移完成：已添加会话全文搜索索引");
+        Ok(())
+    }
+
+    /// 创建会话全文搜索相关表
+    ///
+    /// `session_search_files` 记录每个会话源的变更指纹（同 session_log_sync 按
+    /// source_path + mtime 增量更新），`session_search_fts` 为 FTS5 消息索引。
+    /// `index_version` 记录建索引时的消息投影版本，投影变更后旧行会被重建。
+    /// 使用 trigram 分词以支持中文与代码片段的子串匹配。
+    fn create_session_search_tables(conn: &Connection) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS session_search_files (
+                source_path TEXT PRIMARY KEY,
+                provider_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                title TEXT,
+                project_dir TEXT,
+                last_active_at INTEGER,
+                last_modified INTEGER NOT NULL,
+                message_count INTEGER NOT NULL DEFAULT 0,
+                indexed_at INTEGER NOT NULL,
+                index_version INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )
+        .map_err(|e| AppError::Database(format!("创建 session_search_files 表失败: {e}")))?;
+
+        conn.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS session_search_fts USING fts5(
+                content,
+                source_path UNINDEXED,
+                message_index UNINDEXED,
+                role UNINDEXED,
+                ts UNINDEXED,
+                tokenize = 'trigram'
+            )",
+            [],
+        )
+        .map_err(|e| AppError::Database(format!("创建 session_search_fts 表失败: {e}")))?;
+
+        Ok(())
+    }
+
     /// 插入默认模型定价数据
     /// 格式: (model_id, display_name, input, output, cache_read, cache_creation)
     /// 注意: model_id 使用短横线格式（如 claude-haiku-4-5），与 API 返回的模型名称标准化后一致
@@ -1860,6 +2130,81 @@ impl Database {
         }
 
         log::info!("已插入 {} 条默认模型定价数据", pricing_data.len());
+        Self::seed_model_pricing_modifiers(conn)
+    }
+
+    /// 为默认模型补充定价修饰项（仅填充为空的列，不覆盖用户修改）
+    fn seed_model_pricing_modifiers(conn: &Connection) -> Result<(), AppError> {
+        // 旧版本迁移过程中修饰项列尚未添加，由 v14 -> v15 迁移补齐后再填充
+        if !Self::has_column(conn, "model_pricing", "tiers")? {
+            return Ok(());
+        }
+
+        // Claude 1 小时缓存写入价格为输入价格的 2 倍
+        let cache_1h = [
+            ("claude-opus-4-7", "10"),
+            ("claude-opus-4-6-20260206", "10"),
+            ("claude-sonnet-4-6-20260217", "6"),
+            ("claude-opus-4-5-20251101", "10"),
+            ("claude-sonnet-4-5-20250929", "6"),
+            ("claude-haiku-4-5-20251001", "2"),
+            ("claude-opus-4-20250514", "30"),
+            ("claude-opus-4-1-20250805", "30"),
+            ("claude-sonnet-4-20250514", "6"),
+            ("claude-3-5-haiku-20241022", "1.6"),
+            ("claude-3-5-sonnet-20241022", "6"),
+        ];
+        for (model_id, price) in cache_1h {
+            conn.execute(
+                "UPDATE model_pricing SET cache_creation_1h_cost_per_million = ?2
+                 WHERE model_id = ?1 AND cache_creation_1h_cost_per_million IS NULL",
+                rusqlite::params![model_id, price],
+            )
+            .map_err(|e| AppError::Database(format!("更新缓存写入价格失败: {e}")))?;
+        }
+
+        // 长上下文（> 200k 提示 token）分段价格
+        let sonnet_long_context = r#"[{"aboveInputTokens":200000,"inputCostPerMillion":"6","outputCostPerMillion":"22.5","cacheReadCostPerMillion":"0.6","cacheCreationCostPerMillion":"7.5","cacheCreation1hCostPerMillion":"12"}]"#;
+        let tiers = [
+            ("claude-sonnet-4-6-20260217", sonnet_long_context),
+            ("claude-sonnet-4-5-20250929", sonnet_long_context),
+            ("claude-sonnet-4-20250514", sonnet_long_context),
+            (
+                "gemini-3.1-pro-preview",
+                r#"[{"aboveInputTokens":200000,"inputCostPerMillion":"4","outputCostPerMillion":"18","cacheReadCostPerMillion":"0.4","cacheCreationCostPerMillion":"0"}]"#,
+            ),
+            (
+                "gemini-3-pro-preview",
+                r#"[{"aboveInputTokens":200000,"inputCostPerMillion":"4","outputCostPerMillion":"18","cacheReadCostPerMillion":"0.4","cacheCreationCostPerMillion":"0"}]"#,
+            ),
+            (
+                "gemini-2.5-pro",
+                r#"[{"aboveInputTokens":200000,"inputCostPerMillion":"2.5","outputCostPerMillion":"15","cacheReadCostPerMillion":"0.25","cacheCreationCostPerMillion":"0"}]"#,
+            ),
+        ];
+        for (model_id, tiers) in tiers {
+            conn.execute(
+                "UPDATE model_pricing SET tiers = ?2 WHERE model_id = ?1 AND tiers IS NULL",
+                rusqlite::params![model_id, tiers],
+            )
+            .map_err(|e| AppError::Database(format!("更新分段价格失败: {e}")))?;
+        }
+
+        // 服务等级倍率：Claude Batch 半价；OpenAI Priority 两倍、Flex/Batch 半价
+        for (pattern, multipliers) in [
+            ("claude-%", r#"{"batch":"0.5"}"#),
+            ("gpt-5%", r#"{"priority":"2","flex":"0.5","batch":"0.5"}"#),
+            ("o3", r#"{"priority":"2","flex":"0.5","batch":"0.5"}"#),
+            ("o4-mini", r#"{"priority":"2","flex":"0.5","batch":"0.5"}"#),
+        ] {
+            conn.execute(
+                "UPDATE model_pricing SET service_tier_multipliers = ?2
+                 WHERE model_id LIKE ?1 AND service_tier_multipliers IS NULL",
+                rusqlite::params![pattern, multipliers],
+            )
+            .map_err(|e| AppError::Database(format!("更新服务等级倍率失败: {e}")))?;
+        }
+
         Ok(())
     }
 
@@ -786,6 +786,7 @@ pub fn run() {
                 app_state.db.clone(),
                 app.handle().clone(),
             );
+            crate::services::notification::start_worker(app_state.db.clone());
             // 将同一个实例注入到全局状态，避免重复创建导致的不一致
             app.manage(app_state);
 
@@ -970,6 +971,23 @@ pub fn run() {
                         }
                     }
                 });
+
+                // 消费预测：定期检查是否预计超出供应商限额
+                let db_for_forecast = state.db.clone();
+                let app_for_forecast = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let mut monitor =
+                        crate::services::usage_forecast::ForecastMonitor::default();
+                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+                        crate::services::usage_forecast::FORECAST_CHECK_INTERVAL_SECS,
+                    ));
+                    loop {
+                        interval.tick().await;
+                        if let Err(e) = monitor.check(&db_for_forecast, &app_for_forecast) {
+                            log::warn!("Usage forecast check failed: {e}");
+                        }
+                    }
+                });
             });
 
             // Linux: 禁用 WebKitGTK 硬件加速，防止 EGL 初始化失败导致白屏
@@ -1178,6 +1196,9 @@ pub fn run() {
             commands::get_proxy_status,
             commands::get_proxy_config,
             commands::update_proxy_config,
+            commands::get_proxy_access_config,
+            commands::update_proxy_access_config,
+            commands::regenerate_proxy_access_token,
             // Global & Per-App Config
             commands::get_global_proxy_config,
             commands::update_global_proxy_config,
@@ -1187,6 +1208,18 @@ pub fn run() {
             commands::set_default_cost_multiplier,
             commands::get_pricing_model_source,
             commands::set_pricing_model_source,
+            commands::get_proxy_routing_strategy,
+            commands::set_proxy_routing_strategy,
+            commands::get_proxy_retry_policy,
+            commands::set_proxy_retry_policy,
+            commands::get_model_routing_rules,
+            commands::save_model_routing_rule,
+            commands::delete_model_routing_rule,
+            commands::reorder_model_routing_rules,
+            commands::get_capture_rules,
+            commands::start_request_capture,
+            commands::stop_request_capture,
+            commands::replay_request,
             commands::is_proxy_running,
             commands::is_live_takeover_active,
             commands::switch_proxy_provider,
@@ -1208,10 +1241,18 @@ pub fn run() {
             commands::get_usage_trends,
             commands::get_provider_stats,
             commands::get_model_stats,
+            commands::get_usage_attribution,
+            commands::get_usage_forecast,
             commands::get_request_logs,
             commands::get_request_detail,
+            commands::get_request_capture,
             commands::get_model_pricing,
             commands::update_model_pricing,
+            commands::recompute_usage_costs,
+            commands::preview_pricing_import,
+            commands::apply_pricing_import,
+            commands::export_model_pricing,
+            commands::export_usage_data,
             commands::delete_model_pricing,
             commands::check_provider_limits,
             // Session usage sync
@@ -1222,11 +1263,22 @@ pub fn run() {
             commands::stream_check_all_providers,
             commands::get_stream_check_config,
             commands::save_stream_check_config,
+            commands::get_proxy_health_check_config,
+            commands::save_proxy_health_check_config,
+            // Notifications
+            commands::get_notification_config,
+            commands::save_notification_config,
+            commands::test_notification,
             // Session manager
             commands::list_sessions,
             commands::get_session_messages,
+            commands::get_session_structured_messages,
             commands::delete_session,
             commands::delete_sessions,
+            commands::search_sessions,
+            commands::export_session,
+            commands::export_sessions_zip,
+            commands::convert_session,
             commands::launch_session_terminal,
             commands::get_tool_versions,
             // Provider terminal
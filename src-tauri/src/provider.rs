@@ -236,13 +236,26 @@ pub struct ProviderMeta {
     /// 每月消费限额（USD）
     #[serde(rename = "limitMonthlyUsd", skip_serializing_if = "Option::is_none")]
     pub limit_monthly_usd: Option<String>,
+    /// 限额预警比例（百分比，1-100），达到后发射预警事件，默认 80
+    #[serde(
+        rename = "limitWarningPercent",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub limit_warning_percent: Option<u32>,
+    /// 加权路由的权重（路由策略为 weighted 时生效，默认 1，0 表示仅作为故障转移备用）
+    #[serde(rename = "routingWeight", skip_serializing_if = "Option::is_none")]
+    pub routing_weight: Option<u32>,
     /// 供应商单独的模型测试配置
     #[serde(rename = "testConfig", skip_serializing_if = "Option::is_none")]
     pub test_config: Option<ProviderTestConfig>,
-    /// Claude API 格式（仅 Claude 供应商使用）
+    /// API 格式（Claude / Codex 供应商使用）
     /// - "anthropic": 原生 Anthropic Messages API，直接透传
     /// - "openai_chat": OpenAI Chat Completions 格式，需要转换
     /// - "openai_responses": OpenAI Responses API 格式，需要转换
+    ///
+    /// Codex 供应商同样使用该字段：
+    /// - "openai_responses"（默认）: 原生 Responses API，直接透传
+    /// - "openai_chat": 仅支持 Chat Completions，Responses 请求需要转换
     #[serde(rename = "apiFormat", skip_serializing_if = "Option::is_none")]
     pub api_format: Option<String>,
     /// 通用认证绑定（provider_config / managed_account）
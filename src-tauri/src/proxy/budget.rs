@@ -0,0 +1,282 @@
+//! 供应商消费限额模块
+//!
+//! 在代理请求路径中执行 `ProviderMeta.limit_daily_usd` / `limit_monthly_usd`：
+//! - 超出限额的供应商视为不可用（故障转移队列中跳过）
+//! - 达到预警比例（默认 80%）时发射前端事件，提前提示
+
+use crate::provider::Provider;
+use crate::services::usage_stats::ProviderLimitStatus;
+use serde::Serialize;
+
+/// 默认预警比例（百分比）
+pub const DEFAULT_WARNING_PERCENT: u32 = 80;
+
+/// 预警事件名
+pub const BUDGET_WARNING_EVENT: &str = "provider-budget-warning";
+/// 超限事件名
+pub const BUDGET_EXCEEDED_EVENT: &str = "provider-budget-exceeded";
+/// 预计超限事件名（按当前消费速率推算将超出限额）
+pub const BUDGET_FORECAST_EVENT: &str = "provider-budget-forecast";
+
+/// 限额周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+impl BudgetPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "daily",
+            BudgetPeriod::Monthly => "monthly",
+        }
+    }
+
+    /// 当前周期标识（本地时间），用于事件去重：同一周期内只提示一次
+    pub fn current_key(&self) -> String {
+        let now = chrono::Local::now();
+        match self {
+            BudgetPeriod::Daily => now.format("%Y-%m-%d").to_string(),
+            BudgetPeriod::Monthly => now.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// 单个周期的限额判定结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetVerdict {
+    /// 未设置限额或远低于预警线
+    Within,
+    /// 达到预警线但未超限
+    Warning {
+        period: BudgetPeriod,
+        usage: f64,
+        limit: f64,
+    },
+    /// 已超限
+    Exceeded {
+        period: BudgetPeriod,
+        usage: f64,
+        limit: f64,
+    },
+}
+
+/// 前端事件载荷
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetEventPayload {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub period: BudgetPeriod,
+    pub usage_usd: String,
+    pub limit_usd: String,
+    pub percent: f64,
+}
+
+impl BudgetEventPayload {
+    pub fn new(
+        app_type: &str,
+        provider: &Provider,
+        period: BudgetPeriod,
+        usage: f64,
+        limit: f64,
+    ) -> Self {
+        Self {
+            app_type: app_type.to_string(),
+            provider_id: provider.id.clone(),
+            provider_name: provider.name.clone(),
+            period,
+            usage_usd: format!("{usage:.6}"),
+            limit_usd: format!("{limit:.2}"),
+            percent: if limit > 0.0 {
+                usage / limit * 100.0
+            } else {
+                100.0
+            },
+        }
+    }
+}
+
+/// Provider 是否配置了任意消费限额
+///
+/// 未配置时可跳过数据库查询，避免每个请求都聚合一次日志
+pub fn has_spend_limits(provider: &Provider) -> bool {
+    let (daily, monthly) = spend_limits(provider);
+    daily.is_some() || monthly.is_some()
+}
+
+/// 读取 Provider 的 (日限额, 月限额)，未配置或无效时为 None
+pub fn spend_limits(provider: &Provider) -> (Option<f64>, Option<f64>) {
+    provider
+        .meta
+        .as_ref()
+        .map(|meta| {
+            (
+                parse_limit(meta.limit_daily_usd.as_deref()),
+                parse_limit(meta.limit_monthly_usd.as_deref()),
+            )
+        })
+        .unwrap_or((None, None))
+}
+
+/// 读取 Provider 的预警比例（百分比，1-100），未配置时使用默认 80%
+pub fn warning_percent(provider: &Provider) -> u32 {
+    provider
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.limit_warning_percent)
+        .filter(|p| (1..=100).contains(p))
+        .unwrap_or(DEFAULT_WARNING_PERCENT)
+}
+
+/// 根据限额状态判定预算结果
+///
+/// 多个周期同时命中时，超限优先于预警；同级别下月度优先于日度
+/// （月度超限意味着当月剩余时间都不可用，信息量更大）。
+pub fn evaluate(status: &ProviderLimitStatus, warning_percent: u32) -> BudgetVerdict {
+    let ratio = warning_percent as f64 / 100.0;
+    let periods = [
+        (
+            BudgetPeriod::Monthly,
+            status.monthly_usage.as_str(),
+            status.monthly_limit.as_deref(),
+        ),
+        (
+            BudgetPeriod::Daily,
+            status.daily_usage.as_str(),
+            status.daily_limit.as_deref(),
+        ),
+    ];
+
+    let mut warning = None;
+    for (period, usage, limit) in periods {
+        let Some(limit) = parse_limit(limit) else {
+            continue;
+        };
+        let usage = usage.parse::<f64>().unwrap_or(0.0);
+
+        if usage >= limit {
+            return BudgetVerdict::Exceeded {
+                period,
+                usage,
+                limit,
+            };
+        }
+        if warning.is_none() && usage >= limit * ratio {
+            warning = Some(BudgetVerdict::Warning {
+                period,
+                usage,
+                limit,
+            });
+        }
+    }
+
+    warning.unwrap_or(BudgetVerdict::Within)
+}
+
+/// 解析限额字符串；空值、非数字和非正数均视为未设置
+fn parse_limit(value: Option<&str>) -> Option<f64> {
+    value
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|v| *v > 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn status(
+        daily: &str,
+        daily_limit: Option<&str>,
+        monthly: &str,
+        monthly_limit: Option<&str>,
+    ) -> ProviderLimitStatus {
+        ProviderLimitStatus {
+            provider_id: "p1".to_string(),
+            daily_usage: daily.to_string(),
+            daily_limit: daily_limit.map(str::to_string),
+            daily_exceeded: false,
+            monthly_usage: monthly.to_string(),
+            monthly_limit: monthly_limit.map(str::to_string),
+            monthly_exceeded: false,
+        }
+    }
+
+    #[test]
+    fn no_limits_is_within_budget() {
+        let verdict = evaluate(&status("12.0", None, "300.0", None), 80);
+        assert_eq!(verdict, BudgetVerdict::Within);
+    }
+
+    #[test]
+    fn daily_usage_over_limit_is_exceeded() {
+        let verdict = evaluate(&status("10.5", Some("10.00"), "10.5", None), 80);
+        assert!(matches!(
+            verdict,
+            BudgetVerdict::Exceeded {
+                period: BudgetPeriod::Daily,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn exceeded_takes_priority_over_warning() {
+        // 月度达到预警线，日度已超限 → 超限
+        let verdict = evaluate(&status("5.0", Some("5.00"), "85.0", Some("100.00")), 80);
+        assert!(matches!(
+            verdict,
+            BudgetVerdict::Exceeded {
+                period: BudgetPeriod::Daily,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn warning_threshold_is_configurable() {
+        let s = status("7.0", Some("10.00"), "7.0", None);
+        assert!(matches!(evaluate(&s, 80), BudgetVerdict::Within));
+        assert!(matches!(
+            evaluate(&s, 70),
+            BudgetVerdict::Warning {
+                period: BudgetPeriod::Daily,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn zero_or_invalid_limit_is_ignored() {
+        let verdict = evaluate(&status("1.0", Some("0"), "1.0", Some("abc")), 80);
+        assert_eq!(verdict, BudgetVerdict::Within);
+    }
+
+    #[test]
+    fn has_spend_limits_reads_provider_meta() {
+        let mut provider = Provider::with_id("p1".to_string(), "P1".to_string(), json!({}), None);
+        assert!(!has_spend_limits(&provider));
+
+        provider.meta = Some(ProviderMeta {
+            limit_monthly_usd: Some("50".to_string()),
+            ..ProviderMeta::default()
+        });
+        assert!(has_spend_limits(&provider));
+        assert_eq!(warning_percent(&provider), DEFAULT_WARNING_PERCENT);
+
+        provider.meta = Some(ProviderMeta {
+            limit_daily_usd: Some("".to_string()),
+            limit_warning_percent: Some(90),
+            ..ProviderMeta::default()
+        });
+        assert!(!has_spend_limits(&provider));
+        assert_eq!(warning_percent(&provider), 90);
+    }
+}
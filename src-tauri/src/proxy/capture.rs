@@ -0,0 +1,704 @@
+//! 请求抓包（调试用）
+//!
+//! 按应用或供应商开启限时抓包后，将入站请求、转换后的上游请求以及上游原始响应（含 SSE 流）
+//! 写入 `<配置目录>/captures/<capture_id>/`，请求日志通过 `capture_id` 列关联。
+//!
+//! 写盘前遮蔽凭据：敏感请求头、JSON 中的凭据字段、URL 中的 `key=` 参数，
+//! 以及本次请求 `AuthInfo` 中的 API Key / Access Token 出现在任何位置的原文。
+//! 单个文件与目录总大小均有上限，超龄或超量的抓包会被自动清理。
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::proxy::hyper_client::ProxyResponse;
+use crate::proxy::providers::{AuthInfo, AuthStrategy};
+use crate::proxy::types::CaptureRule;
+use axum::http::{HeaderMap, Uri};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// 单个抓包文件的大小上限（字节），超出部分丢弃
+const MAX_PART_BYTES: usize = 4 * 1024 * 1024;
+/// 抓包目录总大小上限（字节）
+const MAX_TOTAL_BYTES: u64 = 256 * 1024 * 1024;
+/// 抓包保留时长
+const MAX_AGE: Duration = Duration::from_secs(3 * 24 * 3600);
+/// 两次自动清理之间的最小间隔（秒）
+const PRUNE_INTERVAL_SECS: i64 = 60;
+/// 单条抓包规则的最长开启时长（分钟）
+const MAX_RULE_MINUTES: u32 = 24 * 60;
+
+/// 响应体截断标记
+const TRUNCATED_MARKER: &str = "\n[cc-switch: capture truncated]\n";
+
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "api-key",
+    "cookie",
+    "set-cookie",
+];
+
+const SENSITIVE_FIELDS: &[&str] = &[
+    "api_key",
+    "apikey",
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "client_secret",
+    "password",
+];
+
+static SENSITIVE_QUERY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)([?&](?:key|api_key|access_token)=)[^&#\s]+").expect("valid regex")
+});
+
+static LAST_PRUNE: AtomicI64 = AtomicI64::new(0);
+
+/// 距上次清理超过 [`PRUNE_INTERVAL_SECS`] 时占用本轮清理（并发请求中只有一个会成功）
+fn claim_prune(now: i64) -> bool {
+    let last = LAST_PRUNE.load(Ordering::Relaxed);
+    now - last >= PRUNE_INTERVAL_SECS
+        && LAST_PRUNE
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+}
+
+/// 在阻塞线程池中执行文件操作，不占用异步工作线程；不在 tokio 运行时内（如测试）时直接执行
+fn run_blocking(task: impl FnOnce() + Send + 'static) {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn_blocking(task);
+        }
+        Err(_) => task(),
+    }
+}
+
+/// 写入抓包文件（按需创建抓包目录）
+fn write_capture_file(path: &Path, content: impl AsRef<[u8]>) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)
+}
+
+/// 抓包根目录
+pub(crate) fn capture_root() -> PathBuf {
+    crate::config::get_app_config_dir().join("captures")
+}
+
+/// 遮蔽凭据，保留首尾各 4 个字符便于辨认（与 `AuthInfo::masked_key` 一致）
+fn mask_secret(secret: &str) -> String {
+    AuthInfo::new(secret.to_string(), AuthStrategy::Bearer).masked_key()
+}
+
+/// 单次请求的抓包句柄
+///
+/// 克隆后共享同一目录；故障转移时每次上游尝试写入独立的 `upstream_*_<n>` 文件
+#[derive(Clone)]
+pub struct RequestCapture {
+    inner: Arc<CaptureInner>,
+}
+
+struct CaptureInner {
+    id: String,
+    dir: PathBuf,
+    /// (原文, 遮蔽后) 对，写盘时替换任意位置出现的原文
+    secrets: Mutex<Vec<(String, String)>>,
+    attempts: AtomicU32,
+}
+
+impl RequestCapture {
+    /// 按抓包规则为请求开启抓包（未命中时返回 None）
+    ///
+    /// `rules` 为代理缓存的抓包规则（见 `ProxyState::capture_rules`）。
+    /// 清理与写盘均在阻塞线程池中进行，写盘失败只记录日志。
+    pub fn for_request(
+        rules: &[CaptureRule],
+        app_type: &str,
+        provider_ids: &[&str],
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Value,
+    ) -> Option<Self> {
+        let now = chrono::Utc::now().timestamp();
+        if !rules
+            .iter()
+            .any(|rule| rule.matches(app_type, provider_ids.iter().copied(), now))
+        {
+            return None;
+        }
+
+        let root = capture_root();
+        if claim_prune(now) {
+            let root = root.clone();
+            run_blocking(move || {
+                if let Err(e) = prune_captures(&root, SystemTime::now(), MAX_AGE, MAX_TOTAL_BYTES) {
+                    log::warn!("清理过期抓包失败: {e}");
+                }
+            });
+        }
+
+        Some(Self::start(&root, app_type, uri, headers, body))
+    }
+
+    /// 在指定根目录下创建抓包并写入入站请求
+    fn start(root: &Path, app_type: &str, uri: &Uri, headers: &HeaderMap, body: &Value) -> Self {
+        let id = uuid::Uuid::new_v4().to_string();
+        let dir = root.join(&id);
+        let capture = Self {
+            inner: Arc::new(CaptureInner {
+                id,
+                dir,
+                secrets: Mutex::new(Vec::new()),
+                attempts: AtomicU32::new(0),
+            }),
+        };
+        capture.write_json(
+            "inbound.json",
+            json!({
+                "captureId": capture.id(),
+                "appType": app_type,
+                "createdAt": chrono::Utc::now().timestamp_millis(),
+                "method": "POST",
+                "uri": capture.redact_text(&uri.to_string()),
+                "headers": capture.redact_headers(headers),
+                "body": capture.redact_body(body),
+            }),
+        );
+        log::info!("[Capture] 已开启请求抓包: {}", capture.id());
+        capture
+    }
+
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    /// 登记本次上游请求使用的凭据，后续写盘时遮蔽
+    pub fn add_auth(&self, auth: &AuthInfo) {
+        let mut secrets = self.inner.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        for secret in [Some(&auth.api_key), auth.access_token.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            if !secret.is_empty() && !secrets.iter().any(|(s, _)| s == secret) {
+                secrets.push((secret.clone(), mask_secret(secret)));
+            }
+        }
+    }
+
+    /// 写入转换后的上游请求，返回本次尝试序号
+    pub fn record_upstream_request(&self, url: &str, headers: &HeaderMap, body: &Value) -> u32 {
+        let attempt = self.inner.attempts.fetch_add(1, Ordering::Relaxed) + 1;
+        self.write_json(
+            &format!("upstream_request_{attempt}.json"),
+            json!({
+                "url": self.redact_text(url),
+                "headers": self.redact_headers(headers),
+                "body": self.redact_body(body),
+            }),
+        );
+        attempt
+    }
+
+    /// 写入上游响应头，并返回会把响应体同步写盘的响应
+    pub fn tap_response(&self, attempt: u32, response: ProxyResponse) -> ProxyResponse {
+        self.write_json(
+            &format!("upstream_response_{attempt}.json"),
+            json!({
+                "status": response.status().as_u16(),
+                "headers": self.redact_headers(response.headers()),
+            }),
+        );
+        let path = self
+            .inner
+            .dir
+            .join(format!("upstream_response_{attempt}.body"));
+        ProxyResponse::Captured(Box::new(response), ResponseTap::new(self.clone(), path))
+    }
+
+    /// 遮蔽后的 JSON 在当前线程序列化，写盘交给阻塞线程池
+    fn write_json(&self, name: &str, value: Value) {
+        let path = self.inner.dir.join(name);
+        let bytes = match serde_json::to_vec_pretty(&value) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("序列化抓包文件失败 ({}): {e}", path.display());
+                return;
+            }
+        };
+        run_blocking(move || {
+            if let Err(e) = write_capture_file(&path, bytes) {
+                log::warn!("写入抓包文件失败 ({}): {e}", path.display());
+            }
+        });
+    }
+
+    /// 遮蔽完整响应体后写盘，超出大小上限的部分替换为截断标记
+    fn write_response_body(&self, path: &Path, body: &[u8], truncated: bool) {
+        let text = self.redact_text(&String::from_utf8_lossy(body));
+        let mut end = text.len().min(MAX_PART_BYTES);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let mut content = text[..end].to_string();
+        if truncated || end < text.len() {
+            content.push_str(TRUNCATED_MARKER);
+        }
+        if let Err(e) = write_capture_file(path, content) {
+            log::warn!("写入抓包响应体失败 ({}): {e}", path.display());
+        }
+    }
+
+    fn redact_text(&self, text: &str) -> String {
+        let mut out = SENSITIVE_QUERY.replace_all(text, "${1}***").into_owned();
+        let secrets = self.inner.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        for (secret, masked) in secrets.iter() {
+            if out.contains(secret.as_str()) {
+                out = out.replace(secret.as_str(), masked);
+            }
+        }
+        out
+    }
+
+    fn redact_headers(&self, headers: &HeaderMap) -> Value {
+        let mut map = Map::new();
+        for (name, value) in headers {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                match value.split_once(' ') {
+                    Some((scheme, token)) => format!("{scheme} {}", mask_secret(token)),
+                    None => mask_secret(&value),
+                }
+            } else {
+                self.redact_text(&value)
+            };
+            match map.get_mut(name.as_str()) {
+                Some(Value::String(existing)) => {
+                    existing.push_str(", ");
+                    existing.push_str(&value);
+                }
+                _ => {
+                    map.insert(name.to_string(), Value::String(value));
+                }
+            }
+        }
+        Value::Object(map)
+    }
+
+    /// 遮蔽请求体；序列化后超过大小上限时改存截断后的文本
+    fn redact_body(&self, body: &Value) -> Value {
+        let redacted = self.redact_value(body);
+        let len = serde_json::to_string(&redacted)
+            .map(|s| s.len())
+            .unwrap_or(0);
+        if len <= MAX_PART_BYTES {
+            return redacted;
+        }
+        let text = serde_json::to_string(&redacted).unwrap_or_default();
+        let mut end = MAX_PART_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        json!({ "truncated": true, "originalBytes": len, "text": &text[..end] })
+    }
+
+    fn redact_value(&self, value: &Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, value)| {
+                        let value = match value {
+                            Value::String(s)
+                                if SENSITIVE_FIELDS
+                                    .contains(&key.to_ascii_lowercase().as_str()) =>
+                            {
+                                Value::String(mask_secret(s))
+                            }
+                            other => self.redact_value(other),
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            ),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|v| self.redact_value(v)).collect())
+            }
+            Value::String(s) => Value::String(self.redact_text(s)),
+            other => other.clone(),
+        }
+    }
+}
+
+/// 上游响应体抓包（由 `ProxyResponse::Captured` 在读取响应体时调用）
+///
+/// 响应体先缓存在内存中；最后一个句柄释放（响应体读完或流被丢弃）时对完整文本统一遮蔽，
+/// 避免凭据被拆分到两个分块中而漏遮蔽，并在阻塞线程池中写盘，不占用异步工作线程。
+#[derive(Clone)]
+pub struct ResponseTap {
+    inner: Arc<TapInner>,
+}
+
+struct TapInner {
+    capture: RequestCapture,
+    path: PathBuf,
+    /// 已缓存的响应体，以及是否因超出上限被截断
+    buffer: Mutex<(Vec<u8>, bool)>,
+}
+
+/// 超出大小上限后额外缓存的字节数，保证跨越截断点的凭据仍能被完整匹配
+const REDACT_SLACK_BYTES: usize = 4096;
+
+impl ResponseTap {
+    fn new(capture: RequestCapture, path: PathBuf) -> Self {
+        Self {
+            inner: Arc::new(TapInner {
+                capture,
+                path,
+                buffer: Mutex::new((Vec::new(), false)),
+            }),
+        }
+    }
+
+    pub fn write(&self, chunk: &[u8]) {
+        let mut guard = self.inner.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        let (buffer, truncated) = &mut *guard;
+        let remaining = (MAX_PART_BYTES + REDACT_SLACK_BYTES).saturating_sub(buffer.len());
+        if chunk.len() > remaining {
+            *truncated = true;
+        }
+        buffer.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+    }
+}
+
+impl Drop for TapInner {
+    fn drop(&mut self) {
+        let (body, truncated) =
+            std::mem::take(self.buffer.get_mut().unwrap_or_else(|e| e.into_inner()));
+        let capture = self.capture.clone();
+        let path = std::mem::take(&mut self.path);
+        run_blocking(move || capture.write_response_body(&path, &body, truncated));
+    }
+}
+
+/// 单次上游尝试的抓包内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureAttempt {
+    pub attempt: u32,
+    pub request: Option<Value>,
+    pub response: Option<Value>,
+    pub response_body: Option<String>,
+    pub response_body_truncated: bool,
+}
+
+/// 请求抓包详情
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestCaptureDetail {
+    pub capture_id: String,
+    pub inbound: Option<Value>,
+    pub attempts: Vec<CaptureAttempt>,
+}
+
+/// 读取请求日志关联的抓包（未抓包或已被清理时返回 None）
+pub fn load_request_capture(
+    db: &Database,
+    request_id: &str,
+) -> Result<Option<RequestCaptureDetail>, AppError> {
+    match db.get_request_capture_id(request_id)? {
+        Some(capture_id) => load_capture(&capture_root(), &capture_id),
+        None => Ok(None),
+    }
+}
+
+fn load_capture(root: &Path, capture_id: &str) -> Result<Option<RequestCaptureDetail>, AppError> {
+    // capture_id 来自数据库，仍校验格式以免拼出目录外路径
+    if uuid::Uuid::parse_str(capture_id).is_err() {
+        return Err(AppError::InvalidInput(format!(
+            "无效的抓包 ID: {capture_id}"
+        )));
+    }
+    let dir = root.join(capture_id);
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+
+    let read_json = |name: String| -> Option<Value> {
+        let bytes = fs::read(dir.join(name)).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    };
+
+    let mut attempts = Vec::new();
+    for attempt in 1.. {
+        let request = read_json(format!("upstream_request_{attempt}.json"));
+        if request.is_none() {
+            break;
+        }
+        let response_body = fs::read(dir.join(format!("upstream_response_{attempt}.body")))
+            .ok()
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+        attempts.push(CaptureAttempt {
+            attempt,
+            request,
+            response: read_json(format!("upstream_response_{attempt}.json")),
+            response_body_truncated: response_body
+                .as_deref()
+                .is_some_and(|body| body.ends_with(TRUNCATED_MARKER)),
+            response_body,
+        });
+    }
+
+    Ok(Some(RequestCaptureDetail {
+        capture_id: capture_id.to_string(),
+        inbound: read_json("inbound.json".to_string()),
+        attempts,
+    }))
+}
+
+/// 添加或延长抓包规则（同一应用 + 供应商仅保留一条）
+pub fn start_capture_rule(
+    db: &Database,
+    app_type: &str,
+    provider_id: Option<String>,
+    duration_minutes: u32,
+) -> Result<CaptureRule, AppError> {
+    if !(1..=MAX_RULE_MINUTES).contains(&duration_minutes) {
+        return Err(AppError::InvalidInput(format!(
+            "抓包时长需在 1-{MAX_RULE_MINUTES} 分钟之间"
+        )));
+    }
+    let rule = CaptureRule {
+        app_type: app_type.to_string(),
+        provider_id,
+        expires_at: chrono::Utc::now().timestamp() + i64::from(duration_minutes) * 60,
+    };
+    let mut rules = db.get_capture_rules()?;
+    rules.retain(|r| !(r.app_type == rule.app_type && r.provider_id == rule.provider_id));
+    rules.push(rule.clone());
+    db.set_capture_rules(&rules)?;
+    Ok(rule)
+}
+
+/// 关闭抓包规则；未指定供应商时关闭该应用下的全部规则
+pub fn stop_capture_rule(
+    db: &Database,
+    app_type: &str,
+    provider_id: Option<&str>,
+) -> Result<(), AppError> {
+    let mut rules = db.get_capture_rules()?;
+    rules.retain(|r| {
+        r.app_type != app_type || provider_id.is_some_and(|id| r.provider_id.as_deref() != Some(id))
+    });
+    db.set_capture_rules(&rules)
+}
+
+/// 清理抓包目录：先删除超龄抓包，再按时间从旧到新删除直到总大小低于上限
+fn prune_captures(
+    root: &Path,
+    now: SystemTime,
+    max_age: Duration,
+    max_total_bytes: u64,
+) -> std::io::Result<usize> {
+    if !root.is_dir() {
+        return Ok(0);
+    }
+
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let modified = entry.metadata()?.modified()?;
+        let mut size = 0;
+        for file in fs::read_dir(entry.path())? {
+            size += file?.metadata()?.len();
+        }
+        entries.push((modified, size, entry.path()));
+    }
+    entries.sort_by_key(|(modified, _, _)| *modified);
+
+    let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    let mut removed = 0;
+    for (modified, size, path) in entries {
+        let expired = now.duration_since(modified).unwrap_or_default() > max_age;
+        if !expired && total <= max_total_bytes {
+            continue;
+        }
+        fs::remove_dir_all(&path)?;
+        total -= size;
+        removed += 1;
+    }
+    if removed > 0 {
+        log::info!("[Capture] 已清理 {removed} 个抓包");
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn start_in(root: &Path, body: &Value) -> RequestCapture {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-api-key",
+            HeaderValue::from_static("sk-inbound-placeholder"),
+        );
+        headers.insert("user-agent", HeaderValue::from_static("claude-cli"));
+        let uri: Uri = "/v1/messages?beta=true".parse().unwrap();
+        RequestCapture::start(root, "claude", &uri, &headers, body)
+    }
+
+    #[test]
+    fn redacts_auth_secrets_everywhere() {
+        let root = tempfile::tempdir().unwrap();
+        let capture = start_in(root.path(), &json!({"model": "claude-sonnet-4"}));
+
+        let mut auth = AuthInfo::new("sk-upstream-secret-1234".to_string(), AuthStrategy::Bearer);
+        auth.access_token = Some("oauth-access-token-5678".to_string());
+        capture.add_auth(&auth);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            HeaderValue::from_static("Bearer oauth-access-token-5678"),
+        );
+        headers.insert(
+            "x-echo",
+            HeaderValue::from_static("sk-upstream-secret-1234"),
+        );
+        let attempt = capture.record_upstream_request(
+            "https://example.com/v1beta/models/x:generateContent?key=sk-upstream-secret-1234&alt=sse",
+            &headers,
+            &json!({
+                "api_key": "plain-field-secret",
+                "max_tokens": 1024,
+                "messages": [{"content": "echo sk-upstream-secret-1234"}]
+            }),
+        );
+        assert_eq!(attempt, 1);
+
+        let detail = load_capture(root.path(), capture.id()).unwrap().unwrap();
+        let inbound = detail.inbound.unwrap().to_string();
+        assert!(!inbound.contains("sk-inbound-placeholder"));
+        assert!(inbound.contains("claude-cli"));
+
+        let request = detail.attempts[0].request.as_ref().unwrap();
+        let text = request.to_string();
+        assert!(!text.contains("sk-upstream-secret-1234"));
+        assert!(!text.contains("oauth-access-token-5678"));
+        assert!(!text.contains("plain-field-secret"));
+        assert!(request["url"].as_str().unwrap().contains("key=***&alt=sse"));
+        assert_eq!(request["headers"]["authorization"], "Bearer oaut...5678");
+        assert_eq!(request["body"]["max_tokens"], 1024);
+    }
+
+    #[test]
+    fn response_body_is_capped() {
+        let root = tempfile::tempdir().unwrap();
+        let capture = start_in(root.path(), &json!({}));
+        let attempt =
+            capture.record_upstream_request("https://example.com", &HeaderMap::new(), &json!({}));
+
+        let tap = ResponseTap::new(
+            capture.clone(),
+            root.path()
+                .join(capture.id())
+                .join(format!("upstream_response_{attempt}.body")),
+        );
+        let chunk = vec![b'a'; MAX_PART_BYTES / 2 + 1];
+        tap.write(&chunk);
+        tap.write(&chunk);
+        tap.write(&chunk);
+        drop(tap);
+
+        let detail = load_capture(root.path(), capture.id()).unwrap().unwrap();
+        let attempt = &detail.attempts[0];
+        assert!(attempt.response_body_truncated);
+        assert_eq!(
+            attempt.response_body.as_ref().unwrap().len(),
+            MAX_PART_BYTES + TRUNCATED_MARKER.len()
+        );
+    }
+
+    #[test]
+    fn response_body_redacts_secrets_split_across_chunks() {
+        let root = tempfile::tempdir().unwrap();
+        let capture = start_in(root.path(), &json!({}));
+        capture.add_auth(&AuthInfo::new(
+            "sk-upstream-secret-1234".to_string(),
+            AuthStrategy::Bearer,
+        ));
+        let attempt =
+            capture.record_upstream_request("https://example.com", &HeaderMap::new(), &json!({}));
+
+        let tap = ResponseTap::new(
+            capture.clone(),
+            root.path()
+                .join(capture.id())
+                .join(format!("upstream_response_{attempt}.body")),
+        );
+        tap.write(b"data: {\"echo\":\"sk-upstream-");
+        tap.write(b"secret-1234\"}\n\n");
+        tap.write(&[0xff, b'\n']);
+        drop(tap);
+
+        let detail = load_capture(root.path(), capture.id()).unwrap().unwrap();
+        let body = detail.attempts[0].response_body.clone().unwrap();
+        assert!(!body.contains("sk-upstream-secret-1234"));
+        assert!(body.contains("sk-u...1234"));
+        assert!(!detail.attempts[0].response_body_truncated);
+    }
+
+    #[test]
+    fn prunes_expired_then_oldest() {
+        let root = tempfile::tempdir().unwrap();
+        for name in ["a", "b", "c"] {
+            let dir = root.path().join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("inbound.json"), vec![0u8; 100]).unwrap();
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        // 总量 300 字节，上限 250：删除最旧的一个
+        let now = SystemTime::now();
+        assert_eq!(prune_captures(root.path(), now, MAX_AGE, 250).unwrap(), 1);
+        assert!(!root.path().join("a").exists());
+        assert!(root.path().join("c").exists());
+
+        // 全部超龄
+        let later = now + MAX_AGE + Duration::from_secs(1);
+        assert_eq!(
+            prune_captures(root.path(), later, MAX_AGE, u64::MAX).unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn prune_is_claimed_once_per_interval() {
+        let now = LAST_PRUNE.load(Ordering::Relaxed) + PRUNE_INTERVAL_SECS;
+        assert!(claim_prune(now));
+        assert!(!claim_prune(now + 1));
+        assert!(claim_prune(now + PRUNE_INTERVAL_SECS));
+    }
+
+    #[test]
+    fn rejects_invalid_capture_id() {
+        let root = tempfile::tempdir().unwrap();
+        assert!(load_capture(root.path(), "../etc").is_err());
+        assert!(load_capture(root.path(), &uuid::Uuid::new_v4().to_string())
+            .unwrap()
+            .is_none());
+    }
+}
@@ -4,7 +4,7 @@
 
 use super::log_codes::cb as log_cb;
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::RwLock;
@@ -77,6 +77,8 @@ pub struct CircuitBreaker {
     config: Arc<RwLock<CircuitBreakerConfig>>,
     /// 半开状态已放行的请求数（用于限流）
     half_open_requests: Arc<AtomicU32>,
+    /// 当前熔断是否由主动健康检查打开（实时流量触发的熔断为 false）
+    opened_by_probe: Arc<AtomicBool>,
 }
 
 /// 熔断器放行结果
@@ -101,6 +103,7 @@ impl CircuitBreaker {
             last_opened_at: Arc::new(RwLock::new(None)),
             config: Arc::new(RwLock::new(config)),
             half_open_requests: Arc::new(AtomicU32::new(0)),
+            opened_by_probe: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -299,6 +302,31 @@ impl CircuitBreaker {
         self.transition_to_closed().await;
     }
 
+    /// 主动健康检查判定供应商不可用：提前打开熔断器
+    ///
+    /// 已处于 Open 时不刷新打开时间，避免持续失败的探测无限推迟 HalfOpen 恢复。
+    pub async fn force_open(&self) {
+        if *self.state.read().await == CircuitState::Open {
+            return;
+        }
+        log::warn!("[{}] 健康检查失败 → Open", log_cb::PROBE_OPENED);
+        self.transition_to_open().await;
+        self.opened_by_probe.store(true, Ordering::SeqCst);
+    }
+
+    /// 主动健康检查判定供应商已恢复：关闭由健康检查打开的熔断器
+    ///
+    /// 实时流量触发的熔断不受影响，仍按超时 → HalfOpen → 真实请求成功的流程恢复。
+    pub async fn close_if_probe_opened(&self) {
+        if *self.state.read().await == CircuitState::Closed
+            || !self.opened_by_probe.load(Ordering::SeqCst)
+        {
+            return;
+        }
+        log::info!("[{}] 健康检查恢复 → Closed", log_cb::PROBE_CLOSED);
+        self.transition_to_closed().await;
+    }
+
     fn allow_half_open_probe(&self) -> AllowResult {
         // 半开状态限流：只允许有限请求通过进行探测
         let max_half_open_requests = 1u32;
@@ -348,6 +376,7 @@ impl CircuitBreaker {
         *self.last_opened_at.write().await = Some(Instant::now());
         self.consecutive_failures.store(0, Ordering::SeqCst);
         self.consecutive_successes.store(0, Ordering::SeqCst);
+        self.opened_by_probe.store(false, Ordering::SeqCst);
     }
 
     /// 转换到半开状态
@@ -368,6 +397,7 @@ impl CircuitBreaker {
         *self.state.write().await = CircuitState::Closed;
         self.consecutive_failures.store(0, Ordering::SeqCst);
         self.consecutive_successes.store(0, Ordering::SeqCst);
+        self.opened_by_probe.store(false, Ordering::SeqCst);
         // 重置计数器
         self.total_requests.store(0, Ordering::SeqCst);
         self.failed_requests.store(0, Ordering::SeqCst);
@@ -479,4 +509,38 @@ mod tests {
         assert_eq!(breaker.get_state().await, CircuitState::Closed);
         assert!(breaker.allow_request().await.allowed);
     }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_probe_force_open_and_close() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig::default());
+
+        breaker.force_open().await;
+        assert_eq!(breaker.get_state().await, CircuitState::Open);
+        assert!(!breaker.is_available().await);
+
+        // 重复打开不刷新打开时间
+        let opened_at = *breaker.last_opened_at.read().await;
+        breaker.force_open().await;
+        assert_eq!(*breaker.last_opened_at.read().await, opened_at);
+
+        breaker.close_if_probe_opened().await;
+        assert_eq!(breaker.get_state().await, CircuitState::Closed);
+        assert!(breaker.allow_request().await.allowed);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_probe_success_keeps_traffic_opened_breaker() {
+        let config = CircuitBreakerConfig::default();
+        let breaker = CircuitBreaker::new(config.clone());
+
+        for _ in 0..config.failure_threshold {
+            breaker.record_failure(false).await;
+        }
+        assert_eq!(breaker.get_state().await, CircuitState::Open);
+
+        // 实时流量打开的熔断不被健康检查关闭，再次探测失败也不会把它标记为探测打开
+        breaker.force_open().await;
+        breaker.close_if_probe_opened().await;
+        assert_eq!(breaker.get_state().await, CircuitState::Open);
+    }
 }
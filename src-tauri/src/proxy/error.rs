@@ -35,6 +35,10 @@ pub enum ProxyError {
     #[error("未配置供应商")]
     NoProvidersConfigured,
 
+    /// 所有候选供应商均已超出消费限额
+    #[error("供应商已超出消费限额: {0}")]
+    BudgetExceeded(String),
+
     #[allow(dead_code)]
     #[error("Provider不健康: {0}")]
     ProviderUnhealthy(String),
@@ -71,6 +75,14 @@ pub enum ProxyError {
     #[error("认证失败: {0}")]
     AuthError(String),
 
+    /// 入站访问令牌缺失或无效
+    #[error("未授权访问: {0}")]
+    Unauthorized(String),
+
+    /// 入站来源地址不在允许范围内
+    #[error("禁止访问: {0}")]
+    Forbidden(String),
+
     #[allow(dead_code)]
     #[error("内部错误: {0}")]
     Internal(String),
@@ -111,6 +123,19 @@ impl IntoResponse for ProxyError {
 
                 (http_status, error_body)
             }
+            ProxyError::BudgetExceeded(_) => {
+                // 同时兼容 Anthropic（type=error + error.type）与 OpenAI（error.message/code）客户端
+                let error_body = json!({
+                    "type": "error",
+                    "error": {
+                        "type": "rate_limit_error",
+                        "code": "budget_exceeded",
+                        "message": self.to_string(),
+                    }
+                });
+
+                (StatusCode::TOO_MANY_REQUESTS, error_body)
+            }
             _ => {
                 let (http_status, message) = match &self {
                     ProxyError::AlreadyRunning => (StatusCode::CONFLICT, self.to_string()),
@@ -153,10 +178,14 @@ impl IntoResponse for ProxyError {
                         (StatusCode::GATEWAY_TIMEOUT, self.to_string())
                     }
                     ProxyError::AuthError(_) => (StatusCode::UNAUTHORIZED, self.to_string()),
+                    ProxyError::Unauthorized(_) => (StatusCode::UNAUTHORIZED, self.to_string()),
+                    ProxyError::Forbidden(_) => (StatusCode::FORBIDDEN, self.to_string()),
                     ProxyError::Internal(_) => {
                         (StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
                     }
-                    ProxyError::UpstreamError { .. } => unreachable!(),
+                    ProxyError::UpstreamError { .. } | ProxyError::BudgetExceeded(_) => {
+                        unreachable!()
+                    }
                 };
 
                 let error_body = json!({
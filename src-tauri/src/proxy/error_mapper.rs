@@ -33,6 +33,13 @@ pub fn map_proxy_error_to_status(error: &ProxyError) -> u16 {
         // 未配置供应商：503 Service Unavailable
         ProxyError::NoProvidersConfigured => 503,
 
+        // 超出消费限额：429 Too Many Requests
+        ProxyError::BudgetExceeded(_) => 429,
+
+        // 入站鉴权失败：401 / 403
+        ProxyError::Unauthorized(_) => 401,
+        ProxyError::Forbidden(_) => 403,
+
         // 重试耗尽：503 Service Unavailable
         ProxyError::MaxRetriesExceeded => 503,
 
@@ -65,6 +72,7 @@ pub fn get_error_message(error: &ProxyError) -> String {
         ProxyError::NoAvailableProvider => "无可用 Provider".to_string(),
         ProxyError::AllProvidersCircuitOpen => "所有供应商已熔断，无可用渠道".to_string(),
         ProxyError::NoProvidersConfigured => "未配置供应商".to_string(),
+        ProxyError::BudgetExceeded(msg) => format!("超出消费限额: {msg}"),
         ProxyError::MaxRetriesExceeded => "所有 Provider 都失败，重试耗尽".to_string(),
         ProxyError::ProviderUnhealthy(msg) => format!("Provider 不健康: {msg}"),
         ProxyError::DatabaseError(msg) => format!("数据库错误: {msg}"),
@@ -104,6 +112,12 @@ mod tests {
         assert_eq!(map_proxy_error_to_status(&error), 503);
     }
 
+    #[test]
+    fn test_map_budget_exceeded_error() {
+        let error = ProxyError::BudgetExceeded("daily".to_string());
+        assert_eq!(map_proxy_error_to_status(&error), 429);
+    }
+
     #[test]
     fn test_get_error_message() {
         let error = ProxyError::UpstreamError {
@@ -3,10 +3,11 @@
 //! 处理故障转移成功后的供应商切换逻辑，包括：
 //! - 去重控制（避免多个请求同时触发）
 //! - 托盘菜单更新
-//! - 前端事件发射
+//! - 前端事件发射与通知
 
 use crate::database::Database;
 use crate::error::AppError;
+use crate::services::notification::{self, NotificationEvent, NotificationEventKind};
 use std::collections::HashSet;
 use std::sync::Arc;
 use tauri::{Emitter, Manager};
@@ -130,6 +131,14 @@ impl FailoverSwitchManager {
             }
         }
 
+        if switched {
+            notification::notify(
+                NotificationEvent::new(NotificationEventKind::FailoverSwitch, "")
+                    .app(app_type)
+                    .provider(provider_id, Some(provider_name)),
+            );
+        }
+
         Ok(switched)
     }
 }
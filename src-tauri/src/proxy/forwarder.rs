@@ -5,19 +5,23 @@
 use super::hyper_client::ProxyResponse;
 use super::{
     body_filter::filter_private_params_with_whitelist,
+    capture::RequestCapture,
     error::*,
     failover_switch::FailoverSwitchManager,
     log_codes::fwd as log_fwd,
+    model_rules::ModelRoutingRule,
+    otel::{self, RequestTrace, SpanKind, TraceSpan},
     provider_router::ProviderRouter,
     providers::{
         gemini_shadow::GeminiShadowStore, get_adapter, AuthInfo, AuthStrategy, ProviderAdapter,
         ProviderType,
     },
+    retry,
     thinking_budget_rectifier::{rectify_thinking_budget, should_rectify_thinking_budget},
     thinking_rectifier::{
         normalize_thinking_type, rectify_anthropic_request, should_rectify_thinking_signature,
     },
-    types::{CopilotOptimizerConfig, OptimizerConfig, ProxyStatus, RectifierConfig},
+    types::{CopilotOptimizerConfig, OptimizerConfig, ProxyStatus, RectifierConfig, RetryPolicy},
     ProxyError,
 };
 use crate::commands::{CodexOAuthState, CopilotAuthState};
@@ -65,6 +69,14 @@ pub struct RequestForwarder {
     copilot_optimizer_config: CopilotOptimizerConfig,
     /// 非流式请求超时（秒）
     non_streaming_timeout: std::time::Duration,
+    /// 重试策略
+    retry_policy: RetryPolicy,
+    /// 命中的模型路由规则（目标模型仅作用于规则指定的供应商）
+    model_rule: Option<ModelRoutingRule>,
+    /// 请求链路（启用 OTLP 导出时存在）
+    trace: Option<RequestTrace>,
+    /// 请求抓包（命中抓包规则时存在）
+    capture: Option<RequestCapture>,
 }
 
 impl RequestForwarder {
@@ -85,6 +97,10 @@ impl RequestForwarder {
         rectifier_config: RectifierConfig,
         optimizer_config: OptimizerConfig,
         copilot_optimizer_config: CopilotOptimizerConfig,
+        retry_policy: RetryPolicy,
+        model_rule: Option<ModelRoutingRule>,
+        trace: Option<RequestTrace>,
+        capture: Option<RequestCapture>,
     ) -> Self {
         Self {
             router,
@@ -100,9 +116,63 @@ impl RequestForwarder {
             optimizer_config,
             copilot_optimizer_config,
             non_streaming_timeout: std::time::Duration::from_secs(non_streaming_timeout),
+            retry_policy,
+            model_rule,
+            trace,
+            capture,
         }
     }
 
+    /// 在请求链路上开始子 span
+    fn trace_span(&self, name: &str, kind: SpanKind) -> Option<TraceSpan> {
+        otel::child_span(self.trace.as_ref(), name, kind)
+    }
+
+    /// 记录会话亲和：会话由该供应商成功响应（仅客户端提供的会话 ID）
+    ///
+    /// 由模型路由规则指定的目标供应商不记录亲和，避免后续不匹配规则的请求被粘到该供应商。
+    fn pin_session(&self, app_type: &str, provider_id: &str) {
+        let rule_targeted = self
+            .model_rule
+            .as_ref()
+            .is_some_and(|rule| rule.targets_provider(provider_id));
+        if self.session_client_provided && !rule_targeted {
+            self.router
+                .pin_session(app_type, &self.session_id, provider_id);
+        }
+    }
+
+    /// 重复尝试同一供应商前等待
+    ///
+    /// 等待时长取指数退避与限流剩余冷却时间中的较大者；
+    /// 冷却时间超过策略的最大延迟时放弃本次尝试（返回 false），不阻塞请求。
+    async fn wait_before_retry(&self, provider: &Provider, app_type: &str, attempt: u32) -> bool {
+        let backoff = retry::backoff_delay(&self.retry_policy, attempt, retry::random_jitter());
+        let cooldown = self
+            .router
+            .cooldown_remaining(&provider.id, app_type)
+            .await
+            .unwrap_or_default();
+        if cooldown > std::time::Duration::from_millis(self.retry_policy.max_delay_ms) {
+            log::debug!(
+                "[{app_type}] Provider {} 限流冷却剩余 {}ms，跳过第 {attempt} 次尝试",
+                provider.name,
+                cooldown.as_millis()
+            );
+            return false;
+        }
+
+        let delay = backoff.max(cooldown);
+        log::info!(
+            "[{app_type}] [{}] {}ms 后第 {attempt} 次尝试 Provider {}",
+            log_fwd::RETRY_SAME_PROVIDER,
+            delay.as_millis(),
+            provider.name
+        );
+        tokio::time::sleep(delay).await;
+        true
+    }
+
     /// 转发请求（带故障转移）
     ///
     /// # Arguments
@@ -142,8 +212,25 @@ impl RequestForwarder {
         // 单 Provider 场景下跳过熔断器检查（故障转移关闭时）
         let bypass_circuit_breaker = providers.len() == 1;
 
-        // 依次尝试每个供应商
-        for provider in providers.iter() {
+        // 按重试策略生成尝试计划（默认每个供应商一次，依次故障转移）
+        let plan = retry::attempt_plan(&self.retry_policy, providers.len());
+        // 每个供应商最近一次失败是否允许再次尝试
+        let mut retry_eligible = vec![true; providers.len()];
+        let mut tried = vec![false; providers.len()];
+
+        for (index, attempt) in plan {
+            let provider = &providers[index];
+
+            // 重复尝试：仅限可重试错误，并在退避/限流冷却后进行
+            if attempt > 1
+                && (!retry_eligible[index]
+                    || !self
+                        .wait_before_retry(provider, app_type_str, attempt)
+                        .await)
+            {
+                continue;
+            }
+
             // 发起请求前先获取熔断器放行许可（HalfOpen 会占用探测名额）
             // 单 Provider 场景下跳过此检查，避免熔断器阻塞所有请求
             let (allowed, used_half_open_permit) = if bypass_circuit_breaker {
@@ -176,7 +263,10 @@ impl RequestForwarder {
                     body.clone()
                 };
 
-            attempted_providers += 1;
+            if !tried[index] {
+                tried[index] = true;
+                attempted_providers += 1;
+            }
 
             // 更新状态中的当前Provider信息
             {
@@ -187,10 +277,11 @@ impl RequestForwarder {
                 status.last_request_at = Some(chrono::Utc::now().to_rfc3339());
             }
 
-            // 转发请求（每个 Provider 只尝试一次，重试由客户端控制）
+            // 转发请求（同一 Provider 的重复尝试由重试策略控制）
             match self
                 .forward(
                     provider,
+                    app_type_str,
                     endpoint,
                     &provider_body,
                     &headers,
@@ -211,6 +302,7 @@ impl RequestForwarder {
                             None,
                         )
                         .await;
+                    self.pin_session(app_type_str, &provider.id);
 
                     // 更新当前应用类型使用的 provider
                     {
@@ -297,7 +389,19 @@ impl RequestForwarder {
                             }
 
                             // 首次触发：整流请求体
+                            let mut rectifier_span =
+                                self.trace_span("rectifier.thinking_signature", SpanKind::Internal);
                             let rectified = rectify_anthropic_request(&mut provider_body);
+                            if let Some(span) = rectifier_span.as_mut() {
+                                span.set_attribute(
+                                    "cc_switch.rectifier.applied",
+                                    rectified.applied,
+                                );
+                                span.set_attribute(
+                                    "cc_switch.rectifier.removed_thinking_blocks",
+                                    rectified.removed_thinking_blocks as u64,
+                                );
+                            }
 
                             // 整流未生效：继续尝试 budget 整流路径，避免误判后短路
                             if !rectified.applied {
@@ -321,6 +425,7 @@ impl RequestForwarder {
                                 match self
                                     .forward(
                                         provider,
+                                        app_type_str,
                                         endpoint,
                                         &provider_body,
                                         &headers,
@@ -342,6 +447,7 @@ impl RequestForwarder {
                                                 None,
                                             )
                                             .await;
+                                        self.pin_session(app_type_str, &provider.id);
 
                                         // 更新当前应用类型使用的 provider
                                         {
@@ -481,7 +587,15 @@ impl RequestForwarder {
                                 });
                             }
 
+                            let mut rectifier_span =
+                                self.trace_span("rectifier.thinking_budget", SpanKind::Internal);
                             let budget_rectified = rectify_thinking_budget(&mut provider_body);
+                            if let Some(span) = rectifier_span.as_mut() {
+                                span.set_attribute(
+                                    "cc_switch.rectifier.applied",
+                                    budget_rectified.applied,
+                                );
+                            }
                             if !budget_rectified.applied {
                                 log::warn!(
                                     "[{app_type_str}] [RECT-014] budget 整流器触发但无可整流内容，不做无意义重试"
@@ -520,6 +634,7 @@ impl RequestForwarder {
                             match self
                                 .forward(
                                     provider,
+                                    app_type_str,
                                     endpoint,
                                     &provider_body,
                                     &headers,
@@ -540,6 +655,7 @@ impl RequestForwarder {
                                             None,
                                         )
                                         .await;
+                                    self.pin_session(app_type_str, &provider.id);
 
                                     {
                                         let mut current_providers =
@@ -688,6 +804,8 @@ impl RequestForwarder {
                             );
                             log::warn!("[{app_type_str}] [{log_code}] {log_message}");
 
+                            retry_eligible[index] =
+                                retry::is_retryable_error(&self.retry_policy, &e);
                             last_error = Some(e);
                             last_provider = Some(provider.clone());
                             // 继续尝试下一个供应商
@@ -756,9 +874,50 @@ impl RequestForwarder {
     }
 
     /// 转发单个请求（使用适配器）
+    #[allow(clippy::too_many_arguments)]
     async fn forward(
         &self,
         provider: &Provider,
+        app_type: &str,
+        endpoint: &str,
+        body: &Value,
+        headers: &axum::http::HeaderMap,
+        extensions: &Extensions,
+        adapter: &dyn ProviderAdapter,
+    ) -> Result<(ProxyResponse, Option<String>), ProxyError> {
+        let mut span = self.trace_span("provider.attempt", SpanKind::Client);
+        if let Some(span) = span.as_mut() {
+            span.set_attribute("cc_switch.provider.id", &provider.id);
+            span.set_attribute("cc_switch.provider.name", &provider.name);
+            span.set_attribute("url.path", endpoint);
+        }
+
+        let result = self
+            .forward_upstream(
+                provider, app_type, endpoint, body, headers, extensions, adapter,
+            )
+            .await;
+
+        if let Some(span) = span.as_mut() {
+            match &result {
+                Ok((response, api_format)) => {
+                    span.set_attribute("http.response.status_code", response.status().as_u16());
+                    if let Some(api_format) = api_format {
+                        span.set_attribute("cc_switch.api_format", api_format);
+                    }
+                }
+                Err(e) => span.set_error(e.to_string()),
+            }
+        }
+        result
+    }
+
+    /// 向单个供应商发送请求（不含故障转移）
+    #[allow(clippy::too_many_arguments)]
+    async fn forward_upstream(
+        &self,
+        provider: &Provider,
+        app_type: &str,
         endpoint: &str,
         body: &Value,
         headers: &axum::http::HeaderMap,
@@ -774,9 +933,26 @@ impl RequestForwarder {
             .and_then(|meta| meta.is_full_url)
             .unwrap_or(false);
 
+        // Gemini 原生请求的模型在 URI 中：规则目标模型改写端点，不写入请求体
+        let is_gemini = app_type == "gemini";
+        let gemini_endpoint = if is_gemini {
+            super::model_mapper::apply_gemini_model_rule(
+                endpoint,
+                provider,
+                self.model_rule.as_ref(),
+            )
+        } else {
+            None
+        };
+        let endpoint = gemini_endpoint.as_deref().unwrap_or(endpoint);
+
         // 应用模型映射（独立于格式转换）
         let (mapped_body, _original_model, _mapped_model) =
-            super::model_mapper::apply_model_mapping(body.clone(), provider);
+            super::model_mapper::apply_model_mapping(
+                body.clone(),
+                provider,
+                self.model_rule.as_ref().filter(|_| !is_gemini),
+            );
 
         // 与 CCH 对齐：请求前不做 thinking 主动改写（仅保留兼容入口）
         let mut mapped_body = normalize_thinking_type(mapped_body);
@@ -931,6 +1107,11 @@ impl RequestForwarder {
         };
         let needs_transform = match resolved_claude_api_format.as_deref() {
             Some(api_format) => super::providers::claude_api_format_needs_transform(api_format),
+            // Codex 仅 /responses 请求需要转换，/chat/completions 本身即为 Chat 格式
+            None if adapter.name() == "Codex" => {
+                adapter.needs_transform(provider)
+                    && split_endpoint_and_query(endpoint).0.ends_with("/responses")
+            }
             None => adapter.needs_transform(provider),
         };
         let (effective_endpoint, passthrough_query) =
@@ -939,6 +1120,13 @@ impl RequestForwarder {
                     .as_deref()
                     .unwrap_or_else(|| super::providers::get_claude_api_format(provider));
                 rewrite_claude_transform_endpoint(endpoint, api_format, is_copilot, &mapped_body)
+            } else if needs_transform && adapter.name() == "Codex" {
+                let query = split_endpoint_and_query(endpoint).1;
+                let endpoint = match query {
+                    Some(query) => format!("/chat/completions?{query}"),
+                    None => "/chat/completions".to_string(),
+                };
+                (endpoint, query.map(ToString::to_string))
             } else {
                 (
                     endpoint.to_string(),
@@ -962,10 +1150,14 @@ impl RequestForwarder {
 
         // 转换请求体（如果需要）
         let request_body = if needs_transform {
-            if adapter.name() == "Claude" {
+            let mut transform_span = self.trace_span("transform.request", SpanKind::Internal);
+            let transformed = if adapter.name() == "Claude" {
                 let api_format = resolved_claude_api_format
                     .as_deref()
                     .unwrap_or_else(|| super::providers::get_claude_api_format(provider));
+                if let Some(span) = transform_span.as_mut() {
+                    span.set_attribute("cc_switch.api_format", api_format);
+                }
                 super::providers::transform_claude_request_for_api_format(
                     mapped_body,
                     provider,
@@ -973,10 +1165,17 @@ impl RequestForwarder {
                     self.session_client_provided
                         .then_some(self.session_id.as_str()),
                     Some(self.gemini_shadow.as_ref()),
-                )?
+                )
             } else {
-                adapter.transform_request(mapped_body, provider)?
+                adapter.transform_request(mapped_body, provider)
+            };
+            if let Some(span) = transform_span.as_mut() {
+                span.set_attribute("cc_switch.adapter", adapter.name());
+                if let Err(e) = &transformed {
+                    span.set_error(e.to_string());
+                }
             }
+            transformed?
         } else {
             mapped_body
         };
@@ -1097,6 +1296,9 @@ impl RequestForwarder {
                 }
             }
 
+            if let Some(capture) = &self.capture {
+                capture.add_auth(&auth);
+            }
             adapter.get_auth_headers(&auth)
         } else {
             Vec::new()
@@ -1409,6 +1611,12 @@ impl RequestForwarder {
             .parse()
             .map_err(|e| ProxyError::ForwardFailed(format!("Invalid URL '{url}': {e}")))?;
 
+        // 抓包：记录实际发往上游的请求（已遮蔽凭据）
+        let capture_attempt = self.capture.as_ref().map(|capture| {
+            let attempt = capture.record_upstream_request(&url, &ordered_headers, &filtered_body);
+            (capture, attempt)
+        });
+
         // 发送请求
         let response = if is_socks_proxy {
             // SOCKS5 代理：只能走 reqwest（不支持 header case 保留）
@@ -1445,6 +1653,10 @@ impl RequestForwarder {
             )
             .await?
         };
+        let response = match capture_attempt {
+            Some((capture, attempt)) => capture.tap_response(attempt, response),
+            None => response,
+        };
 
         // 检查响应状态
         let status = response.status();
@@ -1453,6 +1665,19 @@ impl RequestForwarder {
             Ok((response, resolved_claude_api_format))
         } else {
             let status_code = status.as_u16();
+            if let Some(cooldown) =
+                retry::rate_limit_cooldown(status_code, response.headers(), chrono::Utc::now())
+            {
+                log::warn!(
+                    "[{app_type}] [{}] Provider {} 被限流 (HTTP {status_code})，冷却 {}s",
+                    log_fwd::RATE_LIMITED,
+                    provider.name,
+                    cooldown.as_secs_f64().ceil()
+                );
+                self.router
+                    .cool_down(&provider.id, app_type, cooldown)
+                    .await;
+            }
             let body_text = String::from_utf8(response.bytes().await?.to_vec()).ok();
 
             Err(ProxyError::UpstreamError {
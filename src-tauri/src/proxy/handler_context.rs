@@ -5,13 +5,19 @@
 use crate::app_config::AppType;
 use crate::provider::Provider;
 use crate::proxy::{
+    capture::RequestCapture,
     extract_session_id,
     forwarder::RequestForwarder,
+    model_rules::{self, ModelRoutingRule},
+    otel::RequestTrace,
+    replay::ReplayTarget,
     server::ProxyState,
-    types::{AppProxyConfig, CopilotOptimizerConfig, OptimizerConfig, RectifierConfig},
+    types::{
+        AppProxyConfig, CopilotOptimizerConfig, OptimizerConfig, RectifierConfig, RetryPolicy,
+    },
     ProxyError,
 };
-use axum::http::HeaderMap;
+use axum::http::{Extensions, HeaderMap, Uri};
 use std::time::Instant;
 
 /// 流式超时配置
@@ -65,6 +71,14 @@ pub struct RequestContext {
     pub optimizer_config: OptimizerConfig,
     /// Copilot 优化器配置
     pub copilot_optimizer_config: CopilotOptimizerConfig,
+    /// 重试策略
+    pub retry_policy: RetryPolicy,
+    /// 命中的模型路由规则
+    pub model_rule: Option<ModelRoutingRule>,
+    /// 请求链路（启用 OTLP 导出时存在）
+    pub trace: Option<RequestTrace>,
+    /// 请求抓包（命中抓包规则时存在）
+    pub capture: Option<RequestCapture>,
 }
 
 impl RequestContext {
@@ -74,21 +88,35 @@ impl RequestContext {
     /// * `state` - 代理服务器状态
     /// * `body` - 请求体 JSON
     /// * `headers` - 请求头（用于提取 Session ID）
+    /// * `uri` - 请求 URI（判断入站格式；Gemini 从中提取模型名）
+    /// * `extensions` - 请求扩展（携带 `ReplayTarget` 时跳过路由，直接使用指定供应商）
     /// * `app_type` - 应用类型
     /// * `tag` - 日志标签
     /// * `app_type_str` - 应用类型字符串
     ///
     /// # Errors
     /// 返回 `ProxyError` 如果 Provider 选择失败
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         state: &ProxyState,
         body: &serde_json::Value,
         headers: &HeaderMap,
+        uri: &Uri,
+        extensions: &Extensions,
         app_type: AppType,
         tag: &'static str,
         app_type_str: &'static str,
     ) -> Result<Self, ProxyError> {
+        let replay_target = extensions.get::<ReplayTarget>();
         let start_time = Instant::now();
+        let trace = state.otel.read().await.clone().map(|exporter| {
+            RequestTrace::start(exporter, format!("{app_type_str} {}", uri.path()))
+        });
+        if let Some(trace) = &trace {
+            log::debug!("[{tag}] OTel trace_id: {}", trace.trace_id());
+            trace.set_attribute("cc_switch.app", app_type_str);
+            trace.set_attribute("url.path", uri.path());
+        }
 
         // 从数据库读取应用级代理配置（per-app）
         let app_config = state
@@ -101,16 +129,42 @@ impl RequestContext {
         let rectifier_config = state.db.get_rectifier_config().unwrap_or_default();
         let optimizer_config = state.db.get_optimizer_config().unwrap_or_default();
         let copilot_optimizer_config = state.db.get_copilot_optimizer_config().unwrap_or_default();
+        let retry_policy = state
+            .db
+            .get_retry_policy(app_type_str)
+            .await
+            .unwrap_or_default();
 
-        let current_provider_id =
-            crate::settings::get_current_provider(&app_type).unwrap_or_default();
+        // 回放请求以目标供应商作为"当前供应商"，避免成功后触发故障转移切换
+        let current_provider_id = match replay_target {
+            Some(target) => target.provider_id.clone(),
+            None => crate::settings::get_current_provider(&app_type).unwrap_or_default(),
+        };
 
-        // 从请求体提取模型名称
-        let request_model = body
-            .get("model")
-            .and_then(|m| m.as_str())
-            .unwrap_or("unknown")
-            .to_string();
+        // 从请求体提取模型名称（Gemini 的模型名称在 URI 中）
+        let request_model = match app_type {
+            AppType::Gemini => model_from_uri(uri),
+            _ => body.get("model").and_then(|m| m.as_str()).map(String::from),
+        }
+        .unwrap_or_else(|| "unknown".to_string());
+
+        // 匹配模型路由规则（第一条命中的规则生效）
+        let client_format = model_rules::client_format_for(&app_type, uri.path());
+        let model_rule = model_rules::find_rule(
+            &state.model_rules.read().await,
+            app_type_str,
+            client_format,
+            &request_model,
+        )
+        .cloned();
+        if let Some(rule) = &model_rule {
+            log::debug!(
+                "[{tag}] 模型 {request_model} ({client_format}) 命中路由规则 {} → provider={:?}, model={:?}",
+                rule.id,
+                rule.target_provider_id,
+                rule.target_model
+            );
+        }
 
         // 提取 Session ID
         let session_result = extract_session_id(headers, body, app_type_str);
@@ -126,23 +180,78 @@ impl RequestContext {
 
         // 使用共享的 ProviderRouter 选择 Provider（熔断器状态跨请求保持）
         // 注意：只在这里调用一次，结果传递给 forwarder，避免重复消耗 HalfOpen 名额
-        let providers = state
-            .provider_router
-            .select_providers(app_type_str)
-            .await
-            .map_err(|e| match e {
-                crate::error::AppError::AllProvidersCircuitOpen => {
-                    ProxyError::AllProvidersCircuitOpen
-                }
-                crate::error::AppError::NoProvidersConfigured => ProxyError::NoProvidersConfigured,
-                _ => ProxyError::DatabaseError(e.to_string()),
-            })?;
+        let providers = match replay_target {
+            Some(target) => {
+                let provider = state
+                    .db
+                    .get_provider_by_id(&target.provider_id, app_type_str)
+                    .map_err(|e| ProxyError::DatabaseError(e.to_string()))?
+                    .ok_or(ProxyError::NoAvailableProvider)?;
+                state
+                    .provider_router
+                    .ensure_within_budget(app_type_str, &provider)
+                    .await
+                    .map_err(|e| match e {
+                        crate::error::AppError::ProviderBudgetExceeded(msg) => {
+                            ProxyError::BudgetExceeded(msg)
+                        }
+                        _ => ProxyError::DatabaseError(e.to_string()),
+                    })?;
+                vec![provider]
+            }
+            None => state
+                .provider_router
+                .select_providers(
+                    app_type_str,
+                    session_result
+                        .client_provided
+                        .then_some(session_id.as_str()),
+                    model_rule
+                        .as_ref()
+                        .and_then(|rule| rule.target_provider_id.as_deref()),
+                )
+                .await
+                .map_err(|e| match e {
+                    crate::error::AppError::AllProvidersCircuitOpen => {
+                        ProxyError::AllProvidersCircuitOpen
+                    }
+                    crate::error::AppError::NoProvidersConfigured => {
+                        ProxyError::NoProvidersConfigured
+                    }
+                    crate::error::AppError::ProviderBudgetExceeded(msg) => {
+                        ProxyError::BudgetExceeded(msg)
+                    }
+                    _ => ProxyError::DatabaseError(e.to_string()),
+                })
+                .inspect_err(|e| {
+                    if let Some(trace) = &trace {
+                        trace.set_error(e.to_string());
+                    }
+                })?,
+        };
 
         let provider = providers
             .first()
             .cloned()
             .ok_or(ProxyError::NoAvailableProvider)?;
 
+        if let Some(trace) = &trace {
+            trace.set_attribute("gen_ai.request.model", &request_model);
+            trace.set_attribute("session.id", &session_id);
+            trace.set_attribute("cc_switch.provider.id", &provider.id);
+            trace.set_attribute("cc_switch.provider.name", &provider.name);
+            trace.set_attribute("cc_switch.failover_chain_length", providers.len() as u64);
+        }
+
+        let capture = RequestCapture::for_request(
+            &state.capture_rules.read().await,
+            app_type_str,
+            &providers.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(),
+            uri,
+            headers,
+            body,
+        );
+
         log::debug!(
             "[{}] Provider: {}, model: {}, failover chain: {} providers, session: {}",
             tag,
@@ -167,30 +276,13 @@ impl RequestContext {
             rectifier_config,
             optimizer_config,
             copilot_optimizer_config,
+            retry_policy,
+            model_rule,
+            trace,
+            capture,
         })
     }
 
-    /// 从 URI 提取模型名称（Gemini 专用）
-    ///
-    /// Gemini API 的模型名称在 URI 中，格式如：
-    /// `/v1beta/models/gemini-pro:generateContent`
-    pub fn with_model_from_uri(mut self, uri: &axum::http::Uri) -> Self {
-        let endpoint = uri
-            .path_and_query()
-            .map(|pq| pq.as_str())
-            .unwrap_or(uri.path());
-
-        self.request_model = endpoint
-            .split('/')
-            .find(|s| s.starts_with("models/"))
-            .and_then(|s| s.strip_prefix("models/"))
-            .map(|s| s.split(':').next().unwrap_or(s))
-            .unwrap_or("unknown")
-            .to_string();
-
-        self
-    }
-
     /// 创建 RequestForwarder
     ///
     /// 使用共享的 ProviderRouter，确保熔断器状态跨请求保持
@@ -232,6 +324,10 @@ impl RequestContext {
             self.rectifier_config.clone(),
             self.optimizer_config.clone(),
             self.copilot_optimizer_config.clone(),
+            self.retry_policy.clone(),
+            self.model_rule.clone(),
+            self.trace.clone(),
+            self.capture.clone(),
         )
     }
 
@@ -270,3 +366,15 @@ impl RequestContext {
         }
     }
 }
+
+/// 从 URI 提取模型名称（Gemini 专用）
+///
+/// Gemini API 的模型名称在 URI 中，格式如：
+/// `/v1beta/models/gemini-pro:generateContent`
+fn model_from_uri(uri: &Uri) -> Option<String> {
+    let (_, rest) = uri.path().split_once("models/")?;
+    rest.split([':', '/'])
+        .next()
+        .filter(|model| !model.is_empty())
+        .map(String::from)
+}
@@ -8,24 +8,31 @@
 //! - Claude 的格式转换逻辑保留在此文件（用于 OpenRouter 旧接口回退）
 
 use super::{
+    capture::RequestCapture,
     error_mapper::{get_error_message, map_proxy_error_to_status},
     handler_config::{
         CLAUDE_PARSER_CONFIG, CODEX_PARSER_CONFIG, GEMINI_PARSER_CONFIG, OPENAI_PARSER_CONFIG,
     },
     handler_context::RequestContext,
+    model_list,
+    otel::{self, RequestTrace, SpanKind},
     providers::{
-        get_adapter, get_claude_api_format, streaming::create_anthropic_sse_stream,
+        get_adapter, get_claude_api_format, get_codex_api_format,
+        streaming::create_anthropic_sse_stream,
+        streaming_chat::create_chat_sse_stream_from_anthropic,
         streaming_gemini::create_anthropic_sse_stream_from_gemini,
-        streaming_responses::create_anthropic_sse_stream_from_responses, transform,
-        transform_gemini, transform_responses,
+        streaming_responses::create_anthropic_sse_stream_from_responses,
+        streaming_responses_chat::create_responses_sse_stream_from_chat, transform, transform_chat,
+        transform_gemini, transform_responses, transform_responses_chat,
     },
     response_processor::{
-        create_logged_passthrough_stream, process_response, read_decoded_body,
-        strip_entity_headers_for_rebuilt_body, strip_hop_by_hop_response_headers,
-        SseUsageCollector,
+        create_logged_passthrough_stream, create_usage_collector, process_response,
+        read_decoded_body, strip_entity_headers_for_rebuilt_body,
+        strip_hop_by_hop_response_headers, SseUsageCollector,
     },
     server::ProxyState,
     sse::{strip_sse_field, take_sse_block},
+    token_count,
     types::*,
     usage::parser::TokenUsage,
     ProxyError,
@@ -57,6 +64,82 @@ pub async fn get_status(State(state): State<ProxyState>) -> Result<Json<ProxySta
     Ok(Json(status))
 }
 
+// ============================================================================
+// 模型列表和 Token 计数（辅助端点，不经过故障转移和使用量记录）
+// ============================================================================
+
+/// 处理 `/v1/models` 请求
+///
+/// 根据路径前缀或请求头判断客户端：
+/// - `/claude/...` 或携带 `anthropic-version` / `x-api-key` → Claude（Anthropic 格式）
+/// - 其余 → Codex（OpenAI 格式）
+pub async fn handle_models(
+    State(state): State<ProxyState>,
+    uri: axum::http::Uri,
+    headers: axum::http::HeaderMap,
+) -> Result<Json<Value>, ProxyError> {
+    let is_claude = uri.path().starts_with("/claude/")
+        || (!uri.path().starts_with("/codex/")
+            && (headers.contains_key("anthropic-version") || headers.contains_key("x-api-key")));
+
+    if is_claude {
+        let provider = current_provider_for(&state, "claude")?;
+        let models = model_list::list_models(&state, &AppType::Claude, &provider).await;
+        Ok(Json(model_list::to_anthropic_list(&models)))
+    } else {
+        let provider = current_provider_for(&state, "codex")?;
+        let models = model_list::list_models(&state, &AppType::Codex, &provider).await;
+        Ok(Json(model_list::to_openai_list(&models)))
+    }
+}
+
+/// 处理 Gemini `/v1beta/models` 请求
+pub async fn handle_gemini_models(
+    State(state): State<ProxyState>,
+) -> Result<Json<Value>, ProxyError> {
+    let provider = current_provider_for(&state, "gemini")?;
+    let models = model_list::list_models(&state, &AppType::Gemini, &provider).await;
+    Ok(Json(model_list::to_gemini_list(&models)))
+}
+
+/// 处理 `/v1/messages/count_tokens` 请求（Claude）
+///
+/// Anthropic 原生格式的供应商直接转发；上游无等价端点或调用失败时本地估算。
+pub async fn handle_count_tokens(
+    State(state): State<ProxyState>,
+    headers: axum::http::HeaderMap,
+    body: Bytes,
+) -> Result<Json<Value>, ProxyError> {
+    let body: Value = serde_json::from_slice(&body)
+        .map_err(|e| ProxyError::Internal(format!("Failed to parse request body: {e}")))?;
+    let provider = current_provider_for(&state, "claude")?;
+
+    if token_count::supports_upstream_count(&provider) {
+        match token_count::count_upstream(&provider, &headers, body.clone()).await {
+            Ok(result) => return Ok(Json(result)),
+            Err(e) => log::debug!(
+                "[Claude] {} count_tokens 转发失败，改为本地估算: {e}",
+                provider.name
+            ),
+        }
+    }
+
+    Ok(Json(json!({
+        "input_tokens": token_count::estimate_input_tokens(&body),
+    })))
+}
+
+fn current_provider_for(
+    state: &ProxyState,
+    app_type: &str,
+) -> Result<crate::provider::Provider, ProxyError> {
+    state
+        .provider_router
+        .current_provider(app_type)
+        .map_err(|e| ProxyError::DatabaseError(e.to_string()))?
+        .ok_or(ProxyError::NoProvidersConfigured)
+}
+
 // ============================================================================
 // Claude API 处理器（包含格式转换逻辑）
 // ============================================================================
@@ -82,8 +165,17 @@ pub async fn handle_messages(
     let body: Value = serde_json::from_slice(&body_bytes)
         .map_err(|e| ProxyError::Internal(format!("Failed to parse request body: {e}")))?;
 
-    let mut ctx =
-        RequestContext::new(&state, &body, &headers, AppType::Claude, "Claude", "claude").await?;
+    let mut ctx = RequestContext::new(
+        &state,
+        &body,
+        &headers,
+        &uri,
+        &extensions,
+        AppType::Claude,
+        "Claude",
+        "claude",
+    )
+    .await?;
 
     let endpoint = uri
         .path_and_query()
@@ -179,59 +271,16 @@ async fn handle_claude_transform(
     let tool_schema_hints = (!tool_schema_hints.is_empty()).then_some(tool_schema_hints);
 
     if use_streaming {
-        // 根据 api_format 选择流式转换器
-        let stream = response.bytes_stream();
-        let sse_stream: Box<
-            dyn futures::Stream<Item = Result<Bytes, std::io::Error>> + Send + Unpin,
-        > = if api_format == "openai_responses" {
-            Box::new(Box::pin(create_anthropic_sse_stream_from_responses(stream)))
-        } else if api_format == "gemini_native" {
-            Box::new(Box::pin(create_anthropic_sse_stream_from_gemini(
-                stream,
-                Some(state.gemini_shadow.clone()),
-                Some(ctx.provider.id.clone()),
-                Some(ctx.session_id.clone()),
-                tool_schema_hints.clone(),
-            )))
-        } else {
-            Box::new(Box::pin(create_anthropic_sse_stream(stream)))
-        };
+        let sse_stream = anthropic_sse_from_upstream(
+            response,
+            api_format,
+            ctx,
+            state,
+            tool_schema_hints.clone(),
+        );
 
         // 创建使用量收集器
-        let usage_collector = {
-            let state = state.clone();
-            let provider_id = ctx.provider.id.clone();
-            let model = ctx.request_model.clone();
-            let status_code = status.as_u16();
-            let start_time = ctx.start_time;
-
-            SseUsageCollector::new(start_time, move |events, first_token_ms| {
-                if let Some(usage) = TokenUsage::from_claude_stream_events(&events) {
-                    let latency_ms = start_time.elapsed().as_millis() as u64;
-                    let state = state.clone();
-                    let provider_id = provider_id.clone();
-                    let model = model.clone();
-
-                    tokio::spawn(async move {
-                        log_usage(
-                            &state,
-                            &provider_id,
-                            "claude",
-                            &model,
-                            &model,
-                            usage,
-                            latency_ms,
-                            first_token_ms,
-                            true,
-                            status_code,
-                        )
-                        .await;
-                    });
-                } else {
-                    log::debug!("[Claude] OpenRouter 流式响应缺少 usage 统计，跳过消费记录");
-                }
-            })
-        };
+        let usage_collector = claude_stream_usage_collector(ctx, state, status.as_u16());
 
         // 获取流式超时配置
         let timeout_config = ctx.streaming_timeout_config();
@@ -278,24 +327,13 @@ async fn handle_claude_transform(
         })?
     };
 
-    // 根据 api_format 选择非流式转换器
-    let anthropic_response = if api_format == "openai_responses" {
-        transform_responses::responses_to_anthropic(upstream_response)
-    } else if api_format == "gemini_native" {
-        transform_gemini::gemini_to_anthropic_with_shadow_and_hints(
-            upstream_response,
-            Some(state.gemini_shadow.as_ref()),
-            Some(&ctx.provider.id),
-            Some(&ctx.session_id),
-            tool_schema_hints.as_ref(),
-        )
-    } else {
-        transform::openai_to_anthropic(upstream_response)
-    }
-    .map_err(|e| {
-        log::error!("[Claude] 转换响应失败: {e}");
-        e
-    })?;
+    let anthropic_response = anthropic_response_from_upstream(
+        upstream_response,
+        api_format,
+        ctx,
+        state,
+        tool_schema_hints.as_ref(),
+    )?;
 
     // 记录使用量
     if let Some(usage) = TokenUsage::from_claude_response(&anthropic_response) {
@@ -310,6 +348,8 @@ async fn handle_claude_transform(
             let state = state.clone();
             let provider_id = ctx.provider.id.clone();
             let model = model.to_string();
+            let trace = ctx.trace.clone();
+            let capture = ctx.capture.clone();
             async move {
                 log_usage(
                     &state,
@@ -322,6 +362,8 @@ async fn handle_claude_transform(
                     None,
                     false,
                     status.as_u16(),
+                    trace.as_ref(),
+                    capture.as_ref(),
                 )
                 .await;
             }
@@ -351,6 +393,334 @@ async fn handle_claude_transform(
     })
 }
 
+/// 处理 /claude/v1/chat/completions 请求（OpenAI Chat Completions 客户端 → Claude 供应商）
+///
+/// 供编辑器插件、脚本等只支持 OpenAI Chat 格式的客户端使用 Claude 应用的供应商：
+/// - 请求转换为 Anthropic Messages 后按 Claude 的故障转移链转发到 `/v1/messages`
+/// - 非 Anthropic 格式的供应商先复用 Claude 的转换逻辑得到 Anthropic 响应
+/// - 响应（含 SSE）再转换回 OpenAI Chat 格式，使用量按 Anthropic 事件记录
+pub async fn handle_claude_chat_completions(
+    State(state): State<ProxyState>,
+    request: axum::extract::Request,
+) -> Result<axum::response::Response, ProxyError> {
+    let (parts, req_body) = request.into_parts();
+    let uri = parts.uri;
+    let headers = parts.headers;
+    let extensions = parts.extensions;
+    let body_bytes = req_body
+        .collect()
+        .await
+        .map_err(|e| ProxyError::Internal(format!("Failed to read request body: {e}")))?
+        .to_bytes();
+    let chat_body: Value = serde_json::from_slice(&body_bytes)
+        .map_err(|e| ProxyError::Internal(format!("Failed to parse request body: {e}")))?;
+
+    let is_stream = chat_body
+        .get("stream")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let include_usage = chat_body
+        .pointer("/stream_options/include_usage")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let body = transform_chat::openai_chat_to_anthropic(chat_body)?;
+
+    let mut ctx = RequestContext::new(
+        &state,
+        &body,
+        &headers,
+        &uri,
+        &extensions,
+        AppType::Claude,
+        "Claude",
+        "claude",
+    )
+    .await?;
+
+    let forwarder = ctx.create_forwarder(&state);
+    let result = match forwarder
+        .forward_with_retry(
+            &AppType::Claude,
+            &endpoint_with_query(&uri, "/v1/messages"),
+            body.clone(),
+            headers,
+            extensions,
+            ctx.get_providers(),
+        )
+        .await
+    {
+        Ok(result) => result,
+        Err(mut err) => {
+            if let Some(provider) = err.provider.take() {
+                ctx.provider = provider;
+            }
+            log_forward_error(&state, &ctx, is_stream, &err.error);
+            return Err(err.error);
+        }
+    };
+
+    ctx.provider = result.provider;
+    let api_format = result
+        .claude_api_format
+        .as_deref()
+        .unwrap_or_else(|| get_claude_api_format(&ctx.provider))
+        .to_string();
+    let response = result.response;
+    let status = response.status();
+    let needs_transform = get_adapter(&AppType::Claude).needs_transform(&ctx.provider);
+    let tool_schema_hints = transform_gemini::extract_anthropic_tool_schema_hints(&body);
+    let tool_schema_hints = (!tool_schema_hints.is_empty()).then_some(tool_schema_hints);
+
+    if is_stream {
+        let anthropic_stream = if needs_transform {
+            anthropic_sse_from_upstream(
+                response,
+                &api_format,
+                &ctx,
+                &state,
+                tool_schema_hints.clone(),
+            )
+        } else {
+            Box::new(Box::pin(response.bytes_stream()))
+        };
+        let logged_stream = create_logged_passthrough_stream(
+            anthropic_stream,
+            "Claude/Chat",
+            Some(claude_stream_usage_collector(&ctx, &state, status.as_u16())),
+            ctx.streaming_timeout_config(),
+        );
+        let chat_stream = create_chat_sse_stream_from_anthropic(logged_stream, include_usage);
+
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(
+            "Content-Type",
+            axum::http::HeaderValue::from_static("text/event-stream"),
+        );
+        headers.insert(
+            "Cache-Control",
+            axum::http::HeaderValue::from_static("no-cache"),
+        );
+        let body = axum::body::Body::from_stream(chat_stream);
+        return Ok((headers, body).into_response());
+    }
+
+    let body_timeout =
+        if ctx.app_config.auto_failover_enabled && ctx.app_config.non_streaming_timeout > 0 {
+            std::time::Duration::from_secs(ctx.app_config.non_streaming_timeout as u64)
+        } else {
+            std::time::Duration::ZERO
+        };
+    let is_sse = response.is_sse();
+    let (mut response_headers, _status, body_bytes) =
+        read_decoded_body(response, ctx.tag, body_timeout).await?;
+    let body_str = String::from_utf8_lossy(&body_bytes);
+
+    let anthropic_response = if needs_transform {
+        // Codex OAuth 会把 stream:false 强制升级为 SSE，这里同样聚合为完整响应
+        let upstream_response = if is_sse && api_format == "openai_responses" {
+            responses_sse_to_response_value(&body_str)?
+        } else {
+            serde_json::from_slice(&body_bytes).map_err(|e| {
+                log::error!("[Claude] 解析上游响应失败: {e}, body: {body_str}");
+                ProxyError::TransformError(format!("Failed to parse upstream response: {e}"))
+            })?
+        };
+        anthropic_response_from_upstream(
+            upstream_response,
+            &api_format,
+            &ctx,
+            &state,
+            tool_schema_hints.as_ref(),
+        )?
+    } else {
+        serde_json::from_slice(&body_bytes).map_err(|e| {
+            log::error!("[Claude] 解析上游响应失败: {e}, body: {body_str}");
+            ProxyError::TransformError(format!("Failed to parse upstream response: {e}"))
+        })?
+    };
+
+    // 记录使用量
+    if let Some(usage) = TokenUsage::from_claude_response(&anthropic_response) {
+        let model = anthropic_response
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        let latency_ms = ctx.latency_ms();
+        let request_model = ctx.request_model.clone();
+        let provider_id = ctx.provider.id.clone();
+        let state = state.clone();
+        let trace = ctx.trace.clone();
+        let capture = ctx.capture.clone();
+        tokio::spawn(async move {
+            log_usage(
+                &state,
+                &provider_id,
+                "claude",
+                &model,
+                &request_model,
+                usage,
+                latency_ms,
+                None,
+                false,
+                status.as_u16(),
+                trace.as_ref(),
+                capture.as_ref(),
+            )
+            .await;
+        });
+    }
+
+    let chat_response = traced_transform(&ctx, "openai_chat", || {
+        transform_chat::anthropic_to_openai_chat(anthropic_response)
+    })?;
+
+    let mut builder = axum::response::Response::builder().status(status);
+    strip_entity_headers_for_rebuilt_body(&mut response_headers);
+    strip_hop_by_hop_response_headers(&mut response_headers);
+    for (key, value) in response_headers.iter() {
+        builder = builder.header(key, value);
+    }
+    builder = builder.header("content-type", "application/json");
+
+    let response_body = serde_json::to_vec(&chat_response)
+        .map_err(|e| ProxyError::TransformError(format!("Failed to serialize response: {e}")))?;
+    builder
+        .body(axum::body::Body::from(response_body))
+        .map_err(|e| ProxyError::Internal(format!("Failed to build response: {e}")))
+}
+
+/// 创建基于 Anthropic SSE 事件的使用量收集器（流结束后记录消费）
+fn claude_stream_usage_collector(
+    ctx: &RequestContext,
+    state: &ProxyState,
+    status_code: u16,
+) -> SseUsageCollector {
+    let state = state.clone();
+    let provider_id = ctx.provider.id.clone();
+    let model = ctx.request_model.clone();
+    let start_time = ctx.start_time;
+    let trace = ctx.trace.clone();
+    let capture = ctx.capture.clone();
+
+    SseUsageCollector::new(start_time, move |events, first_token_ms| {
+        if let Some(usage) = TokenUsage::from_claude_stream_events(&events) {
+            let latency_ms = start_time.elapsed().as_millis() as u64;
+            let state = state.clone();
+            let trace = trace.clone();
+            let capture = capture.clone();
+            let provider_id = provider_id.clone();
+            let model = model.clone();
+
+            tokio::spawn(async move {
+                log_usage(
+                    &state,
+                    &provider_id,
+                    "claude",
+                    &model,
+                    &model,
+                    usage,
+                    latency_ms,
+                    first_token_ms,
+                    true,
+                    status_code,
+                    trace.as_ref(),
+                    capture.as_ref(),
+                )
+                .await;
+            });
+        } else {
+            log::debug!("[Claude] 流式响应缺少 usage 统计，跳过消费记录");
+        }
+    })
+}
+
+/// 按 api_format 选择流式转换器，把上游 SSE 统一转换为 Anthropic SSE
+fn anthropic_sse_from_upstream(
+    response: super::hyper_client::ProxyResponse,
+    api_format: &str,
+    ctx: &RequestContext,
+    state: &ProxyState,
+    tool_schema_hints: Option<transform_gemini::AnthropicToolSchemaHints>,
+) -> Box<dyn futures::Stream<Item = Result<Bytes, std::io::Error>> + Send + Unpin> {
+    let stream = response.bytes_stream();
+    if api_format == "openai_responses" {
+        Box::new(Box::pin(create_anthropic_sse_stream_from_responses(stream)))
+    } else if api_format == "gemini_native" {
+        Box::new(Box::pin(create_anthropic_sse_stream_from_gemini(
+            stream,
+            Some(state.gemini_shadow.clone()),
+            Some(ctx.provider.id.clone()),
+            Some(ctx.session_id.clone()),
+            tool_schema_hints,
+        )))
+    } else {
+        Box::new(Box::pin(create_anthropic_sse_stream(stream)))
+    }
+}
+
+/// 按 api_format 选择非流式转换器，把上游响应统一转换为 Anthropic 响应
+fn anthropic_response_from_upstream(
+    upstream_response: Value,
+    api_format: &str,
+    ctx: &RequestContext,
+    state: &ProxyState,
+    tool_schema_hints: Option<&transform_gemini::AnthropicToolSchemaHints>,
+) -> Result<Value, ProxyError> {
+    traced_transform(ctx, "anthropic", || {
+        anthropic_response_from_upstream_inner(
+            upstream_response,
+            api_format,
+            ctx,
+            state,
+            tool_schema_hints,
+        )
+    })
+}
+
+fn anthropic_response_from_upstream_inner(
+    upstream_response: Value,
+    api_format: &str,
+    ctx: &RequestContext,
+    state: &ProxyState,
+    tool_schema_hints: Option<&transform_gemini::AnthropicToolSchemaHints>,
+) -> Result<Value, ProxyError> {
+    if api_format == "openai_responses" {
+        transform_responses::responses_to_anthropic(upstream_response)
+    } else if api_format == "gemini_native" {
+        transform_gemini::gemini_to_anthropic_with_shadow_and_hints(
+            upstream_response,
+            Some(state.gemini_shadow.as_ref()),
+            Some(&ctx.provider.id),
+            Some(&ctx.session_id),
+            tool_schema_hints,
+        )
+    } else {
+        transform::openai_to_anthropic(upstream_response)
+    }
+    .map_err(|e| {
+        log::error!("[Claude] 转换响应失败: {e}");
+        e
+    })
+}
+
+/// 在请求链路上以子 span 记录一次非流式响应转换
+fn traced_transform<T>(
+    ctx: &RequestContext,
+    target_format: &str,
+    transform: impl FnOnce() -> Result<T, ProxyError>,
+) -> Result<T, ProxyError> {
+    let mut span = otel::child_span(ctx.trace.as_ref(), "transform.response", SpanKind::Internal);
+    let result = transform();
+    if let Some(span) = span.as_mut() {
+        span.set_attribute("cc_switch.target_format", target_format);
+        if let Err(e) = &result {
+            span.set_error(e.to_string());
+        }
+    }
+    result
+}
+
 fn endpoint_with_query(uri: &axum::http::Uri, endpoint: &str) -> String {
     match uri.query() {
         Some(query) => format!("{endpoint}?{query}"),
@@ -379,8 +749,17 @@ pub async fn handle_chat_completions(
     let body: Value = serde_json::from_slice(&body_bytes)
         .map_err(|e| ProxyError::Internal(format!("Failed to parse request body: {e}")))?;
 
-    let mut ctx =
-        RequestContext::new(&state, &body, &headers, AppType::Codex, "Codex", "codex").await?;
+    let mut ctx = RequestContext::new(
+        &state,
+        &body,
+        &headers,
+        &uri,
+        &extensions,
+        AppType::Codex,
+        "Codex",
+        "codex",
+    )
+    .await?;
     let endpoint = endpoint_with_query(&uri, "/chat/completions");
 
     let is_stream = body
@@ -433,8 +812,17 @@ pub async fn handle_responses(
     let body: Value = serde_json::from_slice(&body_bytes)
         .map_err(|e| ProxyError::Internal(format!("Failed to parse request body: {e}")))?;
 
-    let mut ctx =
-        RequestContext::new(&state, &body, &headers, AppType::Codex, "Codex", "codex").await?;
+    let mut ctx = RequestContext::new(
+        &state,
+        &body,
+        &headers,
+        &uri,
+        &extensions,
+        AppType::Codex,
+        "Codex",
+        "codex",
+    )
+    .await?;
     let endpoint = endpoint_with_query(&uri, "/responses");
 
     let is_stream = body
@@ -467,9 +855,112 @@ pub async fn handle_responses(
     ctx.provider = result.provider;
     let response = result.response;
 
+    // 仅支持 Chat Completions 的供应商：请求已由 forwarder 转换，这里把响应转换回 Responses 格式
+    if get_codex_api_format(&ctx.provider) == "openai_chat" {
+        return handle_codex_chat_transform(response, &ctx, &state).await;
+    }
+
     process_response(response, &ctx, &state, &CODEX_PARSER_CONFIG).await
 }
 
+/// Codex Chat Completions → Responses 响应转换
+async fn handle_codex_chat_transform(
+    response: super::hyper_client::ProxyResponse,
+    ctx: &RequestContext,
+    state: &ProxyState,
+) -> Result<axum::response::Response, ProxyError> {
+    let status = response.status();
+
+    if response.is_sse() {
+        let responses_stream = create_responses_sse_stream_from_chat(response.bytes_stream());
+        let usage_collector =
+            create_usage_collector(ctx, state, status.as_u16(), &CODEX_PARSER_CONFIG);
+        let logged_stream = create_logged_passthrough_stream(
+            responses_stream,
+            "Codex/Chat",
+            Some(usage_collector),
+            ctx.streaming_timeout_config(),
+        );
+
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(
+            "Content-Type",
+            axum::http::HeaderValue::from_static("text/event-stream"),
+        );
+        headers.insert(
+            "Cache-Control",
+            axum::http::HeaderValue::from_static("no-cache"),
+        );
+        let body = axum::body::Body::from_stream(logged_stream);
+        return Ok((headers, body).into_response());
+    }
+
+    let body_timeout =
+        if ctx.app_config.auto_failover_enabled && ctx.app_config.non_streaming_timeout > 0 {
+            std::time::Duration::from_secs(ctx.app_config.non_streaming_timeout as u64)
+        } else {
+            std::time::Duration::ZERO
+        };
+    let (mut response_headers, _status, body_bytes) =
+        read_decoded_body(response, ctx.tag, body_timeout).await?;
+    let chat_response: Value = serde_json::from_slice(&body_bytes).map_err(|e| {
+        log::error!(
+            "[Codex] 解析上游响应失败: {e}, body: {}",
+            String::from_utf8_lossy(&body_bytes)
+        );
+        ProxyError::TransformError(format!("Failed to parse upstream response: {e}"))
+    })?;
+    let responses_response = traced_transform(ctx, "openai_responses", || {
+        transform_responses_chat::chat_to_responses(chat_response)
+    })?;
+
+    // 记录使用量
+    if let Some(usage) = TokenUsage::from_codex_response_auto(&responses_response) {
+        let model = responses_response
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or(&ctx.request_model)
+            .to_string();
+        let latency_ms = ctx.latency_ms();
+        let request_model = ctx.request_model.clone();
+        let provider_id = ctx.provider.id.clone();
+        let state = state.clone();
+        let trace = ctx.trace.clone();
+        let capture = ctx.capture.clone();
+        tokio::spawn(async move {
+            log_usage(
+                &state,
+                &provider_id,
+                "codex",
+                &model,
+                &request_model,
+                usage,
+                latency_ms,
+                None,
+                false,
+                status.as_u16(),
+                trace.as_ref(),
+                capture.as_ref(),
+            )
+            .await;
+        });
+    }
+
+    let mut builder = axum::response::Response::builder().status(status);
+    strip_entity_headers_for_rebuilt_body(&mut response_headers);
+    strip_hop_by_hop_response_headers(&mut response_headers);
+    for (key, value) in response_headers.iter() {
+        builder = builder.header(key, value);
+    }
+    builder = builder.header("content-type", "application/json");
+
+    let response_body = serde_json::to_vec(&responses_response)
+        .map_err(|e| ProxyError::TransformError(format!("Failed to serialize response: {e}")))?;
+    builder
+        .body(axum::body::Body::from(response_body))
+        .map_err(|e| ProxyError::Internal(format!("Failed to build response: {e}")))
+}
+
 /// 处理 /v1/responses/compact 请求（OpenAI Responses Compact API - Codex CLI 透传）
 pub async fn handle_responses_compact(
     State(state): State<ProxyState>,
@@ -487,8 +978,17 @@ pub async fn handle_responses_compact(
     let body: Value = serde_json::from_slice(&body_bytes)
         .map_err(|e| ProxyError::Internal(format!("Failed to parse request body: {e}")))?;
 
-    let mut ctx =
-        RequestContext::new(&state, &body, &headers, AppType::Codex, "Codex", "codex").await?;
+    let mut ctx = RequestContext::new(
+        &state,
+        &body,
+        &headers,
+        &uri,
+        &extensions,
+        AppType::Codex,
+        "Codex",
+        "codex",
+    )
+    .await?;
     let endpoint = endpoint_with_query(&uri, "/responses/compact");
 
     let is_stream = body
@@ -546,9 +1046,17 @@ pub async fn handle_gemini(
         .map_err(|e| ProxyError::Internal(format!("Failed to parse request body: {e}")))?;
 
     // Gemini 的模型名称在 URI 中
-    let mut ctx = RequestContext::new(&state, &body, &headers, AppType::Gemini, "Gemini", "gemini")
-        .await?
-        .with_model_from_uri(&uri);
+    let mut ctx = RequestContext::new(
+        &state,
+        &body,
+        &headers,
+        &uri,
+        &extensions,
+        AppType::Gemini,
+        "Gemini",
+        "gemini",
+    )
+    .await?;
 
     // 提取完整的路径和查询参数
     let endpoint = uri
@@ -682,7 +1190,10 @@ fn log_forward_error(
 ) {
     use super::usage::logger::UsageLogger;
 
-    let logger = UsageLogger::new(&state.db);
+    let logger = UsageLogger::new(&state.db)
+        .with_metrics(&state.metrics)
+        .with_trace(ctx.trace.as_ref())
+        .with_capture(ctx.capture.as_ref());
     let status_code = map_proxy_error_to_status(error);
     let error_message = get_error_message(error);
     let request_id = uuid::Uuid::new_v4().to_string();
@@ -716,10 +1227,15 @@ async fn log_usage(
     first_token_ms: Option<u64>,
     is_streaming: bool,
     status_code: u16,
+    trace: Option<&RequestTrace>,
+    capture: Option<&RequestCapture>,
 ) {
     use super::usage::logger::UsageLogger;
 
-    let logger = UsageLogger::new(&state.db);
+    let logger = UsageLogger::new(&state.db)
+        .with_metrics(&state.metrics)
+        .with_trace(trace)
+        .with_capture(capture);
 
     let (multiplier, pricing_model_source) =
         logger.resolve_pricing_config(provider_id, app_type).await;
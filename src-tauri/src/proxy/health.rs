@@ -1,7 +1,320 @@
 //! 健康检查器
 //!
-//! 负责定期检查Provider健康状态（占位实现）
+//! 代理运行期间在后台定期探测各应用故障转移队列中的供应商：
+//! - 复用 `StreamCheckService::check_with_retry`（与手动「测试」按钮相同的检查逻辑）
+//! - 结果写入 `stream_check_logs` 与 `provider_health`
+//! - 探测失败提前打开熔断器，恢复后直接关闭，故障转移不必等真实请求撞上故障上游
+//! - 健康的供应商逐步拉长探测间隔，失败后回到基础间隔
 
-// 占位实现，稍后添加完整逻辑
-#[allow(dead_code)]
-pub struct HealthChecker;
+use super::provider_router::ProviderRouter;
+use crate::app_config::AppType;
+use crate::commands::{
+    failed_check_result, resolve_claude_api_format_override, resolve_copilot_auth_override,
+    resolve_copilot_base_url_override, CopilotAuthState,
+};
+use crate::database::Database;
+use crate::provider::Provider;
+use crate::services::notification::{self, NotificationEvent, NotificationEventKind};
+use crate::services::stream_check::{
+    HealthStatus, StreamCheckConfig, StreamCheckResult, StreamCheckService,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tokio::task::JoinHandle;
+
+/// 调度循环的检查粒度
+const TICK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 后台健康检查配置（存储在 settings 表 `proxy_health_check_config`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckConfig {
+    /// 是否启用（探测会产生少量真实请求费用，默认关闭）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 基础探测间隔（秒）：首次探测及失败后使用
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 健康供应商的最大探测间隔（秒）
+    #[serde(default = "default_max_interval_secs")]
+    pub max_interval_secs: u64,
+}
+
+fn default_interval_secs() -> u64 {
+    60
+}
+
+fn default_max_interval_secs() -> u64 {
+    900
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_interval_secs(),
+            max_interval_secs: default_max_interval_secs(),
+        }
+    }
+}
+
+impl HealthCheckConfig {
+    /// 规范化配置：基础间隔不低于 30 秒，最大间隔不低于基础间隔
+    pub fn normalized(&self) -> Self {
+        let interval_secs = self.interval_secs.max(30);
+        Self {
+            enabled: self.enabled,
+            interval_secs,
+            max_interval_secs: self.max_interval_secs.max(interval_secs),
+        }
+    }
+}
+
+/// 计算下一次探测间隔
+///
+/// - 正常：间隔翻倍，直到最大间隔
+/// - 降级（响应慢）：保持当前间隔
+/// - 失败：回到基础间隔
+fn next_interval(current: Duration, status: &HealthStatus, config: &HealthCheckConfig) -> Duration {
+    let base = Duration::from_secs(config.interval_secs);
+    let max = Duration::from_secs(config.max_interval_secs);
+    match status {
+        HealthStatus::Operational => (current * 2).clamp(base, max),
+        HealthStatus::Degraded => current.clamp(base, max),
+        HealthStatus::Failed => base,
+    }
+}
+
+/// 单个供应商的探测计划
+struct ProbeSchedule {
+    next_at: Instant,
+    interval: Duration,
+}
+
+/// 后台健康检查器
+pub struct HealthChecker {
+    db: Arc<Database>,
+    router: Arc<ProviderRouter>,
+    app_handle: Option<tauri::AppHandle>,
+}
+
+impl HealthChecker {
+    pub fn new(
+        db: Arc<Database>,
+        router: Arc<ProviderRouter>,
+        app_handle: Option<tauri::AppHandle>,
+    ) -> Self {
+        Self {
+            db,
+            router,
+            app_handle,
+        }
+    }
+
+    /// 启动后台调度任务，随代理服务器停止时 abort
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut schedules: HashMap<String, ProbeSchedule> = HashMap::new();
+            let mut ticker = tokio::time::interval(TICK_INTERVAL);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                self.run_due_checks(&mut schedules).await;
+            }
+        })
+    }
+
+    async fn run_due_checks(&self, schedules: &mut HashMap<String, ProbeSchedule>) {
+        let config = match self.db.get_health_check_config() {
+            Ok(config) => config.normalized(),
+            Err(e) => {
+                log::warn!("[HealthCheck] 读取健康检查配置失败: {e}");
+                return;
+            }
+        };
+        if !config.enabled {
+            schedules.clear();
+            return;
+        }
+
+        let check_config = match self.db.get_stream_check_config() {
+            Ok(config) => config,
+            Err(e) => {
+                log::warn!("[HealthCheck] 读取流式检查配置失败: {e}");
+                return;
+            }
+        };
+
+        let mut active_keys = Vec::new();
+        for app_type in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+            for provider in self.probe_targets(&app_type).await {
+                let key = format!("{}:{}", app_type.as_str(), provider.id);
+                active_keys.push(key.clone());
+
+                let now = Instant::now();
+                let schedule = schedules.entry(key).or_insert_with(|| ProbeSchedule {
+                    next_at: now,
+                    interval: Duration::from_secs(config.interval_secs),
+                });
+                if schedule.next_at > now {
+                    continue;
+                }
+
+                let result = self.check(&app_type, &provider, &check_config).await;
+                schedule.interval = next_interval(schedule.interval, &result.status, &config);
+                schedule.next_at = Instant::now() + schedule.interval;
+
+                self.record(&app_type, &provider, &result).await;
+            }
+        }
+
+        // 移出队列或关闭故障转移的供应商不再保留计划
+        schedules.retain(|key, _| active_keys.contains(key));
+    }
+
+    /// 需要探测的供应商：代理已接管且开启自动故障转移的应用的队列成员
+    async fn probe_targets(&self, app_type: &AppType) -> Vec<Provider> {
+        let app_config = match self.db.get_proxy_config_for_app(app_type.as_str()).await {
+            Ok(config) => config,
+            Err(_) => return Vec::new(),
+        };
+        if !app_config.enabled || !app_config.auto_failover_enabled {
+            return Vec::new();
+        }
+        self.db
+            .get_failover_providers(app_type.as_str())
+            .unwrap_or_default()
+    }
+
+    async fn check(
+        &self,
+        app_type: &AppType,
+        provider: &Provider,
+        config: &StreamCheckConfig,
+    ) -> StreamCheckResult {
+        let overrides = match self
+            .app_handle
+            .as_ref()
+            .and_then(|handle| handle.try_state::<CopilotAuthState>())
+        {
+            Some(copilot_state) => {
+                let auth = resolve_copilot_auth_override(provider, &copilot_state).await;
+                let base_url = resolve_copilot_base_url_override(provider, &copilot_state).await;
+                match (auth, base_url) {
+                    (Ok(auth), Ok(base_url)) => {
+                        let api_format = resolve_claude_api_format_override(
+                            app_type,
+                            provider,
+                            config,
+                            &copilot_state,
+                            auth.as_ref(),
+                        )
+                        .await
+                        .unwrap_or(None);
+                        Ok((auth, base_url, api_format))
+                    }
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+            None => Ok((None, None, None)),
+        };
+
+        let result = match overrides {
+            Ok((auth, base_url, api_format)) => {
+                StreamCheckService::check_with_retry(
+                    app_type, provider, config, auth, base_url, api_format,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        result.unwrap_or_else(|e| failed_check_result(&e))
+    }
+
+    async fn record(&self, app_type: &AppType, provider: &Provider, result: &StreamCheckResult) {
+        let app = app_type.as_str();
+        if let Err(e) = self
+            .db
+            .save_stream_check_log(&provider.id, &provider.name, app, result)
+        {
+            log::warn!("[HealthCheck] 保存检查日志失败: {e}");
+        }
+
+        let error = (!result.success).then(|| result.message.clone());
+        if let Err(e) = self
+            .router
+            .apply_health_probe(&provider.id, app, result.success, error)
+            .await
+        {
+            log::warn!("[HealthCheck] 更新健康状态失败: {e}");
+        }
+
+        if result.success {
+            log::debug!(
+                "[HealthCheck] [{app}] {} 正常 ({:?}, {}ms)",
+                provider.name,
+                result.status,
+                result.response_time_ms.unwrap_or(0)
+            );
+        } else {
+            log::warn!(
+                "[HealthCheck] [{app}] {} 探测失败: {}",
+                provider.name,
+                result.message
+            );
+            notification::notify(
+                NotificationEvent::new(
+                    NotificationEventKind::StreamCheckFailed,
+                    result.message.clone(),
+                )
+                .app(app)
+                .provider(&provider.id, Some(&provider.name)),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn healthy_providers_back_off_and_failures_reset() {
+        let config = HealthCheckConfig {
+            enabled: true,
+            interval_secs: 60,
+            max_interval_secs: 300,
+        };
+        let base = Duration::from_secs(60);
+
+        let mut interval = base;
+        for expected in [120, 240, 300, 300] {
+            interval = next_interval(interval, &HealthStatus::Operational, &config);
+            assert_eq!(interval, Duration::from_secs(expected));
+        }
+
+        assert_eq!(
+            next_interval(interval, &HealthStatus::Degraded, &config),
+            interval
+        );
+        assert_eq!(
+            next_interval(interval, &HealthStatus::Failed, &config),
+            base
+        );
+    }
+
+    #[test]
+    fn normalized_config_enforces_minimums() {
+        let config = HealthCheckConfig {
+            enabled: true,
+            interval_secs: 5,
+            max_interval_secs: 10,
+        }
+        .normalized();
+        assert_eq!(config.interval_secs, 30);
+        assert_eq!(config.max_interval_secs, 30);
+    }
+}
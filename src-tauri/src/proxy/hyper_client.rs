@@ -4,6 +4,7 @@
 //! Supports HTTP CONNECT tunneling through upstream proxies.
 //! Falls back to hyper-util Client (title-case headers) when raw write is not feasible.
 
+use super::capture::ResponseTap;
 use super::ProxyError;
 use bytes::Bytes;
 use futures::stream::Stream;
@@ -79,6 +80,9 @@ fn global_hyper_client() -> &'static HyperClient {
 pub enum ProxyResponse {
     Hyper(hyper::Response<hyper::body::Incoming>),
     Reqwest(reqwest::Response),
+    /// Wraps an upstream response while request capture is on; the body is buffered as it is read
+    /// and written to disk (redacted) once the body is consumed or dropped.
+    Captured(Box<ProxyResponse>, ResponseTap),
 }
 
 impl ProxyResponse {
@@ -86,6 +90,7 @@ impl ProxyResponse {
         match self {
             Self::Hyper(r) => r.status(),
             Self::Reqwest(r) => r.status(),
+            Self::Captured(r, _) => r.status(),
         }
     }
 
@@ -93,6 +98,7 @@ impl ProxyResponse {
         match self {
             Self::Hyper(r) => r.headers(),
             Self::Reqwest(r) => r.headers(),
+            Self::Captured(r, _) => r.headers(),
         }
     }
 
@@ -122,6 +128,11 @@ impl ProxyResponse {
             Self::Reqwest(r) => r.bytes().await.map_err(|e| {
                 ProxyError::ForwardFailed(format!("Failed to read response body: {e}"))
             }),
+            Self::Captured(r, tap) => {
+                let bytes = Box::pin(r.bytes()).await?;
+                tap.write(&bytes);
+                Ok(bytes)
+            }
         }
     }
 
@@ -161,6 +172,14 @@ impl ProxyResponse {
                     .map(|r| r.map_err(|e| std::io::Error::other(e.to_string())));
                 Box::pin(stream)
             }
+            Self::Captured(r, tap) => {
+                let stream = r.bytes_stream().inspect(move |chunk| {
+                    if let Ok(chunk) = chunk {
+                        tap.write(chunk);
+                    }
+                });
+                Box::pin(stream)
+            }
         }
     }
 }
@@ -0,0 +1,382 @@
+//! 入站访问控制模块
+//!
+//! 在 axum 路由层校验来源地址与访问令牌，避免任何能连到端口的主机都能消耗供应商额度：
+//! - 本机回环连接始终放行（接管写入 Live 的是占位 Token，本机客户端不携带访问令牌）
+//! - 来源网段白名单（CIDR）；局域网共享模式下未配置白名单时仅允许私有网段
+//! - 访问令牌：`x-api-key` / `Authorization: Bearer` / `x-goog-api-key`
+
+use super::{server::ProxyState, types::ProxyAccessConfig, ProxyError};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+/// 无需鉴权的路径（健康检查）
+const PUBLIC_PATHS: &[&str] = &["/health"];
+
+/// 局域网共享模式下的默认来源网段（RFC1918、链路本地、CGNAT、IPv6 ULA/链路本地）
+const PRIVATE_CIDRS: &[&str] = &[
+    "10.0.0.0/8",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "169.254.0.0/16",
+    "100.64.0.0/10",
+    "fc00::/7",
+    "fe80::/10",
+];
+
+/// 单个 CIDR 网段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CidrBlock {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl CidrBlock {
+    /// 判断地址是否属于该网段（IPv4-mapped IPv6 地址按 IPv4 处理）
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for CidrBlock {
+    type Err = String;
+
+    /// 解析 `地址/前缀`；不带前缀时视为单个主机
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr
+            .trim()
+            .parse()
+            .map_err(|_| format!("无效的 IP 地址: {s}"))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("无效的网段前缀: {s}"))?,
+            None => max,
+        };
+        Ok(Self { network, prefix })
+    }
+}
+
+/// 入站访问策略（由 `ProxyAccessConfig` 编译而来）
+#[derive(Debug, Clone, Default)]
+pub struct AccessPolicy {
+    config: ProxyAccessConfig,
+    cidrs: Vec<CidrBlock>,
+}
+
+impl AccessPolicy {
+    pub fn from_config(config: &ProxyAccessConfig) -> Self {
+        let sources: Vec<&str> = if config.allowed_cidrs.is_empty() && config.lan_mode {
+            PRIVATE_CIDRS.to_vec()
+        } else {
+            config.allowed_cidrs.iter().map(String::as_str).collect()
+        };
+
+        let cidrs = sources
+            .into_iter()
+            .filter_map(|cidr| match cidr.parse::<CidrBlock>() {
+                Ok(block) => Some(block),
+                Err(e) => {
+                    log::warn!("[Proxy] 忽略无效的来源网段: {e}");
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            config: config.clone(),
+            cidrs,
+        }
+    }
+
+    pub fn config(&self) -> &ProxyAccessConfig {
+        &self.config
+    }
+
+    /// 是否要求访问令牌
+    pub fn auth_required(&self) -> bool {
+        self.config.lan_mode || self.config.access_token.is_some()
+    }
+
+    /// 校验一次入站请求
+    ///
+    /// `peer` 为 None（无法获取来源地址）时按非本机连接处理。
+    pub fn check(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Result<(), ProxyError> {
+        if peer.is_some_and(|ip| ip.to_canonical().is_loopback()) {
+            return Ok(());
+        }
+
+        let restrict_source = self.config.lan_mode || !self.config.allowed_cidrs.is_empty();
+        if restrict_source {
+            let allowed = peer.is_some_and(|ip| self.cidrs.iter().any(|c| c.contains(ip)));
+            if !allowed {
+                return Err(ProxyError::Forbidden(format!(
+                    "来源地址 {} 不在允许的网段内",
+                    peer.map(|ip| ip.to_string())
+                        .unwrap_or_else(|| "unknown".to_string())
+                )));
+            }
+        }
+
+        if !self.auth_required() {
+            return Ok(());
+        }
+        // 局域网模式必须配置令牌；缺失时拒绝所有外部连接（fail-closed）
+        let Some(expected) = self.config.access_token.as_deref() else {
+            return Err(ProxyError::Unauthorized(
+                "局域网共享模式未配置访问令牌".to_string(),
+            ));
+        };
+
+        match extract_token(headers) {
+            Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+            Some(_) => Err(ProxyError::Unauthorized("访问令牌无效".to_string())),
+            None => Err(ProxyError::Unauthorized("缺少访问令牌".to_string())),
+        }
+    }
+}
+
+/// 校验访问控制配置（保存前调用）
+pub fn validate_access_config(config: &ProxyAccessConfig) -> Result<(), String> {
+    for cidr in &config.allowed_cidrs {
+        cidr.parse::<CidrBlock>()?;
+    }
+    if let Some(token) = config.access_token.as_deref() {
+        if token.trim().len() < 8 {
+            return Err("访问令牌至少需要 8 个字符".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// 生成新的访问令牌
+pub fn generate_access_token() -> String {
+    format!("ccs-{}", uuid::Uuid::new_v4().simple())
+}
+
+/// axum 中间件：在进入各 handler 之前执行入站访问控制
+pub async fn enforce(State(state): State<ProxyState>, request: Request, next: Next) -> Response {
+    if PUBLIC_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+
+    let verdict = state.access.read().await.check(peer, request.headers());
+    match verdict {
+        Ok(()) => next.run(request).await,
+        Err(e) => {
+            log::warn!(
+                "[Proxy] 拒绝来自 {} 的请求 {}: {e}",
+                peer.map(|ip| ip.to_string())
+                    .unwrap_or_else(|| "unknown".to_string()),
+                request.uri().path()
+            );
+            e.into_response()
+        }
+    }
+}
+
+/// 从请求头提取客户端携带的令牌
+fn extract_token(headers: &HeaderMap) -> Option<&str> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+
+    header("x-api-key")
+        .or_else(|| {
+            header("authorization").map(|v| {
+                v.strip_prefix("Bearer ")
+                    .or_else(|| v.strip_prefix("bearer "))
+                    .unwrap_or(v)
+                    .trim()
+            })
+        })
+        .or_else(|| header("x-goog-api-key"))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    fn headers_with(name: &'static str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn cidr_matching() {
+        let block: CidrBlock = "192.168.1.0/24".parse().unwrap();
+        assert!(block.contains("192.168.1.42".parse().unwrap()));
+        assert!(!block.contains("192.168.2.1".parse().unwrap()));
+        // IPv4-mapped IPv6 来源地址
+        assert!(block.contains("::ffff:192.168.1.7".parse().unwrap()));
+
+        let host: CidrBlock = "10.0.0.5".parse().unwrap();
+        assert!(host.contains("10.0.0.5".parse().unwrap()));
+        assert!(!host.contains("10.0.0.6".parse().unwrap()));
+
+        let any: CidrBlock = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains("8.8.8.8".parse().unwrap()));
+
+        let v6: CidrBlock = "fd00::/8".parse().unwrap();
+        assert!(v6.contains("fd12::1".parse().unwrap()));
+
+        assert!("192.168.1.0/33".parse::<CidrBlock>().is_err());
+        assert!("not-an-ip/8".parse::<CidrBlock>().is_err());
+    }
+
+    #[test]
+    fn default_policy_allows_everything() {
+        let policy = AccessPolicy::default();
+        assert!(policy.check(ip("203.0.113.9"), &HeaderMap::new()).is_ok());
+    }
+
+    #[test]
+    fn loopback_bypasses_token() {
+        let policy = AccessPolicy::from_config(&ProxyAccessConfig {
+            access_token: Some("secret-token".to_string()),
+            allowed_cidrs: vec![],
+            lan_mode: true,
+        });
+        assert!(policy.check(ip("127.0.0.1"), &HeaderMap::new()).is_ok());
+        assert!(policy.check(ip("::1"), &HeaderMap::new()).is_ok());
+    }
+
+    #[test]
+    fn token_is_checked_for_remote_peers() {
+        let policy = AccessPolicy::from_config(&ProxyAccessConfig {
+            access_token: Some("secret-token".to_string()),
+            ..ProxyAccessConfig::default()
+        });
+        let peer = ip("192.168.1.20");
+
+        assert!(matches!(
+            policy.check(peer, &HeaderMap::new()),
+            Err(ProxyError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            policy.check(peer, &headers_with("x-api-key", "wrong-token")),
+            Err(ProxyError::Unauthorized(_))
+        ));
+        assert!(policy
+            .check(peer, &headers_with("x-api-key", "secret-token"))
+            .is_ok());
+        assert!(policy
+            .check(peer, &headers_with("authorization", "Bearer secret-token"))
+            .is_ok());
+        assert!(policy
+            .check(peer, &headers_with("x-goog-api-key", "secret-token"))
+            .is_ok());
+    }
+
+    #[test]
+    fn lan_mode_defaults_to_private_networks() {
+        let policy = AccessPolicy::from_config(&ProxyAccessConfig {
+            access_token: Some("secret-token".to_string()),
+            allowed_cidrs: vec![],
+            lan_mode: true,
+        });
+        let headers = headers_with("x-api-key", "secret-token");
+
+        assert!(policy.check(ip("192.168.1.20"), &headers).is_ok());
+        assert!(policy.check(ip("10.1.2.3"), &headers).is_ok());
+        assert!(matches!(
+            policy.check(ip("203.0.113.9"), &headers),
+            Err(ProxyError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn lan_mode_without_token_fails_closed() {
+        let policy = AccessPolicy::from_config(&ProxyAccessConfig {
+            access_token: None,
+            allowed_cidrs: vec![],
+            lan_mode: true,
+        });
+        assert!(matches!(
+            policy.check(ip("192.168.1.20"), &HeaderMap::new()),
+            Err(ProxyError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn explicit_allow_list_without_token() {
+        let policy = AccessPolicy::from_config(&ProxyAccessConfig {
+            access_token: None,
+            allowed_cidrs: vec!["172.17.0.0/16".to_string()],
+            lan_mode: false,
+        });
+        assert!(policy.check(ip("172.17.0.2"), &HeaderMap::new()).is_ok());
+        assert!(matches!(
+            policy.check(ip("192.168.1.20"), &HeaderMap::new()),
+            Err(ProxyError::Forbidden(_))
+        ));
+        // 无法获取来源地址时不放行
+        assert!(policy.check(None, &HeaderMap::new()).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_bad_input() {
+        assert!(validate_access_config(&ProxyAccessConfig {
+            allowed_cidrs: vec!["10.0.0.0/40".to_string()],
+            ..ProxyAccessConfig::default()
+        })
+        .is_err());
+        assert!(validate_access_config(&ProxyAccessConfig {
+            access_token: Some("short".to_string()),
+            ..ProxyAccessConfig::default()
+        })
+        .is_err());
+        assert!(validate_access_config(&ProxyAccessConfig {
+            access_token: Some(generate_access_token()),
+            allowed_cidrs: vec!["192.168.0.0/16".to_string()],
+            lan_mode: true,
+        })
+        .is_ok());
+    }
+}
@@ -18,6 +18,8 @@ pub mod cb {
     pub const TRIGGERED_FAILURES: &str = "CB-004";
     pub const TRIGGERED_ERROR_RATE: &str = "CB-005";
     pub const MANUAL_RESET: &str = "CB-006";
+    pub const PROBE_OPENED: &str = "CB-007";
+    pub const PROBE_CLOSED: &str = "CB-008";
 }
 
 /// 服务器日志码
@@ -35,6 +37,8 @@ pub mod fwd {
     pub const PROVIDER_FAILED_RETRY: &str = "FWD-001";
     pub const ALL_PROVIDERS_FAILED: &str = "FWD-002";
     pub const SINGLE_PROVIDER_FAILED: &str = "FWD-003";
+    pub const RETRY_SAME_PROVIDER: &str = "FWD-004";
+    pub const RATE_LIMITED: &str = "FWD-005";
 }
 
 /// 故障转移日志码
@@ -44,6 +48,9 @@ pub mod fo {
     pub const LIVE_BACKUP_ERROR: &str = "FO-003";
     pub const ALL_CIRCUIT_OPEN: &str = "FO-004";
     pub const NO_PROVIDERS: &str = "FO-005";
+    pub const BUDGET_EXCEEDED: &str = "FO-006";
+    pub const BUDGET_WARNING: &str = "FO-007";
+    pub const ALL_OVER_BUDGET: &str = "FO-008";
 }
 
 /// 响应处理日志码
@@ -0,0 +1,420 @@
+//! Prometheus 指标
+//!
+//! 在代理进程内累积请求计数、延迟 / 首字直方图与 token、成本计数，
+//! 抓取时再合并 `ProxyStatus` 与各供应商熔断器状态，按 Prometheus 文本格式输出到 `/metrics`。
+//!
+//! 请求类指标与请求日志同源（在 `UsageLogger` 记录请求时写入）；关闭请求日志只跳过数据库写入，
+//! 指标照常增长。
+
+use super::{circuit_breaker::CircuitState, server::ProxyState, usage::logger::RequestLog};
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// 延迟类直方图的桶上界（秒）
+const DURATION_BUCKETS: [f64; 12] = [
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+/// Prometheus 文本格式的 Content-Type
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// 请求维度：应用 / 供应商 / 模型
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SeriesKey {
+    app_type: String,
+    provider_id: String,
+    model: String,
+}
+
+impl SeriesKey {
+    fn labels(&self) -> Vec<(&'static str, &str)> {
+        vec![
+            ("app", &self.app_type),
+            ("provider", &self.provider_id),
+            ("model", &self.model),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+#[derive(Debug, Default)]
+struct SeriesMetrics {
+    /// 按状态码的请求数
+    requests: BTreeMap<u16, u64>,
+    duration: Histogram,
+    first_token: Histogram,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_tokens: u64,
+    cache_creation_tokens: u64,
+    reasoning_tokens: u64,
+    cost_usd: f64,
+}
+
+/// 代理指标注册表（随 `ProxyState` 共享）
+#[derive(Debug, Default)]
+pub struct ProxyMetrics {
+    series: Mutex<BTreeMap<SeriesKey, SeriesMetrics>>,
+}
+
+impl ProxyMetrics {
+    /// 记录一条请求日志
+    pub fn record_request(&self, log: &RequestLog) {
+        let key = SeriesKey {
+            app_type: log.app_type.clone(),
+            provider_id: log.provider_id.clone(),
+            model: log.model.clone(),
+        };
+        let mut series = match self.series.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let entry = series.entry(key).or_default();
+
+        *entry.requests.entry(log.status_code).or_default() += 1;
+        entry.duration.observe(log.latency_ms as f64 / 1000.0);
+        if let Some(first_token_ms) = log.first_token_ms {
+            entry.first_token.observe(first_token_ms as f64 / 1000.0);
+        }
+        entry.input_tokens += u64::from(log.usage.input_tokens);
+        entry.output_tokens += u64::from(log.usage.output_tokens);
+        entry.cache_read_tokens += u64::from(log.usage.cache_read_tokens);
+        entry.cache_creation_tokens += u64::from(log.usage.cache_creation_tokens);
+        entry.reasoning_tokens += u64::from(log.usage.reasoning_tokens);
+        if let Some(cost) = &log.cost {
+            entry.cost_usd += cost.total_cost.to_f64().unwrap_or(0.0);
+        }
+    }
+
+    /// 输出请求类指标
+    pub(super) fn render_requests(&self, out: &mut String) {
+        let series = match self.series.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        write_header(
+            out,
+            "cc_switch_proxy_requests_total",
+            "counter",
+            "Proxied requests by app, provider, model and status code",
+        );
+        for (key, metrics) in series.iter() {
+            for (status, count) in &metrics.requests {
+                let status = status.to_string();
+                let mut labels = key.labels();
+                labels.push(("status", &status));
+                write_sample(
+                    out,
+                    "cc_switch_proxy_requests_total",
+                    &labels,
+                    *count as f64,
+                );
+            }
+        }
+
+        write_header(
+            out,
+            "cc_switch_proxy_request_duration_seconds",
+            "histogram",
+            "End-to-end request latency",
+        );
+        for (key, metrics) in series.iter() {
+            write_histogram(
+                out,
+                "cc_switch_proxy_request_duration_seconds",
+                &key.labels(),
+                &metrics.duration,
+            );
+        }
+
+        write_header(
+            out,
+            "cc_switch_proxy_first_token_seconds",
+            "histogram",
+            "Time to first token for streaming requests",
+        );
+        for (key, metrics) in series.iter().filter(|(_, m)| m.first_token.count > 0) {
+            write_histogram(
+                out,
+                "cc_switch_proxy_first_token_seconds",
+                &key.labels(),
+                &metrics.first_token,
+            );
+        }
+
+        write_header(
+            out,
+            "cc_switch_proxy_tokens_total",
+            "counter",
+            "Tokens reported by upstream usage, by token type",
+        );
+        for (key, metrics) in series.iter() {
+            for (token_type, value) in [
+                ("input", metrics.input_tokens),
+                ("output", metrics.output_tokens),
+                ("cache_read", metrics.cache_read_tokens),
+                ("cache_creation", metrics.cache_creation_tokens),
+                ("reasoning", metrics.reasoning_tokens),
+            ] {
+                let mut labels = key.labels();
+                labels.push(("type", token_type));
+                write_sample(out, "cc_switch_proxy_tokens_total", &labels, value as f64);
+            }
+        }
+
+        write_header(
+            out,
+            "cc_switch_proxy_cost_usd_total",
+            "counter",
+            "Estimated request cost in USD",
+        );
+        for (key, metrics) in series.iter() {
+            write_sample(
+                out,
+                "cc_switch_proxy_cost_usd_total",
+                &key.labels(),
+                metrics.cost_usd,
+            );
+        }
+    }
+}
+
+/// 处理 `/metrics` 请求（未在代理配置中启用时返回 404）
+pub async fn handle_metrics(State(state): State<ProxyState>) -> Response {
+    if !state.config.read().await.metrics_enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let mut out = String::new();
+    state.metrics.render_requests(&mut out);
+
+    let status = state.status.read().await.clone();
+    let uptime = state
+        .start_time
+        .read()
+        .await
+        .map(|start| start.elapsed().as_secs_f64())
+        .unwrap_or(0.0);
+
+    write_header(
+        &mut out,
+        "cc_switch_proxy_up",
+        "gauge",
+        "Whether the proxy server is running",
+    );
+    write_sample(
+        &mut out,
+        "cc_switch_proxy_up",
+        &[],
+        if status.running { 1.0 } else { 0.0 },
+    );
+    write_header(
+        &mut out,
+        "cc_switch_proxy_uptime_seconds",
+        "gauge",
+        "Seconds since the proxy server started",
+    );
+    write_sample(&mut out, "cc_switch_proxy_uptime_seconds", &[], uptime);
+    write_header(
+        &mut out,
+        "cc_switch_proxy_failovers_total",
+        "counter",
+        "Requests served by a provider other than the current one",
+    );
+    write_sample(
+        &mut out,
+        "cc_switch_proxy_failovers_total",
+        &[],
+        status.failover_count as f64,
+    );
+
+    let breakers = state.provider_router.all_circuit_breaker_stats().await;
+    write_header(
+        &mut out,
+        "cc_switch_proxy_circuit_breaker_state",
+        "gauge",
+        "Circuit breaker state (0 = closed, 1 = open, 2 = half open)",
+    );
+    for (app_type, provider_id, stats) in &breakers {
+        let value = match stats.state {
+            CircuitState::Closed => 0.0,
+            CircuitState::Open => 1.0,
+            CircuitState::HalfOpen => 2.0,
+        };
+        write_sample(
+            &mut out,
+            "cc_switch_proxy_circuit_breaker_state",
+            &[("app", app_type), ("provider", provider_id)],
+            value,
+        );
+    }
+    write_header(
+        &mut out,
+        "cc_switch_proxy_circuit_breaker_consecutive_failures",
+        "gauge",
+        "Consecutive failures recorded by the circuit breaker",
+    );
+    for (app_type, provider_id, stats) in &breakers {
+        write_sample(
+            &mut out,
+            "cc_switch_proxy_circuit_breaker_consecutive_failures",
+            &[("app", app_type), ("provider", provider_id)],
+            stats.consecutive_failures as f64,
+        );
+    }
+
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], out).into_response()
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn write_sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        out.push('{');
+        for (index, (label, label_value)) in labels.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "{label}=\"{}\"", escape_label_value(label_value));
+        }
+        out.push('}');
+    }
+    let _ = writeln!(out, " {value}");
+}
+
+fn write_histogram(out: &mut String, name: &str, labels: &[(&str, &str)], histogram: &Histogram) {
+    for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+        let le = bound.to_string();
+        let mut bucket_labels = labels.to_vec();
+        bucket_labels.push(("le", &le));
+        write_sample(out, &format!("{name}_bucket"), &bucket_labels, count as f64);
+    }
+    let mut inf_labels = labels.to_vec();
+    inf_labels.push(("le", "+Inf"));
+    write_sample(
+        out,
+        &format!("{name}_bucket"),
+        &inf_labels,
+        histogram.count as f64,
+    );
+    write_sample(out, &format!("{name}_sum"), labels, histogram.sum);
+    write_sample(
+        out,
+        &format!("{name}_count"),
+        labels,
+        histogram.count as f64,
+    );
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::usage::{calculator::CostBreakdown, parser::TokenUsage};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn request_log(status_code: u16, latency_ms: u64, first_token_ms: Option<u64>) -> RequestLog {
+        RequestLog {
+            request_id: "req".to_string(),
+            provider_id: "p1".to_string(),
+            app_type: "claude".to_string(),
+            model: "claude-\"sonnet\"".to_string(),
+            request_model: "claude-sonnet".to_string(),
+            usage: TokenUsage {
+                input_tokens: 100,
+                output_tokens: 20,
+                reasoning_tokens: 5,
+                ..Default::default()
+            },
+            cost: Some(CostBreakdown {
+                input_cost: Decimal::ZERO,
+                output_cost: Decimal::ZERO,
+                cache_read_cost: Decimal::ZERO,
+                cache_creation_cost: Decimal::ZERO,
+                total_cost: Decimal::from_str("0.25").unwrap(),
+            }),
+            latency_ms,
+            first_token_ms,
+            status_code,
+            error_message: None,
+            session_id: None,
+            provider_type: None,
+            is_streaming: first_token_ms.is_some(),
+            cost_multiplier: "1".to_string(),
+        }
+    }
+
+    #[test]
+    fn renders_request_counters_and_histograms() {
+        let metrics = ProxyMetrics::default();
+        metrics.record_request(&request_log(200, 800, Some(300)));
+        metrics.record_request(&request_log(200, 3_000, None));
+        metrics.record_request(&request_log(529, 50, None));
+
+        let mut out = String::new();
+        metrics.render_requests(&mut out);
+
+        let labels = r#"app="claude",provider="p1",model="claude-\"sonnet\"""#;
+        assert!(out.contains(&format!(
+            "cc_switch_proxy_requests_total{{{labels},status=\"200\"}} 2\n"
+        )));
+        assert!(out.contains(&format!(
+            "cc_switch_proxy_requests_total{{{labels},status=\"529\"}} 1\n"
+        )));
+        assert!(out.contains(&format!(
+            "cc_switch_proxy_request_duration_seconds_bucket{{{labels},le=\"1\"}} 2\n"
+        )));
+        assert!(out.contains(&format!(
+            "cc_switch_proxy_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 3\n"
+        )));
+        assert!(out.contains(&format!(
+            "cc_switch_proxy_first_token_seconds_count{{{labels}}} 1\n"
+        )));
+        assert!(out.contains(&format!(
+            "cc_switch_proxy_tokens_total{{{labels},type=\"input\"}} 300\n"
+        )));
+        assert!(out.contains(&format!(
+            "cc_switch_proxy_tokens_total{{{labels},type=\"reasoning\"}} 15\n"
+        )));
+        assert!(out.contains(&format!(
+            "cc_switch_proxy_cost_usd_total{{{labels}}} 0.75\n"
+        )));
+    }
+}
@@ -3,7 +3,9 @@
 //! 提供本地HTTP代理服务，支持多Provider故障转移和请求透传
 
 pub mod body_filter;
+pub mod budget;
 pub mod cache_injector;
+pub(crate) mod capture;
 pub mod circuit_breaker;
 pub mod copilot_optimizer;
 pub mod error;
@@ -14,22 +16,32 @@ pub mod gemini_url;
 pub mod handler_config;
 pub mod handler_context;
 mod handlers;
-mod health;
+pub(crate) mod health;
 pub mod http_client;
 pub mod hyper_client;
+pub(crate) mod inbound_auth;
 pub mod log_codes;
+pub(crate) mod metrics;
+pub(crate) mod model_list;
 pub mod model_mapper;
+pub(crate) mod model_rules;
+pub(crate) mod otel;
 pub mod provider_router;
 pub mod providers;
+pub(crate) mod replay;
 pub mod response_handler;
 pub mod response_processor;
+pub(crate) mod retry;
+pub(crate) mod routing;
 pub(crate) mod server;
 pub mod session;
+pub mod session_affinity;
 pub(crate) mod sse;
 pub(crate) mod switch_lock;
 pub mod thinking_budget_rectifier;
 pub mod thinking_optimizer;
 pub mod thinking_rectifier;
+pub(crate) mod token_count;
 pub(crate) mod types;
 pub mod usage;
 
@@ -0,0 +1,473 @@
+//! 模型列表端点模块
+//!
+//! 代理接管时响应客户端的模型列表探测（`/v1/models`、`/v1beta/models`）：
+//! - 从当前供应商拉取上游模型列表（OpenAI 兼容 / Anthropic 原生 / Gemini 原生 / Copilot）
+//! - Claude 按模型映射反向改名，客户端看到的是它会发送的模型名
+//! - 上游不支持或拉取失败时，回退到供应商配置中声明的模型
+
+use super::{
+    model_mapper::ModelMapping,
+    providers::{get_adapter, get_claude_api_format, AuthStrategy, ProviderType},
+    server::ProxyState,
+};
+use crate::app_config::AppType;
+use crate::commands::CopilotAuthState;
+use crate::provider::Provider;
+use crate::services::model_fetch;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::Manager;
+
+/// 上游拉取超时（秒），与 model_fetch 保持一致
+const FETCH_TIMEOUT_SECS: u64 = 15;
+
+/// 列表中的单个模型
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedModel {
+    pub id: String,
+    pub display_name: Option<String>,
+    pub owned_by: Option<String>,
+}
+
+impl ListedModel {
+    fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            display_name: None,
+            owned_by: None,
+        }
+    }
+}
+
+/// 获取当前供应商的模型列表（已应用反向映射）
+pub async fn list_models(
+    state: &ProxyState,
+    app_type: &AppType,
+    provider: &Provider,
+) -> Vec<ListedModel> {
+    let upstream = match fetch_upstream_models(state, app_type, provider).await {
+        Ok(models) => models,
+        Err(e) => {
+            log::debug!(
+                "[{}] 拉取 {} 模型列表失败，使用配置中的模型: {e}",
+                app_type.as_str(),
+                provider.name
+            );
+            Vec::new()
+        }
+    };
+
+    let mut models = merge_configured(upstream, configured_models(app_type, provider));
+    if matches!(app_type, AppType::Claude) {
+        models = apply_reverse_mapping(models, &ModelMapping::from_provider(provider));
+    }
+    models
+}
+
+/// Anthropic `/v1/models` 响应格式
+pub fn to_anthropic_list(models: &[ListedModel]) -> Value {
+    let data: Vec<Value> = models
+        .iter()
+        .map(|m| {
+            json!({
+                "type": "model",
+                "id": m.id,
+                "display_name": m.display_name.as_deref().unwrap_or(&m.id),
+                "created_at": "1970-01-01T00:00:00Z",
+            })
+        })
+        .collect();
+    json!({
+        "data": data,
+        "has_more": false,
+        "first_id": models.first().map(|m| m.id.as_str()),
+        "last_id": models.last().map(|m| m.id.as_str()),
+    })
+}
+
+/// OpenAI `/v1/models` 响应格式
+pub fn to_openai_list(models: &[ListedModel]) -> Value {
+    let data: Vec<Value> = models
+        .iter()
+        .map(|m| {
+            json!({
+                "id": m.id,
+                "object": "model",
+                "created": 0,
+                "owned_by": m.owned_by.as_deref().unwrap_or("cc-switch"),
+            })
+        })
+        .collect();
+    json!({ "object": "list", "data": data })
+}
+
+/// Gemini `/v1beta/models` 响应格式
+pub fn to_gemini_list(models: &[ListedModel]) -> Value {
+    let data: Vec<Value> = models
+        .iter()
+        .map(|m| {
+            json!({
+                "name": format!("models/{}", m.id),
+                "displayName": m.display_name.as_deref().unwrap_or(&m.id),
+                "supportedGenerationMethods": ["generateContent", "streamGenerateContent"],
+            })
+        })
+        .collect();
+    json!({ "models": data })
+}
+
+/// 从上游拉取模型列表
+async fn fetch_upstream_models(
+    state: &ProxyState,
+    app_type: &AppType,
+    provider: &Provider,
+) -> Result<Vec<ListedModel>, String> {
+    let provider_type = ProviderType::from_app_type_and_config(app_type, provider);
+    match provider_type {
+        ProviderType::GitHubCopilot => return fetch_copilot_models(state, provider).await,
+        ProviderType::CodexOAuth => {
+            return Err("Codex OAuth does not expose a model list".to_string())
+        }
+        _ => {}
+    }
+
+    let adapter = get_adapter(app_type);
+    let base_url = adapter
+        .extract_base_url(provider)
+        .map_err(|e| e.to_string())?;
+    let auth = adapter
+        .extract_auth(provider)
+        .ok_or_else(|| "API Key is required to fetch models".to_string())?;
+    let is_full_url = provider
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.is_full_url)
+        .unwrap_or(false);
+
+    match app_type {
+        AppType::Claude => match get_claude_api_format(provider) {
+            "anthropic" if !is_full_url => {
+                let url = adapter.build_url(&base_url, "/v1/models");
+                let mut headers = adapter.get_auth_headers(&auth);
+                headers.push((
+                    http::HeaderName::from_static("anthropic-version"),
+                    http::HeaderValue::from_static("2023-06-01"),
+                ));
+                fetch_native(&url, headers, parse_anthropic_models).await
+            }
+            "openai_chat" | "openai_responses" => {
+                fetch_openai_compatible(&base_url, &auth.api_key, is_full_url).await
+            }
+            other => Err(format!("Model listing is not supported for {other}")),
+        },
+        AppType::Gemini => {
+            if auth.strategy == AuthStrategy::GoogleOAuth || is_full_url {
+                return Err("Model listing is not supported for this Gemini provider".into());
+            }
+            let url = adapter.build_url(&base_url, "/v1beta/models");
+            fetch_native(&url, adapter.get_auth_headers(&auth), parse_gemini_models).await
+        }
+        _ => fetch_openai_compatible(&base_url, &auth.api_key, is_full_url).await,
+    }
+}
+
+async fn fetch_openai_compatible(
+    base_url: &str,
+    api_key: &str,
+    is_full_url: bool,
+) -> Result<Vec<ListedModel>, String> {
+    let models = model_fetch::fetch_models(base_url, api_key, is_full_url).await?;
+    Ok(models
+        .into_iter()
+        .map(|m| ListedModel {
+            id: m.id,
+            display_name: None,
+            owned_by: m.owned_by,
+        })
+        .collect())
+}
+
+async fn fetch_copilot_models(
+    state: &ProxyState,
+    provider: &Provider,
+) -> Result<Vec<ListedModel>, String> {
+    let app_handle = state
+        .app_handle
+        .as_ref()
+        .ok_or_else(|| "Copilot auth is unavailable".to_string())?;
+    let copilot_state = app_handle.state::<CopilotAuthState>();
+    let copilot_auth = copilot_state.0.read().await;
+
+    let account_id = provider
+        .meta
+        .as_ref()
+        .and_then(|m| m.managed_account_id_for("github_copilot"));
+    let models = match &account_id {
+        Some(id) => copilot_auth.fetch_models_for_account(id).await,
+        None => copilot_auth.fetch_models().await,
+    }
+    .map_err(|e| e.to_string())?;
+
+    Ok(models
+        .into_iter()
+        .filter(|m| m.model_picker_enabled)
+        .map(|m| ListedModel {
+            id: m.id,
+            display_name: Some(m.name),
+            owned_by: Some(m.vendor),
+        })
+        .collect())
+}
+
+/// 以上游原生格式拉取模型列表（使用适配器的认证头）
+async fn fetch_native(
+    url: &str,
+    auth_headers: Vec<(http::HeaderName, http::HeaderValue)>,
+    parse: fn(&Value) -> Vec<ListedModel>,
+) -> Result<Vec<ListedModel>, String> {
+    let mut request = crate::proxy::http_client::get()
+        .get(url)
+        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS));
+    for (name, value) in auth_headers {
+        request = request.header(name, value);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("HTTP {status}: {body}"));
+    }
+
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {e}"))?;
+    Ok(parse(&body))
+}
+
+fn parse_anthropic_models(body: &Value) -> Vec<ListedModel> {
+    body.get("data")
+        .and_then(|d| d.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let id = item.get("id")?.as_str()?;
+                    Some(ListedModel {
+                        id: id.to_string(),
+                        display_name: item
+                            .get("display_name")
+                            .and_then(|v| v.as_str())
+                            .map(String::from),
+                        owned_by: None,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_gemini_models(body: &Value) -> Vec<ListedModel> {
+    body.get("models")
+        .and_then(|d| d.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let name = item.get("name")?.as_str()?;
+                    Some(ListedModel {
+                        id: name.strip_prefix("models/").unwrap_or(name).to_string(),
+                        display_name: item
+                            .get("displayName")
+                            .and_then(|v| v.as_str())
+                            .map(String::from),
+                        owned_by: Some("google".to_string()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 供应商配置中声明的模型（上游列表不可用时的兜底）
+fn configured_models(app_type: &AppType, provider: &Provider) -> Vec<String> {
+    let env_model = |key: &str| {
+        provider
+            .settings_config
+            .get("env")
+            .and_then(|env| env.get(key))
+            .and_then(|v| v.as_str())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    match app_type {
+        AppType::Claude => {
+            let mapping = ModelMapping::from_provider(provider);
+            [
+                mapping.default_model,
+                mapping.opus_model,
+                mapping.sonnet_model,
+                mapping.haiku_model,
+            ]
+            .into_iter()
+            .flatten()
+            .collect()
+        }
+        AppType::Gemini => env_model("GEMINI_MODEL").into_iter().collect(),
+        _ => provider
+            .settings_config
+            .get("config")
+            .and_then(|v| v.as_str())
+            .and_then(|text| toml::from_str::<toml::Table>(text).ok())
+            .and_then(|table| {
+                table
+                    .get("model")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.trim().to_string())
+            })
+            .filter(|s| !s.is_empty())
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// 合并上游列表与配置模型（去重，配置模型追加在后）
+fn merge_configured(mut upstream: Vec<ListedModel>, configured: Vec<String>) -> Vec<ListedModel> {
+    let mut seen: HashSet<String> = upstream.iter().map(|m| m.id.clone()).collect();
+    for id in configured {
+        if seen.insert(id.clone()) {
+            upstream.push(ListedModel::new(id));
+        }
+    }
+    upstream
+}
+
+/// 应用反向模型映射
+///
+/// 被映射的上游模型以客户端别名（`opus` / `sonnet` / `haiku`）列出并排在最前，
+/// 显示名保留上游模型以便识别；其余模型原样保留。
+fn apply_reverse_mapping(models: Vec<ListedModel>, mapping: &ModelMapping) -> Vec<ListedModel> {
+    if !mapping.has_mapping() {
+        return models;
+    }
+
+    let mut aliased = Vec::new();
+    let mut rest = Vec::new();
+    for model in models {
+        let aliases = mapping.client_aliases(&model.id);
+        if aliases.is_empty() {
+            rest.push(model);
+            continue;
+        }
+        for alias in aliases {
+            aliased.push(ListedModel {
+                id: alias.to_string(),
+                display_name: Some(format!("{alias} → {}", model.id)),
+                owned_by: model.owned_by.clone(),
+            });
+        }
+    }
+
+    // 保持 opus / sonnet / haiku 的固定顺序
+    let order = |id: &str| match id {
+        "opus" => 0,
+        "sonnet" => 1,
+        _ => 2,
+    };
+    aliased.sort_by_key(|m| order(&m.id));
+    aliased.extend(rest);
+    aliased
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claude_provider(env: Value) -> Provider {
+        Provider::with_id(
+            "p1".to_string(),
+            "P1".to_string(),
+            json!({ "env": env }),
+            None,
+        )
+    }
+
+    #[test]
+    fn reverse_mapping_renames_mapped_models() {
+        let provider = claude_provider(json!({
+            "ANTHROPIC_DEFAULT_SONNET_MODEL": "glm-4.6",
+            "ANTHROPIC_DEFAULT_HAIKU_MODEL": "glm-4.5-air",
+            "ANTHROPIC_DEFAULT_OPUS_MODEL": "glm-4.6"
+        }));
+        let upstream = vec![
+            ListedModel::new("glm-4.5-air"),
+            ListedModel::new("glm-4.6"),
+            ListedModel::new("glm-4.5v"),
+        ];
+
+        let models = merge_configured(upstream, configured_models(&AppType::Claude, &provider));
+        let models = apply_reverse_mapping(models, &ModelMapping::from_provider(&provider));
+        let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+
+        assert_eq!(ids, vec!["opus", "sonnet", "haiku", "glm-4.5v"]);
+        assert_eq!(models[1].display_name.as_deref(), Some("sonnet → glm-4.6"));
+    }
+
+    #[test]
+    fn configured_models_fill_in_when_upstream_is_empty() {
+        let provider = claude_provider(json!({ "ANTHROPIC_MODEL": "kimi-k2" }));
+        let models = merge_configured(Vec::new(), configured_models(&AppType::Claude, &provider));
+        let models = apply_reverse_mapping(models, &ModelMapping::from_provider(&provider));
+        assert_eq!(models, vec![ListedModel::new("kimi-k2")]);
+
+        let codex = Provider::with_id(
+            "c1".to_string(),
+            "C1".to_string(),
+            json!({ "config": "model = \"gpt-5-codex\"\n" }),
+            None,
+        );
+        assert_eq!(
+            configured_models(&AppType::Codex, &codex),
+            vec!["gpt-5-codex".to_string()]
+        );
+    }
+
+    #[test]
+    fn list_formats_match_client_expectations() {
+        let models = vec![ListedModel::new("m1")];
+
+        let anthropic = to_anthropic_list(&models);
+        assert_eq!(anthropic["data"][0]["type"], "model");
+        assert_eq!(anthropic["first_id"], "m1");
+
+        let openai = to_openai_list(&models);
+        assert_eq!(openai["object"], "list");
+        assert_eq!(openai["data"][0]["object"], "model");
+
+        let gemini = to_gemini_list(&models);
+        assert_eq!(gemini["models"][0]["name"], "models/m1");
+    }
+
+    #[test]
+    fn parse_native_model_lists() {
+        let anthropic = parse_anthropic_models(&json!({
+            "data": [{"type": "model", "id": "claude-sonnet-4-5", "display_name": "Claude Sonnet 4.5"}]
+        }));
+        assert_eq!(anthropic[0].id, "claude-sonnet-4-5");
+        assert_eq!(
+            anthropic[0].display_name.as_deref(),
+            Some("Claude Sonnet 4.5")
+        );
+
+        let gemini = parse_gemini_models(&json!({
+            "models": [{"name": "models/gemini-2.5-pro", "displayName": "Gemini 2.5 Pro"}]
+        }));
+        assert_eq!(gemini[0].id, "gemini-2.5-pro");
+    }
+}
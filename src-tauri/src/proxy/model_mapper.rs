@@ -2,6 +2,7 @@
 //!
 //! 在请求转发前，根据 Provider 配置替换请求中的模型名称
 
+use super::model_rules::ModelRoutingRule;
 use crate::provider::Provider;
 use serde_json::Value;
 
@@ -79,15 +80,48 @@ impl ModelMapping {
         // 3. 无映射，保持原样
         original_model.to_string()
     }
+
+    /// 反向映射：找出映射到指定上游模型的客户端模型别名
+    ///
+    /// 用于 `/v1/models` 列表，让客户端看到期望的名称（`opus` / `sonnet` / `haiku`），
+    /// 这些别名在请求时会经 `map_model` 重新映射回上游模型。
+    pub fn client_aliases(&self, upstream_model: &str) -> Vec<&'static str> {
+        [
+            ("opus", &self.opus_model),
+            ("sonnet", &self.sonnet_model),
+            ("haiku", &self.haiku_model),
+        ]
+        .into_iter()
+        .filter(|(_, target)| target.as_deref() == Some(upstream_model))
+        .map(|(alias, _)| alias)
+        .collect()
+    }
 }
 
 /// 对请求体应用模型映射
 ///
+/// 命中的模型路由规则配置了目标模型且作用于该供应商时优先使用规则，
+/// 否则使用供应商 env 中的模型映射。
+///
 /// 返回 (映射后的请求体, 原始模型名, 映射后模型名)
 pub fn apply_model_mapping(
     mut body: Value,
     provider: &Provider,
+    rule: Option<&ModelRoutingRule>,
 ) -> (Value, Option<String>, Option<String>) {
+    if let Some(target) = rule.and_then(|rule| rule.target_model_for(&provider.id)) {
+        let original = body.get("model").and_then(|m| m.as_str()).map(String::from);
+        if original.as_deref() == Some(target) {
+            return (body, original, None);
+        }
+        log::debug!(
+            "[ModelMapper] 模型路由规则映射: {} → {target}",
+            original.as_deref().unwrap_or("unknown")
+        );
+        body["model"] = serde_json::json!(target);
+        return (body, original, Some(target.to_string()));
+    }
+
     let mapping = ModelMapping::from_provider(provider);
 
     // 如果没有配置映射，直接返回
@@ -112,6 +146,32 @@ pub fn apply_model_mapping(
     (body, original_model, None)
 }
 
+/// 对 Gemini 原生请求应用模型路由规则
+///
+/// Gemini 的模型名在 URI 中（`.../models/{model}:method`），规则的目标模型改写
+/// URI 中的模型段而非请求体。返回改写后的端点（无需改写时为 None）。
+pub fn apply_gemini_model_rule(
+    endpoint: &str,
+    provider: &Provider,
+    rule: Option<&ModelRoutingRule>,
+) -> Option<String> {
+    let target = rule.and_then(|rule| rule.target_model_for(&provider.id))?;
+    let start = endpoint.find("models/")? + "models/".len();
+    let len = endpoint[start..]
+        .find([':', '/', '?'])
+        .unwrap_or(endpoint.len() - start);
+    let original = &endpoint[start..start + len];
+    if original.is_empty() || original == target {
+        return None;
+    }
+    log::debug!("[ModelMapper] 模型路由规则映射 (Gemini URI): {original} → {target}");
+    Some(format!(
+        "{}{target}{}",
+        &endpoint[..start],
+        &endpoint[start + len..]
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,7 +222,7 @@ mod tests {
     fn test_sonnet_mapping() {
         let provider = create_provider_with_mapping();
         let body = json!({"model": "claude-sonnet-4-5-20250929"});
-        let (result, original, mapped) = apply_model_mapping(body, &provider);
+        let (result, original, mapped) = apply_model_mapping(body, &provider, None);
         assert_eq!(result["model"], "sonnet-mapped");
         assert_eq!(original, Some("claude-sonnet-4-5-20250929".to_string()));
         assert_eq!(mapped, Some("sonnet-mapped".to_string()));
@@ -172,7 +232,7 @@ mod tests {
     fn test_haiku_mapping() {
         let provider = create_provider_with_mapping();
         let body = json!({"model": "claude-haiku-4-5"});
-        let (result, _, mapped) = apply_model_mapping(body, &provider);
+        let (result, _, mapped) = apply_model_mapping(body, &provider, None);
         assert_eq!(result["model"], "haiku-mapped");
         assert_eq!(mapped, Some("haiku-mapped".to_string()));
     }
@@ -181,7 +241,7 @@ mod tests {
     fn test_opus_mapping() {
         let provider = create_provider_with_mapping();
         let body = json!({"model": "claude-opus-4-5"});
-        let (result, _, mapped) = apply_model_mapping(body, &provider);
+        let (result, _, mapped) = apply_model_mapping(body, &provider, None);
         assert_eq!(result["model"], "opus-mapped");
         assert_eq!(mapped, Some("opus-mapped".to_string()));
     }
@@ -194,7 +254,7 @@ mod tests {
             "model": "claude-sonnet-4-5",
             "thinking": {"type": "enabled"}
         });
-        let (result, _, mapped) = apply_model_mapping(body, &provider);
+        let (result, _, mapped) = apply_model_mapping(body, &provider, None);
         assert_eq!(result["model"], "sonnet-mapped");
         assert_eq!(mapped, Some("sonnet-mapped".to_string()));
     }
@@ -207,7 +267,7 @@ mod tests {
             "model": "claude-sonnet-4-5",
             "thinking": {"type": "adaptive"}
         });
-        let (result, _, mapped) = apply_model_mapping(body, &provider);
+        let (result, _, mapped) = apply_model_mapping(body, &provider, None);
         assert_eq!(result["model"], "sonnet-mapped");
         assert_eq!(mapped, Some("sonnet-mapped".to_string()));
     }
@@ -219,7 +279,7 @@ mod tests {
             "model": "claude-sonnet-4-5",
             "thinking": {"type": "disabled"}
         });
-        let (result, _, mapped) = apply_model_mapping(body, &provider);
+        let (result, _, mapped) = apply_model_mapping(body, &provider, None);
         assert_eq!(result["model"], "sonnet-mapped");
         assert_eq!(mapped, Some("sonnet-mapped".to_string()));
     }
@@ -228,7 +288,7 @@ mod tests {
     fn test_unknown_model_uses_default() {
         let provider = create_provider_with_mapping();
         let body = json!({"model": "some-unknown-model"});
-        let (result, _, mapped) = apply_model_mapping(body, &provider);
+        let (result, _, mapped) = apply_model_mapping(body, &provider, None);
         assert_eq!(result["model"], "default-model");
         assert_eq!(mapped, Some("default-model".to_string()));
     }
@@ -237,18 +297,111 @@ mod tests {
     fn test_no_mapping_configured() {
         let provider = create_provider_without_mapping();
         let body = json!({"model": "claude-sonnet-4-5"});
-        let (result, original, mapped) = apply_model_mapping(body, &provider);
+        let (result, original, mapped) = apply_model_mapping(body, &provider, None);
         assert_eq!(result["model"], "claude-sonnet-4-5");
         assert_eq!(original, Some("claude-sonnet-4-5".to_string()));
         assert!(mapped.is_none());
     }
 
+    #[test]
+    fn test_client_aliases_reverse_mapping() {
+        let mut provider = create_provider_with_mapping();
+        provider.settings_config["env"]["ANTHROPIC_DEFAULT_OPUS_MODEL"] = json!("sonnet-mapped");
+        let mapping = ModelMapping::from_provider(&provider);
+
+        assert_eq!(
+            mapping.client_aliases("sonnet-mapped"),
+            vec!["opus", "sonnet"]
+        );
+        assert_eq!(mapping.client_aliases("haiku-mapped"), vec!["haiku"]);
+        // 默认模型不产生别名：任意名称都会回落到默认模型
+        assert!(mapping.client_aliases("default-model").is_empty());
+        // 别名经正向映射后回到上游模型
+        assert_eq!(mapping.map_model("haiku"), "haiku-mapped");
+    }
+
     #[test]
     fn test_case_insensitive() {
         let provider = create_provider_with_mapping();
         let body = json!({"model": "Claude-SONNET-4-5"});
-        let (result, _, mapped) = apply_model_mapping(body, &provider);
+        let (result, _, mapped) = apply_model_mapping(body, &provider, None);
         assert_eq!(result["model"], "sonnet-mapped");
         assert_eq!(mapped, Some("sonnet-mapped".to_string()));
     }
+
+    #[test]
+    fn test_rule_target_model_overrides_env_mapping() {
+        use crate::proxy::model_rules::{ModelRoutingRule, RuleMatchType};
+
+        let provider = create_provider_with_mapping();
+        let mut rule = ModelRoutingRule {
+            id: "r1".to_string(),
+            app_type: Some("claude".to_string()),
+            client_format: None,
+            pattern: "*haiku*".to_string(),
+            match_type: RuleMatchType::Glob,
+            target_provider_id: Some("test".to_string()),
+            target_model: Some("glm-4.5-air".to_string()),
+            enabled: true,
+            sort_index: 0,
+        };
+
+        let body = json!({"model": "claude-haiku-4-5"});
+        let (result, _, mapped) = apply_model_mapping(body.clone(), &provider, Some(&rule));
+        assert_eq!(result["model"], "glm-4.5-air");
+        assert_eq!(mapped, Some("glm-4.5-air".to_string()));
+
+        // 故障转移到非目标供应商时回到 env 映射
+        rule.target_provider_id = Some("other".to_string());
+        let (result, _, _) = apply_model_mapping(body, &provider, Some(&rule));
+        assert_eq!(result["model"], "haiku-mapped");
+    }
+
+    #[test]
+    fn test_gemini_rule_rewrites_uri_model() {
+        use crate::proxy::model_rules::{ModelRoutingRule, RuleMatchType};
+
+        let provider = create_provider_without_mapping();
+        let rule = ModelRoutingRule {
+            id: "r1".to_string(),
+            app_type: Some("gemini".to_string()),
+            client_format: None,
+            pattern: "gemini-*-pro".to_string(),
+            match_type: RuleMatchType::Glob,
+            target_provider_id: None,
+            target_model: Some("gemini-2.5-flash".to_string()),
+            enabled: true,
+            sort_index: 0,
+        };
+
+        assert_eq!(
+            apply_gemini_model_rule(
+                "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse",
+                &provider,
+                Some(&rule),
+            )
+            .as_deref(),
+            Some("/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse")
+        );
+        assert_eq!(
+            apply_gemini_model_rule(
+                "/v1beta/models/gemini-2.5-flash:generateContent",
+                &provider,
+                Some(&rule),
+            ),
+            None
+        );
+        assert_eq!(
+            apply_gemini_model_rule("/v1beta/models", &provider, Some(&rule)),
+            None
+        );
+        assert_eq!(
+            apply_gemini_model_rule(
+                "/v1beta/models/gemini-2.5-pro:generateContent",
+                &provider,
+                None
+            ),
+            None
+        );
+    }
 }
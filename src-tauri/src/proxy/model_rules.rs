@@ -0,0 +1,415 @@
+//! 模型路由规则模块
+//!
+//! 按请求的模型名称把请求路由到指定供应商，并可改写上游模型名：
+//! 例如 haiku 后台请求走便宜的中转，opus 走官方 API，全部通过同一个代理端口。
+//!
+//! 规则存储在 `model_routing_rules` 表，按 `sort_index` 顺序匹配，第一条命中的规则生效：
+//! - 目标供应商：由 `ProviderRouter` 排到故障转移链首位（不可用时按原有顺序故障转移）
+//! - 目标模型：由 `apply_model_mapping` 写入请求体，优先于供应商 env 中的模型映射；
+//!   Gemini 原生请求的模型在 URI 中，由 `apply_gemini_model_rule` 改写端点
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// 入站请求格式
+pub const CLIENT_FORMATS: [&str; 4] = ["anthropic", "openai_chat", "openai_responses", "gemini"];
+
+/// 模型名匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleMatchType {
+    /// 通配符：`*` 匹配任意字符串，`?` 匹配单个字符，忽略大小写
+    #[default]
+    Glob,
+    /// 正则表达式（部分匹配，需要整串匹配时自行加 `^...$`），忽略大小写
+    Regex,
+}
+
+impl RuleMatchType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleMatchType::Glob => "glob",
+            RuleMatchType::Regex => "regex",
+        }
+    }
+}
+
+impl std::str::FromStr for RuleMatchType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "glob" => Ok(RuleMatchType::Glob),
+            "regex" => Ok(RuleMatchType::Regex),
+            other => Err(format!("未知匹配方式: {other}")),
+        }
+    }
+}
+
+/// 模型路由规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelRoutingRule {
+    pub id: String,
+    /// 限定应用（None 表示所有应用）
+    #[serde(default)]
+    pub app_type: Option<String>,
+    /// 限定入站格式（见 [`CLIENT_FORMATS`]，None 表示任意格式）
+    #[serde(default)]
+    pub client_format: Option<String>,
+    /// 匹配请求模型名的模式
+    pub pattern: String,
+    #[serde(default)]
+    pub match_type: RuleMatchType,
+    /// 目标供应商 ID（需同时限定应用）
+    #[serde(default)]
+    pub target_provider_id: Option<String>,
+    /// 改写后的上游模型名
+    #[serde(default)]
+    pub target_model: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 匹配顺序（越小越优先）
+    #[serde(default)]
+    pub sort_index: i64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ModelRoutingRule {
+    /// 校验规则（保存前调用）
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.pattern.trim().is_empty() {
+            return Err(AppError::localized(
+                "error.modelRulePatternEmpty",
+                "匹配模式不能为空",
+                "Match pattern cannot be empty",
+            ));
+        }
+        if self.match_type == RuleMatchType::Regex {
+            build_regex(&self.pattern)?;
+        }
+        if let Some(app_type) = self.app_type.as_deref() {
+            if !matches!(app_type, "claude" | "codex" | "gemini") {
+                return Err(AppError::localized(
+                    "error.modelRuleInvalidApp",
+                    format!("无效应用类型: {app_type}"),
+                    format!("Invalid app type: {app_type}"),
+                ));
+            }
+        }
+        if let Some(format) = self.client_format.as_deref() {
+            if !CLIENT_FORMATS.contains(&format) {
+                return Err(AppError::localized(
+                    "error.modelRuleInvalidFormat",
+                    format!("无效入站格式: {format}"),
+                    format!("Invalid client format: {format}"),
+                ));
+            }
+        }
+        if self.target_provider_id.is_none() && self.target_model.is_none() {
+            return Err(AppError::localized(
+                "error.modelRuleNoTarget",
+                "需要指定目标供应商或目标模型",
+                "A target provider or target model is required",
+            ));
+        }
+        if self.target_provider_id.is_some() && self.app_type.is_none() {
+            return Err(AppError::localized(
+                "error.modelRuleProviderNeedsApp",
+                "指定目标供应商时必须限定应用",
+                "An app must be selected when a target provider is set",
+            ));
+        }
+        Ok(())
+    }
+
+    /// 规则的启用状态、应用与入站格式是否适用于本次请求（不含模型名匹配）
+    fn applies_to(&self, app_type: &str, client_format: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if self.app_type.as_deref().is_some_and(|app| app != app_type) {
+            return false;
+        }
+        if self
+            .client_format
+            .as_deref()
+            .is_some_and(|format| format != client_format)
+        {
+            return false;
+        }
+        true
+    }
+
+    /// 该规则的目标模型是否作用于指定供应商
+    ///
+    /// 未指定目标供应商时对所有供应商生效；指定时仅作用于目标供应商，
+    /// 故障转移到其他供应商后仍使用其自身的模型映射。
+    pub fn target_model_for(&self, provider_id: &str) -> Option<&str> {
+        match self.target_provider_id.as_deref() {
+            Some(target) if target != provider_id => None,
+            _ => self.target_model.as_deref(),
+        }
+    }
+
+    /// 该规则是否将请求强制路由到指定供应商
+    pub fn targets_provider(&self, provider_id: &str) -> bool {
+        self.target_provider_id.as_deref() == Some(provider_id)
+    }
+}
+
+/// 预编译的路由规则
+///
+/// 规则在加载或保存时编译一次，正则不在每个请求上重复构建。
+#[derive(Debug, Clone)]
+pub struct CompiledRule {
+    pub rule: ModelRoutingRule,
+    regex: Option<Regex>,
+}
+
+impl CompiledRule {
+    /// 编译规则（无效正则返回错误）
+    pub fn compile(rule: ModelRoutingRule) -> Result<Self, AppError> {
+        let regex = match rule.match_type {
+            RuleMatchType::Glob => None,
+            RuleMatchType::Regex => Some(build_regex(&rule.pattern)?),
+        };
+        Ok(Self { rule, regex })
+    }
+
+    /// 规则是否匹配本次请求
+    pub fn matches(&self, app_type: &str, client_format: &str, model: &str) -> bool {
+        if !self.rule.applies_to(app_type, client_format) {
+            return false;
+        }
+        match &self.regex {
+            Some(re) => re.is_match(model),
+            None => glob_match(&self.rule.pattern, model),
+        }
+    }
+}
+
+/// 编译已启用的规则，跳过并记录无法编译的规则
+pub fn compile_rules(rules: Vec<ModelRoutingRule>) -> Vec<CompiledRule> {
+    rules
+        .into_iter()
+        .filter(|rule| rule.enabled)
+        .filter_map(|rule| {
+            let id = rule.id.clone();
+            CompiledRule::compile(rule)
+                .map_err(|e| log::warn!("[ModelRules] 跳过无效路由规则 {id}: {e}"))
+                .ok()
+        })
+        .collect()
+}
+
+/// 按顺序查找第一条命中的规则（`rules` 须已按 sort_index 排序）
+pub fn find_rule<'a>(
+    rules: &'a [CompiledRule],
+    app_type: &str,
+    client_format: &str,
+    model: &str,
+) -> Option<&'a ModelRoutingRule> {
+    rules
+        .iter()
+        .find(|compiled| compiled.matches(app_type, client_format, model))
+        .map(|compiled| &compiled.rule)
+}
+
+/// 构建忽略大小写的正则
+fn build_regex(pattern: &str) -> Result<Regex, AppError> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| {
+            AppError::localized(
+                "error.modelRuleInvalidRegex",
+                format!("无效正则表达式: {e}"),
+                format!("Invalid regular expression: {e}"),
+            )
+        })
+}
+
+/// 根据应用与请求路径判断入站格式
+pub fn client_format_for(app_type: &AppType, path: &str) -> &'static str {
+    match app_type {
+        AppType::Gemini => "gemini",
+        AppType::Codex if path.contains("/responses") => "openai_responses",
+        AppType::Codex => "openai_chat",
+        _ if path.contains("/chat/completions") => "openai_chat",
+        _ if path.contains("/responses") => "openai_responses",
+        _ => "anthropic",
+    }
+}
+
+/// 通配符匹配（忽略大小写）
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 `*` 的位置及其匹配到的文本位置，用于回溯
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, pattern: &str, match_type: RuleMatchType) -> ModelRoutingRule {
+        ModelRoutingRule {
+            id: id.to_string(),
+            app_type: Some("claude".to_string()),
+            client_format: None,
+            pattern: pattern.to_string(),
+            match_type,
+            target_provider_id: Some(format!("{id}-provider")),
+            target_model: None,
+            enabled: true,
+            sort_index: 0,
+        }
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_match("claude-*-haiku*", "claude-3-5-haiku-20241022"));
+        assert!(glob_match("*HAIKU*", "claude-haiku-4-5"));
+        assert!(glob_match("gpt-?o", "gpt-4o"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("claude-opus-*", "claude-sonnet-4"));
+        assert!(!glob_match("gpt-?o", "gpt-4o-mini"));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = compile_rules(vec![
+            rule("cheap", "*haiku*", RuleMatchType::Glob),
+            rule("official", r"^claude-opus-4-\d", RuleMatchType::Regex),
+            rule("catch-all", "*", RuleMatchType::Glob),
+        ]);
+
+        let pick = |model| find_rule(&rules, "claude", "anthropic", model).map(|r| r.id.as_str());
+        assert_eq!(pick("claude-haiku-4-5"), Some("cheap"));
+        assert_eq!(pick("claude-opus-4-7"), Some("official"));
+        assert_eq!(pick("claude-sonnet-4-6"), Some("catch-all"));
+        // 限定 app 的规则不影响其他应用
+        assert_eq!(find_rule(&rules, "codex", "openai_chat", "gpt-5"), None);
+    }
+
+    #[test]
+    fn disabled_and_format_scoped_rules_are_skipped() {
+        let mut disabled = rule("disabled", "*", RuleMatchType::Glob);
+        disabled.enabled = false;
+        let mut chat_only = rule("chat", "*", RuleMatchType::Glob);
+        chat_only.client_format = Some("openai_chat".to_string());
+        let rules = compile_rules(vec![disabled, chat_only]);
+
+        assert_eq!(
+            find_rule(&rules, "claude", "anthropic", "claude-opus-4-7"),
+            None
+        );
+        assert_eq!(
+            find_rule(&rules, "claude", "openai_chat", "claude-opus-4-7").map(|r| r.id.as_str()),
+            Some("chat")
+        );
+    }
+
+    #[test]
+    fn invalid_regex_rules_are_skipped_on_compile() {
+        let rules = compile_rules(vec![
+            rule("broken", "claude-(", RuleMatchType::Regex),
+            rule("catch-all", "*", RuleMatchType::Glob),
+        ]);
+
+        assert_eq!(rules.len(), 1);
+        assert!(CompiledRule::compile(rule("broken", "claude-(", RuleMatchType::Regex)).is_err());
+        assert_eq!(
+            find_rule(&rules, "claude", "anthropic", "claude-(").map(|r| r.id.as_str()),
+            Some("catch-all")
+        );
+    }
+
+    #[test]
+    fn target_model_only_applies_to_target_provider() {
+        let mut scoped = rule("cheap", "*haiku*", RuleMatchType::Glob);
+        scoped.target_model = Some("glm-4.5-air".to_string());
+        assert_eq!(
+            scoped.target_model_for("cheap-provider"),
+            Some("glm-4.5-air")
+        );
+        assert_eq!(scoped.target_model_for("fallback"), None);
+
+        scoped.target_provider_id = None;
+        assert_eq!(scoped.target_model_for("fallback"), Some("glm-4.5-air"));
+    }
+
+    #[test]
+    fn targets_provider_only_for_rule_target() {
+        let mut routed = rule("cheap", "*haiku*", RuleMatchType::Glob);
+        assert!(routed.targets_provider("cheap-provider"));
+        assert!(!routed.targets_provider("fallback"));
+
+        routed.target_provider_id = None;
+        assert!(!routed.targets_provider("cheap-provider"));
+    }
+
+    #[test]
+    fn validation_rejects_incomplete_rules() {
+        let mut invalid_regex = rule("r", "claude-(", RuleMatchType::Regex);
+        assert!(invalid_regex.validate().is_err());
+        invalid_regex.pattern = "claude-.*".to_string();
+        assert!(invalid_regex.validate().is_ok());
+
+        let mut no_target = rule("n", "*", RuleMatchType::Glob);
+        no_target.target_provider_id = None;
+        assert!(no_target.validate().is_err());
+
+        let mut provider_without_app = rule("p", "*", RuleMatchType::Glob);
+        provider_without_app.app_type = None;
+        assert!(provider_without_app.validate().is_err());
+    }
+
+    #[test]
+    fn client_format_from_path() {
+        assert_eq!(
+            client_format_for(&AppType::Claude, "/v1/messages"),
+            "anthropic"
+        );
+        assert_eq!(
+            client_format_for(&AppType::Codex, "/v1/responses"),
+            "openai_responses"
+        );
+        assert_eq!(
+            client_format_for(&AppType::Codex, "/v1/chat/completions"),
+            "openai_chat"
+        );
+        assert_eq!(
+            client_format_for(
+                &AppType::Gemini,
+                "/v1beta/models/gemini-pro:generateContent"
+            ),
+            "gemini"
+        );
+    }
+}
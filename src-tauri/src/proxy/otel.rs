@@ -0,0 +1,529 @@
+//! OpenTelemetry 链路导出（OTLP/HTTP JSON）
+//!
+//! 每个客户端请求对应一个根 span，供应商尝试、整流重试、格式转换等步骤作为子 span 挂在其下。
+//! 根 span 在写入请求日志时补充 token 用量与成本并结束；未记录日志（如关闭日志、客户端断开）时，
+//! 在最后一个引用释放时结束。结束后整条链路一次性 POST 到配置的 OTLP 端点。
+
+use super::usage::logger::RequestLog;
+use rust_decimal::prelude::ToPrimitive;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// OTLP 导出超时
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 上报的服务名
+const SERVICE_NAME: &str = "cc-switch-proxy";
+
+/// Span 类型（对应 OTLP `SpanKind`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Internal = 1,
+    Server = 2,
+    Client = 3,
+}
+
+/// Span 属性值
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    String(String),
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+impl AttributeValue {
+    fn to_otlp(&self) -> Value {
+        match self {
+            AttributeValue::String(v) => json!({ "stringValue": v }),
+            // OTLP JSON 中 64 位整数以字符串编码
+            AttributeValue::Int(v) => json!({ "intValue": v.to_string() }),
+            AttributeValue::Double(v) => json!({ "doubleValue": v }),
+            AttributeValue::Bool(v) => json!({ "boolValue": v }),
+        }
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::String(value.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::String(value)
+    }
+}
+
+impl From<&String> for AttributeValue {
+    fn from(value: &String) -> Self {
+        AttributeValue::String(value.clone())
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        AttributeValue::Int(value)
+    }
+}
+
+impl From<u64> for AttributeValue {
+    fn from(value: u64) -> Self {
+        AttributeValue::Int(value as i64)
+    }
+}
+
+impl From<u32> for AttributeValue {
+    fn from(value: u32) -> Self {
+        AttributeValue::Int(i64::from(value))
+    }
+}
+
+impl From<u16> for AttributeValue {
+    fn from(value: u16) -> Self {
+        AttributeValue::Int(i64::from(value))
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        AttributeValue::Double(value)
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        AttributeValue::Bool(value)
+    }
+}
+
+/// 已结束的 span
+#[derive(Debug, Clone)]
+struct SpanData {
+    span_id: String,
+    parent_span_id: Option<String>,
+    name: String,
+    kind: SpanKind,
+    start_unix_nano: u64,
+    end_unix_nano: u64,
+    attributes: Vec<(String, AttributeValue)>,
+    error: Option<String>,
+}
+
+impl SpanData {
+    fn to_otlp(&self, trace_id: &str) -> Value {
+        let status = match &self.error {
+            Some(message) => json!({ "code": 2, "message": message }),
+            None => json!({ "code": 1 }),
+        };
+        json!({
+            "traceId": trace_id,
+            "spanId": self.span_id,
+            "parentSpanId": self.parent_span_id.as_deref().unwrap_or(""),
+            "name": self.name,
+            "kind": self.kind as i32,
+            "startTimeUnixNano": self.start_unix_nano.to_string(),
+            "endTimeUnixNano": self.end_unix_nano.to_string(),
+            "attributes": attributes_to_otlp(&self.attributes),
+            "status": status,
+        })
+    }
+}
+
+fn attributes_to_otlp(attributes: &[(String, AttributeValue)]) -> Vec<Value> {
+    attributes
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": value.to_otlp() }))
+        .collect()
+}
+
+/// OTLP/HTTP JSON 导出器
+#[derive(Debug)]
+pub struct OtlpExporter {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl OtlpExporter {
+    /// 创建导出器
+    ///
+    /// 端点只填写到主机（如 `http://127.0.0.1:4318`）时自动补全 `/v1/traces`。
+    pub fn new(endpoint: &str) -> Result<Self, String> {
+        let endpoint = normalize_endpoint(endpoint)?;
+        // 本地 collector 不应经过用户配置的上游代理
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .timeout(EXPORT_TIMEOUT)
+            .build()
+            .map_err(|e| format!("创建 OTLP 客户端失败: {e}"))?;
+        Ok(Self { endpoint, client })
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn export(&self, trace_id: &str, spans: &[SpanData]) {
+        if spans.is_empty() {
+            return;
+        }
+        let payload = build_payload(trace_id, spans);
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            log::debug!("[OTel] 无可用运行时，丢弃链路 {trace_id}");
+            return;
+        };
+        let client = self.client.clone();
+        let endpoint = self.endpoint.clone();
+        handle.spawn(async move {
+            match client.post(&endpoint).json(&payload).send().await {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => {
+                    log::debug!("[OTel] 导出链路失败: {endpoint} 返回 {}", response.status());
+                }
+                Err(e) => log::debug!("[OTel] 导出链路失败: {endpoint}: {e}"),
+            }
+        });
+    }
+}
+
+/// 校验并补全 OTLP 端点
+pub fn normalize_endpoint(endpoint: &str) -> Result<String, String> {
+    let endpoint = endpoint.trim();
+    let url = url::Url::parse(endpoint).map_err(|e| format!("OTLP 端点无效: {endpoint} ({e})"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("OTLP 端点仅支持 http/https: {endpoint}"));
+    }
+    if url.path() == "/" || url.path().is_empty() {
+        Ok(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+    } else {
+        Ok(endpoint.to_string())
+    }
+}
+
+fn build_payload(trace_id: &str, spans: &[SpanData]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": attributes_to_otlp(&[
+                    ("service.name".to_string(), SERVICE_NAME.into()),
+                    ("service.version".to_string(), env!("CARGO_PKG_VERSION").into()),
+                ]),
+            },
+            "scopeSpans": [{
+                "scope": { "name": "cc-switch.proxy", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans.iter().map(|span| span.to_otlp(trace_id)).collect::<Vec<_>>(),
+            }],
+        }],
+    })
+}
+
+#[derive(Debug, Default)]
+struct TraceState {
+    attributes: Vec<(String, AttributeValue)>,
+    error: Option<String>,
+    spans: Vec<SpanData>,
+    finished: bool,
+}
+
+#[derive(Debug)]
+struct TraceInner {
+    exporter: Arc<OtlpExporter>,
+    trace_id: String,
+    span_id: String,
+    name: String,
+    start_unix_nano: u64,
+    state: Mutex<TraceState>,
+}
+
+impl TraceInner {
+    fn state(&self) -> std::sync::MutexGuard<'_, TraceState> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// 结束根 span 并导出整条链路（仅首次生效）
+    fn finish(&self) {
+        let spans = {
+            let mut state = self.state();
+            if state.finished {
+                return;
+            }
+            state.finished = true;
+            let mut spans = std::mem::take(&mut state.spans);
+            spans.push(SpanData {
+                span_id: self.span_id.clone(),
+                parent_span_id: None,
+                name: self.name.clone(),
+                kind: SpanKind::Server,
+                start_unix_nano: self.start_unix_nano,
+                end_unix_nano: unix_nanos(),
+                attributes: std::mem::take(&mut state.attributes),
+                error: state.error.take(),
+            });
+            spans
+        };
+        self.exporter.export(&self.trace_id, &spans);
+    }
+
+    fn record_span(&self, span: SpanData) {
+        let mut state = self.state();
+        if state.finished {
+            // 根 span 已导出（如流式转换晚于日志结束），单独补发
+            drop(state);
+            self.exporter.export(&self.trace_id, &[span]);
+        } else {
+            state.spans.push(span);
+        }
+    }
+}
+
+impl Drop for TraceInner {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// 单个客户端请求的链路
+#[derive(Debug, Clone)]
+pub struct RequestTrace {
+    inner: Arc<TraceInner>,
+}
+
+impl RequestTrace {
+    /// 开始根 span
+    pub fn start(exporter: Arc<OtlpExporter>, name: impl Into<String>) -> Self {
+        Self {
+            inner: Arc::new(TraceInner {
+                exporter,
+                trace_id: random_hex(16),
+                span_id: random_hex(8),
+                name: name.into(),
+                start_unix_nano: unix_nanos(),
+                state: Mutex::new(TraceState::default()),
+            }),
+        }
+    }
+
+    pub fn trace_id(&self) -> &str {
+        &self.inner.trace_id
+    }
+
+    /// 设置根 span 属性（同名属性覆盖）
+    pub fn set_attribute(&self, key: &str, value: impl Into<AttributeValue>) {
+        set_attribute(&mut self.inner.state().attributes, key, value.into());
+    }
+
+    /// 标记请求失败
+    pub fn set_error(&self, message: impl Into<String>) {
+        self.inner.state().error = Some(message.into());
+    }
+
+    /// 开始子 span
+    pub fn span(&self, name: impl Into<String>, kind: SpanKind) -> TraceSpan {
+        TraceSpan {
+            trace: self.clone(),
+            data: Some(SpanData {
+                span_id: random_hex(8),
+                parent_span_id: Some(self.inner.span_id.clone()),
+                name: name.into(),
+                kind,
+                start_unix_nano: unix_nanos(),
+                end_unix_nano: 0,
+                attributes: Vec::new(),
+                error: None,
+            }),
+        }
+    }
+
+    /// 按请求日志补充根 span 属性（最终供应商、模型、用量、成本）并结束
+    pub fn record_request_log(&self, log: &RequestLog) {
+        {
+            let mut state = self.inner.state();
+            let attributes = &mut state.attributes;
+            for (key, value) in [
+                ("cc_switch.provider.id", log.provider_id.as_str().into()),
+                ("gen_ai.request.model", log.request_model.as_str().into()),
+                ("gen_ai.response.model", log.model.as_str().into()),
+                ("http.response.status_code", log.status_code.into()),
+                ("cc_switch.streaming", log.is_streaming.into()),
+                ("cc_switch.latency_ms", log.latency_ms.into()),
+                ("gen_ai.usage.input_tokens", log.usage.input_tokens.into()),
+                ("gen_ai.usage.output_tokens", log.usage.output_tokens.into()),
+                (
+                    "cc_switch.usage.cache_read_tokens",
+                    log.usage.cache_read_tokens.into(),
+                ),
+                (
+                    "cc_switch.usage.cache_creation_tokens",
+                    log.usage.cache_creation_tokens.into(),
+                ),
+                (
+                    "cc_switch.usage.reasoning_tokens",
+                    log.usage.reasoning_tokens.into(),
+                ),
+            ] {
+                set_attribute(attributes, key, value);
+            }
+            if let Some(first_token_ms) = log.first_token_ms {
+                set_attribute(
+                    attributes,
+                    "cc_switch.first_token_ms",
+                    first_token_ms.into(),
+                );
+            }
+            if let Some(cost) = &log.cost {
+                set_attribute(
+                    attributes,
+                    "cc_switch.cost_usd",
+                    cost.total_cost.to_f64().unwrap_or(0.0).into(),
+                );
+            }
+            if let Some(error) = &log.error_message {
+                state.error = Some(error.clone());
+            }
+        }
+        self.finish();
+    }
+
+    /// 结束根 span 并导出
+    pub fn finish(&self) {
+        self.inner.finish();
+    }
+}
+
+/// 子 span（释放时自动结束）
+#[derive(Debug)]
+pub struct TraceSpan {
+    trace: RequestTrace,
+    data: Option<SpanData>,
+}
+
+impl TraceSpan {
+    pub fn set_attribute(&mut self, key: &str, value: impl Into<AttributeValue>) {
+        if let Some(data) = self.data.as_mut() {
+            set_attribute(&mut data.attributes, key, value.into());
+        }
+    }
+
+    pub fn set_error(&mut self, message: impl Into<String>) {
+        if let Some(data) = self.data.as_mut() {
+            data.error = Some(message.into());
+        }
+    }
+
+    fn record(&mut self) {
+        if let Some(mut data) = self.data.take() {
+            data.end_unix_nano = unix_nanos();
+            self.trace.inner.record_span(data);
+        }
+    }
+}
+
+impl Drop for TraceSpan {
+    fn drop(&mut self) {
+        self.record();
+    }
+}
+
+/// 可选链路上开始子 span（未启用导出时返回 None）
+pub fn child_span(
+    trace: Option<&RequestTrace>,
+    name: impl Into<String>,
+    kind: SpanKind,
+) -> Option<TraceSpan> {
+    trace.map(|trace| trace.span(name, kind))
+}
+
+fn set_attribute(attributes: &mut Vec<(String, AttributeValue)>, key: &str, value: AttributeValue) {
+    match attributes.iter_mut().find(|(k, _)| k == key) {
+        Some((_, existing)) => *existing = value,
+        None => attributes.push((key.to_string(), value)),
+    }
+}
+
+fn unix_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// 生成指定字节数的随机 ID（十六进制）
+fn random_hex(bytes: usize) -> String {
+    let mut hex = String::with_capacity(bytes * 2);
+    while hex.len() < bytes * 2 {
+        hex.push_str(&uuid::Uuid::new_v4().simple().to_string());
+    }
+    hex.truncate(bytes * 2);
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_endpoint() {
+        assert_eq!(
+            normalize_endpoint("http://127.0.0.1:4318").unwrap(),
+            "http://127.0.0.1:4318/v1/traces"
+        );
+        assert_eq!(
+            normalize_endpoint("http://collector:4318/").unwrap(),
+            "http://collector:4318/v1/traces"
+        );
+        assert_eq!(
+            normalize_endpoint("https://otel.example.com/custom/traces").unwrap(),
+            "https://otel.example.com/custom/traces"
+        );
+        assert!(normalize_endpoint("grpc://127.0.0.1:4317").is_err());
+        assert!(normalize_endpoint("not a url").is_err());
+    }
+
+    #[test]
+    fn builds_otlp_payload_with_parent_links() {
+        let exporter = Arc::new(OtlpExporter::new("http://127.0.0.1:4318").unwrap());
+        let trace = RequestTrace::start(exporter, "POST /v1/messages");
+        trace.set_attribute("gen_ai.request.model", "claude-sonnet-4-5");
+        trace.set_attribute("gen_ai.usage.input_tokens", 120u32);
+
+        let mut attempt = trace.span("provider.attempt", SpanKind::Client);
+        attempt.set_attribute("cc_switch.provider.id", "p1");
+        attempt.set_error("upstream 529");
+        drop(attempt);
+
+        let spans = trace.inner.state().spans.clone();
+        assert_eq!(spans.len(), 1);
+        let payload = build_payload(trace.trace_id(), &spans);
+        let span = &payload["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["traceId"].as_str().unwrap().len(), 32);
+        assert_eq!(span["spanId"].as_str().unwrap().len(), 16);
+        assert_eq!(span["parentSpanId"], trace.inner.span_id.as_str());
+        assert_eq!(span["kind"], 3);
+        assert_eq!(span["status"]["code"], 2);
+        assert_eq!(
+            span["attributes"][0],
+            json!({ "key": "cc_switch.provider.id", "value": { "stringValue": "p1" } })
+        );
+        assert_eq!(
+            AttributeValue::from(120u32).to_otlp(),
+            json!({ "intValue": "120" })
+        );
+    }
+
+    #[test]
+    fn finish_only_exports_once() {
+        let exporter = Arc::new(OtlpExporter::new("http://127.0.0.1:4318").unwrap());
+        let trace = RequestTrace::start(exporter, "request");
+        drop(trace.span("transform.request", SpanKind::Internal));
+        trace.finish();
+        assert!(trace.inner.state().finished);
+        assert!(trace.inner.state().spans.is_empty());
+        // 再次结束不应 panic 或重复导出
+        trace.finish();
+    }
+}
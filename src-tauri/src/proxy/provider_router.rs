@@ -6,10 +6,22 @@ use crate::app_config::AppType;
 use crate::database::Database;
 use crate::error::AppError;
 use crate::provider::Provider;
-use crate::proxy::circuit_breaker::{AllowResult, CircuitBreaker, CircuitBreakerConfig};
-use std::collections::HashMap;
+use crate::proxy::budget::{self, BudgetEventPayload, BudgetVerdict};
+use crate::proxy::circuit_breaker::{
+    AllowResult, CircuitBreaker, CircuitBreakerConfig, CircuitState,
+};
+use crate::proxy::log_codes::fo as log_fo;
+use crate::proxy::routing::{self, RoutingKey};
+use crate::proxy::session_affinity::SessionAffinityStore;
+use crate::proxy::types::RoutingStrategy;
+use crate::services::notification::{self, NotificationEvent, NotificationEventKind};
+use crate::services::usage_stats::ProviderRoutingStats;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
 use tokio::sync::RwLock;
 
 /// 供应商路由器
@@ -18,6 +30,32 @@ pub struct ProviderRouter {
     db: Arc<Database>,
     /// 熔断器管理器 - key 格式: "app_type:provider_id"
     circuit_breakers: Arc<RwLock<HashMap<String, Arc<CircuitBreaker>>>>,
+    /// AppHandle，用于发射限额预警/超限事件（测试场景为 None）
+    app_handle: Option<tauri::AppHandle>,
+    /// 已发射过的限额事件 - key 格式: "app_type:provider_id:kind:period_key"
+    ///
+    /// 同一周期内每种事件只发射一次，避免每个请求都刷屏
+    budget_notified: Arc<RwLock<HashSet<String>>>,
+    /// 无会话标识请求的轮询序号
+    routing_sequence: Arc<AtomicU64>,
+    /// 路由统计缓存 - key: app_type，定期刷新以保持排序稳定
+    routing_stats: Arc<RwLock<HashMap<String, RoutingStatsEntry>>>,
+    /// 会话亲和表：会话固定在首个成功响应的供应商上，仅在故障转移时改绑
+    session_affinity: Arc<SessionAffinityStore>,
+    /// 限流冷却截止时间 - key 格式: "app_type:provider_id"
+    ///
+    /// 上游返回 429/529 并给出重置时间后，冷却结束前该供应商排到故障转移链末尾
+    rate_limit_cooldowns: Arc<RwLock<HashMap<String, Instant>>>,
+    /// 是否把请求结果写入供应商健康状态并发送熔断通知（回放等独立实例关闭）
+    persist_health: bool,
+}
+
+/// 路由统计缓存刷新间隔
+const ROUTING_STATS_TTL: Duration = Duration::from_secs(60);
+
+struct RoutingStatsEntry {
+    loaded_at: Instant,
+    stats: Arc<HashMap<String, ProviderRoutingStats>>,
 }
 
 impl ProviderRouter {
@@ -26,18 +64,49 @@ impl ProviderRouter {
         Self {
             db,
             circuit_breakers: Arc::new(RwLock::new(HashMap::new())),
+            app_handle: None,
+            budget_notified: Arc::new(RwLock::new(HashSet::new())),
+            routing_sequence: Arc::new(AtomicU64::new(0)),
+            routing_stats: Arc::new(RwLock::new(HashMap::new())),
+            session_affinity: Arc::new(SessionAffinityStore::default()),
+            rate_limit_cooldowns: Arc::new(RwLock::new(HashMap::new())),
+            persist_health: true,
         }
     }
 
-    /// 选择可用的供应商（支持故障转移）
+    /// 独立路由器：请求结果只影响自身的熔断器，不写入供应商健康状态、不发送熔断通知
+    pub fn detached(mut self) -> Self {
+        self.persist_health = false;
+        self
+    }
+
+    /// 设置 AppHandle（用于发射限额事件）
+    pub fn with_app_handle(mut self, app_handle: Option<tauri::AppHandle>) -> Self {
+        self.app_handle = app_handle;
+        self
+    }
+
+    /// 选择可用的供应商（支持故障转移与路由策略）
     ///
-    /// 返回按优先级排序的可用供应商列表：
+    /// 返回按尝试顺序排列的可用供应商列表：
     /// - 故障转移关闭时：仅返回当前供应商
-    /// - 故障转移开启时：仅使用故障转移队列，按队列顺序依次尝试（P1 → P2 → ...）
-    pub async fn select_providers(&self, app_type: &str) -> Result<Vec<Provider>, AppError> {
+    /// - 故障转移开启时：仅使用故障转移队列，默认按队列顺序依次尝试（P1 → P2 → ...），
+    ///   配置了其他路由策略时按策略重排（见 [`routing`]）
+    ///
+    /// `session_id` 为客户端提供的会话 ID，用于让同一会话保持相同的首选供应商。
+    /// `rule_target_provider` 为命中的模型路由规则指定的供应商（见 [`crate::proxy::model_rules`]），
+    /// 可用时排在首位。
+    /// 两种模式下都会跳过已超出消费限额（limitDailyUsd / limitMonthlyUsd）的供应商。
+    pub async fn select_providers(
+        &self,
+        app_type: &str,
+        session_id: Option<&str>,
+        rule_target_provider: Option<&str>,
+    ) -> Result<Vec<Provider>, AppError> {
         let mut result = Vec::new();
         let mut total_providers = 0usize;
         let mut circuit_open_count = 0usize;
+        let mut over_budget: Vec<String> = Vec::new();
 
         // 检查该应用的自动故障转移开关是否开启（从 proxy_config 表读取）
         let auto_failover_enabled = match self.db.get_proxy_config_for_app(app_type).await {
@@ -70,33 +139,50 @@ impl ProviderRouter {
                 let circuit_key = format!("{app_type}:{}", provider.id);
                 let breaker = self.get_or_create_circuit_breaker(&circuit_key).await;
 
-                if breaker.is_available().await {
-                    result.push(provider);
-                } else {
+                if !breaker.is_available().await {
                     circuit_open_count += 1;
+                } else if let Some(reason) = self.check_budget(app_type, &provider).await {
+                    over_budget.push(reason);
+                } else {
+                    result.push(provider);
                 }
             }
+
+            if result.len() > 1 {
+                result = self
+                    .apply_routing_strategy(app_type, result, session_id)
+                    .await;
+                if let Some(session_id) = session_id {
+                    self.apply_session_affinity(app_type, session_id, &mut result);
+                }
+                self.defer_cooling_down(app_type, &mut result).await;
+            }
         } else {
             // 故障转移关闭：仅使用当前供应商，跳过熔断器检查
-            let current_id = AppType::from_str(app_type)
-                .ok()
-                .and_then(|app_enum| {
-                    crate::settings::get_effective_current_provider(&self.db, &app_enum)
-                        .ok()
-                        .flatten()
-                })
-                .or_else(|| self.db.get_current_provider(app_type).ok().flatten());
-
-            if let Some(current_id) = current_id {
-                if let Some(current) = self.db.get_provider_by_id(&current_id, app_type)? {
-                    total_providers = 1;
+            if let Some(current) = self.current_provider(app_type)? {
+                total_providers = 1;
+                if let Some(reason) = self.check_budget(app_type, &current).await {
+                    over_budget.push(reason);
+                } else {
                     result.push(current);
                 }
             }
         }
 
+        if let Some(target_id) = rule_target_provider {
+            self.apply_rule_target(app_type, target_id, auto_failover_enabled, &mut result)
+                .await;
+        }
+
         if result.is_empty() {
-            if total_providers > 0 && circuit_open_count == total_providers {
+            if !over_budget.is_empty() && over_budget.len() + circuit_open_count == total_providers
+            {
+                log::warn!(
+                    "[{app_type}] [{}] 所有可用供应商均已超出消费限额",
+                    log_fo::ALL_OVER_BUDGET
+                );
+                return Err(AppError::ProviderBudgetExceeded(over_budget.join("; ")));
+            } else if total_providers > 0 && circuit_open_count == total_providers {
                 log::warn!("[{app_type}] [FO-004] 所有供应商均已熔断");
                 return Err(AppError::AllProvidersCircuitOpen);
             } else {
@@ -108,6 +194,356 @@ impl ProviderRouter {
         Ok(result)
     }
 
+    /// 按应用配置的路由策略重排候选供应商
+    async fn apply_routing_strategy(
+        &self,
+        app_type: &str,
+        providers: Vec<Provider>,
+        session_id: Option<&str>,
+    ) -> Vec<Provider> {
+        let strategy = match self.db.get_routing_strategy(app_type).await {
+            Ok(strategy) => strategy,
+            Err(e) => {
+                log::warn!("[{app_type}] 读取路由策略失败: {e}，使用队列优先级");
+                return providers;
+            }
+        };
+
+        let stats = match strategy {
+            RoutingStrategy::LeastLatency | RoutingStrategy::LeastCost => {
+                self.routing_stats_for(app_type).await
+            }
+            _ => Arc::default(),
+        };
+        let key = match session_id {
+            Some(id) => RoutingKey::Session(id),
+            None => RoutingKey::Sequence(self.routing_sequence.fetch_add(1, Ordering::Relaxed)),
+        };
+
+        let ordered = routing::order_providers(strategy, providers, &stats, key);
+        log::debug!(
+            "[{app_type}] 路由策略 {}: {}",
+            strategy.as_str(),
+            ordered
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" → ")
+        );
+        ordered
+    }
+
+    /// 会话已绑定且绑定的供应商仍可用时，将其提到首位
+    ///
+    /// 绑定的供应商已熔断、超限或移出队列时不在候选中，按策略顺序故障转移，
+    /// 成功后由 [`Self::pin_session`] 改绑。
+    fn apply_session_affinity(
+        &self,
+        app_type: &str,
+        session_id: &str,
+        providers: &mut Vec<Provider>,
+    ) {
+        let Some(pinned) = self.session_affinity.get(app_type, session_id) else {
+            return;
+        };
+        if let Some(pos) = providers.iter().position(|p| p.id == pinned) {
+            if pos > 0 {
+                let provider = providers.remove(pos);
+                providers.insert(0, provider);
+            }
+        } else {
+            log::debug!(
+                "[{app_type}] 会话 {session_id} 绑定的供应商 {pinned} 当前不可用，将故障转移"
+            );
+        }
+    }
+
+    /// 模型路由规则指定了目标供应商时，将其提到首位（优先于路由策略与会话亲和）
+    ///
+    /// 目标供应商不必在故障转移队列中；已熔断、超限或限流冷却中时不做调整，按原有顺序处理。
+    /// 故障转移关闭时目标供应商替代当前供应商。
+    async fn apply_rule_target(
+        &self,
+        app_type: &str,
+        target_id: &str,
+        auto_failover_enabled: bool,
+        providers: &mut Vec<Provider>,
+    ) {
+        if self.cooldown_remaining(target_id, app_type).await.is_some() {
+            log::debug!("[{app_type}] 模型路由目标供应商 {target_id} 限流冷却中，按原有顺序处理");
+            return;
+        }
+
+        // 已在候选中：已通过熔断器与限额检查
+        if let Some(pos) = providers.iter().position(|p| p.id == target_id) {
+            let provider = providers.remove(pos);
+            providers.insert(0, provider);
+            return;
+        }
+
+        let provider = match self.db.get_all_providers(app_type) {
+            Ok(all) => all.get(target_id).cloned(),
+            Err(e) => {
+                log::warn!("[{app_type}] 读取模型路由目标供应商失败: {e}");
+                return;
+            }
+        };
+        let Some(provider) = provider else {
+            log::warn!("[{app_type}] 模型路由规则的目标供应商 {target_id} 不存在");
+            return;
+        };
+
+        if auto_failover_enabled {
+            let circuit_key = format!("{app_type}:{target_id}");
+            let breaker = self.get_or_create_circuit_breaker(&circuit_key).await;
+            if !breaker.is_available().await {
+                log::debug!("[{app_type}] 模型路由目标供应商 {target_id} 已熔断，按原有顺序处理");
+                return;
+            }
+        }
+        if let Some(reason) = self.check_budget(app_type, &provider).await {
+            log::debug!("[{app_type}] 模型路由目标供应商不可用: {reason}");
+            return;
+        }
+
+        if auto_failover_enabled {
+            providers.insert(0, provider);
+        } else {
+            *providers = vec![provider];
+        }
+    }
+
+    /// 记录会话由哪个供应商成功响应（首轮绑定，故障转移后改绑）
+    pub fn pin_session(&self, app_type: &str, session_id: &str, provider_id: &str) {
+        self.session_affinity.pin(app_type, session_id, provider_id);
+    }
+
+    /// 记录供应商限流冷却（上游 429/529 给出的重置时间）
+    pub async fn cool_down(&self, provider_id: &str, app_type: &str, duration: Duration) {
+        let key = format!("{app_type}:{provider_id}");
+        let until = Instant::now() + duration;
+        let mut cooldowns = self.rate_limit_cooldowns.write().await;
+        // 多个并发请求同时撞上限流时保留最晚的重置时间
+        let entry = cooldowns.entry(key).or_insert(until);
+        if *entry < until {
+            *entry = until;
+        }
+    }
+
+    /// 供应商剩余的限流冷却时间（未冷却返回 None）
+    pub async fn cooldown_remaining(&self, provider_id: &str, app_type: &str) -> Option<Duration> {
+        let key = format!("{app_type}:{provider_id}");
+        let until = *self.rate_limit_cooldowns.read().await.get(&key)?;
+        let remaining = until.saturating_duration_since(Instant::now());
+        (!remaining.is_zero()).then_some(remaining)
+    }
+
+    /// 把仍在限流冷却中的供应商移到末尾（保持相对顺序），仍可作为最后的兜底
+    async fn defer_cooling_down(&self, app_type: &str, providers: &mut Vec<Provider>) {
+        let now = Instant::now();
+        let cooling: HashSet<String> = {
+            let mut cooldowns = self.rate_limit_cooldowns.write().await;
+            cooldowns.retain(|_, until| *until > now);
+            if cooldowns.is_empty() {
+                return;
+            }
+            providers
+                .iter()
+                .filter(|p| cooldowns.contains_key(&format!("{app_type}:{}", p.id)))
+                .map(|p| p.id.clone())
+                .collect()
+        };
+        if cooling.is_empty() || cooling.len() == providers.len() {
+            return;
+        }
+
+        log::debug!(
+            "[{app_type}] 限流冷却中的供应商排到末尾: {}",
+            cooling.iter().cloned().collect::<Vec<_>>().join(", ")
+        );
+        let (ready, deferred): (Vec<_>, Vec<_>) = std::mem::take(providers)
+            .into_iter()
+            .partition(|p| !cooling.contains(&p.id));
+        providers.extend(ready);
+        providers.extend(deferred);
+    }
+
+    /// 获取路由统计（带缓存，查询失败时返回空统计，按队列顺序兜底）
+    async fn routing_stats_for(
+        &self,
+        app_type: &str,
+    ) -> Arc<HashMap<String, ProviderRoutingStats>> {
+        if let Some(entry) = self.routing_stats.read().await.get(app_type) {
+            if entry.loaded_at.elapsed() < ROUTING_STATS_TTL {
+                return entry.stats.clone();
+            }
+        }
+
+        let since = chrono::Utc::now().timestamp() - routing::STATS_WINDOW_SECS;
+        let stats = match self.db.get_provider_routing_stats(app_type, since) {
+            Ok(stats) => Arc::new(stats),
+            Err(e) => {
+                log::warn!("[{app_type}] 读取路由统计失败: {e}");
+                Arc::default()
+            }
+        };
+        self.routing_stats.write().await.insert(
+            app_type.to_string(),
+            RoutingStatsEntry {
+                loaded_at: Instant::now(),
+                stats: stats.clone(),
+            },
+        );
+        stats
+    }
+
+    /// 获取应用当前供应商（与 UI/托盘展示一致）
+    ///
+    /// 不经过熔断器与限额检查，也不消耗 HalfOpen 名额，
+    /// 适用于模型列表等不计入故障转移统计的辅助端点。
+    pub fn current_provider(&self, app_type: &str) -> Result<Option<Provider>, AppError> {
+        let current_id = AppType::from_str(app_type)
+            .ok()
+            .and_then(|app_enum| {
+                crate::settings::get_effective_current_provider(&self.db, &app_enum)
+                    .ok()
+                    .flatten()
+            })
+            .or_else(|| self.db.get_current_provider(app_type).ok().flatten());
+
+        match current_id {
+            Some(id) => self.db.get_provider_by_id(&id, app_type),
+            None => Ok(None),
+        }
+    }
+
+    /// 确认指定供应商未超出消费限额（跳过路由直接使用指定供应商时调用）
+    pub async fn ensure_within_budget(
+        &self,
+        app_type: &str,
+        provider: &Provider,
+    ) -> Result<(), AppError> {
+        match self.check_budget(app_type, provider).await {
+            Some(reason) => Err(AppError::ProviderBudgetExceeded(reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// 检查供应商消费限额
+    ///
+    /// 返回 `Some(原因)` 表示已超限、应跳过；未配置限额或查询失败时放行（fail-open），
+    /// 避免统计异常导致代理整体不可用。
+    async fn check_budget(&self, app_type: &str, provider: &Provider) -> Option<String> {
+        if !budget::has_spend_limits(provider) {
+            return None;
+        }
+
+        let status = match self.db.check_provider_limits(&provider.id, app_type) {
+            Ok(status) => status,
+            Err(e) => {
+                log::warn!(
+                    "[{app_type}] 读取 {} 消费限额失败，放行: {e}",
+                    provider.name
+                );
+                return None;
+            }
+        };
+
+        match budget::evaluate(&status, budget::warning_percent(provider)) {
+            BudgetVerdict::Within => None,
+            BudgetVerdict::Warning {
+                period,
+                usage,
+                limit,
+            } => {
+                log::info!(
+                    "[{app_type}] [{}] {} {} 消费已达 ${usage:.4} / ${limit:.2}",
+                    log_fo::BUDGET_WARNING,
+                    provider.name,
+                    period.as_str()
+                );
+                self.notify_budget(
+                    budget::BUDGET_WARNING_EVENT,
+                    BudgetEventPayload::new(app_type, provider, period, usage, limit),
+                )
+                .await;
+                None
+            }
+            BudgetVerdict::Exceeded {
+                period,
+                usage,
+                limit,
+            } => {
+                log::warn!(
+                    "[{app_type}] [{}] {} 已超出 {} 限额 (${usage:.4} / ${limit:.2})，跳过",
+                    log_fo::BUDGET_EXCEEDED,
+                    provider.name,
+                    period.as_str()
+                );
+                self.notify_budget(
+                    budget::BUDGET_EXCEEDED_EVENT,
+                    BudgetEventPayload::new(app_type, provider, period, usage, limit),
+                )
+                .await;
+                Some(format!(
+                    "{} exceeded {} limit (${usage:.2} / ${limit:.2})",
+                    provider.name,
+                    period.as_str()
+                ))
+            }
+        }
+    }
+
+    /// 发射限额事件（同一周期内去重）
+    async fn notify_budget(&self, event: &str, payload: BudgetEventPayload) {
+        let Some(app_handle) = &self.app_handle else {
+            return;
+        };
+
+        let period_key = payload.period.current_key();
+        let key = format!(
+            "{}:{}:{event}:{period_key}",
+            payload.app_type, payload.provider_id
+        );
+
+        {
+            let mut notified = self.budget_notified.write().await;
+            if notified.contains(&key) {
+                return;
+            }
+            // 清理过期周期的记录，防止长期运行时无限增长
+            let daily_key = budget::BudgetPeriod::Daily.current_key();
+            let monthly_key = budget::BudgetPeriod::Monthly.current_key();
+            notified.retain(|k| k.ends_with(&daily_key) || k.ends_with(&monthly_key));
+            notified.insert(key);
+        }
+
+        let kind = if event == budget::BUDGET_EXCEEDED_EVENT {
+            NotificationEventKind::BudgetExceeded
+        } else {
+            NotificationEventKind::BudgetWarning
+        };
+        notification::notify(
+            NotificationEvent::new(
+                kind,
+                format!(
+                    "{} ${} / ${}",
+                    payload.period.as_str(),
+                    payload.usage_usd,
+                    payload.limit_usd
+                ),
+            )
+            .app(&payload.app_type)
+            .provider(&payload.provider_id, Some(&payload.provider_name))
+            .value(payload.percent)
+            .dedupe_key(payload.period.as_str()),
+        );
+
+        if let Err(e) = app_handle.emit(event, &payload) {
+            log::error!("[Budget] 发射事件 {event} 失败: {e}");
+        }
+    }
+
     /// 请求执行前获取熔断器“放行许可”
     ///
     /// - Closed：直接放行
@@ -144,7 +580,15 @@ impl ProviderRouter {
         if success {
             breaker.record_success(used_half_open_permit).await;
         } else {
+            let was_open = breaker.get_state().await == CircuitState::Open;
             breaker.record_failure(used_half_open_permit).await;
+            if self.persist_health && !was_open && breaker.get_state().await == CircuitState::Open {
+                notify_circuit_open(app_type, provider_id, error_msg.as_deref());
+            }
+        }
+
+        if !self.persist_health {
+            return Ok(());
         }
 
         // 3. 更新数据库健康状态（使用配置的阈值）
@@ -161,6 +605,39 @@ impl ProviderRouter {
         Ok(())
     }
 
+    /// 应用主动健康检查结果
+    ///
+    /// 探测失败直接打开熔断器，让后续请求在故障转移时提前跳过该供应商；
+    /// 探测成功只关闭由探测打开的熔断器，不必等待 HalfOpen 超时；实时流量触发的
+    /// 熔断仍由 HalfOpen 阶段的真实请求决定恢复，此时也不覆盖健康状态。
+    pub async fn apply_health_probe(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+        success: bool,
+        error_msg: Option<String>,
+    ) -> Result<(), AppError> {
+        let circuit_key = format!("{app_type}:{provider_id}");
+        let breaker = self.get_or_create_circuit_breaker(&circuit_key).await;
+        if success {
+            breaker.close_if_probe_opened().await;
+            if breaker.get_state().await != CircuitState::Closed {
+                return Ok(());
+            }
+        } else {
+            let was_open = breaker.get_state().await == CircuitState::Open;
+            breaker.force_open().await;
+            if !was_open {
+                notify_circuit_open(app_type, provider_id, error_msg.as_deref());
+            }
+        }
+
+        // 探测本身已带重试，一次失败即标记为不健康
+        self.db
+            .update_provider_health_with_threshold(provider_id, app_type, success, error_msg, 1)
+            .await
+    }
+
     /// 重置熔断器（手动恢复）
     pub async fn reset_circuit_breaker(&self, circuit_key: &str) {
         let breakers = self.circuit_breakers.read().await;
@@ -172,6 +649,7 @@ impl ProviderRouter {
     /// 重置指定供应商的熔断器
     pub async fn reset_provider_breaker(&self, provider_id: &str, app_type: &str) {
         let circuit_key = format!("{app_type}:{provider_id}");
+        self.rate_limit_cooldowns.write().await.remove(&circuit_key);
         self.reset_circuit_breaker(&circuit_key).await;
     }
 
@@ -218,6 +696,37 @@ impl ProviderRouter {
         }
     }
 
+    /// 获取所有已创建熔断器的状态（app_type, provider_id, stats）
+    pub async fn all_circuit_breaker_stats(
+        &self,
+    ) -> Vec<(
+        String,
+        String,
+        crate::proxy::circuit_breaker::CircuitBreakerStats,
+    )> {
+        let breakers: Vec<(String, Arc<CircuitBreaker>)> = self
+            .circuit_breakers
+            .read()
+            .await
+            .iter()
+            .map(|(key, breaker)| (key.clone(), breaker.clone()))
+            .collect();
+
+        let mut stats = Vec::with_capacity(breakers.len());
+        for (key, breaker) in breakers {
+            let Some((app_type, provider_id)) = key.split_once(':') else {
+                continue;
+            };
+            stats.push((
+                app_type.to_string(),
+                provider_id.to_string(),
+                breaker.get_stats().await,
+            ));
+        }
+        stats.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        stats
+    }
+
     /// 获取或创建熔断器
     async fn get_or_create_circuit_breaker(&self, key: &str) -> Arc<CircuitBreaker> {
         // 先尝试读锁获取
@@ -258,6 +767,18 @@ impl ProviderRouter {
     }
 }
 
+/// 熔断器由关闭 / 半开转为打开时发送通知
+fn notify_circuit_open(app_type: &str, provider_id: &str, error: Option<&str>) {
+    notification::notify(
+        NotificationEvent::new(
+            NotificationEventKind::CircuitOpen,
+            error.unwrap_or_default(),
+        )
+        .app(app_type)
+        .provider(provider_id, None),
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -343,7 +864,7 @@ mod tests {
         db.add_to_failover_queue("claude", "b").unwrap();
 
         let router = ProviderRouter::new(db.clone());
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router.select_providers("claude", None, None).await.unwrap();
 
         assert_eq!(providers.len(), 1);
         assert_eq!(providers[0].id, "a");
@@ -376,7 +897,7 @@ mod tests {
         db.update_proxy_config_for_app(config).await.unwrap();
 
         let router = ProviderRouter::new(db.clone());
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router.select_providers("claude", None, None).await.unwrap();
 
         assert_eq!(providers.len(), 2);
         // 故障转移开启时：仅按队列顺序选择（忽略当前供应商）
@@ -408,7 +929,7 @@ mod tests {
         db.update_proxy_config_for_app(config).await.unwrap();
 
         let router = ProviderRouter::new(db.clone());
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router.select_providers("claude", None, None).await.unwrap();
 
         assert_eq!(providers.len(), 1);
         assert_eq!(providers[0].id, "b");
@@ -451,7 +972,7 @@ mod tests {
             .await
             .unwrap();
 
-        let providers = router.select_providers("claude").await.unwrap();
+        let providers = router.select_providers("claude", None, None).await.unwrap();
         assert_eq!(providers.len(), 2);
 
         assert!(router.allow_provider_request("b", "claude").await.allowed);
@@ -509,4 +1030,306 @@ mod tests {
         assert!(third.allowed);
         assert!(third.used_half_open_permit);
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_round_robin_rotates_primary_and_keeps_failover_chain() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        for (id, sort_index) in [("a", 1), ("b", 2)] {
+            let mut provider =
+                Provider::with_id(id.to_string(), id.to_uppercase(), json!({}), None);
+            provider.sort_index = Some(sort_index);
+            db.save_provider("claude", &provider).unwrap();
+            db.add_to_failover_queue("claude", id).unwrap();
+        }
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+        db.set_routing_strategy("claude", "round_robin")
+            .await
+            .unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+        let first = router.select_providers("claude", None, None).await.unwrap();
+        let second = router.select_providers("claude", None, None).await.unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(second.len(), 2);
+        assert_ne!(first[0].id, second[0].id);
+
+        // 同一会话多次请求首选供应商不变
+        let pinned = router
+            .select_providers("claude", Some("session-1"), None)
+            .await
+            .unwrap();
+        for _ in 0..5 {
+            let again = router
+                .select_providers("claude", Some("session-1"), None)
+                .await
+                .unwrap();
+            assert_eq!(again[0].id, pinned[0].id);
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_session_affinity_keeps_pinned_provider_until_unavailable() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        for (id, sort_index) in [("a", 1), ("b", 2)] {
+            let mut provider =
+                Provider::with_id(id.to_string(), id.to_uppercase(), json!({}), None);
+            provider.sort_index = Some(sort_index);
+            db.save_provider("claude", &provider).unwrap();
+            db.add_to_failover_queue("claude", id).unwrap();
+        }
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+        // 会话上一轮由 b 响应（例如 a 曾故障转移）
+        router.pin_session("claude", "s1", "b");
+
+        let pinned = router
+            .select_providers("claude", Some("s1"), None)
+            .await
+            .unwrap();
+        assert_eq!(pinned[0].id, "b");
+        assert_eq!(pinned[1].id, "a");
+
+        // 其他会话不受影响，仍按队列优先级
+        let other = router
+            .select_providers("claude", Some("s2"), None)
+            .await
+            .unwrap();
+        assert_eq!(other[0].id, "a");
+
+        // 绑定的供应商移出队列后回到正常顺序
+        db.remove_from_failover_queue("claude", "b").unwrap();
+        let fallback = router
+            .select_providers("claude", Some("s1"), None)
+            .await
+            .unwrap();
+        assert_eq!(fallback.len(), 1);
+        assert_eq!(fallback[0].id, "a");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_rate_limited_provider_is_deferred_until_cooldown_ends() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        for (id, sort_index) in [("a", 1), ("b", 2)] {
+            let mut provider =
+                Provider::with_id(id.to_string(), id.to_uppercase(), json!({}), None);
+            provider.sort_index = Some(sort_index);
+            db.save_provider("claude", &provider).unwrap();
+            db.add_to_failover_queue("claude", id).unwrap();
+        }
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+        router
+            .cool_down("a", "claude", Duration::from_secs(60))
+            .await;
+        assert!(router.cooldown_remaining("a", "claude").await.is_some());
+        assert!(router.cooldown_remaining("a", "codex").await.is_none());
+
+        // 冷却中的供应商排到末尾，但仍保留为兜底
+        let providers = router.select_providers("claude", None, None).await.unwrap();
+        assert_eq!(providers[0].id, "b");
+        assert_eq!(providers[1].id, "a");
+
+        // 手动重置熔断器时一并清除冷却
+        router.reset_provider_breaker("a", "claude").await;
+        assert!(router.cooldown_remaining("a", "claude").await.is_none());
+        let providers = router.select_providers("claude", None, None).await.unwrap();
+        assert_eq!(providers[0].id, "a");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_model_rule_target_provider_goes_first() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        for (id, sort_index, in_queue) in [("a", 1, true), ("b", 2, true), ("relay", 3, false)] {
+            let mut provider =
+                Provider::with_id(id.to_string(), id.to_uppercase(), json!({}), None);
+            provider.sort_index = Some(sort_index);
+            db.save_provider("claude", &provider).unwrap();
+            if in_queue {
+                db.add_to_failover_queue("claude", id).unwrap();
+            }
+        }
+
+        let router = ProviderRouter::new(db.clone());
+
+        // 故障转移关闭：目标供应商替代当前供应商
+        let providers = router
+            .select_providers("claude", None, Some("relay"))
+            .await
+            .unwrap();
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].id, "relay");
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+
+        // 故障转移开启：目标供应商在首位，队列其余供应商作为兜底；优先于会话亲和
+        router.pin_session("claude", "s1", "a");
+        let providers = router
+            .select_providers("claude", Some("s1"), Some("b"))
+            .await
+            .unwrap();
+        assert_eq!(providers[0].id, "b");
+        assert_eq!(providers[1].id, "a");
+
+        let providers = router
+            .select_providers("claude", None, Some("relay"))
+            .await
+            .unwrap();
+        assert_eq!(providers.len(), 3);
+        assert_eq!(providers[0].id, "relay");
+
+        // 目标不存在时按原有顺序
+        let providers = router
+            .select_providers("claude", None, Some("missing"))
+            .await
+            .unwrap();
+        assert_eq!(providers[0].id, "a");
+    }
+
+    fn insert_cost_log(db: &Database, request_id: &str, provider_id: &str, cost: &str) {
+        let conn = db.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model, total_cost_usd,
+                latency_ms, status_code, created_at
+            ) VALUES (?1, ?2, 'claude', 'claude-sonnet-4', ?3, 100, 200, ?4)",
+            rusqlite::params![
+                request_id,
+                provider_id,
+                cost,
+                chrono::Utc::now().timestamp()
+            ],
+        )
+        .unwrap();
+    }
+
+    fn provider_with_daily_limit(id: &str, limit: &str) -> Provider {
+        let mut provider = Provider::with_id(id.to_string(), id.to_uppercase(), json!({}), None);
+        provider.meta = Some(crate::provider::ProviderMeta {
+            limit_daily_usd: Some(limit.to_string()),
+            ..Default::default()
+        });
+        provider
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_failover_skips_provider_over_budget() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        let mut provider_a = provider_with_daily_limit("a", "1.00");
+        provider_a.sort_index = Some(1);
+        let mut provider_b = provider_with_daily_limit("b", "100.00");
+        provider_b.sort_index = Some(2);
+
+        db.save_provider("claude", &provider_a).unwrap();
+        db.save_provider("claude", &provider_b).unwrap();
+        db.add_to_failover_queue("claude", "a").unwrap();
+        db.add_to_failover_queue("claude", "b").unwrap();
+
+        let mut config = db.get_proxy_config_for_app("claude").await.unwrap();
+        config.auto_failover_enabled = true;
+        db.update_proxy_config_for_app(config).await.unwrap();
+
+        // a 今日已消费 1.5 USD，超出 1.00 的日限额
+        insert_cost_log(&db, "req-a", "a", "1.5");
+
+        let router = ProviderRouter::new(db.clone());
+        let providers = router.select_providers("claude", None, None).await.unwrap();
+
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].id, "b");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_single_provider_over_budget_is_rejected() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        let provider_a = provider_with_daily_limit("a", "1.00");
+        db.save_provider("claude", &provider_a).unwrap();
+        db.set_current_provider("claude", "a").unwrap();
+
+        let router = ProviderRouter::new(db.clone());
+        assert_eq!(
+            router
+                .select_providers("claude", None, None)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        insert_cost_log(&db, "req-a", "a", "2.0");
+
+        let err = router
+            .select_providers("claude", None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::ProviderBudgetExceeded(_)));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_detached_router_does_not_persist_health() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        let router = ProviderRouter::new(db.clone()).detached();
+        router
+            .record_result("a", "claude", false, false, Some("boom".to_string()))
+            .await
+            .unwrap();
+
+        let health = db.get_provider_health("a", "claude").await.unwrap();
+        assert_eq!(health.consecutive_failures, 0);
+        assert!(health.last_error.is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_ensure_within_budget_rejects_provider_over_limit() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().unwrap());
+
+        let provider_a = provider_with_daily_limit("a", "1.00");
+        db.save_provider("claude", &provider_a).unwrap();
+        let router = ProviderRouter::new(db.clone());
+        assert!(router
+            .ensure_within_budget("claude", &provider_a)
+            .await
+            .is_ok());
+
+        insert_cost_log(&db, "req-a", "a", "2.0");
+        assert!(matches!(
+            router.ensure_within_budget("claude", &provider_a).await,
+            Err(AppError::ProviderBudgetExceeded(_))
+        ));
+    }
 }
@@ -38,7 +38,6 @@ impl AuthInfo {
     ///
     /// 显示前4位和后4位，中间用 `...` 代替
     /// 如果 key 长度不足8位，则返回 `***`
-    #[allow(dead_code)]
     pub fn masked_key(&self) -> String {
         if self.api_key.chars().count() > 8 {
             let prefix: String = self.api_key.chars().take(4).collect();
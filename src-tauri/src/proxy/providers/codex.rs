@@ -1,6 +1,8 @@
 //! Codex (OpenAI) Provider Adapter
 //!
-//! 仅透传模式，支持直连 OpenAI API
+//! 默认透传模式，支持直连 OpenAI API；
+//! `meta.apiFormat = "openai_chat"` 的供应商仅支持 Chat Completions，
+//! `/responses` 请求会转换为 `/chat/completions` 后转发
 //!
 //! ## 客户端检测
 //! 支持检测官方 Codex 客户端 (codex_vscode, codex_cli_rs)
@@ -16,6 +18,21 @@ use std::sync::LazyLock;
 static CODEX_CLIENT_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^(codex_vscode|codex_cli_rs)/[\d.]+").unwrap());
 
+/// 获取 Codex 供应商的 API 格式
+///
+/// - "openai_responses"（默认）: 原生 Responses API，直接透传
+/// - "openai_chat": 仅支持 Chat Completions，Responses 请求需要转换
+pub fn get_codex_api_format(provider: &Provider) -> &'static str {
+    match provider
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.api_format.as_deref())
+    {
+        Some("openai_chat") => "openai_chat",
+        _ => "openai_responses",
+    }
+}
+
 /// Codex 适配器
 pub struct CodexAdapter;
 
@@ -180,6 +197,18 @@ impl ProviderAdapter for CodexAdapter {
             http::HeaderValue::from_str(&bearer).unwrap(),
         )]
     }
+
+    fn needs_transform(&self, provider: &Provider) -> bool {
+        get_codex_api_format(provider) == "openai_chat"
+    }
+
+    fn transform_request(
+        &self,
+        body: serde_json::Value,
+        _provider: &Provider,
+    ) -> Result<serde_json::Value, ProxyError> {
+        super::transform_responses_chat::responses_to_chat(body)
+    }
 }
 
 #[cfg(test)]
@@ -302,4 +331,19 @@ mod tests {
             "prefix_codex_cli_rs/1.0.0"
         ));
     }
+
+    #[test]
+    fn test_chat_api_format_needs_transform() {
+        let adapter = CodexAdapter::new();
+        let mut provider = create_provider(json!({}));
+        assert_eq!(get_codex_api_format(&provider), "openai_responses");
+        assert!(!adapter.needs_transform(&provider));
+
+        provider.meta = Some(crate::provider::ProviderMeta {
+            api_format: Some("openai_chat".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(get_codex_api_format(&provider), "openai_chat");
+        assert!(adapter.needs_transform(&provider));
+    }
 }
@@ -22,11 +22,15 @@ pub(crate) mod gemini_schema;
 pub mod gemini_shadow;
 pub mod models;
 pub mod streaming;
+pub mod streaming_chat;
 pub mod streaming_gemini;
 pub mod streaming_responses;
+pub mod streaming_responses_chat;
 pub mod transform;
+pub mod transform_chat;
 pub mod transform_gemini;
 pub mod transform_responses;
+pub mod transform_responses_chat;
 
 use crate::app_config::AppType;
 use crate::provider::Provider;
@@ -39,7 +43,7 @@ pub use claude::{
     claude_api_format_needs_transform, get_claude_api_format,
     transform_claude_request_for_api_format, ClaudeAdapter,
 };
-pub use codex::CodexAdapter;
+pub use codex::{get_codex_api_format, CodexAdapter};
 pub use gemini::GeminiAdapter;
 
 /// 供应商类型枚举
@@ -0,0 +1,282 @@
+//! OpenAI Chat Completions 入站流式转换模块
+//!
+//! 实现 Anthropic SSE → OpenAI `chat.completion.chunk` SSE 格式转换
+
+use super::transform_chat::{map_stop_reason, usage_to_openai};
+use crate::proxy::sse::{strip_sse_field, take_sse_block};
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// 流式转换状态
+struct ChatChunkState {
+    id: String,
+    model: String,
+    created: i64,
+    /// Anthropic content block index → OpenAI tool_calls index
+    tool_indices: HashMap<u64, usize>,
+    usage: Value,
+    finish_reason: Option<&'static str>,
+}
+
+impl ChatChunkState {
+    fn chunk(&self, delta: Value, finish_reason: Option<&str>) -> Bytes {
+        let chunk = json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": finish_reason
+            }]
+        });
+        sse_data(&chunk)
+    }
+
+    fn usage_chunk(&self) -> Bytes {
+        let chunk = json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [],
+            "usage": usage_to_openai(&self.usage)
+        });
+        sse_data(&chunk)
+    }
+}
+
+fn sse_data(value: &Value) -> Bytes {
+    Bytes::from(format!(
+        "data: {}\n\n",
+        serde_json::to_string(value).unwrap_or_default()
+    ))
+}
+
+/// 合并 Anthropic usage 字段（message_start 与 message_delta 各携带一部分）
+fn merge_usage(target: &mut Value, usage: &Value) {
+    if let (Some(target), Some(usage)) = (target.as_object_mut(), usage.as_object()) {
+        for (key, value) in usage {
+            if value.is_u64() {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// 创建 OpenAI Chat Completions SSE 流
+///
+/// `include_usage` 对应客户端请求的 `stream_options.include_usage`，
+/// 为 true 时在结束前追加一个 `choices` 为空、携带 usage 的 chunk。
+pub fn create_chat_sse_stream_from_anthropic<E: std::error::Error + Send + 'static>(
+    stream: impl Stream<Item = Result<Bytes, E>> + Send + 'static,
+    include_usage: bool,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send {
+    async_stream::stream! {
+        let mut buffer = String::new();
+        let mut utf8_remainder: Vec<u8> = Vec::new();
+        let mut state = ChatChunkState {
+            id: String::new(),
+            model: String::new(),
+            created: chrono::Utc::now().timestamp(),
+            tool_indices: HashMap::new(),
+            usage: json!({}),
+            finish_reason: None,
+        };
+        let mut done = false;
+
+        tokio::pin!(stream);
+
+        while let Some(chunk) = stream.next().await {
+            let bytes = match chunk {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    log::error!("[Claude/Chat] 流错误: {e}");
+                    yield Err(std::io::Error::other(e.to_string()));
+                    return;
+                }
+            };
+            crate::proxy::sse::append_utf8_safe(&mut buffer, &mut utf8_remainder, &bytes);
+
+            while let Some(block) = take_sse_block(&mut buffer) {
+                let Some(data) = block.lines().find_map(|l| strip_sse_field(l, "data")) else {
+                    continue;
+                };
+                let Ok(event) = serde_json::from_str::<Value>(data) else {
+                    continue;
+                };
+
+                match event.get("type").and_then(|t| t.as_str()).unwrap_or("") {
+                    "message_start" => {
+                        let message = &event["message"];
+                        state.id = message["id"].as_str().unwrap_or_default().to_string();
+                        state.model = message["model"].as_str().unwrap_or_default().to_string();
+                        merge_usage(&mut state.usage, &message["usage"]);
+                        yield Ok(state.chunk(json!({"role": "assistant", "content": ""}), None));
+                    }
+                    "content_block_start" => {
+                        let block = &event["content_block"];
+                        if block["type"] == "tool_use" {
+                            let tool_index = state.tool_indices.len();
+                            if let Some(index) = event["index"].as_u64() {
+                                state.tool_indices.insert(index, tool_index);
+                            }
+                            yield Ok(state.chunk(json!({
+                                "tool_calls": [{
+                                    "index": tool_index,
+                                    "id": block["id"],
+                                    "type": "function",
+                                    "function": {"name": block["name"], "arguments": ""}
+                                }]
+                            }), None));
+                        }
+                    }
+                    "content_block_delta" => {
+                        let delta = &event["delta"];
+                        match delta["type"].as_str().unwrap_or("") {
+                            "text_delta" => {
+                                yield Ok(state.chunk(json!({"content": delta["text"]}), None));
+                            }
+                            "thinking_delta" => {
+                                yield Ok(state.chunk(
+                                    json!({"reasoning_content": delta["thinking"]}),
+                                    None,
+                                ));
+                            }
+                            "input_json_delta" => {
+                                let tool_index = event["index"]
+                                    .as_u64()
+                                    .and_then(|i| state.tool_indices.get(&i).copied());
+                                if let Some(tool_index) = tool_index {
+                                    yield Ok(state.chunk(json!({
+                                        "tool_calls": [{
+                                            "index": tool_index,
+                                            "function": {"arguments": delta["partial_json"]}
+                                        }]
+                                    }), None));
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    "message_delta" => {
+                        merge_usage(&mut state.usage, &event["usage"]);
+                        let finish_reason =
+                            map_stop_reason(event.pointer("/delta/stop_reason").and_then(|r| r.as_str()));
+                        state.finish_reason = Some(finish_reason);
+                        yield Ok(state.chunk(json!({}), Some(finish_reason)));
+                    }
+                    "message_stop" => {
+                        if include_usage {
+                            yield Ok(state.usage_chunk());
+                        }
+                        yield Ok(Bytes::from("data: [DONE]\n\n"));
+                        done = true;
+                    }
+                    "error" => {
+                        log::warn!("[Claude/Chat] 上游流式错误: {data}");
+                        yield Ok(sse_data(&json!({"error": event["error"]})));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // 上游未发送 message_stop 时补齐结束标记，避免客户端一直等待
+        if !done {
+            if state.finish_reason.is_none() && !state.id.is_empty() {
+                yield Ok(state.chunk(json!({}), Some("stop")));
+            }
+            yield Ok(Bytes::from("data: [DONE]\n\n"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    async fn convert(input: &str, include_usage: bool) -> Vec<Value> {
+        let upstream = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(
+            input.as_bytes().to_vec(),
+        ))]);
+        let chunks: Vec<_> = create_chat_sse_stream_from_anthropic(upstream, include_usage)
+            .collect()
+            .await;
+        let merged = chunks
+            .into_iter()
+            .map(|c| String::from_utf8_lossy(c.unwrap().as_ref()).to_string())
+            .collect::<String>();
+        assert!(merged.ends_with("data: [DONE]\n\n"));
+        merged
+            .split("\n\n")
+            .filter_map(|block| strip_sse_field(block, "data"))
+            .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_text_stream_with_usage() {
+        let input = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"claude-sonnet-4-5\",\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\n\n",
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":7}}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\":\"message_stop\"}\n\n"
+        );
+
+        let chunks = convert(input, true).await;
+        assert_eq!(chunks[0]["object"], "chat.completion.chunk");
+        assert_eq!(chunks[0]["id"], "msg_1");
+        assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(chunks[1]["choices"][0]["delta"]["content"], "Hello");
+        assert_eq!(chunks[2]["choices"][0]["finish_reason"], "stop");
+
+        let usage_chunk = chunks.last().unwrap();
+        assert!(usage_chunk["choices"].as_array().unwrap().is_empty());
+        assert_eq!(usage_chunk["usage"]["prompt_tokens"], 12);
+        assert_eq!(usage_chunk["usage"]["completion_tokens"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_tool_use_stream() {
+        let input = concat!(
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_2\",\"model\":\"claude-sonnet-4-5\",\"usage\":{\"input_tokens\":5}}}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Checking\"}}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"get_weather\",\"input\":{}}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"city\\\":\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"Tokyo\\\"}\"}}\n\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":9}}\n\n",
+            "data: {\"type\":\"message_stop\"}\n\n"
+        );
+
+        let chunks = convert(input, false).await;
+        let tool_start = &chunks[2]["choices"][0]["delta"]["tool_calls"][0];
+        assert_eq!(tool_start["index"], 0);
+        assert_eq!(tool_start["id"], "toolu_1");
+        assert_eq!(tool_start["function"]["name"], "get_weather");
+
+        let arguments: String = chunks
+            .iter()
+            .filter_map(|c| c.pointer("/choices/0/delta/tool_calls/0/function/arguments"))
+            .filter_map(|a| a.as_str())
+            .collect();
+        assert_eq!(arguments, "{\"city\":\"Tokyo\"}");
+        assert_eq!(
+            chunks.last().unwrap()["choices"][0]["finish_reason"],
+            "tool_calls"
+        );
+        // 未请求 include_usage 时不追加 usage chunk
+        assert!(chunks.iter().all(|c| c.get("usage").is_none()));
+    }
+}
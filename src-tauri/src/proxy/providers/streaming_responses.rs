@@ -671,7 +671,11 @@ pub fn create_anthropic_sse_stream_from_responses<E: std::error::Error + Send +
                                 fallback_open_index = None;
 
                                 let usage_json = response_obj.get("usage").map(|u| {
-                                    build_anthropic_usage_from_responses(Some(u))
+                                    let mut usage = build_anthropic_usage_from_responses(Some(u));
+                                    if let Some(tier) = response_obj.get("service_tier").and_then(|t| t.as_str()) {
+                                        usage["service_tier"] = json!(tier);
+                                    }
+                                    usage
                                 });
 
                                 // Emit message_delta (with usage + stop_reason)
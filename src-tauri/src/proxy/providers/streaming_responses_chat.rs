@@ -0,0 +1,459 @@
+//! Chat Completions → Responses 流式转换模块
+//!
+//! 实现 OpenAI `chat.completion.chunk` SSE → OpenAI Responses SSE 事件格式转换，
+//! 用于仅支持 Chat Completions 的 Codex 供应商
+
+use super::transform_responses_chat::{
+    apply_status, function_call_item, message_item, usage_to_responses,
+};
+use crate::proxy::sse::{strip_sse_field, take_sse_block};
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use serde_json::{json, Value};
+
+/// 正在输出的文本消息
+struct TextItem {
+    output_index: usize,
+    id: String,
+    text: String,
+}
+
+/// 正在输出的函数调用（按 Chat tool_calls 的 index 区分）
+struct ToolCallItem {
+    chat_index: u64,
+    output_index: usize,
+    call_id: String,
+    name: String,
+    arguments: String,
+}
+
+/// 流式转换状态
+#[derive(Default)]
+struct ResponsesStreamState {
+    response_id: String,
+    model: String,
+    created_at: i64,
+    started: bool,
+    finished: bool,
+    next_output_index: usize,
+    text_item: Option<TextItem>,
+    tool_calls: Vec<ToolCallItem>,
+    /// 已完成的 output item（output_index, item）
+    completed_items: Vec<(usize, Value)>,
+    finish_reason: Option<String>,
+    usage: Option<Value>,
+}
+
+impl ResponsesStreamState {
+    fn response_object(&self, status: &str) -> Value {
+        json!({
+            "id": self.response_id,
+            "object": "response",
+            "created_at": self.created_at,
+            "status": status,
+            "model": self.model,
+            "output": []
+        })
+    }
+
+    fn start(&mut self, chunk: &Value) -> Vec<Bytes> {
+        self.started = true;
+        let chat_id = chunk.get("id").and_then(|i| i.as_str()).unwrap_or_default();
+        self.response_id = format!("resp_{chat_id}");
+        self.model = chunk
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or_default()
+            .to_string();
+        self.created_at = chunk
+            .get("created")
+            .and_then(|c| c.as_i64())
+            .unwrap_or_else(|| chrono::Utc::now().timestamp());
+
+        let response = self.response_object("in_progress");
+        vec![
+            event("response.created", json!({"response": response})),
+            event("response.in_progress", json!({"response": response})),
+        ]
+    }
+
+    fn text_delta(&mut self, delta: &str) -> Vec<Bytes> {
+        let mut events = Vec::new();
+        if self.text_item.is_none() {
+            let output_index = self.next_output_index;
+            self.next_output_index += 1;
+            let id = format!("msg_{}_{output_index}", self.response_id);
+            events.push(event(
+                "response.output_item.added",
+                json!({
+                    "output_index": output_index,
+                    "item": {
+                        "type": "message",
+                        "id": id,
+                        "status": "in_progress",
+                        "role": "assistant",
+                        "content": []
+                    }
+                }),
+            ));
+            events.push(event(
+                "response.content_part.added",
+                json!({
+                    "item_id": id,
+                    "output_index": output_index,
+                    "content_index": 0,
+                    "part": {"type": "output_text", "text": "", "annotations": []}
+                }),
+            ));
+            self.text_item = Some(TextItem {
+                output_index,
+                id,
+                text: String::new(),
+            });
+        }
+
+        if let Some(item) = self.text_item.as_mut() {
+            item.text.push_str(delta);
+            events.push(event(
+                "response.output_text.delta",
+                json!({
+                    "item_id": item.id,
+                    "output_index": item.output_index,
+                    "content_index": 0,
+                    "delta": delta
+                }),
+            ));
+        }
+        events
+    }
+
+    fn close_text_item(&mut self) -> Vec<Bytes> {
+        let Some(item) = self.text_item.take() else {
+            return Vec::new();
+        };
+        let done_item = message_item(&item.id, &item.text, "completed");
+        let events = vec![
+            event(
+                "response.output_text.done",
+                json!({
+                    "item_id": item.id,
+                    "output_index": item.output_index,
+                    "content_index": 0,
+                    "text": item.text
+                }),
+            ),
+            event(
+                "response.content_part.done",
+                json!({
+                    "item_id": item.id,
+                    "output_index": item.output_index,
+                    "content_index": 0,
+                    "part": {"type": "output_text", "text": item.text, "annotations": []}
+                }),
+            ),
+            event(
+                "response.output_item.done",
+                json!({"output_index": item.output_index, "item": done_item}),
+            ),
+        ];
+        self.completed_items.push((item.output_index, done_item));
+        events
+    }
+
+    fn tool_call_delta(&mut self, tool_call: &Value) -> Vec<Bytes> {
+        let mut events = Vec::new();
+        let chat_index = tool_call.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+
+        let position = match self
+            .tool_calls
+            .iter()
+            .position(|c| c.chat_index == chat_index)
+        {
+            Some(position) => position,
+            None => {
+                // 文本消息先于函数调用结束
+                events.extend(self.close_text_item());
+                let output_index = self.next_output_index;
+                self.next_output_index += 1;
+                let call_id = tool_call
+                    .get("id")
+                    .and_then(|i| i.as_str())
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("call_{}_{output_index}", self.response_id));
+                let name = tool_call
+                    .pointer("/function/name")
+                    .and_then(|n| n.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                events.push(event(
+                    "response.output_item.added",
+                    json!({
+                        "output_index": output_index,
+                        "item": function_call_item(&call_id, &name, "", "in_progress")
+                    }),
+                ));
+                self.tool_calls.push(ToolCallItem {
+                    chat_index,
+                    output_index,
+                    call_id,
+                    name,
+                    arguments: String::new(),
+                });
+                self.tool_calls.len() - 1
+            }
+        };
+
+        if let Some(arguments) = tool_call
+            .pointer("/function/arguments")
+            .and_then(|a| a.as_str())
+            .filter(|a| !a.is_empty())
+        {
+            let call = &mut self.tool_calls[position];
+            call.arguments.push_str(arguments);
+            events.push(event(
+                "response.function_call_arguments.delta",
+                json!({
+                    "item_id": format!("fc_{}", call.call_id),
+                    "output_index": call.output_index,
+                    "delta": arguments
+                }),
+            ));
+        }
+        events
+    }
+
+    fn finish(&mut self) -> Vec<Bytes> {
+        if self.finished || !self.started {
+            return Vec::new();
+        }
+        self.finished = true;
+
+        let mut events = self.close_text_item();
+        for call in std::mem::take(&mut self.tool_calls) {
+            let item = function_call_item(&call.call_id, &call.name, &call.arguments, "completed");
+            events.push(event(
+                "response.function_call_arguments.done",
+                json!({
+                    "item_id": format!("fc_{}", call.call_id),
+                    "output_index": call.output_index,
+                    "arguments": call.arguments
+                }),
+            ));
+            events.push(event(
+                "response.output_item.done",
+                json!({"output_index": call.output_index, "item": item}),
+            ));
+            self.completed_items.push((call.output_index, item));
+        }
+
+        self.completed_items.sort_by_key(|(index, _)| *index);
+        let mut response = self.response_object("completed");
+        response["output"] = json!(self
+            .completed_items
+            .iter()
+            .map(|(_, item)| item.clone())
+            .collect::<Vec<_>>());
+        apply_status(&mut response, self.finish_reason.as_deref());
+        if let Some(usage) = &self.usage {
+            response["usage"] = usage_to_responses(usage);
+        }
+        events.push(event("response.completed", json!({"response": response})));
+        events
+    }
+}
+
+/// 生成 Responses SSE 事件（`type` 字段与 event 名一致）
+fn event(event_type: &str, mut data: Value) -> Bytes {
+    data["type"] = json!(event_type);
+    Bytes::from(format!(
+        "event: {event_type}\ndata: {}\n\n",
+        serde_json::to_string(&data).unwrap_or_default()
+    ))
+}
+
+/// 创建 OpenAI Responses SSE 流
+pub fn create_responses_sse_stream_from_chat<E: std::error::Error + Send + 'static>(
+    stream: impl Stream<Item = Result<Bytes, E>> + Send + 'static,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send {
+    async_stream::stream! {
+        let mut buffer = String::new();
+        let mut utf8_remainder: Vec<u8> = Vec::new();
+        let mut state = ResponsesStreamState::default();
+
+        tokio::pin!(stream);
+
+        while let Some(chunk) = stream.next().await {
+            let bytes = match chunk {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    log::error!("[Codex/Chat] 流错误: {e}");
+                    yield Err(std::io::Error::other(e.to_string()));
+                    return;
+                }
+            };
+            crate::proxy::sse::append_utf8_safe(&mut buffer, &mut utf8_remainder, &bytes);
+
+            while let Some(block) = take_sse_block(&mut buffer) {
+                let Some(data) = block.lines().find_map(|l| strip_sse_field(l, "data")) else {
+                    continue;
+                };
+                if data.trim() == "[DONE]" {
+                    for bytes in state.finish() {
+                        yield Ok(bytes);
+                    }
+                    continue;
+                }
+                let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+                    continue;
+                };
+
+                if let Some(error) = chunk.get("error") {
+                    log::warn!("[Codex/Chat] 上游流式错误: {data}");
+                    let mut response = state.response_object("failed");
+                    response["error"] = json!({
+                        "code": error.get("code").cloned().unwrap_or_else(|| json!("upstream_error")),
+                        "message": error.get("message").cloned().unwrap_or_else(|| json!(error.to_string()))
+                    });
+                    state.finished = true;
+                    yield Ok(event("response.failed", json!({"response": response})));
+                    continue;
+                }
+
+                if !state.started {
+                    for bytes in state.start(&chunk) {
+                        yield Ok(bytes);
+                    }
+                }
+                if let Some(usage) = chunk.get("usage").filter(|u| u.is_object()) {
+                    state.usage = Some(usage.clone());
+                }
+
+                let Some(choice) = chunk.pointer("/choices/0") else {
+                    continue;
+                };
+                if let Some(content) = choice.pointer("/delta/content").and_then(|c| c.as_str()) {
+                    if !content.is_empty() {
+                        for bytes in state.text_delta(content) {
+                            yield Ok(bytes);
+                        }
+                    }
+                }
+                if let Some(tool_calls) = choice.pointer("/delta/tool_calls").and_then(|t| t.as_array()) {
+                    for tool_call in tool_calls {
+                        for bytes in state.tool_call_delta(tool_call) {
+                            yield Ok(bytes);
+                        }
+                    }
+                }
+                if let Some(reason) = choice.get("finish_reason").and_then(|r| r.as_str()) {
+                    state.finish_reason = Some(reason.to_string());
+                }
+            }
+        }
+
+        // 上游未发送 [DONE] 时同样补齐 response.completed
+        for bytes in state.finish() {
+            yield Ok(bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    async fn convert(input: &str) -> Vec<Value> {
+        let upstream = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(
+            input.as_bytes().to_vec(),
+        ))]);
+        let chunks: Vec<_> = create_responses_sse_stream_from_chat(upstream)
+            .collect()
+            .await;
+        let merged = chunks
+            .into_iter()
+            .map(|c| String::from_utf8_lossy(c.unwrap().as_ref()).to_string())
+            .collect::<String>();
+        merged
+            .split("\n\n")
+            .filter_map(|block| block.lines().find_map(|l| strip_sse_field(l, "data")))
+            .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+            .collect()
+    }
+
+    fn types(events: &[Value]) -> Vec<&str> {
+        events.iter().filter_map(|e| e["type"].as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_text_stream() {
+        let input = concat!(
+            "data: {\"id\":\"chatcmpl-1\",\"created\":1700000000,\"model\":\"qwen3-coder\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"qwen3-coder\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hello\"}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"qwen3-coder\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\" world\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"qwen3-coder\",\"choices\":[],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":3}}\n\n",
+            "data: [DONE]\n\n"
+        );
+
+        let events = convert(input).await;
+        assert_eq!(
+            types(&events),
+            vec![
+                "response.created",
+                "response.in_progress",
+                "response.output_item.added",
+                "response.content_part.added",
+                "response.output_text.delta",
+                "response.output_text.delta",
+                "response.output_text.done",
+                "response.content_part.done",
+                "response.output_item.done",
+                "response.completed",
+            ]
+        );
+
+        let completed = &events.last().unwrap()["response"];
+        assert_eq!(completed["id"], "resp_chatcmpl-1");
+        assert_eq!(completed["status"], "completed");
+        assert_eq!(completed["output"][0]["content"][0]["text"], "Hello world");
+        assert_eq!(completed["usage"]["input_tokens"], 12);
+        assert_eq!(completed["usage"]["output_tokens"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_function_call_stream() {
+        let input = concat!(
+            "data: {\"id\":\"chatcmpl-2\",\"model\":\"qwen3-coder\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Running\"}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-2\",\"model\":\"qwen3-coder\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"shell\",\"arguments\":\"\"}}]}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-2\",\"model\":\"qwen3-coder\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"command\\\":\"}}]}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-2\",\"model\":\"qwen3-coder\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"[\\\"ls\\\"]}\"}}]},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: [DONE]\n\n"
+        );
+
+        let events = convert(input).await;
+        let added: Vec<&Value> = events
+            .iter()
+            .filter(|e| e["type"] == "response.output_item.added")
+            .collect();
+        assert_eq!(added.len(), 2);
+        assert_eq!(added[1]["output_index"], 1);
+        assert_eq!(added[1]["item"]["type"], "function_call");
+        assert_eq!(added[1]["item"]["call_id"], "call_1");
+        assert_eq!(added[1]["item"]["name"], "shell");
+
+        let arguments: String = events
+            .iter()
+            .filter(|e| e["type"] == "response.function_call_arguments.delta")
+            .filter_map(|e| e["delta"].as_str())
+            .collect();
+        assert_eq!(arguments, "{\"command\":[\"ls\"]}");
+
+        let completed = &events.last().unwrap()["response"];
+        assert_eq!(completed["output"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            completed["output"][1]["arguments"],
+            "{\"command\":[\"ls\"]}"
+        );
+        assert!(completed.get("usage").is_none());
+    }
+}
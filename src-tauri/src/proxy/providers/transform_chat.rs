@@ -0,0 +1,465 @@
+//! OpenAI Chat Completions 入站转换模块
+//!
+//! 与 `transform` 方向相反：客户端使用 OpenAI Chat Completions 格式，
+//! 上游是 Claude 格式供应商。
+//! - 请求：OpenAI Chat → Anthropic Messages
+//! - 响应：Anthropic Messages → OpenAI Chat
+
+use crate::proxy::error::ProxyError;
+use serde_json::{json, Map, Value};
+
+/// Anthropic 要求必须携带 max_tokens，客户端未指定时使用该值
+const DEFAULT_MAX_TOKENS: u64 = 8192;
+
+/// OpenAI Chat Completions 请求 → Anthropic Messages 请求
+pub fn openai_chat_to_anthropic(body: Value) -> Result<Value, ProxyError> {
+    let msgs = body
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .ok_or_else(|| ProxyError::TransformError("Missing messages array".to_string()))?;
+
+    let mut result = json!({});
+    if let Some(model) = body.get("model") {
+        result["model"] = model.clone();
+    }
+
+    let mut system_blocks: Vec<Value> = Vec::new();
+    let mut messages: Vec<Value> = Vec::new();
+
+    for msg in msgs {
+        let role = msg.get("role").and_then(|r| r.as_str()).unwrap_or("user");
+        match role {
+            "system" | "developer" => {
+                for text in content_texts(msg.get("content")) {
+                    system_blocks.push(json!({"type": "text", "text": text}));
+                }
+            }
+            "assistant" => {
+                let mut content = convert_content(msg.get("content"));
+                if let Some(calls) = msg.get("tool_calls").and_then(|t| t.as_array()) {
+                    for call in calls {
+                        content.push(convert_tool_call(call));
+                    }
+                }
+                push_message(&mut messages, "assistant", content);
+            }
+            "tool" => {
+                let tool_use_id = msg
+                    .get("tool_call_id")
+                    .and_then(|id| id.as_str())
+                    .unwrap_or_default();
+                let text = content_texts(msg.get("content")).join("\n");
+                push_message(
+                    &mut messages,
+                    "user",
+                    vec![json!({
+                        "type": "tool_result",
+                        "tool_use_id": tool_use_id,
+                        "content": text
+                    })],
+                );
+            }
+            _ => {
+                let content = convert_content(msg.get("content"));
+                push_message(&mut messages, "user", content);
+            }
+        }
+    }
+
+    if !system_blocks.is_empty() {
+        result["system"] = json!(system_blocks);
+    }
+    result["messages"] = json!(messages);
+
+    // 参数转换
+    let max_tokens = body
+        .get("max_completion_tokens")
+        .or_else(|| body.get("max_tokens"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_MAX_TOKENS);
+    result["max_tokens"] = json!(max_tokens);
+    if let Some(v) = body.get("temperature") {
+        result["temperature"] = v.clone();
+    }
+    if let Some(v) = body.get("top_p") {
+        result["top_p"] = v.clone();
+    }
+    match body.get("stop") {
+        Some(Value::String(s)) => result["stop_sequences"] = json!([s]),
+        Some(Value::Array(arr)) if !arr.is_empty() => result["stop_sequences"] = json!(arr),
+        _ => {}
+    }
+    if let Some(v) = body.get("stream") {
+        result["stream"] = v.clone();
+    }
+    if let Some(user) = body.get("user").and_then(|u| u.as_str()) {
+        result["metadata"] = json!({"user_id": user});
+    }
+
+    // 工具定义
+    if let Some(tools) = body.get("tools").and_then(|t| t.as_array()) {
+        let anthropic_tools: Vec<Value> = tools
+            .iter()
+            .filter(|t| t.get("type").and_then(|v| v.as_str()).unwrap_or("function") == "function")
+            .filter_map(|t| {
+                let function = t.get("function")?;
+                let mut tool = json!({
+                    "name": function.get("name")?.clone(),
+                    "input_schema": function
+                        .get("parameters")
+                        .cloned()
+                        .unwrap_or_else(|| json!({"type": "object", "properties": {}}))
+                });
+                if let Some(desc) = function.get("description") {
+                    tool["description"] = desc.clone();
+                }
+                Some(tool)
+            })
+            .collect();
+        if !anthropic_tools.is_empty() {
+            result["tools"] = json!(anthropic_tools);
+        }
+    }
+
+    let mut tool_choice = match body.get("tool_choice") {
+        Some(Value::String(s)) => match s.as_str() {
+            "none" => Some(json!({"type": "none"})),
+            "required" => Some(json!({"type": "any"})),
+            _ => Some(json!({"type": "auto"})),
+        },
+        Some(Value::Object(obj)) => obj
+            .get("function")
+            .and_then(|f| f.get("name"))
+            .map(|name| json!({"type": "tool", "name": name})),
+        _ => None,
+    };
+    if body.get("parallel_tool_calls") == Some(&Value::Bool(false)) && result.get("tools").is_some()
+    {
+        let choice = tool_choice.get_or_insert_with(|| json!({"type": "auto"}));
+        if choice["type"] != "none" {
+            choice["disable_parallel_tool_use"] = json!(true);
+        }
+    }
+    if let Some(choice) = tool_choice {
+        result["tool_choice"] = choice;
+    }
+
+    Ok(result)
+}
+
+/// Anthropic Messages 响应 → OpenAI Chat Completions 响应
+pub fn anthropic_to_openai_chat(body: Value) -> Result<Value, ProxyError> {
+    let content = body
+        .get("content")
+        .and_then(|c| c.as_array())
+        .ok_or_else(|| ProxyError::TransformError("No content in response".to_string()))?;
+
+    let mut text = String::new();
+    let mut reasoning = String::new();
+    let mut tool_calls = Vec::new();
+    for block in content {
+        match block.get("type").and_then(|t| t.as_str()) {
+            Some("text") => {
+                if let Some(t) = block.get("text").and_then(|t| t.as_str()) {
+                    text.push_str(t);
+                }
+            }
+            Some("thinking") => {
+                if let Some(t) = block.get("thinking").and_then(|t| t.as_str()) {
+                    reasoning.push_str(t);
+                }
+            }
+            Some("tool_use") => {
+                let arguments = block
+                    .get("input")
+                    .map(|input| serde_json::to_string(input).unwrap_or_default())
+                    .unwrap_or_else(|| "{}".to_string());
+                tool_calls.push(json!({
+                    "id": block.get("id").cloned().unwrap_or_default(),
+                    "type": "function",
+                    "function": {
+                        "name": block.get("name").cloned().unwrap_or_default(),
+                        "arguments": arguments
+                    }
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    let mut message = json!({
+        "role": "assistant",
+        "content": if text.is_empty() && !tool_calls.is_empty() { Value::Null } else { json!(text) }
+    });
+    if !reasoning.is_empty() {
+        message["reasoning_content"] = json!(reasoning);
+    }
+    if !tool_calls.is_empty() {
+        message["tool_calls"] = json!(tool_calls);
+    }
+
+    let mut result = json!({
+        "id": body.get("id").cloned().unwrap_or_default(),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": body.get("model").cloned().unwrap_or_default(),
+        "choices": [{
+            "index": 0,
+            "message": message,
+            "finish_reason": map_stop_reason(body.get("stop_reason").and_then(|r| r.as_str()))
+        }]
+    });
+    if let Some(usage) = body.get("usage") {
+        result["usage"] = usage_to_openai(usage);
+    }
+
+    Ok(result)
+}
+
+/// Anthropic stop_reason → OpenAI finish_reason
+pub fn map_stop_reason(stop_reason: Option<&str>) -> &'static str {
+    match stop_reason {
+        Some("max_tokens") | Some("model_context_window_exceeded") => "length",
+        Some("tool_use") => "tool_calls",
+        Some("refusal") => "content_filter",
+        _ => "stop",
+    }
+}
+
+/// Anthropic usage → OpenAI usage
+///
+/// OpenAI 的 prompt_tokens 包含缓存命中部分，因此需要把缓存读写 token 加回输入
+pub fn usage_to_openai(usage: &Value) -> Value {
+    let get = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    let cache_read = get("cache_read_input_tokens");
+    let prompt_tokens = get("input_tokens") + cache_read + get("cache_creation_input_tokens");
+    let completion_tokens = get("output_tokens");
+    json!({
+        "prompt_tokens": prompt_tokens,
+        "completion_tokens": completion_tokens,
+        "total_tokens": prompt_tokens + completion_tokens,
+        "prompt_tokens_details": {"cached_tokens": cache_read}
+    })
+}
+
+/// 追加消息，连续同角色的消息合并为一条（tool_result 必须紧跟在 tool_use 之后的 user 消息中）
+fn push_message(messages: &mut Vec<Value>, role: &str, content: Vec<Value>) {
+    if content.is_empty() {
+        return;
+    }
+    if let Some(last) = messages.last_mut() {
+        if last["role"] == role {
+            if let Some(arr) = last["content"].as_array_mut() {
+                arr.extend(content);
+                return;
+            }
+        }
+    }
+    messages.push(json!({"role": role, "content": content}));
+}
+
+/// 提取消息中的纯文本片段
+fn content_texts(content: Option<&Value>) -> Vec<String> {
+    match content {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .map(String::from)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// OpenAI 消息内容 → Anthropic content blocks
+fn convert_content(content: Option<&Value>) -> Vec<Value> {
+    match content {
+        Some(Value::String(s)) if !s.is_empty() => vec![json!({"type": "text", "text": s})],
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| match part.get("type").and_then(|t| t.as_str()) {
+                Some("text") => part
+                    .get("text")
+                    .and_then(|t| t.as_str())
+                    .map(|t| json!({"type": "text", "text": t})),
+                Some("image_url") => part
+                    .pointer("/image_url/url")
+                    .and_then(|u| u.as_str())
+                    .map(convert_image_url),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// image_url（data URL 或普通 URL）→ Anthropic image block
+fn convert_image_url(url: &str) -> Value {
+    if let Some(rest) = url.strip_prefix("data:") {
+        if let Some((media_type, data)) = rest.split_once(";base64,") {
+            return json!({
+                "type": "image",
+                "source": {"type": "base64", "media_type": media_type, "data": data}
+            });
+        }
+    }
+    json!({"type": "image", "source": {"type": "url", "url": url}})
+}
+
+/// OpenAI tool_call → Anthropic tool_use block
+fn convert_tool_call(call: &Value) -> Value {
+    let function = call.get("function");
+    let input = function
+        .and_then(|f| f.get("arguments"))
+        .and_then(|a| a.as_str())
+        .and_then(|a| serde_json::from_str::<Value>(a).ok())
+        .filter(|v| v.is_object())
+        .unwrap_or_else(|| Value::Object(Map::new()));
+    json!({
+        "type": "tool_use",
+        "id": call.get("id").cloned().unwrap_or_default(),
+        "name": function.and_then(|f| f.get("name")).cloned().unwrap_or_default(),
+        "input": input
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_system_and_params() {
+        let input = json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [
+                {"role": "system", "content": "You are helpful."},
+                {"role": "user", "content": "Hello"}
+            ],
+            "max_completion_tokens": 1024,
+            "temperature": 0.2,
+            "stop": "END",
+            "stream": true
+        });
+
+        let result = openai_chat_to_anthropic(input).unwrap();
+        assert_eq!(result["model"], "claude-sonnet-4-5");
+        assert_eq!(result["system"][0]["text"], "You are helpful.");
+        assert_eq!(result["messages"][0]["role"], "user");
+        assert_eq!(result["messages"][0]["content"][0]["text"], "Hello");
+        assert_eq!(result["max_tokens"], 1024);
+        assert_eq!(result["stop_sequences"], json!(["END"]));
+        assert_eq!(result["stream"], true);
+    }
+
+    #[test]
+    fn test_request_default_max_tokens_and_image() {
+        let input = json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "What is this?"},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBOR"}}
+                ]
+            }]
+        });
+
+        let result = openai_chat_to_anthropic(input).unwrap();
+        assert_eq!(result["max_tokens"], DEFAULT_MAX_TOKENS);
+        let image = &result["messages"][0]["content"][1];
+        assert_eq!(image["type"], "image");
+        assert_eq!(image["source"]["media_type"], "image/png");
+        assert_eq!(image["source"]["data"], "iVBOR");
+    }
+
+    #[test]
+    fn test_request_tools_and_tool_results() {
+        let input = json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [
+                {"role": "user", "content": "Weather in Tokyo and Paris?"},
+                {"role": "assistant", "content": null, "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Tokyo\"}"}},
+                    {"id": "call_2", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "Sunny"},
+                {"role": "tool", "tool_call_id": "call_2", "content": "Rainy"}
+            ],
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Get weather",
+                    "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+                }
+            }],
+            "tool_choice": "required",
+            "parallel_tool_calls": false
+        });
+
+        let result = openai_chat_to_anthropic(input).unwrap();
+        assert_eq!(result["tools"][0]["name"], "get_weather");
+        assert_eq!(result["tools"][0]["input_schema"]["type"], "object");
+        assert_eq!(result["tool_choice"]["type"], "any");
+        assert_eq!(result["tool_choice"]["disable_parallel_tool_use"], true);
+
+        let messages = result["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["content"][0]["type"], "tool_use");
+        assert_eq!(messages[1]["content"][0]["id"], "call_1");
+        assert_eq!(messages[1]["content"][1]["input"]["city"], "Paris");
+        // 连续的 tool 消息合并为同一条 user 消息
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "call_1");
+        assert_eq!(messages[2]["content"][1]["content"], "Rainy");
+    }
+
+    #[test]
+    fn test_response_text_and_usage() {
+        let input = json!({
+            "id": "msg_1",
+            "type": "message",
+            "model": "claude-sonnet-4-5",
+            "content": [{"type": "text", "text": "Hi there"}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 10, "output_tokens": 5, "cache_read_input_tokens": 20}
+        });
+
+        let result = anthropic_to_openai_chat(input).unwrap();
+        assert_eq!(result["object"], "chat.completion");
+        assert_eq!(result["choices"][0]["message"]["content"], "Hi there");
+        assert_eq!(result["choices"][0]["finish_reason"], "stop");
+        assert_eq!(result["usage"]["prompt_tokens"], 30);
+        assert_eq!(result["usage"]["completion_tokens"], 5);
+        assert_eq!(
+            result["usage"]["prompt_tokens_details"]["cached_tokens"],
+            20
+        );
+    }
+
+    #[test]
+    fn test_response_tool_use() {
+        let input = json!({
+            "id": "msg_2",
+            "model": "claude-sonnet-4-5",
+            "content": [{
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": "get_weather",
+                "input": {"city": "Tokyo"}
+            }],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 10, "output_tokens": 5}
+        });
+
+        let result = anthropic_to_openai_chat(input).unwrap();
+        let message = &result["choices"][0]["message"];
+        assert!(message["content"].is_null());
+        assert_eq!(message["tool_calls"][0]["id"], "toolu_1");
+        assert_eq!(
+            message["tool_calls"][0]["function"]["arguments"],
+            "{\"city\":\"Tokyo\"}"
+        );
+        assert_eq!(result["choices"][0]["finish_reason"], "tool_calls");
+    }
+}
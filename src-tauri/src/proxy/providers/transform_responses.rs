@@ -500,7 +500,11 @@ pub fn responses_to_anthropic(body: Value) -> Result<Value, ProxyError> {
             .and_then(|r| r.as_str()),
     );
 
-    let usage_json = build_anthropic_usage_from_responses(body.get("usage"));
+    let mut usage_json = build_anthropic_usage_from_responses(body.get("usage"));
+    // 保留实际服务等级（如 FAST mode 的 priority），用于按服务等级计费
+    if let Some(tier) = body.get("service_tier").and_then(|t| t.as_str()) {
+        usage_json["service_tier"] = json!(tier);
+    }
 
     let result = json!({
         "id": body.get("id").and_then(|i| i.as_str()).unwrap_or(""),
@@ -0,0 +1,445 @@
+//! Responses ↔ Chat Completions 格式转换模块
+//!
+//! 用于仅支持 `/v1/chat/completions` 的 Codex 供应商（`meta.apiFormat = "openai_chat"`）：
+//! - 请求：OpenAI Responses → OpenAI Chat Completions
+//! - 响应：OpenAI Chat Completions → OpenAI Responses
+
+use crate::proxy::error::ProxyError;
+use serde_json::{json, Value};
+
+/// OpenAI Responses 请求 → OpenAI Chat Completions 请求
+pub fn responses_to_chat(body: Value) -> Result<Value, ProxyError> {
+    let mut result = json!({});
+    if let Some(model) = body.get("model") {
+        result["model"] = model.clone();
+    }
+
+    let mut messages: Vec<Value> = Vec::new();
+
+    if let Some(instructions) = body.get("instructions").and_then(|i| i.as_str()) {
+        if !instructions.is_empty() {
+            messages.push(json!({"role": "system", "content": instructions}));
+        }
+    }
+
+    match body.get("input") {
+        Some(Value::String(text)) => messages.push(json!({"role": "user", "content": text})),
+        Some(Value::Array(items)) => {
+            for item in items {
+                convert_input_item(item, &mut messages);
+            }
+        }
+        _ => {}
+    }
+    result["messages"] = json!(messages);
+
+    // 参数转换
+    if let Some(v) = body.get("max_output_tokens") {
+        result["max_tokens"] = v.clone();
+    }
+    for key in ["temperature", "top_p", "parallel_tool_calls", "user"] {
+        if let Some(v) = body.get(key) {
+            result[key] = v.clone();
+        }
+    }
+    if let Some(effort) = body.pointer("/reasoning/effort") {
+        result["reasoning_effort"] = effort.clone();
+    }
+    if body.get("stream").and_then(|s| s.as_bool()) == Some(true) {
+        result["stream"] = json!(true);
+        // 要求上游在流末尾返回 usage，用于 response.completed 和消费记录
+        result["stream_options"] = json!({"include_usage": true});
+    }
+
+    // 结构化输出
+    if let Some(format) = body.pointer("/text/format") {
+        match format.get("type").and_then(|t| t.as_str()) {
+            Some("json_schema") => {
+                let mut json_schema = json!({
+                    "name": format.get("name").cloned().unwrap_or_else(|| json!("response")),
+                    "schema": format.get("schema").cloned().unwrap_or_else(|| json!({}))
+                });
+                if let Some(strict) = format.get("strict") {
+                    json_schema["strict"] = strict.clone();
+                }
+                result["response_format"] =
+                    json!({"type": "json_schema", "json_schema": json_schema});
+            }
+            Some("json_object") => result["response_format"] = json!({"type": "json_object"}),
+            _ => {}
+        }
+    }
+
+    // 工具定义：Chat Completions 仅支持 function 工具，内置工具（web_search 等）直接丢弃
+    if let Some(tools) = body.get("tools").and_then(|t| t.as_array()) {
+        let chat_tools: Vec<Value> = tools
+            .iter()
+            .filter_map(|tool| {
+                if tool.get("type").and_then(|t| t.as_str()) != Some("function") {
+                    log::debug!(
+                        "[Codex] Chat Completions 不支持的工具类型，已忽略: {}",
+                        tool.get("type")
+                            .and_then(|t| t.as_str())
+                            .unwrap_or("unknown")
+                    );
+                    return None;
+                }
+                let mut function = json!({
+                    "name": tool.get("name")?.clone(),
+                    "parameters": tool
+                        .get("parameters")
+                        .cloned()
+                        .unwrap_or_else(|| json!({"type": "object", "properties": {}}))
+                });
+                if let Some(desc) = tool.get("description") {
+                    function["description"] = desc.clone();
+                }
+                if let Some(strict) = tool.get("strict") {
+                    function["strict"] = strict.clone();
+                }
+                Some(json!({"type": "function", "function": function}))
+            })
+            .collect();
+        if !chat_tools.is_empty() {
+            result["tools"] = json!(chat_tools);
+            match body.get("tool_choice") {
+                Some(Value::String(choice)) => result["tool_choice"] = json!(choice),
+                Some(Value::Object(choice)) => {
+                    if let Some(name) = choice.get("name") {
+                        result["tool_choice"] =
+                            json!({"type": "function", "function": {"name": name}});
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// 转换单个 Responses input item 并追加到 Chat messages
+fn convert_input_item(item: &Value, messages: &mut Vec<Value>) {
+    let item_type = item
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or("message");
+    match item_type {
+        "message" => {
+            let role = match item.get("role").and_then(|r| r.as_str()) {
+                Some("developer") | Some("system") => "system",
+                Some("assistant") => "assistant",
+                _ => "user",
+            };
+            let content = match item.get("content") {
+                Some(Value::String(text)) => json!(text),
+                Some(Value::Array(parts)) => convert_content_parts(parts, role),
+                _ => return,
+            };
+            messages.push(json!({"role": role, "content": content}));
+        }
+        "function_call" => {
+            let tool_call = json!({
+                "id": item.get("call_id").cloned().unwrap_or_default(),
+                "type": "function",
+                "function": {
+                    "name": item.get("name").cloned().unwrap_or_default(),
+                    "arguments": item.get("arguments").cloned().unwrap_or_else(|| json!("{}"))
+                }
+            });
+            // 连续的 function_call 合并到同一条 assistant 消息
+            if let Some(last) = messages.last_mut() {
+                if last["role"] == "assistant" {
+                    if let Some(calls) = last.get_mut("tool_calls").and_then(|c| c.as_array_mut()) {
+                        calls.push(tool_call);
+                        return;
+                    }
+                    if last.get("tool_calls").is_none() {
+                        last["tool_calls"] = json!([tool_call]);
+                        return;
+                    }
+                }
+            }
+            messages.push(json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [tool_call]
+            }));
+        }
+        "function_call_output" => {
+            let output = match item.get("output") {
+                Some(Value::String(text)) => text.clone(),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            messages.push(json!({
+                "role": "tool",
+                "tool_call_id": item.get("call_id").cloned().unwrap_or_default(),
+                "content": output
+            }));
+        }
+        // reasoning 等条目在 Chat Completions 中没有对应表示
+        _ => {}
+    }
+}
+
+/// Responses content parts → Chat content
+///
+/// assistant 消息只保留文本；user/system 消息保留图片时使用 parts 数组
+fn convert_content_parts(parts: &[Value], role: &str) -> Value {
+    let has_image = parts
+        .iter()
+        .any(|p| p.get("type").and_then(|t| t.as_str()) == Some("input_image"));
+    if !has_image || role != "user" {
+        let text: Vec<&str> = parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect();
+        return json!(text.join(""));
+    }
+
+    let chat_parts: Vec<Value> = parts
+        .iter()
+        .filter_map(|part| match part.get("type").and_then(|t| t.as_str()) {
+            Some("input_text") | Some("output_text") => part
+                .get("text")
+                .map(|text| json!({"type": "text", "text": text})),
+            Some("input_image") => part
+                .get("image_url")
+                .map(|url| json!({"type": "image_url", "image_url": {"url": url}})),
+            _ => None,
+        })
+        .collect();
+    json!(chat_parts)
+}
+
+/// OpenAI Chat Completions 响应 → OpenAI Responses 响应
+pub fn chat_to_responses(body: Value) -> Result<Value, ProxyError> {
+    let choice = body
+        .get("choices")
+        .and_then(|c| c.as_array())
+        .and_then(|c| c.first())
+        .ok_or_else(|| ProxyError::TransformError("No choices in response".to_string()))?;
+    let message = choice.get("message").cloned().unwrap_or_else(|| json!({}));
+    let chat_id = body.get("id").and_then(|i| i.as_str()).unwrap_or_default();
+
+    let mut output = Vec::new();
+    if let Some(text) = message.get("content").and_then(|c| c.as_str()) {
+        if !text.is_empty() {
+            output.push(message_item(&format!("msg_{chat_id}"), text, "completed"));
+        }
+    }
+    if let Some(calls) = message.get("tool_calls").and_then(|c| c.as_array()) {
+        for call in calls {
+            let call_id = call.get("id").and_then(|i| i.as_str()).unwrap_or_default();
+            output.push(function_call_item(
+                call_id,
+                call.pointer("/function/name")
+                    .and_then(|n| n.as_str())
+                    .unwrap_or_default(),
+                call.pointer("/function/arguments")
+                    .and_then(|a| a.as_str())
+                    .unwrap_or("{}"),
+                "completed",
+            ));
+        }
+    }
+
+    let finish_reason = choice.get("finish_reason").and_then(|r| r.as_str());
+    let mut result = json!({
+        "id": format!("resp_{chat_id}"),
+        "object": "response",
+        "created_at": body.get("created").cloned().unwrap_or_else(|| json!(chrono::Utc::now().timestamp())),
+        "model": body.get("model").cloned().unwrap_or_default(),
+        "output": output,
+    });
+    apply_status(&mut result, finish_reason);
+    if let Some(usage) = body.get("usage") {
+        result["usage"] = usage_to_responses(usage);
+    }
+
+    Ok(result)
+}
+
+/// 根据 Chat finish_reason 设置 Responses status / incomplete_details
+pub fn apply_status(response: &mut Value, finish_reason: Option<&str>) {
+    match finish_reason {
+        Some("length") => {
+            response["status"] = json!("incomplete");
+            response["incomplete_details"] = json!({"reason": "max_output_tokens"});
+        }
+        Some("content_filter") => {
+            response["status"] = json!("incomplete");
+            response["incomplete_details"] = json!({"reason": "content_filter"});
+        }
+        _ => response["status"] = json!("completed"),
+    }
+}
+
+/// Responses message output item
+pub fn message_item(id: &str, text: &str, status: &str) -> Value {
+    json!({
+        "type": "message",
+        "id": id,
+        "status": status,
+        "role": "assistant",
+        "content": [{"type": "output_text", "text": text, "annotations": []}]
+    })
+}
+
+/// Responses function_call output item
+pub fn function_call_item(call_id: &str, name: &str, arguments: &str, status: &str) -> Value {
+    json!({
+        "type": "function_call",
+        "id": format!("fc_{call_id}"),
+        "call_id": call_id,
+        "name": name,
+        "arguments": arguments,
+        "status": status
+    })
+}
+
+/// Chat usage → Responses usage
+pub fn usage_to_responses(usage: &Value) -> Value {
+    let input_tokens = usage
+        .get("prompt_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let output_tokens = usage
+        .get("completion_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let cached_tokens = usage
+        .pointer("/prompt_tokens_details/cached_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let reasoning_tokens = usage
+        .pointer("/completion_tokens_details/reasoning_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    json!({
+        "input_tokens": input_tokens,
+        "input_tokens_details": {"cached_tokens": cached_tokens},
+        "output_tokens": output_tokens,
+        "output_tokens_details": {"reasoning_tokens": reasoning_tokens},
+        "total_tokens": input_tokens + output_tokens
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_instructions_input_and_params() {
+        let input = json!({
+            "model": "gpt-5",
+            "instructions": "You are Codex.",
+            "input": [
+                {"type": "message", "role": "developer", "content": [{"type": "input_text", "text": "Be brief."}]},
+                {"type": "message", "role": "user", "content": [{"type": "input_text", "text": "List files"}]}
+            ],
+            "max_output_tokens": 2048,
+            "reasoning": {"effort": "high", "summary": "auto"},
+            "stream": true,
+            "store": false
+        });
+
+        let result = responses_to_chat(input).unwrap();
+        let messages = result["messages"].as_array().unwrap();
+        assert_eq!(
+            messages[0],
+            json!({"role": "system", "content": "You are Codex."})
+        );
+        assert_eq!(
+            messages[1],
+            json!({"role": "system", "content": "Be brief."})
+        );
+        assert_eq!(
+            messages[2],
+            json!({"role": "user", "content": "List files"})
+        );
+        assert_eq!(result["max_tokens"], 2048);
+        assert_eq!(result["reasoning_effort"], "high");
+        assert_eq!(result["stream_options"]["include_usage"], true);
+        assert!(result.get("store").is_none());
+    }
+
+    #[test]
+    fn test_request_function_calls_and_outputs() {
+        let input = json!({
+            "model": "gpt-5",
+            "input": [
+                {"type": "message", "role": "user", "content": [{"type": "input_text", "text": "Run ls and pwd"}]},
+                {"type": "reasoning", "summary": []},
+                {"type": "function_call", "call_id": "call_1", "name": "shell", "arguments": "{\"command\":[\"ls\"]}"},
+                {"type": "function_call", "call_id": "call_2", "name": "shell", "arguments": "{\"command\":[\"pwd\"]}"},
+                {"type": "function_call_output", "call_id": "call_1", "output": "a.txt"},
+                {"type": "function_call_output", "call_id": "call_2", "output": "/tmp"}
+            ],
+            "tools": [
+                {"type": "function", "name": "shell", "description": "Run a command", "parameters": {"type": "object"}, "strict": false},
+                {"type": "web_search"}
+            ],
+            "tool_choice": "auto"
+        });
+
+        let result = responses_to_chat(input).unwrap();
+        let messages = result["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[1]["role"], "assistant");
+        assert_eq!(messages[1]["tool_calls"].as_array().unwrap().len(), 2);
+        assert_eq!(messages[1]["tool_calls"][1]["id"], "call_2");
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["tool_call_id"], "call_1");
+        assert_eq!(messages[3]["content"], "/tmp");
+
+        let tools = result["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0]["function"]["name"], "shell");
+        assert_eq!(result["tool_choice"], "auto");
+    }
+
+    #[test]
+    fn test_response_text_tool_calls_and_usage() {
+        let input = json!({
+            "id": "chatcmpl-1",
+            "created": 1700000000,
+            "model": "qwen3-coder",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "Running ls",
+                    "tool_calls": [{"id": "call_1", "type": "function", "function": {"name": "shell", "arguments": "{}"}}]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 100, "completion_tokens": 20, "prompt_tokens_details": {"cached_tokens": 40}}
+        });
+
+        let result = chat_to_responses(input).unwrap();
+        assert_eq!(result["object"], "response");
+        assert_eq!(result["status"], "completed");
+        assert_eq!(result["output"][0]["type"], "message");
+        assert_eq!(result["output"][0]["content"][0]["text"], "Running ls");
+        assert_eq!(result["output"][1]["type"], "function_call");
+        assert_eq!(result["output"][1]["call_id"], "call_1");
+        assert_eq!(result["usage"]["input_tokens"], 100);
+        assert_eq!(result["usage"]["input_tokens_details"]["cached_tokens"], 40);
+        assert_eq!(result["usage"]["total_tokens"], 120);
+    }
+
+    #[test]
+    fn test_response_length_is_incomplete() {
+        let input = json!({
+            "id": "chatcmpl-2",
+            "model": "qwen3-coder",
+            "choices": [{"message": {"role": "assistant", "content": "partial"}, "finish_reason": "length"}]
+        });
+
+        let result = chat_to_responses(input).unwrap();
+        assert_eq!(result["status"], "incomplete");
+        assert_eq!(result["incomplete_details"]["reason"], "max_output_tokens");
+    }
+}
@@ -0,0 +1,541 @@
+//! 请求回放
+//!
+//! 把历史请求（请求抓包中的入站请求，或同格式的 fixture 文件）经完整代理管线
+//! （模型映射、整流器、`ProviderAdapter::transform_request`、流式转换）重新发送到指定供应商，
+//! 汇总状态码、延迟、用量与归一化输出，用于在切换真实流量前验证新中转或转换改动。
+//!
+//! 回放运行在独立的代理实例上：熔断器、会话亲和与运行状态不与正在运行的代理共享，
+//! 不写入供应商健康状态、不发送熔断通知，也不会触发故障转移切换。
+//! 回放产生的真实消费与普通请求一样受供应商消费限额约束，并写入请求日志。
+
+use super::capture;
+use super::handler_config::{
+    UsageParserConfig, CLAUDE_PARSER_CONFIG, CODEX_PARSER_CONFIG, GEMINI_PARSER_CONFIG,
+    OPENAI_PARSER_CONFIG,
+};
+use super::server::ProxyServer;
+use super::sse::{strip_sse_field, take_sse_block};
+use super::usage::parser::TokenUsage;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::services::usage_stats::RequestLogDetail;
+use axum::extract::ConnectInfo;
+use axum::Router;
+use http_body_util::BodyExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use tower::Service;
+
+/// 回放目标（请求扩展）：RequestContext 据此跳过路由，直接使用指定供应商
+#[derive(Debug, Clone)]
+pub(crate) struct ReplayTarget {
+    pub provider_id: String,
+}
+
+/// 不随回放发送的入站请求头（连接相关头由管线重建，凭据由目标供应商重新注入）
+const SKIPPED_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "connection",
+    "transfer-encoding",
+    "accept-encoding",
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "api-key",
+    "cookie",
+];
+
+/// 错误响应体保留的最大字符数
+const MAX_ERROR_CHARS: usize = 4000;
+
+/// 回放输入
+///
+/// 与请求抓包的 `inbound.json` 格式一致，抓包文件可直接作为 fixture 保存和复用
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayFixture {
+    /// 应用类型（claude/codex/gemini）
+    pub app_type: String,
+    /// 入站路径（含查询参数），如 `/v1/messages`
+    pub uri: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: Value,
+}
+
+impl ReplayFixture {
+    /// 从 fixture 文件读取
+    pub fn from_file(path: &Path) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+        let fixture: Self = serde_json::from_str(&content).map_err(|e| {
+            AppError::InvalidInput(format!("无效的回放文件 {}: {e}", path.display()))
+        })?;
+        fixture.validate()?;
+        Ok(fixture)
+    }
+
+    /// 从请求日志关联的抓包读取
+    pub fn from_request_capture(db: &Database, request_id: &str) -> Result<Self, AppError> {
+        let inbound = capture::load_request_capture(db, request_id)?
+            .and_then(|detail| detail.inbound)
+            .ok_or_else(|| {
+                AppError::InvalidInput(format!(
+                    "请求 {request_id} 没有可回放的抓包（需在请求前开启请求抓包）"
+                ))
+            })?;
+        let fixture: Self = serde_json::from_value(inbound)
+            .map_err(|e| AppError::InvalidInput(format!("抓包内容无法解析: {e}")))?;
+        fixture.validate()?;
+        Ok(fixture)
+    }
+
+    fn validate(&self) -> Result<(), AppError> {
+        if !matches!(self.app_type.as_str(), "claude" | "codex" | "gemini") {
+            return Err(AppError::InvalidInput(format!(
+                "不支持回放的应用类型: {}",
+                self.app_type
+            )));
+        }
+        if !self.uri.starts_with('/') {
+            return Err(AppError::InvalidInput(format!(
+                "回放路径必须以 / 开头: {}",
+                self.uri
+            )));
+        }
+        // 超过抓包大小上限的请求体只保存了截断文本，无法原样重发
+        if self.body.get("truncated").and_then(Value::as_bool) == Some(true) {
+            return Err(AppError::InvalidInput(
+                "请求体在抓包时已被截断，无法回放".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn path(&self) -> &str {
+        self.uri.split('?').next().unwrap_or_default()
+    }
+
+    /// 输出是否为 Anthropic Messages 格式（可归一化为完整 message）
+    fn is_anthropic_output(&self) -> bool {
+        self.app_type == "claude" && !self.path().ends_with("/chat/completions")
+    }
+
+    /// 按入站路径选择用量解析器（与对应 handler 一致）
+    fn parser_config(&self) -> &'static UsageParserConfig {
+        match self.app_type.as_str() {
+            "gemini" => &GEMINI_PARSER_CONFIG,
+            "codex" if self.path().contains("/responses") => &CODEX_PARSER_CONFIG,
+            _ if self.is_anthropic_output() => &CLAUDE_PARSER_CONFIG,
+            _ => &OPENAI_PARSER_CONFIG,
+        }
+    }
+}
+
+/// 回放用量
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cache_read_tokens: u32,
+    pub cache_creation_tokens: u32,
+    pub reasoning_tokens: u32,
+    pub model: Option<String>,
+}
+
+impl From<TokenUsage> for ReplayUsage {
+    fn from(usage: TokenUsage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_read_tokens: usage.cache_read_tokens,
+            cache_creation_tokens: usage.cache_creation_tokens,
+            reasoning_tokens: usage.reasoning_tokens,
+            model: usage.model,
+        }
+    }
+}
+
+/// 单个供应商的回放结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayOutcome {
+    pub provider_id: String,
+    pub provider_name: Option<String>,
+    pub status_code: u16,
+    pub latency_ms: u64,
+    pub first_token_ms: Option<u64>,
+    pub is_streaming: bool,
+    pub usage: Option<ReplayUsage>,
+    /// 归一化输出：Claude 请求为完整的 Anthropic message（流式事件已聚合），
+    /// 其他格式为原始响应 JSON（流式时为事件数组）
+    pub output: Option<Value>,
+    pub error: Option<String>,
+}
+
+/// 回放对比报告
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayReport {
+    pub app_type: String,
+    pub uri: String,
+    pub request_model: Option<String>,
+    /// 原始请求的日志记录（从请求日志回放时存在）
+    pub original: Option<RequestLogDetail>,
+    pub results: Vec<ReplayOutcome>,
+}
+
+/// 依次把请求回放到各目标供应商
+pub async fn replay(
+    db: Arc<Database>,
+    fixture: &ReplayFixture,
+    provider_ids: &[String],
+    original: Option<RequestLogDetail>,
+) -> Result<ReplayReport, AppError> {
+    if provider_ids.is_empty() {
+        return Err(AppError::InvalidInput(
+            "请至少选择一个回放供应商".to_string(),
+        ));
+    }
+
+    let config = db.get_proxy_config().await?;
+    let router = ProxyServer::detached(config, db.clone()).build_router();
+
+    let mut results = Vec::with_capacity(provider_ids.len());
+    for provider_id in provider_ids {
+        let provider_name = db
+            .get_provider_by_id(provider_id, &fixture.app_type)?
+            .map(|p| p.name);
+        log::info!(
+            "[Replay] 回放 {} {} → {}",
+            fixture.app_type,
+            fixture.uri,
+            provider_name.as_deref().unwrap_or(provider_id)
+        );
+        let mut outcome = replay_once(router.clone(), fixture, provider_id).await;
+        outcome.provider_name = provider_name;
+        results.push(outcome);
+    }
+
+    Ok(ReplayReport {
+        app_type: fixture.app_type.clone(),
+        uri: fixture.uri.clone(),
+        request_model: fixture
+            .body
+            .get("model")
+            .and_then(Value::as_str)
+            .map(String::from),
+        original,
+        results,
+    })
+}
+
+async fn replay_once(
+    mut router: Router,
+    fixture: &ReplayFixture,
+    provider_id: &str,
+) -> ReplayOutcome {
+    let mut outcome = ReplayOutcome {
+        provider_id: provider_id.to_string(),
+        provider_name: None,
+        status_code: 0,
+        latency_ms: 0,
+        first_token_ms: None,
+        is_streaming: false,
+        usage: None,
+        output: None,
+        error: None,
+    };
+
+    let request = match build_request(fixture, provider_id) {
+        Ok(request) => request,
+        Err(e) => {
+            outcome.error = Some(e);
+            return outcome;
+        }
+    };
+
+    let start = Instant::now();
+    // Router 始终就绪，无需 poll_ready
+    let response = match router.call(request).await {
+        Ok(response) => response,
+        Err(infallible) => match infallible {},
+    };
+    outcome.status_code = response.status().as_u16();
+    outcome.is_streaming = response
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("text/event-stream"));
+
+    let mut body = response.into_body();
+    let mut bytes = Vec::new();
+    while let Some(frame) = body.frame().await {
+        match frame {
+            Ok(frame) => {
+                if let Ok(data) = frame.into_data() {
+                    if outcome.first_token_ms.is_none() && !data.is_empty() {
+                        outcome.first_token_ms = Some(start.elapsed().as_millis() as u64);
+                    }
+                    bytes.extend_from_slice(&data);
+                }
+            }
+            Err(e) => {
+                outcome.error = Some(format!("读取响应失败: {e}"));
+                break;
+            }
+        }
+    }
+    outcome.latency_ms = start.elapsed().as_millis() as u64;
+
+    let text = String::from_utf8_lossy(&bytes);
+    let parser = fixture.parser_config();
+    if !(200..300).contains(&outcome.status_code) {
+        outcome.output = serde_json::from_str(&text).ok();
+        outcome
+            .error
+            .get_or_insert_with(|| text.chars().take(MAX_ERROR_CHARS).collect());
+    } else if outcome.is_streaming {
+        let events = parse_sse_events(&text);
+        outcome.usage = (parser.stream_parser)(&events).map(ReplayUsage::from);
+        outcome.output = if fixture.is_anthropic_output() {
+            anthropic_message_from_events(&events)
+        } else {
+            Some(Value::Array(events))
+        };
+    } else {
+        match serde_json::from_str::<Value>(&text) {
+            Ok(value) => {
+                outcome.usage = (parser.response_parser)(&value).map(ReplayUsage::from);
+                outcome.output = Some(value);
+            }
+            Err(e) => {
+                outcome
+                    .error
+                    .get_or_insert_with(|| format!("响应不是有效的 JSON: {e}"));
+            }
+        }
+    }
+    outcome
+}
+
+fn build_request(
+    fixture: &ReplayFixture,
+    provider_id: &str,
+) -> Result<axum::extract::Request, String> {
+    let mut builder = http::Request::post(&fixture.uri);
+    for (name, value) in &fixture.headers {
+        if !SKIPPED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+            builder = builder.header(name, value);
+        }
+    }
+    let body = serde_json::to_vec(&fixture.body).map_err(|e| format!("序列化请求体失败: {e}"))?;
+    let mut request = builder
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(axum::body::Body::from(body))
+        .map_err(|e| format!("构造回放请求失败: {e}"))?;
+    request.extensions_mut().insert(ReplayTarget {
+        provider_id: provider_id.to_string(),
+    });
+    // 回放请求视为本机连接，不受入站访问控制约束
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 0))));
+    Ok(request)
+}
+
+/// 解析 SSE 文本中的 JSON 事件（忽略 `[DONE]` 与非 JSON 数据）
+fn parse_sse_events(text: &str) -> Vec<Value> {
+    let mut buffer = text.to_string();
+    if !buffer.ends_with("\n\n") {
+        buffer.push_str("\n\n");
+    }
+    let mut events = Vec::new();
+    while let Some(block) = take_sse_block(&mut buffer) {
+        let data: Vec<&str> = block
+            .lines()
+            .filter_map(|line| strip_sse_field(line, "data"))
+            .collect();
+        if data.is_empty() {
+            continue;
+        }
+        if let Ok(event) = serde_json::from_str::<Value>(&data.join("\n")) {
+            events.push(event);
+        }
+    }
+    events
+}
+
+/// 把 Anthropic 流式事件聚合为完整的 message（与非流式响应结构一致）
+fn anthropic_message_from_events(events: &[Value]) -> Option<Value> {
+    let mut message = events
+        .iter()
+        .find(|e| e.get("type").and_then(Value::as_str) == Some("message_start"))?
+        .get("message")?
+        .clone();
+    let mut content: Vec<Value> = Vec::new();
+    let mut partial_json: BTreeMap<usize, String> = BTreeMap::new();
+
+    for event in events {
+        let index = event
+            .get("index")
+            .and_then(Value::as_u64)
+            .map(|i| i as usize);
+        match event.get("type").and_then(Value::as_str) {
+            Some("content_block_start") => {
+                if let (Some(index), Some(block)) = (index, event.get("content_block")) {
+                    if content.len() <= index {
+                        content.resize(index + 1, Value::Null);
+                    }
+                    content[index] = block.clone();
+                }
+            }
+            Some("content_block_delta") => {
+                let (Some(index), Some(delta)) = (index, event.get("delta")) else {
+                    continue;
+                };
+                let Some(block) = content.get_mut(index).and_then(Value::as_object_mut) else {
+                    continue;
+                };
+                let append = |block: &mut serde_json::Map<String, Value>, key: &str, text: &str| {
+                    let existing = block.get(key).and_then(Value::as_str).unwrap_or_default();
+                    let combined = format!("{existing}{text}");
+                    block.insert(key.to_string(), Value::String(combined));
+                };
+                match delta.get("type").and_then(Value::as_str) {
+                    Some("text_delta") => {
+                        append(block, "text", delta["text"].as_str().unwrap_or_default())
+                    }
+                    Some("thinking_delta") => append(
+                        block,
+                        "thinking",
+                        delta["thinking"].as_str().unwrap_or_default(),
+                    ),
+                    Some("signature_delta") => {
+                        block.insert("signature".to_string(), delta["signature"].clone());
+                    }
+                    Some("input_json_delta") => partial_json
+                        .entry(index)
+                        .or_default()
+                        .push_str(delta["partial_json"].as_str().unwrap_or_default()),
+                    _ => {}
+                }
+            }
+            Some("content_block_stop") => {
+                let Some(index) = index else { continue };
+                if let (Some(json), Some(block)) = (
+                    partial_json.remove(&index),
+                    content.get_mut(index).and_then(Value::as_object_mut),
+                ) {
+                    let input = serde_json::from_str(&json).unwrap_or(Value::String(json));
+                    block.insert("input".to_string(), input);
+                }
+            }
+            Some("message_delta") => {
+                if let Some(delta) = event.get("delta").and_then(Value::as_object) {
+                    for (key, value) in delta {
+                        message[key] = value.clone();
+                    }
+                }
+                if let Some(usage) = event.get("usage").and_then(Value::as_object) {
+                    if !message["usage"].is_object() {
+                        message["usage"] = json!({});
+                    }
+                    for (key, value) in usage {
+                        message["usage"][key] = value.clone();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    message["content"] = Value::Array(content.into_iter().filter(|b| !b.is_null()).collect());
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_anthropic_stream_into_message() {
+        let sse = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"type\":\"message\",\"role\":\"assistant\",\"model\":\"claude-sonnet-4\",\"content\":[],\"stop_reason\":null,\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"lo\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"Read\",\"input\":{}}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"path\\\":\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"a.rs\\\"}\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":30}}\n\n",
+            "data: {\"type\":\"message_stop\"}"
+        );
+
+        let events = parse_sse_events(sse);
+        assert_eq!(events.len(), 11);
+
+        let message = anthropic_message_from_events(&events).unwrap();
+        assert_eq!(message["stop_reason"], "tool_use");
+        assert_eq!(message["content"][0]["text"], "Hello");
+        assert_eq!(message["content"][1]["input"], json!({"path": "a.rs"}));
+        assert_eq!(message["usage"]["input_tokens"], 12);
+        assert_eq!(message["usage"]["output_tokens"], 30);
+
+        let usage = (CLAUDE_PARSER_CONFIG.stream_parser)(&events).unwrap();
+        assert_eq!(usage.input_tokens, 12);
+        assert_eq!(usage.output_tokens, 30);
+    }
+
+    #[test]
+    fn fixture_drops_credentials_and_targets_provider() {
+        let fixture: ReplayFixture = serde_json::from_value(json!({
+            "captureId": "ignored",
+            "appType": "claude",
+            "uri": "/v1/messages?beta=true",
+            "headers": {
+                "x-api-key": "sk-a...wxyz",
+                "anthropic-version": "2023-06-01",
+                "content-length": "42"
+            },
+            "body": {"model": "claude-sonnet-4", "messages": []}
+        }))
+        .unwrap();
+        fixture.validate().unwrap();
+        assert!(fixture.is_anthropic_output());
+
+        let request = build_request(&fixture, "relay-b").unwrap();
+        assert!(request.headers().get("x-api-key").is_none());
+        assert_eq!(request.headers()["anthropic-version"], "2023-06-01");
+        assert_eq!(
+            request
+                .extensions()
+                .get::<ReplayTarget>()
+                .unwrap()
+                .provider_id,
+            "relay-b"
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_capture_body() {
+        let fixture: ReplayFixture = serde_json::from_value(json!({
+            "appType": "codex",
+            "uri": "/v1/responses",
+            "body": {"truncated": true, "originalBytes": 1, "text": "{"}
+        }))
+        .unwrap();
+        assert!(fixture.validate().is_err());
+        assert!(!fixture.is_anthropic_output());
+    }
+}
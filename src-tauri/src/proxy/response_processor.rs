@@ -3,9 +3,11 @@
 //! 统一处理流式和非流式 API 响应
 
 use super::{
+    capture::RequestCapture,
     handler_config::UsageParserConfig,
     handler_context::{RequestContext, StreamingTimeoutConfig},
     hyper_client::ProxyResponse,
+    otel::RequestTrace,
     server::ProxyState,
     sse::{strip_sse_field, take_sse_block},
     usage::parser::TokenUsage,
@@ -419,17 +421,12 @@ impl SseUsageCollector {
 // ============================================================================
 
 /// 创建使用量收集器
-fn create_usage_collector(
+pub fn create_usage_collector(
     ctx: &RequestContext,
     state: &ProxyState,
     status_code: u16,
     parser_config: &UsageParserConfig,
 ) -> SseUsageCollector {
-    let logging_enabled = state
-        .config
-        .try_read()
-        .map(|c| c.enable_logging)
-        .unwrap_or(true);
     let state = state.clone();
     let provider_id = ctx.provider.id.clone();
     let request_model = ctx.request_model.clone();
@@ -439,11 +436,10 @@ fn create_usage_collector(
     let stream_parser = parser_config.stream_parser;
     let model_extractor = parser_config.model_extractor;
     let session_id = ctx.session_id.clone();
+    let trace = ctx.trace.clone();
+    let capture = ctx.capture.clone();
 
     SseUsageCollector::new(start_time, move |events, first_token_ms| {
-        if !logging_enabled {
-            return;
-        }
         if let Some(usage) = stream_parser(&events) {
             let model = model_extractor(&events, &request_model);
             let latency_ms = start_time.elapsed().as_millis() as u64;
@@ -452,6 +448,8 @@ fn create_usage_collector(
             let provider_id = provider_id.clone();
             let session_id = session_id.clone();
             let request_model = request_model.clone();
+            let trace = trace.clone();
+            let capture = capture.clone();
 
             tokio::spawn(async move {
                 log_usage_internal(
@@ -466,6 +464,8 @@ fn create_usage_collector(
                     true, // is_streaming
                     status_code,
                     Some(session_id),
+                    trace.as_ref(),
+                    capture.as_ref(),
                 )
                 .await;
             });
@@ -476,6 +476,8 @@ fn create_usage_collector(
             let provider_id = provider_id.clone();
             let session_id = session_id.clone();
             let request_model = request_model.clone();
+            let trace = trace.clone();
+            let capture = capture.clone();
 
             tokio::spawn(async move {
                 log_usage_internal(
@@ -490,6 +492,8 @@ fn create_usage_collector(
                     true, // is_streaming
                     status_code,
                     Some(session_id),
+                    trace.as_ref(),
+                    capture.as_ref(),
                 )
                 .await;
             });
@@ -508,13 +512,6 @@ fn spawn_log_usage(
     status_code: u16,
     is_streaming: bool,
 ) {
-    // Check enable_logging before spawning the log task
-    if let Ok(config) = state.config.try_read() {
-        if !config.enable_logging {
-            return;
-        }
-    }
-
     let state = state.clone();
     let provider_id = ctx.provider.id.clone();
     let app_type_str = ctx.app_type_str.to_string();
@@ -522,6 +519,8 @@ fn spawn_log_usage(
     let request_model = request_model.to_string();
     let latency_ms = ctx.latency_ms();
     let session_id = ctx.session_id.clone();
+    let trace = ctx.trace.clone();
+    let capture = ctx.capture.clone();
 
     tokio::spawn(async move {
         log_usage_internal(
@@ -536,12 +535,16 @@ fn spawn_log_usage(
             is_streaming,
             status_code,
             Some(session_id),
+            trace.as_ref(),
+            capture.as_ref(),
         )
         .await;
     });
 }
 
 /// 内部使用量记录函数
+///
+/// 指标与链路始终记录；关闭请求日志时只跳过数据库写入。
 #[allow(clippy::too_many_arguments)]
 async fn log_usage_internal(
     state: &ProxyState,
@@ -555,10 +558,21 @@ async fn log_usage_internal(
     is_streaming: bool,
     status_code: u16,
     session_id: Option<String>,
+    trace: Option<&RequestTrace>,
+    capture: Option<&RequestCapture>,
 ) {
     use super::usage::logger::UsageLogger;
 
-    let logger = UsageLogger::new(&state.db);
+    let logging_enabled = state
+        .config
+        .try_read()
+        .map(|c| c.enable_logging)
+        .unwrap_or(true);
+    let logger = UsageLogger::new(&state.db)
+        .with_metrics(&state.metrics)
+        .with_trace(trace)
+        .with_capture(capture)
+        .with_persist(logging_enabled);
     let (multiplier, pricing_model_source) =
         logger.resolve_pricing_config(provider_id, app_type).await;
     let pricing_model = if pricing_model_source == "request" {
@@ -847,6 +861,11 @@ mod tests {
             gemini_shadow: Arc::new(GeminiShadowStore::default()),
             app_handle: None,
             failover_manager: Arc::new(FailoverSwitchManager::new(db)),
+            access: Arc::new(RwLock::new(Default::default())),
+            metrics: Arc::new(Default::default()),
+            otel: Arc::new(RwLock::new(None)),
+            model_rules: Arc::new(RwLock::new(Vec::new())),
+            capture_rules: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
@@ -909,6 +928,7 @@ mod tests {
             cache_creation_tokens: 0,
             model: None,
             message_id: None,
+            ..Default::default()
         };
 
         log_usage_internal(
@@ -923,6 +943,8 @@ mod tests {
             false,
             200,
             None,
+            None,
+            None,
         )
         .await;
 
@@ -969,6 +991,7 @@ mod tests {
             cache_creation_tokens: 0,
             model: None,
             message_id: None,
+            ..Default::default()
         };
 
         log_usage_internal(
@@ -983,6 +1006,8 @@ mod tests {
             false,
             200,
             None,
+            None,
+            None,
         )
         .await;
 
@@ -1006,4 +1031,44 @@ mod tests {
         );
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_log_usage_records_metrics_when_logging_disabled() -> Result<(), AppError> {
+        let db = Arc::new(Database::memory()?);
+        seed_pricing(&db)?;
+        insert_provider(&db, "provider-3", "claude", ProviderMeta::default())?;
+
+        let state = build_state(db.clone());
+        state.config.write().await.enable_logging = false;
+
+        log_usage_internal(
+            &state,
+            "provider-3",
+            "claude",
+            "resp-model",
+            "req-model",
+            TokenUsage::default(),
+            10,
+            None,
+            true,
+            200,
+            None,
+            None,
+            None,
+        )
+        .await;
+
+        let mut out = String::new();
+        state.metrics.render_requests(&mut out);
+        assert!(out.contains(r#"provider="provider-3",model="resp-model",status="200"} 1"#));
+
+        let conn = crate::database::lock_conn!(db.conn);
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM proxy_request_logs", [], |row| {
+                row.get(0)
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        assert_eq!(rows, 0);
+        Ok(())
+    }
 }
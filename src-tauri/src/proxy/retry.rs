@@ -0,0 +1,364 @@
+//! 重试策略模块
+//!
+//! - 按应用配置的 [`RetryPolicy`] 生成尝试计划：先重试同一供应商，或按轮次遍历整个队列
+//! - 带抖动的指数退避
+//! - 解析 429/529 响应的限流头（`retry-after`、`anthropic-ratelimit-*`、`x-ratelimit-*`），
+//!   得出供应商的冷却时长，由 `ProviderRouter` 在冷却结束前把它排到故障转移链末尾
+
+use super::types::RetryPolicy;
+use super::ProxyError;
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// 冷却时长上限：避免异常的重置时间让供应商长期不可用
+pub const MAX_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+
+/// Anthropic 限流维度（`anthropic-ratelimit-{kind}-remaining/reset`）
+const ANTHROPIC_LIMIT_KINDS: [&str; 4] = ["requests", "tokens", "input-tokens", "output-tokens"];
+
+/// OpenAI 兼容限流维度（`x-ratelimit-remaining-{kind}` / `x-ratelimit-reset-{kind}`）
+const OPENAI_LIMIT_KINDS: [&str; 2] = ["requests", "tokens"];
+
+/// 生成尝试计划：`(供应商下标, 该供应商的第几次尝试)`，尝试次数从 1 开始
+///
+/// - `retry_same_provider = true`：每个供应商用尽尝试次数后再故障转移（P1 P1 P2 P2 ...）
+/// - `retry_same_provider = false`：先按队列故障转移，整轮失败后再开始下一轮（P1 P2 P1 P2 ...）
+pub fn attempt_plan(policy: &RetryPolicy, provider_count: usize) -> Vec<(usize, u32)> {
+    let attempts = policy.max_attempts_per_provider.max(1);
+    if policy.retry_same_provider {
+        (0..provider_count)
+            .flat_map(|index| (1..=attempts).map(move |attempt| (index, attempt)))
+            .collect()
+    } else {
+        (1..=attempts)
+            .flat_map(|attempt| (0..provider_count).map(move |index| (index, attempt)))
+            .collect()
+    }
+}
+
+/// 该错误是否值得对同一供应商再次尝试
+///
+/// 网络类错误总是可重试；上游 HTTP 错误仅在状态码位于策略列表中时重试。
+/// 这里只约束重复尝试，切换到其他供应商的故障转移不受影响。
+pub fn is_retryable_error(policy: &RetryPolicy, error: &ProxyError) -> bool {
+    match error {
+        ProxyError::UpstreamError { status, .. } => policy.retryable_status_codes.contains(status),
+        ProxyError::Timeout(_)
+        | ProxyError::ForwardFailed(_)
+        | ProxyError::StreamIdleTimeout(_) => true,
+        _ => false,
+    }
+}
+
+/// 第 `attempt` 次尝试前的退避时长（`attempt >= 2`）
+///
+/// 指数增长 `base * 2^(attempt-2)`，不超过 `max`；`jitter` ∈ [0, 1) 决定在
+/// `[delay/2, delay)` 区间内的取值（equal jitter），避免多个请求同时重试。
+pub fn backoff_delay(policy: &RetryPolicy, attempt: u32, jitter: f64) -> Duration {
+    if attempt <= 1 {
+        return Duration::ZERO;
+    }
+    let exponent = (attempt - 2).min(31);
+    let delay_ms = policy
+        .base_delay_ms
+        .saturating_mul(1u64 << exponent)
+        .min(policy.max_delay_ms);
+    let half = delay_ms / 2;
+    let jitter = jitter.clamp(0.0, 1.0);
+    Duration::from_millis(half + ((delay_ms - half) as f64 * jitter) as u64)
+}
+
+/// 生成 [0, 1) 区间的抖动系数
+pub fn random_jitter() -> f64 {
+    // RandomState 每次构造使用不同的随机键，无需额外引入随机数依赖
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// 从限流响应头解析供应商需要冷却的时长
+///
+/// 仅处理 429 / 529。优先使用 `retry-after-ms` / `retry-after`；
+/// 否则取已耗尽（remaining = 0）维度中最晚的重置时间。无法确定时返回 `None`。
+pub fn rate_limit_cooldown(
+    status: u16,
+    headers: &HeaderMap,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    if !matches!(status, 429 | 529) {
+        return None;
+    }
+
+    let cooldown = retry_after(headers, now).or_else(|| exhausted_limit_reset(headers, now))?;
+    (!cooldown.is_zero()).then(|| cooldown.min(MAX_COOLDOWN))
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+/// `retry-after-ms`（毫秒）或 `retry-after`（秒数或 HTTP 日期）
+fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    if let Some(ms) = header_str(headers, "retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        if ms.is_finite() && ms >= 0.0 {
+            return Some(Duration::from_millis(ms as u64));
+        }
+    }
+
+    let value = header_str(headers, "retry-after")?;
+    if let Ok(secs) = value.parse::<f64>() {
+        return saturating_secs(secs);
+    }
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|at| until(at.with_timezone(&Utc), now))
+}
+
+/// 已耗尽维度的最晚重置时间
+fn exhausted_limit_reset(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let anthropic = ANTHROPIC_LIMIT_KINDS.iter().filter_map(|kind| {
+        exhausted(headers, &format!("anthropic-ratelimit-{kind}-remaining"))?;
+        let reset = header_str(headers, &format!("anthropic-ratelimit-{kind}-reset"))?;
+        DateTime::parse_from_rfc3339(reset)
+            .ok()
+            .map(|at| until(at.with_timezone(&Utc), now))
+    });
+    let openai = OPENAI_LIMIT_KINDS.iter().filter_map(|kind| {
+        exhausted(headers, &format!("x-ratelimit-remaining-{kind}"))?;
+        header_str(headers, &format!("x-ratelimit-reset-{kind}")).and_then(parse_reset_duration)
+    });
+    anthropic.chain(openai).max()
+}
+
+fn exhausted(headers: &HeaderMap, name: &str) -> Option<()> {
+    let remaining = header_str(headers, name)?.parse::<f64>().ok()?;
+    (remaining <= 0.0).then_some(())
+}
+
+fn until(at: DateTime<Utc>, now: DateTime<Utc>) -> Duration {
+    (at - now).to_std().unwrap_or(Duration::ZERO)
+}
+
+/// 秒数转为时长；超出 `Duration` 表示范围时饱和为最大值（随后由 [`MAX_COOLDOWN`] 截断）
+fn saturating_secs(secs: f64) -> Option<Duration> {
+    (secs.is_finite() && secs >= 0.0)
+        .then(|| Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX))
+}
+
+/// 解析 OpenAI 风格的重置时长：`"20ms"`、`"1s"`、`"6m0s"`、`"1h2m3.5s"`，纯数字按秒处理
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<f64>() {
+        return saturating_secs(secs);
+    }
+
+    let mut total = 0.0f64;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        if number_len == 0 {
+            return None;
+        }
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total += number * scale;
+    }
+    saturating_secs(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    fn policy(attempts: u32, same_provider: bool) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts_per_provider: attempts,
+            retry_same_provider: same_provider,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn attempt_plan_orders_by_mode() {
+        assert_eq!(
+            attempt_plan(&policy(2, true), 2),
+            vec![(0, 1), (0, 2), (1, 1), (1, 2)]
+        );
+        assert_eq!(
+            attempt_plan(&policy(2, false), 2),
+            vec![(0, 1), (1, 1), (0, 2), (1, 2)]
+        );
+        // 默认策略：每个供应商一次，与旧行为一致
+        assert_eq!(
+            attempt_plan(&RetryPolicy::default(), 3),
+            vec![(0, 1), (1, 1), (2, 1)]
+        );
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_with_jitter_and_cap() {
+        let policy = RetryPolicy {
+            base_delay_ms: 500,
+            max_delay_ms: 3000,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(backoff_delay(&policy, 1, 0.5), Duration::ZERO);
+        assert_eq!(backoff_delay(&policy, 2, 0.0), Duration::from_millis(250));
+        assert_eq!(backoff_delay(&policy, 3, 0.0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(&policy, 3, 0.999), Duration::from_millis(999));
+        assert_eq!(backoff_delay(&policy, 10, 0.0), Duration::from_millis(1500));
+        assert!(backoff_delay(&policy, 40, 0.999) < Duration::from_millis(3000));
+
+        let jitter = random_jitter();
+        assert!((0.0..1.0).contains(&jitter));
+    }
+
+    #[test]
+    fn retryable_errors_follow_status_list() {
+        let policy = RetryPolicy::default();
+        let upstream = |status| ProxyError::UpstreamError { status, body: None };
+        assert!(is_retryable_error(&policy, &upstream(429)));
+        assert!(is_retryable_error(&policy, &upstream(529)));
+        assert!(!is_retryable_error(&policy, &upstream(400)));
+        assert!(is_retryable_error(
+            &policy,
+            &ProxyError::Timeout("t".to_string())
+        ));
+        assert!(!is_retryable_error(
+            &policy,
+            &ProxyError::ConfigError("c".to_string())
+        ));
+    }
+
+    #[test]
+    fn cooldown_prefers_retry_after() {
+        let now = Utc::now();
+        assert_eq!(
+            rate_limit_cooldown(429, &headers(&[("retry-after", "30")]), now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            rate_limit_cooldown(529, &headers(&[("retry-after-ms", "1500")]), now),
+            Some(Duration::from_millis(1500))
+        );
+        let date = (now + chrono::Duration::seconds(120)).to_rfc2822();
+        let parsed = rate_limit_cooldown(429, &headers(&[("retry-after", &date)]), now).unwrap();
+        assert!(parsed > Duration::from_secs(118) && parsed <= Duration::from_secs(120));
+
+        // 非限流状态码不冷却
+        assert_eq!(
+            rate_limit_cooldown(500, &headers(&[("retry-after", "30")]), now),
+            None
+        );
+        // 超长重置时间被截断
+        assert_eq!(
+            rate_limit_cooldown(429, &headers(&[("retry-after", "86400")]), now),
+            Some(MAX_COOLDOWN)
+        );
+        // 超出 Duration 表示范围的值同样截断，而不是 panic
+        assert_eq!(
+            rate_limit_cooldown(429, &headers(&[("retry-after", "1e20")]), now),
+            Some(MAX_COOLDOWN)
+        );
+        assert_eq!(
+            rate_limit_cooldown(429, &headers(&[("retry-after-ms", "1e30")]), now),
+            Some(MAX_COOLDOWN)
+        );
+    }
+
+    #[test]
+    fn cooldown_uses_latest_exhausted_limit_reset() {
+        let now = Utc::now();
+        let soon = (now + chrono::Duration::seconds(10)).to_rfc3339();
+        let later = (now + chrono::Duration::seconds(50)).to_rfc3339();
+        let anthropic = headers(&[
+            ("anthropic-ratelimit-requests-remaining", "0"),
+            ("anthropic-ratelimit-requests-reset", &soon),
+            ("anthropic-ratelimit-tokens-remaining", "1200"),
+            ("anthropic-ratelimit-tokens-reset", &later),
+        ]);
+        let cooldown = rate_limit_cooldown(429, &anthropic, now).unwrap();
+        assert!(cooldown > Duration::from_secs(9) && cooldown <= Duration::from_secs(10));
+
+        let openai = headers(&[
+            ("x-ratelimit-remaining-requests", "0"),
+            ("x-ratelimit-reset-requests", "1m30s"),
+            ("x-ratelimit-remaining-tokens", "0"),
+            ("x-ratelimit-reset-tokens", "250ms"),
+        ]);
+        assert_eq!(
+            rate_limit_cooldown(429, &openai, now),
+            Some(Duration::from_secs(90))
+        );
+
+        // 没有耗尽的维度：无法判断冷却时长
+        let healthy = headers(&[
+            ("x-ratelimit-remaining-requests", "5"),
+            ("x-ratelimit-reset-requests", "10s"),
+        ]);
+        assert_eq!(rate_limit_cooldown(429, &healthy, now), None);
+    }
+
+    #[test]
+    fn parses_go_style_durations() {
+        assert_eq!(
+            parse_reset_duration("20ms"),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(
+            parse_reset_duration("1h2m3.5s"),
+            Some(Duration::from_secs_f64(3723.5))
+        );
+        assert_eq!(parse_reset_duration("2"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_reset_duration("soon"), None);
+        assert_eq!(
+            parse_reset_duration("99999999999999999999h"),
+            Some(Duration::MAX)
+        );
+        assert_eq!(parse_reset_duration("1e300"), Some(Duration::MAX));
+
+        let huge = headers(&[
+            ("x-ratelimit-remaining-requests", "0"),
+            ("x-ratelimit-reset-requests", "99999999999999999999h"),
+        ]);
+        assert_eq!(
+            rate_limit_cooldown(429, &huge, Utc::now()),
+            Some(MAX_COOLDOWN)
+        );
+    }
+}
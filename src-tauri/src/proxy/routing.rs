@@ -0,0 +1,273 @@
+//! 路由策略模块
+//!
+//! 在故障转移队列内决定供应商的尝试顺序。队列顺序仍是兜底：
+//! 策略只重排已通过熔断器与限额检查的候选，排在后面的供应商照常作为故障转移目标。
+//!
+//! - round_robin / weighted：以会话 ID 为键做确定性选择，同一会话始终落在同一首选供应商，
+//!   不同会话按轮询 / 权重分散
+//! - least_latency / least_cost：按近期统计排序，统计定期刷新而非逐请求变化，避免会话内来回切换
+
+use super::types::RoutingStrategy;
+use crate::provider::Provider;
+use crate::services::usage_stats::ProviderRoutingStats;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// 参与延迟/成本排序所需的最少样本数
+///
+/// 样本不足的供应商排在前面优先尝试，以便尽快积累统计数据。
+pub const MIN_SAMPLES: u64 = 3;
+
+/// 统计窗口（秒）
+pub const STATS_WINDOW_SECS: i64 = 3600;
+
+/// 未配置权重时的默认权重
+const DEFAULT_WEIGHT: u32 = 1;
+
+/// 路由键：决定轮询偏移和加权选择的随机源
+#[derive(Debug, Clone, Copy)]
+pub enum RoutingKey<'a> {
+    /// 客户端提供的会话 ID，同一会话得到相同顺序
+    Session(&'a str),
+    /// 无会话标识时使用的请求序号
+    Sequence(u64),
+}
+
+impl RoutingKey<'_> {
+    fn seed(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match self {
+            RoutingKey::Session(id) => id.hash(&mut hasher),
+            RoutingKey::Sequence(seq) => seq.hash(&mut hasher),
+        }
+        hasher.finish()
+    }
+}
+
+/// 按策略重排候选供应商
+///
+/// `providers` 须已按故障转移队列顺序排列；priority 策略原样返回。
+pub fn order_providers(
+    strategy: RoutingStrategy,
+    mut providers: Vec<Provider>,
+    stats: &HashMap<String, ProviderRoutingStats>,
+    key: RoutingKey<'_>,
+) -> Vec<Provider> {
+    if providers.len() < 2 {
+        return providers;
+    }
+
+    match strategy {
+        RoutingStrategy::Priority => {}
+        RoutingStrategy::RoundRobin => {
+            let offset = match key {
+                RoutingKey::Session(_) => key.seed(),
+                RoutingKey::Sequence(seq) => seq,
+            } % providers.len() as u64;
+            providers.rotate_left(offset as usize);
+        }
+        RoutingStrategy::Weighted => {
+            let seed = key.seed();
+            let mut scored: Vec<(f64, Provider)> = providers
+                .into_iter()
+                .map(|provider| (weighted_score(seed, &provider), provider))
+                .collect();
+            scored.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+            providers = scored.into_iter().map(|(_, provider)| provider).collect();
+        }
+        RoutingStrategy::LeastLatency => {
+            sort_by_metric(&mut providers, stats, |s| s.avg_latency_ms);
+        }
+        RoutingStrategy::LeastCost => {
+            sort_by_metric(&mut providers, stats, |s| s.cost_per_million_tokens);
+        }
+    }
+
+    providers
+}
+
+/// 供应商的路由权重（`ProviderMeta.routing_weight`，默认 1）
+fn routing_weight(provider: &Provider) -> u32 {
+    provider
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.routing_weight)
+        .unwrap_or(DEFAULT_WEIGHT)
+}
+
+/// 加权随机排序分数（指数竞速：score = -ln(u) / w，越小越靠前）
+///
+/// 首位落在某供应商的概率正比于其权重；u 由路由键与供应商 ID 确定性生成，
+/// 因此同一会话的顺序稳定。权重为 0 的供应商排在最后，仅作为故障转移备用。
+fn weighted_score(seed: u64, provider: &Provider) -> f64 {
+    let weight = routing_weight(provider);
+    if weight == 0 {
+        return f64::INFINITY;
+    }
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    provider.id.hash(&mut hasher);
+    let u = (hasher.finish() as f64 + 1.0) / (u64::MAX as f64 + 2.0);
+    -u.ln() / f64::from(weight)
+}
+
+/// 按统计指标升序排序；样本不足或无数据的供应商保持队列顺序排在最前
+fn sort_by_metric(
+    providers: &mut [Provider],
+    stats: &HashMap<String, ProviderRoutingStats>,
+    metric: impl Fn(&ProviderRoutingStats) -> Option<f64>,
+) {
+    let value = |provider: &Provider| {
+        stats
+            .get(&provider.id)
+            .filter(|s| s.samples >= MIN_SAMPLES)
+            .and_then(&metric)
+    };
+    // sort_by 为稳定排序，相同指标保持队列顺序
+    providers.sort_by(|a, b| match (value(a), value(b)) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider(id: &str, weight: Option<u32>) -> Provider {
+        let mut provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            routing_weight: weight,
+            ..ProviderMeta::default()
+        });
+        provider
+    }
+
+    fn ids(providers: &[Provider]) -> Vec<&str> {
+        providers.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    fn stats(samples: u64, latency: f64, cost: f64) -> ProviderRoutingStats {
+        ProviderRoutingStats {
+            samples,
+            avg_latency_ms: Some(latency),
+            cost_per_million_tokens: Some(cost),
+        }
+    }
+
+    #[test]
+    fn round_robin_rotates_by_sequence_and_sticks_per_session() {
+        let queue = vec![
+            provider("a", None),
+            provider("b", None),
+            provider("c", None),
+        ];
+        let empty = HashMap::new();
+
+        let first: Vec<String> = (0..3)
+            .map(|seq| {
+                order_providers(
+                    RoutingStrategy::RoundRobin,
+                    queue.clone(),
+                    &empty,
+                    RoutingKey::Sequence(seq),
+                )[0]
+                .id
+                .clone()
+            })
+            .collect();
+        assert_eq!(first, vec!["a", "b", "c"]);
+
+        let once = order_providers(
+            RoutingStrategy::RoundRobin,
+            queue.clone(),
+            &empty,
+            RoutingKey::Session("s1"),
+        );
+        let again = order_providers(
+            RoutingStrategy::RoundRobin,
+            queue,
+            &empty,
+            RoutingKey::Session("s1"),
+        );
+        assert_eq!(ids(&once), ids(&again));
+        assert_eq!(once.len(), 3);
+    }
+
+    #[test]
+    fn weighted_distribution_follows_weights() {
+        let queue = vec![
+            provider("heavy", Some(3)),
+            provider("light", Some(1)),
+            provider("backup", Some(0)),
+        ];
+        let empty = HashMap::new();
+
+        let mut heavy_first = 0;
+        for seq in 0..4000u64 {
+            let ordered = order_providers(
+                RoutingStrategy::Weighted,
+                queue.clone(),
+                &empty,
+                RoutingKey::Sequence(seq),
+            );
+            // 权重为 0 的供应商永远排在最后
+            assert_eq!(ordered[2].id, "backup");
+            if ordered[0].id == "heavy" {
+                heavy_first += 1;
+            }
+        }
+        // 期望 75%，留出统计波动余量
+        assert!(
+            (2700..=3300).contains(&heavy_first),
+            "heavy_first={heavy_first}"
+        );
+    }
+
+    #[test]
+    fn least_latency_and_cost_rank_known_providers_after_unsampled() {
+        let queue = vec![
+            provider("a", None),
+            provider("b", None),
+            provider("c", None),
+        ];
+        let mut table = HashMap::new();
+        table.insert("a".to_string(), stats(10, 900.0, 1.0));
+        table.insert("b".to_string(), stats(10, 300.0, 5.0));
+        // c 样本不足，优先尝试
+        table.insert("c".to_string(), stats(1, 50.0, 0.1));
+
+        let by_latency = order_providers(
+            RoutingStrategy::LeastLatency,
+            queue.clone(),
+            &table,
+            RoutingKey::Sequence(0),
+        );
+        assert_eq!(ids(&by_latency), vec!["c", "b", "a"]);
+
+        let by_cost = order_providers(
+            RoutingStrategy::LeastCost,
+            queue,
+            &table,
+            RoutingKey::Sequence(0),
+        );
+        assert_eq!(ids(&by_cost), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn priority_keeps_queue_order() {
+        let queue = vec![provider("a", None), provider("b", None)];
+        let ordered = order_providers(
+            RoutingStrategy::Priority,
+            queue,
+            &HashMap::new(),
+            RoutingKey::Session("s"),
+        );
+        assert_eq!(ids(&ordered), vec!["a", "b"]);
+    }
+}
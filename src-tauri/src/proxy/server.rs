@@ -9,13 +9,23 @@
 //! a direct (non-proxied) CLI request.
 
 use super::{
-    failover_switch::FailoverSwitchManager, handlers, log_codes::srv as log_srv,
-    provider_router::ProviderRouter, providers::gemini_shadow::GeminiShadowStore, types::*,
+    failover_switch::FailoverSwitchManager,
+    handlers,
+    health::HealthChecker,
+    inbound_auth::{self, AccessPolicy},
+    log_codes::srv as log_srv,
+    metrics::{self, ProxyMetrics},
+    model_rules::{self, CompiledRule},
+    otel::OtlpExporter,
+    provider_router::ProviderRouter,
+    providers::gemini_shadow::GeminiShadowStore,
+    types::*,
     ProxyError,
 };
 use crate::database::Database;
 use axum::{
-    extract::DefaultBodyLimit,
+    extract::{ConnectInfo, DefaultBodyLimit},
+    middleware,
     routing::{get, post},
     Router,
 };
@@ -42,6 +52,16 @@ pub struct ProxyState {
     pub app_handle: Option<tauri::AppHandle>,
     /// 故障转移切换管理器
     pub failover_manager: Arc<FailoverSwitchManager>,
+    /// 入站访问控制策略（启动时从数据库加载，可热更新）
+    pub access: Arc<RwLock<AccessPolicy>>,
+    /// Prometheus 指标注册表
+    pub metrics: Arc<ProxyMetrics>,
+    /// OTLP 链路导出器（未配置端点时为 None）
+    pub otel: Arc<RwLock<Option<Arc<OtlpExporter>>>>,
+    /// 已编译的模型路由规则（规则变更时由 `reload_model_routing_rules` 刷新）
+    pub model_rules: Arc<RwLock<Vec<CompiledRule>>>,
+    /// 请求抓包规则（开启 / 关闭抓包时由 `reload_capture_rules` 刷新）
+    pub capture_rules: Arc<RwLock<Vec<CaptureRule>>>,
 }
 
 /// 代理HTTP服务器
@@ -51,6 +71,8 @@ pub struct ProxyServer {
     shutdown_tx: Arc<RwLock<Option<oneshot::Sender<()>>>>,
     /// 服务器任务句柄，用于等待服务器实际关闭
     server_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    /// 后台健康检查任务句柄
+    health_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
 }
 
 impl ProxyServer {
@@ -60,12 +82,28 @@ impl ProxyServer {
         app_handle: Option<tauri::AppHandle>,
     ) -> Self {
         // 创建共享的 ProviderRouter（熔断器状态将跨所有请求保持）
-        let provider_router = Arc::new(ProviderRouter::new(db.clone()));
+        let provider_router = ProviderRouter::new(db.clone()).with_app_handle(app_handle.clone());
+        Self::with_router(config, db, app_handle, provider_router)
+    }
+
+    /// 创建独立实例（用于回放）：不写入供应商健康状态、不发送熔断通知与事件
+    pub fn detached(config: ProxyConfig, db: Arc<Database>) -> Self {
+        let provider_router = ProviderRouter::new(db.clone()).detached();
+        Self::with_router(config, db, None, provider_router)
+    }
+
+    fn with_router(
+        config: ProxyConfig,
+        db: Arc<Database>,
+        app_handle: Option<tauri::AppHandle>,
+        provider_router: ProviderRouter,
+    ) -> Self {
+        let provider_router = Arc::new(provider_router);
         // 创建故障转移切换管理器
         let failover_manager = Arc::new(FailoverSwitchManager::new(db.clone()));
 
         let state = ProxyState {
-            db,
+            db: db.clone(),
             config: Arc::new(RwLock::new(config.clone())),
             status: Arc::new(RwLock::new(ProxyStatus::default())),
             start_time: Arc::new(RwLock::new(None)),
@@ -74,6 +112,11 @@ impl ProxyServer {
             gemini_shadow: Arc::new(GeminiShadowStore::default()),
             app_handle,
             failover_manager,
+            access: Arc::new(RwLock::new(AccessPolicy::default())),
+            metrics: Arc::new(ProxyMetrics::default()),
+            otel: Arc::new(RwLock::new(otlp_exporter(&config))),
+            model_rules: Arc::new(RwLock::new(load_model_rules(&db))),
+            capture_rules: Arc::new(RwLock::new(load_capture_rules(&db))),
         };
 
         Self {
@@ -81,6 +124,7 @@ impl ProxyServer {
             state,
             shutdown_tx: Arc::new(RwLock::new(None)),
             server_handle: Arc::new(RwLock::new(None)),
+            health_handle: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -90,10 +134,37 @@ impl ProxyServer {
             return Err(ProxyError::AlreadyRunning);
         }
 
-        let addr: SocketAddr =
-            format!("{}:{}", self.config.listen_address, self.config.listen_port)
-                .parse()
-                .map_err(|e| ProxyError::BindFailed(format!("无效的地址: {e}")))?;
+        // 加载入站访问控制；局域网共享模式强制要求令牌，缺失时自动生成并持久化
+        let mut access = self
+            .state
+            .db
+            .get_proxy_access_config()
+            .await
+            .map_err(|e| ProxyError::DatabaseError(e.to_string()))?;
+        if access.lan_mode && access.access_token.is_none() {
+            access.access_token = Some(inbound_auth::generate_access_token());
+            self.state
+                .db
+                .update_proxy_access_config(&access)
+                .await
+                .map_err(|e| ProxyError::DatabaseError(e.to_string()))?;
+            log::info!("[{}] 局域网共享模式已自动生成访问令牌", log_srv::STARTED);
+        }
+        let bind_address = bind_address(&self.config.listen_address, access.lan_mode);
+        if !is_loopback_address(&bind_address)
+            && access.access_token.is_none()
+            && access.allowed_cidrs.is_empty()
+        {
+            log::warn!(
+                "[{}] 代理监听 {bind_address} 且未配置访问令牌或来源白名单，局域网内任意设备均可使用",
+                log_srv::STARTED
+            );
+        }
+        *self.state.access.write().await = AccessPolicy::from_config(&access);
+
+        let addr: SocketAddr = format!("{bind_address}:{}", self.config.listen_port)
+            .parse()
+            .map_err(|e| ProxyError::BindFailed(format!("无效的地址: {e}")))?;
 
         // 创建关闭通道
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
@@ -117,7 +188,7 @@ impl ProxyServer {
         // 更新状态
         let mut status = self.state.status.write().await;
         status.running = true;
-        status.address = self.config.listen_address.clone();
+        status.address = bind_address.clone();
         status.port = self.config.listen_port;
         drop(status);
 
@@ -132,7 +203,7 @@ impl ProxyServer {
             loop {
                 tokio::select! {
                     result = listener.accept() => {
-                        let (stream, _remote_addr) = match result {
+                        let (stream, remote_addr) = match result {
                             Ok(v) => v,
                             Err(e) => {
                                 log::error!("[{SRV}] accept 失败: {e}", SRV = log_srv::ACCEPT_ERR);
@@ -173,6 +244,8 @@ impl ProxyServer {
 
                                     // Insert our own header case map alongside hyper's internal one
                                     parts.extensions.insert(cases);
+                                    // 来源地址，供入站访问控制使用
+                                    parts.extensions.insert(ConnectInfo(remote_addr));
 
                                     let body = axum::body::Body::new(body);
                                     let axum_req = http::Request::from_parts(parts, body);
@@ -204,8 +277,16 @@ impl ProxyServer {
         // 保存服务器任务句柄
         *self.server_handle.write().await = Some(handle);
 
+        // 启动后台健康检查（未启用时调度循环空转，配置可热切换）
+        let health_checker = HealthChecker::new(
+            self.state.db.clone(),
+            self.state.provider_router.clone(),
+            self.state.app_handle.clone(),
+        );
+        *self.health_handle.write().await = Some(health_checker.spawn());
+
         Ok(ProxyServerInfo {
-            address: self.config.listen_address.clone(),
+            address: bind_address,
             port: self.config.listen_port,
             started_at: chrono::Utc::now().to_rfc3339(),
         })
@@ -219,6 +300,10 @@ impl ProxyServer {
             return Err(ProxyError::NotRunning);
         }
 
+        if let Some(handle) = self.health_handle.write().await.take() {
+            handle.abort();
+        }
+
         // 2. 等待服务器任务结束（带 5 秒超时保护）
         if let Some(handle) = self.server_handle.write().await.take() {
             match tokio::time::timeout(std::time::Duration::from_secs(5), handle).await {
@@ -261,6 +346,13 @@ impl ProxyServer {
                 provider_name: provider_name.clone(),
             })
             .collect();
+        drop(current_providers);
+
+        let access = self.state.access.read().await;
+        status.auth_required = access.auth_required();
+        status.access_token = access.config().access_token.clone();
+        status.lan_mode = access.config().lan_mode;
+        status.allowed_cidrs = access.config().allowed_cidrs.clone();
 
         status
     }
@@ -277,14 +369,35 @@ impl ProxyServer {
         );
     }
 
-    fn build_router(&self) -> Router {
+    pub(crate) fn build_router(&self) -> Router {
         Router::new()
             // 健康检查
             .route("/health", get(handlers::health_check))
             .route("/status", get(handlers::get_status))
+            // Prometheus 指标（需在代理配置中启用）
+            .route("/metrics", get(metrics::handle_metrics))
             // Claude API (支持带前缀和不带前缀两种格式)
             .route("/v1/messages", post(handlers::handle_messages))
             .route("/claude/v1/messages", post(handlers::handle_messages))
+            .route(
+                "/v1/messages/count_tokens",
+                post(handlers::handle_count_tokens),
+            )
+            .route(
+                "/claude/v1/messages/count_tokens",
+                post(handlers::handle_count_tokens),
+            )
+            // OpenAI Chat Completions 客户端使用 Claude 供应商
+            .route(
+                "/claude/v1/chat/completions",
+                post(handlers::handle_claude_chat_completions),
+            )
+            // 模型列表（Claude 返回 Anthropic 格式，Codex 返回 OpenAI 格式）
+            .route("/models", get(handlers::handle_models))
+            .route("/v1/models", get(handlers::handle_models))
+            .route("/v1/v1/models", get(handlers::handle_models))
+            .route("/claude/v1/models", get(handlers::handle_models))
+            .route("/codex/v1/models", get(handlers::handle_models))
             // OpenAI Chat Completions API (Codex CLI，支持带前缀和不带前缀)
             .route("/chat/completions", post(handlers::handle_chat_completions))
             .route(
@@ -322,8 +435,15 @@ impl ProxyServer {
                 post(handlers::handle_responses_compact),
             )
             // Gemini API (支持带前缀和不带前缀)
+            .route("/v1beta/models", get(handlers::handle_gemini_models))
+            .route("/gemini/v1beta/models", get(handlers::handle_gemini_models))
             .route("/v1beta/*path", post(handlers::handle_gemini))
             .route("/gemini/v1beta/*path", post(handlers::handle_gemini))
+            // 入站访问控制（令牌 / 来源网段），在各 handler 之前执行
+            .layer(middleware::from_fn_with_state(
+                self.state.clone(),
+                inbound_auth::enforce,
+            ))
             // 提高默认请求体大小限制（避免 413 Payload Too Large）
             .layer(DefaultBodyLimit::max(200 * 1024 * 1024))
             .with_state(self.state.clone())
@@ -331,9 +451,36 @@ impl ProxyServer {
 
     /// 在不重启服务的情况下更新运行时配置
     pub async fn apply_runtime_config(&self, config: &ProxyConfig) {
+        let endpoint_changed = self.state.config.read().await.otlp_endpoint != config.otlp_endpoint;
+        if endpoint_changed {
+            *self.state.otel.write().await = otlp_exporter(config);
+        }
         *self.state.config.write().await = config.clone();
     }
 
+    /// 热更新入站访问控制（令牌 / 来源网段）
+    ///
+    /// 局域网模式的开关会改变监听地址，需要由调用方 `stop` 后重新 `start`
+    /// （同一实例重启会保留熔断器、会话亲和等运行时状态）。
+    pub async fn apply_access_config(&self, config: &ProxyAccessConfig) {
+        *self.state.access.write().await = AccessPolicy::from_config(config);
+    }
+
+    /// 重新加载并编译模型路由规则
+    pub async fn reload_model_routing_rules(&self) {
+        *self.state.model_rules.write().await = load_model_rules(&self.state.db);
+    }
+
+    /// 重新加载请求抓包规则
+    pub async fn reload_capture_rules(&self) {
+        *self.state.capture_rules.write().await = load_capture_rules(&self.state.db);
+    }
+
+    /// 当前是否处于局域网共享模式
+    pub async fn is_lan_mode(&self) -> bool {
+        self.state.access.read().await.config().lan_mode
+    }
+
     /// 热更新熔断器配置
     ///
     /// 将新配置应用到所有已创建的熔断器实例
@@ -352,3 +499,58 @@ impl ProxyServer {
             .await;
     }
 }
+
+/// 从数据库加载并编译已启用的模型路由规则（读取失败时不启用规则）
+fn load_model_rules(db: &Database) -> Vec<CompiledRule> {
+    match db.get_model_routing_rules() {
+        Ok(rules) => model_rules::compile_rules(rules),
+        Err(e) => {
+            log::warn!("[ProxyServer] 读取模型路由规则失败: {e}");
+            Vec::new()
+        }
+    }
+}
+
+/// 从数据库加载请求抓包规则（读取失败时不抓包）
+fn load_capture_rules(db: &Database) -> Vec<CaptureRule> {
+    db.get_capture_rules().unwrap_or_else(|e| {
+        log::warn!("[ProxyServer] 读取抓包规则失败: {e}");
+        Vec::new()
+    })
+}
+
+/// 按配置创建 OTLP 导出器（端点为空或无效时不导出）
+fn otlp_exporter(config: &ProxyConfig) -> Option<Arc<OtlpExporter>> {
+    let endpoint = config
+        .otlp_endpoint
+        .as_deref()
+        .map(str::trim)
+        .filter(|endpoint| !endpoint.is_empty())?;
+    match OtlpExporter::new(endpoint) {
+        Ok(exporter) => {
+            log::info!("[OTel] 链路导出已启用: {}", exporter.endpoint());
+            Some(Arc::new(exporter))
+        }
+        Err(e) => {
+            log::warn!("[OTel] 链路导出未启用: {e}");
+            None
+        }
+    }
+}
+
+/// 计算实际监听地址：局域网共享模式下，回环地址改为监听所有网卡
+fn bind_address(listen_address: &str, lan_mode: bool) -> String {
+    if lan_mode && is_loopback_address(listen_address) {
+        "0.0.0.0".to_string()
+    } else {
+        listen_address.to_string()
+    }
+}
+
+fn is_loopback_address(address: &str) -> bool {
+    address == "localhost"
+        || address
+            .parse::<std::net::IpAddr>()
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(false)
+}
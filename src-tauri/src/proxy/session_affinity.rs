@@ -0,0 +1,177 @@
+//! 会话亲和性模块
+//!
+//! 把客户端会话固定在首轮成功响应的供应商上，只有该供应商不可用（熔断 / 超限 / 移出队列）
+//! 而发生故障转移时才改绑到新的供应商。这样同一会话的提示词缓存持续命中，
+//! 也避免跨供应商切换导致 thinking 签名失效。
+//!
+//! 仅记录客户端提供的会话 ID；代理自行生成的 ID 每个请求都不同，没有亲和意义。
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// 默认亲和有效期：与 Anthropic 1 小时提示词缓存对齐
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// 默认最多记录的会话数
+const DEFAULT_MAX_SESSIONS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct AffinityKey {
+    app_type: String,
+    session_id: String,
+}
+
+#[derive(Debug, Clone)]
+struct AffinityEntry {
+    provider_id: String,
+    last_used: Instant,
+}
+
+/// 会话 → 供应商 亲和表
+///
+/// - 每次成功响应都会重新绑定并刷新有效期（滑动 TTL）
+/// - 超过容量时先清理过期项，仍不足则淘汰最久未使用的会话
+#[derive(Debug)]
+pub struct SessionAffinityStore {
+    ttl: Duration,
+    max_sessions: usize,
+    inner: RwLock<HashMap<AffinityKey, AffinityEntry>>,
+}
+
+impl Default for SessionAffinityStore {
+    fn default() -> Self {
+        Self::with_limits(DEFAULT_TTL, DEFAULT_MAX_SESSIONS)
+    }
+}
+
+impl SessionAffinityStore {
+    pub fn with_limits(ttl: Duration, max_sessions: usize) -> Self {
+        Self {
+            ttl,
+            max_sessions: max_sessions.max(1),
+            inner: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 查询会话当前绑定的供应商（过期视为未绑定）
+    pub fn get(&self, app_type: &str, session_id: &str) -> Option<String> {
+        self.get_at(app_type, session_id, Instant::now())
+    }
+
+    /// 绑定（或改绑）会话到供应商
+    pub fn pin(&self, app_type: &str, session_id: &str, provider_id: &str) {
+        self.pin_at(app_type, session_id, provider_id, Instant::now());
+    }
+
+    fn get_at(&self, app_type: &str, session_id: &str, now: Instant) -> Option<String> {
+        let key = AffinityKey {
+            app_type: app_type.to_string(),
+            session_id: session_id.to_string(),
+        };
+        let inner = self.inner.read().ok()?;
+        inner
+            .get(&key)
+            .filter(|entry| now.saturating_duration_since(entry.last_used) < self.ttl)
+            .map(|entry| entry.provider_id.clone())
+    }
+
+    fn pin_at(&self, app_type: &str, session_id: &str, provider_id: &str, now: Instant) {
+        let Ok(mut inner) = self.inner.write() else {
+            return;
+        };
+        let key = AffinityKey {
+            app_type: app_type.to_string(),
+            session_id: session_id.to_string(),
+        };
+
+        if let Some(entry) = inner.get_mut(&key) {
+            if entry.provider_id != provider_id {
+                log::debug!(
+                    "[{app_type}] 会话 {session_id} 亲和供应商改绑: {} → {provider_id}",
+                    entry.provider_id
+                );
+                entry.provider_id = provider_id.to_string();
+            }
+            entry.last_used = now;
+            return;
+        }
+
+        if inner.len() >= self.max_sessions {
+            let ttl = self.ttl;
+            inner.retain(|_, entry| now.saturating_duration_since(entry.last_used) < ttl);
+        }
+        if inner.len() >= self.max_sessions {
+            if let Some(oldest) = inner
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            {
+                inner.remove(&oldest);
+            }
+        }
+
+        inner.insert(
+            key,
+            AffinityEntry {
+                provider_id: provider_id.to_string(),
+                last_used: now,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pin_and_repin_on_failover() {
+        let store = SessionAffinityStore::default();
+        assert_eq!(store.get("claude", "s1"), None);
+
+        store.pin("claude", "s1", "a");
+        assert_eq!(store.get("claude", "s1").as_deref(), Some("a"));
+        // 按 app 隔离
+        assert_eq!(store.get("codex", "s1"), None);
+
+        store.pin("claude", "s1", "b");
+        assert_eq!(store.get("claude", "s1").as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn entries_expire_after_ttl_and_refresh_on_use() {
+        let store = SessionAffinityStore::with_limits(Duration::from_secs(60), 10);
+        let start = Instant::now();
+
+        store.pin_at("claude", "s1", "a", start);
+        assert!(store
+            .get_at("claude", "s1", start + Duration::from_secs(59))
+            .is_some());
+        assert!(store
+            .get_at("claude", "s1", start + Duration::from_secs(61))
+            .is_none());
+
+        // 再次绑定刷新有效期
+        store.pin_at("claude", "s1", "a", start + Duration::from_secs(50));
+        assert!(store
+            .get_at("claude", "s1", start + Duration::from_secs(100))
+            .is_some());
+    }
+
+    #[test]
+    fn evicts_least_recently_used_when_full() {
+        let store = SessionAffinityStore::with_limits(Duration::from_secs(600), 2);
+        let start = Instant::now();
+
+        store.pin_at("claude", "s1", "a", start);
+        store.pin_at("claude", "s2", "a", start + Duration::from_secs(1));
+        store.pin_at("claude", "s1", "a", start + Duration::from_secs(2));
+        store.pin_at("claude", "s3", "b", start + Duration::from_secs(3));
+
+        let now = start + Duration::from_secs(4);
+        assert!(store.get_at("claude", "s1", now).is_some());
+        assert!(store.get_at("claude", "s2", now).is_none());
+        assert!(store.get_at("claude", "s3", now).is_some());
+    }
+}
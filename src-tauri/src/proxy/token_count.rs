@@ -0,0 +1,258 @@
+//! Token 计数模块
+//!
+//! 响应 Claude 的 `/v1/messages/count_tokens`：
+//! - Anthropic 原生格式的供应商：直接转发到上游 count_tokens 端点
+//! - OpenAI / Gemini 等无等价端点的上游，或上游不支持时：本地估算
+
+use super::{
+    model_mapper::apply_model_mapping,
+    providers::{get_adapter, get_claude_api_format, AuthStrategy, ProviderType},
+};
+use crate::app_config::AppType;
+use crate::provider::Provider;
+use axum::http::HeaderMap;
+use serde_json::Value;
+use std::time::Duration;
+
+/// 上游 count_tokens 超时（秒）
+const COUNT_TOKENS_TIMEOUT_SECS: u64 = 15;
+
+/// 图片/文档块的固定估算值
+///
+/// 实际消耗取决于分辨率和页数，这里取常见截图的量级，只求不严重低估。
+const MEDIA_BLOCK_TOKENS: u64 = 1600;
+
+/// 每条消息的结构开销（角色、分隔符等）
+const MESSAGE_OVERHEAD_TOKENS: u64 = 4;
+
+/// 供应商是否可以直接转发 count_tokens
+///
+/// 仅 Anthropic 原生格式且使用静态 API Key 认证的供应商才有等价端点；
+/// Copilot / Codex OAuth / OpenAI / Gemini 格式一律本地估算。
+pub fn supports_upstream_count(provider: &Provider) -> bool {
+    if get_claude_api_format(provider) != "anthropic" {
+        return false;
+    }
+    if provider
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.is_full_url)
+        .unwrap_or(false)
+    {
+        return false;
+    }
+    if !matches!(
+        ProviderType::from_app_type_and_config(&AppType::Claude, provider),
+        ProviderType::Claude | ProviderType::ClaudeAuth | ProviderType::OpenRouter
+    ) {
+        return false;
+    }
+    get_adapter(&AppType::Claude)
+        .extract_auth(provider)
+        .map(|auth| {
+            matches!(
+                auth.strategy,
+                AuthStrategy::Anthropic | AuthStrategy::ClaudeAuth | AuthStrategy::Bearer
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// 转发 count_tokens 到上游
+///
+/// 返回上游的 JSON 响应体；任何失败（含上游 404 等不支持的情况）返回 Err，由调用方回退到本地估算。
+pub async fn count_upstream(
+    provider: &Provider,
+    headers: &HeaderMap,
+    body: Value,
+) -> Result<Value, String> {
+    let adapter = get_adapter(&AppType::Claude);
+    let base_url = adapter
+        .extract_base_url(provider)
+        .map_err(|e| e.to_string())?;
+    let auth = adapter
+        .extract_auth(provider)
+        .ok_or_else(|| "Missing API key".to_string())?;
+    let url = adapter.build_url(&base_url, "/v1/messages/count_tokens");
+    let (body, _, _) = apply_model_mapping(body, provider, None);
+
+    let mut request = crate::proxy::http_client::get()
+        .post(&url)
+        .timeout(Duration::from_secs(COUNT_TOKENS_TIMEOUT_SECS))
+        .header("content-type", "application/json")
+        .header(
+            "anthropic-version",
+            headers
+                .get("anthropic-version")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("2023-06-01"),
+        );
+    if let Some(beta) = headers.get("anthropic-beta") {
+        request = request.header("anthropic-beta", beta);
+    }
+    for (name, value) in adapter.get_auth_headers(&auth) {
+        request = request.header(name, value);
+    }
+
+    let response = request
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {status}"));
+    }
+
+    let value: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {e}"))?;
+    if value.get("input_tokens").and_then(|v| v.as_u64()).is_none() {
+        return Err("Response has no input_tokens".to_string());
+    }
+    Ok(value)
+}
+
+/// 本地估算 Anthropic Messages 请求的输入 token 数
+///
+/// 粗略规则：ASCII 约 4 字符 1 token，CJK 等宽字符约 1 字符 1 token；
+/// 工具定义和 tool_use 入参按序列化后的 JSON 计算。
+pub fn estimate_input_tokens(body: &Value) -> u64 {
+    let mut tokens = 0u64;
+
+    if let Some(system) = body.get("system") {
+        tokens += estimate_content(system);
+    }
+
+    if let Some(messages) = body.get("messages").and_then(|m| m.as_array()) {
+        for message in messages {
+            tokens += MESSAGE_OVERHEAD_TOKENS;
+            if let Some(content) = message.get("content") {
+                tokens += estimate_content(content);
+            }
+        }
+    }
+
+    if let Some(tools) = body.get("tools").and_then(|t| t.as_array()) {
+        for tool in tools {
+            tokens += estimate_text(&tool.to_string());
+        }
+    }
+
+    tokens.max(1)
+}
+
+/// 估算 content 字段（字符串或内容块数组）
+fn estimate_content(content: &Value) -> u64 {
+    match content {
+        Value::String(text) => estimate_text(text),
+        Value::Array(blocks) => blocks.iter().map(estimate_block).sum(),
+        Value::Null => 0,
+        other => estimate_text(&other.to_string()),
+    }
+}
+
+fn estimate_block(block: &Value) -> u64 {
+    match block.get("type").and_then(|t| t.as_str()) {
+        Some("text") => block
+            .get("text")
+            .and_then(|t| t.as_str())
+            .map(estimate_text)
+            .unwrap_or(0),
+        Some("thinking") => block
+            .get("thinking")
+            .and_then(|t| t.as_str())
+            .map(estimate_text)
+            .unwrap_or(0),
+        // redacted_thinking 不计入后续轮次的输入
+        Some("redacted_thinking") => 0,
+        Some("image") | Some("document") => MEDIA_BLOCK_TOKENS,
+        Some("tool_use") => {
+            let name = block.get("name").and_then(|n| n.as_str()).unwrap_or("");
+            let input = block
+                .get("input")
+                .map(|i| i.to_string())
+                .unwrap_or_default();
+            estimate_text(name) + estimate_text(&input)
+        }
+        Some("tool_result") => block.get("content").map(estimate_content).unwrap_or(0),
+        _ => estimate_text(&block.to_string()),
+    }
+}
+
+/// 估算纯文本 token 数
+fn estimate_text(text: &str) -> u64 {
+    let (ascii, wide) = text.chars().fold((0u64, 0u64), |(ascii, wide), c| {
+        if c.is_ascii() {
+            (ascii + 1, wide)
+        } else {
+            (ascii, wide + 1)
+        }
+    });
+    ascii.div_ceil(4) + wide
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    #[test]
+    fn estimate_counts_system_messages_and_tools() {
+        let body = json!({
+            "model": "claude-sonnet-4-5",
+            "system": "abcdabcd",
+            "messages": [
+                {"role": "user", "content": "abcd"},
+                {"role": "assistant", "content": [
+                    {"type": "text", "text": "你好"},
+                    {"type": "tool_use", "id": "t1", "name": "ls", "input": {}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "t1", "content": "abcdabcdabcd"}
+                ]}
+            ]
+        });
+        // system 2 + 3 条消息开销 12 + "abcd" 1 + "你好" 2 + tool_use("ls" 1 + "{}" 1) + tool_result 3
+        assert_eq!(estimate_input_tokens(&body), 22);
+
+        let mut with_tools = body.clone();
+        with_tools["tools"] = json!([{"name": "ls", "input_schema": {"type": "object"}}]);
+        assert!(estimate_input_tokens(&with_tools) > 22);
+    }
+
+    #[test]
+    fn estimate_never_returns_zero() {
+        assert_eq!(estimate_input_tokens(&json!({"messages": []})), 1);
+    }
+
+    #[test]
+    fn only_anthropic_format_providers_forward_upstream() {
+        let anthropic = Provider::with_id(
+            "a".to_string(),
+            "A".to_string(),
+            json!({"env": {
+                "ANTHROPIC_BASE_URL": "https://api.anthropic.com",
+                "ANTHROPIC_AUTH_TOKEN": "sk-ant-test"
+            }}),
+            None,
+        );
+        assert!(supports_upstream_count(&anthropic));
+
+        let mut openai_chat = anthropic.clone();
+        openai_chat.meta = Some(ProviderMeta {
+            api_format: Some("openai_chat".to_string()),
+            ..ProviderMeta::default()
+        });
+        assert!(!supports_upstream_count(&openai_chat));
+
+        let mut copilot = anthropic.clone();
+        copilot.meta = Some(ProviderMeta {
+            provider_type: Some("github_copilot".to_string()),
+            ..ProviderMeta::default()
+        });
+        assert!(!supports_upstream_count(&copilot));
+    }
+}
@@ -25,6 +25,12 @@ pub struct ProxyConfig {
     /// 非流式总超时（秒）- 非流式请求的总超时时间，范围 60-1200 秒，默认 600 秒（10 分钟）
     #[serde(default = "default_non_streaming_timeout")]
     pub non_streaming_timeout: u64,
+    /// 是否在 `/metrics` 暴露 Prometheus 指标
+    #[serde(default)]
+    pub metrics_enabled: bool,
+    /// OTLP/HTTP 链路导出端点（如 `http://127.0.0.1:4318`），None 表示不导出
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
 }
 
 fn default_streaming_first_byte_timeout() -> u64 {
@@ -51,6 +57,8 @@ impl Default for ProxyConfig {
             streaming_first_byte_timeout: 60,
             streaming_idle_timeout: 120,
             non_streaming_timeout: 600,
+            metrics_enabled: false,
+            otlp_endpoint: None,
         }
     }
 }
@@ -89,6 +97,18 @@ pub struct ProxyStatus {
     /// 当前活跃的代理目标列表
     #[serde(default)]
     pub active_targets: Vec<ActiveTarget>,
+    /// 是否要求入站访问令牌
+    #[serde(default)]
+    pub auth_required: bool,
+    /// 入站访问令牌（供局域网内其他设备配置使用）
+    #[serde(default)]
+    pub access_token: Option<String>,
+    /// 是否处于局域网共享模式
+    #[serde(default)]
+    pub lan_mode: bool,
+    /// 生效中的来源网段白名单
+    #[serde(default)]
+    pub allowed_cidrs: Vec<String>,
 }
 
 /// 活跃的代理目标信息
@@ -164,6 +184,122 @@ pub struct GlobalProxyConfig {
     pub enable_logging: bool,
 }
 
+/// 代理入站访问控制配置（统一字段，三行镜像）
+///
+/// 本机回环连接始终放行（接管写入 Live 的是占位 Token），以下规则只约束来自其他主机的连接。
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyAccessConfig {
+    /// 访问令牌（通过 `x-api-key` / `Authorization: Bearer` / `x-goog-api-key` 携带），None 表示不校验
+    #[serde(default)]
+    pub access_token: Option<String>,
+    /// 允许访问的来源网段（CIDR，如 `192.168.1.0/24`），为空表示不限制
+    #[serde(default)]
+    pub allowed_cidrs: Vec<String>,
+    /// 局域网共享模式：监听所有网卡，强制要求访问令牌，未配置白名单时仅允许私有网段
+    #[serde(default)]
+    pub lan_mode: bool,
+}
+
+/// 故障转移队列的路由策略（每个 app 独立，存储在 proxy_config.routing_strategy）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingStrategy {
+    /// 严格按队列优先级（P1 → P2 → ...），第一个可用供应商承担全部流量
+    #[default]
+    Priority,
+    /// 轮询：按会话轮流分配首选供应商
+    RoundRobin,
+    /// 加权：按 `ProviderMeta.routing_weight` 比例分配首选供应商
+    Weighted,
+    /// 最低延迟：按近期请求的平均延迟排序
+    LeastLatency,
+    /// 最低成本：按近期每百万 token 的实际成本排序
+    LeastCost,
+}
+
+impl RoutingStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoutingStrategy::Priority => "priority",
+            RoutingStrategy::RoundRobin => "round_robin",
+            RoutingStrategy::Weighted => "weighted",
+            RoutingStrategy::LeastLatency => "least_latency",
+            RoutingStrategy::LeastCost => "least_cost",
+        }
+    }
+}
+
+impl std::str::FromStr for RoutingStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "priority" => Ok(RoutingStrategy::Priority),
+            "round_robin" => Ok(RoutingStrategy::RoundRobin),
+            "weighted" => Ok(RoutingStrategy::Weighted),
+            "least_latency" => Ok(RoutingStrategy::LeastLatency),
+            "least_cost" => Ok(RoutingStrategy::LeastCost),
+            other => Err(format!("未知路由策略: {other}")),
+        }
+    }
+}
+
+/// 重试策略（每个 app 独立，存储在 proxy_config 的 retry_* 列）
+///
+/// 默认每个供应商只尝试一次，失败即故障转移到下一个，与未配置时的行为一致。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    /// 每个供应商的最大尝试次数（含首次）
+    pub max_attempts_per_provider: u32,
+    /// 退避基础延迟（毫秒）
+    pub base_delay_ms: u64,
+    /// 退避最大延迟（毫秒）；限流重置时间超过该值时不等待，直接故障转移
+    pub max_delay_ms: u64,
+    /// 允许重复尝试的上游状态码（网络错误/超时总是可重试）
+    pub retryable_status_codes: Vec<u16>,
+    /// 是否先重试同一供应商再故障转移；关闭时整个队列失败后再开始下一轮
+    pub retry_same_provider: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts_per_provider: 1,
+            base_delay_ms: 500,
+            max_delay_ms: 8000,
+            retryable_status_codes: vec![429, 500, 502, 503, 504, 529],
+            retry_same_provider: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 尝试次数上限
+    pub const MAX_ATTEMPTS: u32 = 10;
+    /// 延迟上限（毫秒）
+    pub const MAX_DELAY_MS: u64 = 120_000;
+
+    /// 状态码列表的存储格式（逗号分隔）
+    pub fn status_codes_to_string(&self) -> String {
+        self.retryable_status_codes
+            .iter()
+            .map(u16::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// 解析逗号分隔的状态码列表，忽略无法识别的项
+    pub fn parse_status_codes(value: &str) -> Vec<u16> {
+        value
+            .split(',')
+            .filter_map(|code| code.trim().parse::<u16>().ok())
+            .filter(|code| (400..600).contains(code))
+            .collect()
+    }
+}
+
 /// 应用级代理配置（每个 app 独立）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -369,6 +505,43 @@ impl LogConfig {
     }
 }
 
+/// 请求抓包规则
+///
+/// 存储在 settings 表的 proxy_capture_rules 字段中（JSON 数组），到期后自动失效
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureRule {
+    /// 应用类型（claude/codex/gemini）
+    pub app_type: String,
+    /// 指定供应商，为空表示该应用下所有供应商
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_id: Option<String>,
+    /// 到期时间（Unix 秒）
+    pub expires_at: i64,
+}
+
+impl CaptureRule {
+    /// 规则是否仍在有效期内
+    pub fn is_active(&self, now: i64) -> bool {
+        self.expires_at > now
+    }
+
+    /// 是否命中指定应用与故障转移链中的任一供应商
+    pub fn matches<'a>(
+        &self,
+        app_type: &str,
+        mut provider_ids: impl Iterator<Item = &'a str>,
+        now: i64,
+    ) -> bool {
+        self.is_active(now)
+            && self.app_type == app_type
+            && match &self.provider_id {
+                Some(id) => provider_ids.any(|p| p == id),
+                None => true,
+            }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
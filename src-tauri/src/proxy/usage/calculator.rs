@@ -1,12 +1,22 @@
 //! Cost Calculator - 计算 API 请求成本
 //!
 //! 使用高精度 Decimal 类型避免浮点数精度问题
+//!
+//! 除基础的输入/输出/缓存价格外，还支持：
+//! - 长上下文分段价格：提示 token 数超过阈值后，整单按该档价格计费
+//! - 1 小时缓存写入价格（Anthropic extended cache TTL）
+//! - 推理 token 单独定价
+//! - 服务等级倍率（priority / batch / flex 等）
 
 use super::parser::TokenUsage;
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::str::FromStr;
 
 /// 成本明细
+///
+/// 推理 token 成本计入 `output_cost`，1 小时缓存写入成本计入 `cache_creation_cost`
 #[derive(Debug, Clone)]
 pub struct CostBreakdown {
     pub input_cost: Decimal,
@@ -16,6 +26,31 @@ pub struct CostBreakdown {
     pub total_cost: Decimal,
 }
 
+/// 分段定价（存储于 `model_pricing.tiers`，价格为每百万 token 的 USD 字符串）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingTier {
+    /// 提示 token 数超过该值时启用本档价格
+    pub above_input_tokens: u64,
+    pub input_cost_per_million: String,
+    pub output_cost_per_million: String,
+    pub cache_read_cost_per_million: String,
+    pub cache_creation_cost_per_million: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_1h_cost_per_million: Option<String>,
+}
+
+/// 单档价格
+#[derive(Debug, Clone, PartialEq)]
+pub struct PricingRates {
+    pub input_cost_per_million: Decimal,
+    pub output_cost_per_million: Decimal,
+    pub cache_read_cost_per_million: Decimal,
+    pub cache_creation_cost_per_million: Decimal,
+    /// 1 小时缓存写入价格（None 时按 5 分钟缓存写入价格计费）
+    pub cache_creation_1h_cost_per_million: Option<Decimal>,
+}
+
 /// 模型定价信息
 #[derive(Debug, Clone)]
 pub struct ModelPricing {
@@ -23,6 +58,14 @@ pub struct ModelPricing {
     pub output_cost_per_million: Decimal,
     pub cache_read_cost_per_million: Decimal,
     pub cache_creation_cost_per_million: Decimal,
+    /// 1 小时缓存写入价格（None 时按 5 分钟缓存写入价格计费）
+    pub cache_creation_1h_cost_per_million: Option<Decimal>,
+    /// 推理 token 价格（None 时按输出价格计费）
+    pub reasoning_cost_per_million: Option<Decimal>,
+    /// 长上下文分段价格，按阈值升序排列
+    pub tiers: Vec<(u64, PricingRates)>,
+    /// 服务等级倍率，键为小写的 service_tier（如 priority、batch）
+    pub service_tier_multipliers: HashMap<String, Decimal>,
 }
 
 /// 成本计算器
@@ -37,9 +80,13 @@ impl CostCalculator {
     /// - `cost_multiplier`: 成本倍数 (provider 自定义)
     ///
     /// # 计算逻辑
+    /// - 先按提示 token 数选择价格档位（未超过任何阈值时使用基础价格）
     /// - input_cost: (input_tokens - cache_read_tokens) × 输入价格
     /// - cache_read_cost: cache_read_tokens × 缓存读取价格
     /// - 这样避免缓存部分被重复计费
+    /// - output_cost: 普通输出 × 输出价格 + 推理 token × 推理价格
+    /// - cache_creation_cost: 5 分钟缓存写入 × 写入价格 + 1 小时缓存写入 × 1 小时写入价格
+    /// - 服务等级倍率作用于各项成本（属于官方价格差异）
     /// - total_cost: 各项成本之和 × 倍率（倍率只作用于最终总价）
     pub fn calculate(
         usage: &TokenUsage,
@@ -47,20 +94,45 @@ impl CostCalculator {
         cost_multiplier: Decimal,
     ) -> CostBreakdown {
         let million = Decimal::from(1_000_000);
+        let rates = pricing.rates_for(usage.prompt_tokens());
+        let tier_multiplier = pricing.service_tier_multiplier(usage.service_tier.as_deref());
 
         // 计算实际需要按输入价格计费的 token 数（减去缓存命中部分）
         let billable_input_tokens = usage.input_tokens.saturating_sub(usage.cache_read_tokens);
 
+        // 推理 token 包含在输出中，单独定价时拆分计费
+        let reasoning_tokens = usage.reasoning_tokens.min(usage.output_tokens);
+        let plain_output_tokens = usage.output_tokens - reasoning_tokens;
+        let reasoning_price = pricing
+            .reasoning_cost_per_million
+            .unwrap_or(rates.output_cost_per_million);
+
+        // 1 小时缓存写入包含在缓存写入总数中
+        let cache_1h_tokens = usage
+            .cache_creation_1h_tokens
+            .min(usage.cache_creation_tokens);
+        let cache_5m_tokens = usage.cache_creation_tokens - cache_1h_tokens;
+        let cache_1h_price = rates
+            .cache_creation_1h_cost_per_million
+            .or(pricing.cache_creation_1h_cost_per_million)
+            .unwrap_or(rates.cache_creation_cost_per_million);
+
         // 各项基础成本（不含倍率）
-        let input_cost =
-            Decimal::from(billable_input_tokens) * pricing.input_cost_per_million / million;
-        let output_cost =
-            Decimal::from(usage.output_tokens) * pricing.output_cost_per_million / million;
+        let input_cost = Decimal::from(billable_input_tokens) * rates.input_cost_per_million
+            / million
+            * tier_multiplier;
+        let output_cost = (Decimal::from(plain_output_tokens) * rates.output_cost_per_million
+            + Decimal::from(reasoning_tokens) * reasoning_price)
+            / million
+            * tier_multiplier;
         let cache_read_cost =
-            Decimal::from(usage.cache_read_tokens) * pricing.cache_read_cost_per_million / million;
-        let cache_creation_cost = Decimal::from(usage.cache_creation_tokens)
-            * pricing.cache_creation_cost_per_million
-            / million;
+            Decimal::from(usage.cache_read_tokens) * rates.cache_read_cost_per_million / million
+                * tier_multiplier;
+        let cache_creation_cost = (Decimal::from(cache_5m_tokens)
+            * rates.cache_creation_cost_per_million
+            + Decimal::from(cache_1h_tokens) * cache_1h_price)
+            / million
+            * tier_multiplier;
 
         // 总成本 = 各项基础成本之和 × 倍率
         let base_total = input_cost + output_cost + cache_read_cost + cache_creation_cost;
@@ -98,8 +170,108 @@ impl ModelPricing {
             output_cost_per_million: Decimal::from_str(output)?,
             cache_read_cost_per_million: Decimal::from_str(cache_read)?,
             cache_creation_cost_per_million: Decimal::from_str(cache_creation)?,
+            cache_creation_1h_cost_per_million: None,
+            reasoning_cost_per_million: None,
+            tiers: Vec::new(),
+            service_tier_multipliers: HashMap::new(),
         })
     }
+
+    /// 附加定价修饰项（对应 `model_pricing` 表中的可选列）
+    ///
+    /// `tiers` 为 [`PricingTier`] 数组的 JSON，`service_tier_multipliers` 为
+    /// `{"priority": "2", "batch": "0.5"}` 形式的 JSON。
+    pub fn with_modifiers(
+        mut self,
+        cache_creation_1h: Option<&str>,
+        reasoning: Option<&str>,
+        tiers: Option<&str>,
+        service_tier_multipliers: Option<&str>,
+    ) -> Result<Self, String> {
+        self.cache_creation_1h_cost_per_million = parse_optional_decimal(cache_creation_1h)?;
+        self.reasoning_cost_per_million = parse_optional_decimal(reasoning)?;
+        if let Some(json) = tiers.filter(|s| !s.trim().is_empty()) {
+            let tiers: Vec<PricingTier> =
+                serde_json::from_str(json).map_err(|e| format!("分段价格格式错误: {e}"))?;
+            self.tiers = tiers
+                .iter()
+                .map(|tier| Ok((tier.above_input_tokens, PricingRates::from_tier(tier)?)))
+                .collect::<Result<_, String>>()?;
+            self.tiers.sort_by_key(|(threshold, _)| *threshold);
+        }
+        if let Some(json) = service_tier_multipliers.filter(|s| !s.trim().is_empty()) {
+            let multipliers: HashMap<String, String> =
+                serde_json::from_str(json).map_err(|e| format!("服务等级倍率格式错误: {e}"))?;
+            self.service_tier_multipliers = multipliers
+                .into_iter()
+                .map(|(tier, value)| {
+                    Decimal::from_str(value.trim())
+                        .map(|v| (tier.trim().to_lowercase(), v))
+                        .map_err(|e| format!("服务等级 {tier} 倍率无效: {e}"))
+                })
+                .collect::<Result<_, String>>()?;
+        }
+        Ok(self)
+    }
+
+    /// 按提示 token 数选择价格档位
+    pub fn rates_for(&self, prompt_tokens: u64) -> PricingRates {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|(threshold, _)| prompt_tokens > *threshold)
+            .map(|(_, rates)| rates.clone())
+            .unwrap_or_else(|| PricingRates {
+                input_cost_per_million: self.input_cost_per_million,
+                output_cost_per_million: self.output_cost_per_million,
+                cache_read_cost_per_million: self.cache_read_cost_per_million,
+                cache_creation_cost_per_million: self.cache_creation_cost_per_million,
+                cache_creation_1h_cost_per_million: self.cache_creation_1h_cost_per_million,
+            })
+    }
+
+    /// 服务等级倍率（未配置的等级按 1 计算）
+    pub fn service_tier_multiplier(&self, service_tier: Option<&str>) -> Decimal {
+        service_tier
+            .and_then(|tier| {
+                self.service_tier_multipliers
+                    .get(&tier.trim().to_lowercase())
+                    .copied()
+            })
+            .unwrap_or(Decimal::ONE)
+    }
+}
+
+impl PricingRates {
+    fn from_tier(tier: &PricingTier) -> Result<Self, String> {
+        let parse = |value: &str, field: &str| {
+            Decimal::from_str(value.trim())
+                .map_err(|e| format!("分段价格 {} 的 {field} 无效: {e}", tier.above_input_tokens))
+        };
+        Ok(Self {
+            input_cost_per_million: parse(&tier.input_cost_per_million, "input")?,
+            output_cost_per_million: parse(&tier.output_cost_per_million, "output")?,
+            cache_read_cost_per_million: parse(&tier.cache_read_cost_per_million, "cacheRead")?,
+            cache_creation_cost_per_million: parse(
+                &tier.cache_creation_cost_per_million,
+                "cacheCreation",
+            )?,
+            cache_creation_1h_cost_per_million: tier
+                .cache_creation_1h_cost_per_million
+                .as_deref()
+                .map(|v| parse(v, "cacheCreation1h"))
+                .transpose()?,
+        })
+    }
+}
+
+fn parse_optional_decimal(value: Option<&str>) -> Result<Option<Decimal>, String> {
+    match value.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(v) => Decimal::from_str(v)
+            .map(Some)
+            .map_err(|e| format!("价格 {v} 无效: {e}")),
+        None => Ok(None),
+    }
 }
 
 #[cfg(test)]
@@ -115,6 +287,7 @@ mod tests {
             cache_creation_tokens: 100,
             model: None,
             message_id: None,
+            ..Default::default()
         };
 
         let pricing = ModelPricing::from_strings("3.0", "15.0", "0.3", "3.75").unwrap();
@@ -146,6 +319,7 @@ mod tests {
             cache_creation_tokens: 0,
             model: None,
             message_id: None,
+            ..Default::default()
         };
 
         let pricing = ModelPricing::from_strings("3.0", "15.0", "0", "0").unwrap();
@@ -168,6 +342,7 @@ mod tests {
             cache_creation_tokens: 0,
             model: None,
             message_id: None,
+            ..Default::default()
         };
 
         let multiplier = Decimal::from_str("1.0").unwrap();
@@ -185,6 +360,7 @@ mod tests {
             cache_creation_tokens: 1,
             model: None,
             message_id: None,
+            ..Default::default()
         };
 
         let pricing = ModelPricing::from_strings("0.075", "0.3", "0.01875", "0.075").unwrap();
@@ -196,4 +372,106 @@ mod tests {
         assert!(cost.total_cost > Decimal::ZERO);
         assert!(cost.total_cost.to_string().len() > 2); // 确保保留了小数位
     }
+
+    fn sonnet_pricing() -> ModelPricing {
+        ModelPricing::from_strings("3", "15", "0.3", "3.75")
+            .unwrap()
+            .with_modifiers(
+                Some("6"),
+                None,
+                Some(
+                    r#"[{"aboveInputTokens":200000,"inputCostPerMillion":"6","outputCostPerMillion":"22.5",
+                        "cacheReadCostPerMillion":"0.6","cacheCreationCostPerMillion":"7.5",
+                        "cacheCreation1hCostPerMillion":"12"}]"#,
+                ),
+                Some(r#"{"batch":"0.5"}"#),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_long_context_tier() {
+        let pricing = sonnet_pricing();
+
+        // 200k 以内按基础价格
+        let usage = TokenUsage {
+            input_tokens: 200_000,
+            output_tokens: 1000,
+            ..Default::default()
+        };
+        let cost = CostCalculator::calculate(&usage, &pricing, Decimal::ONE);
+        assert_eq!(cost.input_cost, Decimal::from_str("0.6").unwrap());
+        assert_eq!(cost.output_cost, Decimal::from_str("0.015").unwrap());
+
+        // Anthropic 口径：input 不含缓存命中，合计超过 200k 后整单按长上下文价格
+        let usage = TokenUsage {
+            input_tokens: 1000,
+            output_tokens: 1000,
+            cache_read_tokens: 250_000,
+            ..Default::default()
+        };
+        let cost = CostCalculator::calculate(&usage, &pricing, Decimal::ONE);
+        assert_eq!(cost.output_cost, Decimal::from_str("0.0225").unwrap());
+        assert_eq!(cost.cache_read_cost, Decimal::from_str("0.15").unwrap());
+    }
+
+    #[test]
+    fn test_one_hour_cache_and_reasoning() {
+        let pricing = ModelPricing::from_strings("1.25", "10", "0.125", "1.25")
+            .unwrap()
+            .with_modifiers(Some("2.5"), Some("20"), None, None)
+            .unwrap();
+        let usage = TokenUsage {
+            output_tokens: 1000,
+            reasoning_tokens: 400,
+            cache_creation_tokens: 1000,
+            cache_creation_1h_tokens: 600,
+            ..Default::default()
+        };
+        let cost = CostCalculator::calculate(&usage, &pricing, Decimal::ONE);
+
+        // output: 600 * 10 / 1M + 400 * 20 / 1M = 0.006 + 0.008
+        assert_eq!(cost.output_cost, Decimal::from_str("0.014").unwrap());
+        // cache_creation: 400 * 1.25 / 1M + 600 * 2.5 / 1M = 0.0005 + 0.0015
+        assert_eq!(
+            cost.cache_creation_cost,
+            Decimal::from_str("0.002").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_service_tier_multiplier() {
+        let pricing = sonnet_pricing();
+        let usage = TokenUsage {
+            input_tokens: 1000,
+            output_tokens: 1000,
+            service_tier: Some("Batch".to_string()),
+            ..Default::default()
+        };
+        let multiplier = Decimal::from_str("2").unwrap();
+        let cost = CostCalculator::calculate(&usage, &pricing, multiplier);
+
+        // 服务等级倍率作用于各项成本，供应商倍率只作用于总价
+        assert_eq!(cost.input_cost, Decimal::from_str("0.0015").unwrap());
+        assert_eq!(cost.output_cost, Decimal::from_str("0.0075").unwrap());
+        assert_eq!(cost.total_cost, Decimal::from_str("0.018").unwrap());
+
+        // 未配置的等级不调整价格
+        assert_eq!(
+            pricing.service_tier_multiplier(Some("priority")),
+            Decimal::ONE
+        );
+    }
+
+    #[test]
+    fn test_invalid_modifiers_rejected() {
+        let base = ModelPricing::from_strings("3", "15", "0.3", "3.75").unwrap();
+        assert!(base
+            .clone()
+            .with_modifiers(None, None, Some("not json"), None)
+            .is_err());
+        assert!(base
+            .with_modifiers(None, None, None, Some(r#"{"priority":"x"}"#))
+            .is_err());
+    }
 }
@@ -4,7 +4,10 @@ use super::calculator::{CostBreakdown, CostCalculator, ModelPricing};
 use super::parser::TokenUsage;
 use crate::database::Database;
 use crate::error::AppError;
-use crate::services::usage_stats::find_model_pricing_row;
+use crate::proxy::capture::RequestCapture;
+use crate::proxy::metrics::ProxyMetrics;
+use crate::proxy::otel::RequestTrace;
+use crate::services::usage_stats::find_model_pricing;
 use rust_decimal::Decimal;
 use std::{str::FromStr, time::SystemTime};
 
@@ -34,15 +37,59 @@ pub struct RequestLog {
 /// 使用量记录器
 pub struct UsageLogger<'a> {
     db: &'a Database,
+    metrics: Option<&'a ProxyMetrics>,
+    trace: Option<&'a RequestTrace>,
+    capture: Option<&'a RequestCapture>,
+    persist: bool,
 }
 
 impl<'a> UsageLogger<'a> {
     pub fn new(db: &'a Database) -> Self {
-        Self { db }
+        Self {
+            db,
+            metrics: None,
+            trace: None,
+            capture: None,
+            persist: true,
+        }
+    }
+
+    /// 同时将请求记录到 Prometheus 指标
+    pub fn with_metrics(mut self, metrics: &'a ProxyMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// 写日志时为请求链路补充用量与成本并结束根 span
+    pub fn with_trace(mut self, trace: Option<&'a RequestTrace>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// 写日志时关联请求抓包（capture_id）
+    pub fn with_capture(mut self, capture: Option<&'a RequestCapture>) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    /// 是否写入请求日志表（关闭请求日志时仍记录指标与链路）
+    pub fn with_persist(mut self, persist: bool) -> Self {
+        self.persist = persist;
+        self
     }
 
     /// 记录成功的请求
     pub fn log_request(&self, log: &RequestLog) -> Result<(), AppError> {
+        if let Some(metrics) = self.metrics {
+            metrics.record_request(log);
+        }
+        if let Some(trace) = self.trace {
+            trace.record_request_log(log);
+        }
+        if !self.persist {
+            return Ok(());
+        }
+
         let conn = crate::database::lock_conn!(self.db.conn);
 
         let (input_cost, output_cost, cache_read_cost, cache_creation_cost, total_cost) =
@@ -78,8 +125,9 @@ impl<'a> UsageLogger<'a> {
                 input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
                 input_cost_usd, output_cost_usd, cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
                 latency_ms, first_token_ms, status_code, error_message, session_id,
-                provider_type, is_streaming, cost_multiplier, created_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
+                provider_type, is_streaming, cost_multiplier, created_at,
+                cache_creation_1h_tokens, reasoning_tokens, service_tier, capture_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27)",
             rusqlite::params![
                 log.request_id,
                 log.provider_id,
@@ -104,6 +152,10 @@ impl<'a> UsageLogger<'a> {
                 log.is_streaming as i64,
                 log.cost_multiplier,
                 created_at,
+                log.usage.cache_creation_1h_tokens,
+                log.usage.reasoning_tokens,
+                log.usage.service_tier,
+                self.capture.map(|c| c.id()),
             ],
         )
         .map_err(|e| AppError::Database(format!("记录请求日志失败: {e}")))?;
@@ -189,15 +241,7 @@ impl<'a> UsageLogger<'a> {
     /// 获取模型定价
     pub fn get_model_pricing(&self, model_id: &str) -> Result<Option<ModelPricing>, AppError> {
         let conn = crate::database::lock_conn!(self.db.conn);
-        let row = find_model_pricing_row(&conn, model_id)?;
-        match row {
-            Some((input, output, cache_read, cache_creation)) => {
-                ModelPricing::from_strings(&input, &output, &cache_read, &cache_creation)
-                    .map(Some)
-                    .map_err(|e| AppError::Database(format!("解析定价数据失败: {e}")))
-            }
-            None => Ok(None),
-        }
+        find_model_pricing(&conn, model_id)
     }
 
     /// 获取有效的倍率与计费模式来源（供应商优先，未配置则回退全局默认）
@@ -359,6 +403,7 @@ mod tests {
             cache_creation_tokens: 0,
             model: None,
             message_id: None,
+            ..Default::default()
         };
 
         logger.log_with_calculation(
@@ -19,6 +19,15 @@ pub struct TokenUsage {
     pub output_tokens: u32,
     pub cache_read_tokens: u32,
     pub cache_creation_tokens: u32,
+    /// 缓存写入中按 1 小时 TTL 计费的部分（包含在 `cache_creation_tokens` 内）
+    #[serde(default)]
+    pub cache_creation_1h_tokens: u32,
+    /// 推理 token 数（包含在 `output_tokens` 内）
+    #[serde(default)]
+    pub reasoning_tokens: u32,
+    /// 实际使用的服务等级（priority / batch / flex 等）
+    #[serde(default)]
+    pub service_tier: Option<String>,
     /// 从响应中提取的实际模型名称（如果可用）
     pub model: Option<String>,
     /// 从响应中提取的消息 ID（用于跨源去重）
@@ -37,6 +46,34 @@ impl TokenUsage {
             .map(|mid| format!("{SESSION_REQUEST_ID_PREFIX}{mid}"))
             .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
     }
+
+    /// 本次请求的提示 token 总数，用于选择长上下文分段价格
+    ///
+    /// OpenAI / Gemini 口径的 input_tokens 已包含缓存命中部分；Anthropic 口径不包含，
+    /// 此时缓存命中数可能大于 input_tokens，需要相加。
+    pub fn prompt_tokens(&self) -> u64 {
+        let input = self.input_tokens as u64;
+        let cache_read = self.cache_read_tokens as u64;
+        let cached_outside_input = if cache_read > input { cache_read } else { 0 };
+        input + cached_outside_input + self.cache_creation_tokens as u64
+    }
+}
+
+/// 解析 Anthropic usage 中 1 小时缓存写入的 token 数
+fn claude_cache_creation_1h(usage: &Value) -> u32 {
+    usage
+        .pointer("/cache_creation/ephemeral_1h_input_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32
+}
+
+/// 解析服务等级：优先取 usage 内字段（Anthropic），其次取响应顶层字段（OpenAI）
+fn service_tier(body: &Value, usage: &Value) -> Option<String> {
+    usage
+        .get("service_tier")
+        .or_else(|| body.get("service_tier"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
 }
 
 /// API 类型
@@ -74,8 +111,11 @@ impl TokenUsage {
                 .get("cache_creation_input_tokens")
                 .and_then(|v| v.as_u64())
                 .unwrap_or(0) as u32,
+            cache_creation_1h_tokens: claude_cache_creation_1h(usage),
+            service_tier: service_tier(body, usage),
             model,
             message_id,
+            ..Default::default()
         })
     }
 
@@ -119,6 +159,11 @@ impl TokenUsage {
                                 .and_then(|v| v.as_u64())
                                 .unwrap_or(0)
                                 as u32;
+                            usage.cache_creation_1h_tokens = claude_cache_creation_1h(msg_usage);
+                            usage.service_tier = msg_usage
+                                .get("service_tier")
+                                .and_then(|v| v.as_str())
+                                .map(|s| s.to_string());
                         }
                     }
                     "message_delta" => {
@@ -157,6 +202,15 @@ impl TokenUsage {
                                     usage.cache_creation_tokens = cache_creation as u32;
                                 }
                             }
+                            if usage.cache_creation_1h_tokens == 0 {
+                                usage.cache_creation_1h_tokens =
+                                    claude_cache_creation_1h(delta_usage);
+                            }
+                            if let Some(tier) =
+                                delta_usage.get("service_tier").and_then(|v| v.as_str())
+                            {
+                                usage.service_tier = Some(tier.to_string());
+                            }
                         }
                     }
                     _ => {}
@@ -184,6 +238,7 @@ impl TokenUsage {
             cache_creation_tokens: 0,
             model: None,
             message_id: None,
+            ..Default::default()
         })
     }
 
@@ -232,8 +287,14 @@ impl TokenUsage {
                 .get("cache_creation_input_tokens")
                 .and_then(|v| v.as_u64())
                 .unwrap_or(0) as u32,
+            reasoning_tokens: usage
+                .pointer("/output_tokens_details/reasoning_tokens")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) as u32,
+            service_tier: service_tier(body, usage),
             model,
             message_id: None,
+            ..Default::default()
         })
     }
 
@@ -276,8 +337,14 @@ impl TokenUsage {
                 .get("cache_creation_input_tokens")
                 .and_then(|v| v.as_u64())
                 .unwrap_or(0) as u32,
+            reasoning_tokens: usage
+                .pointer("/output_tokens_details/reasoning_tokens")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) as u32,
+            service_tier: service_tier(body, usage),
             model,
             message_id: None,
+            ..Default::default()
         })
     }
 
@@ -371,8 +438,14 @@ impl TokenUsage {
             output_tokens: completion_tokens as u32,
             cache_read_tokens: cached_tokens,
             cache_creation_tokens: 0,
+            reasoning_tokens: usage
+                .pointer("/completion_tokens_details/reasoning_tokens")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) as u32,
+            service_tier: service_tier(body, usage),
             model,
             message_id: None,
+            ..Default::default()
         })
     }
 
@@ -416,8 +489,13 @@ impl TokenUsage {
                 .and_then(|v| v.as_u64())
                 .unwrap_or(0) as u32,
             cache_creation_tokens: 0,
+            reasoning_tokens: usage
+                .get("thoughtsTokenCount")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) as u32,
             model,
             message_id: None,
+            ..Default::default()
         })
     }
 
@@ -427,6 +505,7 @@ impl TokenUsage {
         let mut total_input = 0u32;
         let mut total_tokens = 0u32;
         let mut total_cache_read = 0u32;
+        let mut total_reasoning = 0u32;
         let mut model: Option<String> = None;
 
         for chunk in chunks {
@@ -448,6 +527,12 @@ impl TokenUsage {
                     .get("cachedContentTokenCount")
                     .and_then(|v| v.as_u64())
                     .unwrap_or(0) as u32;
+
+                // 思考 tokens（包含在输出中）
+                total_reasoning = usage
+                    .get("thoughtsTokenCount")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0) as u32;
             }
 
             // 提取实际使用的模型名称（modelVersion 字段）
@@ -467,8 +552,10 @@ impl TokenUsage {
                 output_tokens: total_output,
                 cache_read_tokens: total_cache_read,
                 cache_creation_tokens: 0,
+                reasoning_tokens: total_reasoning,
                 model,
                 message_id: None,
+                ..Default::default()
             })
         } else {
             None
@@ -933,4 +1020,76 @@ mod tests {
         assert_eq!(usage.output_tokens, 50);
         assert_eq!(usage.model, Some("gpt-4o".to_string()));
     }
+
+    #[test]
+    fn test_claude_cache_ttl_and_service_tier() {
+        let response = json!({
+            "id": "msg_1",
+            "model": "claude-sonnet-4-5",
+            "usage": {
+                "input_tokens": 10,
+                "output_tokens": 20,
+                "cache_creation_input_tokens": 1000,
+                "cache_creation": {
+                    "ephemeral_5m_input_tokens": 400,
+                    "ephemeral_1h_input_tokens": 600
+                },
+                "service_tier": "batch"
+            }
+        });
+
+        let usage = TokenUsage::from_claude_response(&response).unwrap();
+        assert_eq!(usage.cache_creation_tokens, 1000);
+        assert_eq!(usage.cache_creation_1h_tokens, 600);
+        assert_eq!(usage.service_tier.as_deref(), Some("batch"));
+    }
+
+    #[test]
+    fn test_openai_reasoning_tokens_and_service_tier() {
+        let chat = json!({
+            "model": "o3",
+            "service_tier": "flex",
+            "usage": {
+                "prompt_tokens": 100,
+                "completion_tokens": 500,
+                "completion_tokens_details": {"reasoning_tokens": 320}
+            }
+        });
+        let usage = TokenUsage::from_openai_response(&chat).unwrap();
+        assert_eq!(usage.reasoning_tokens, 320);
+        assert_eq!(usage.service_tier.as_deref(), Some("flex"));
+
+        let responses = json!({
+            "model": "gpt-5",
+            "service_tier": "priority",
+            "usage": {
+                "input_tokens": 100,
+                "output_tokens": 80,
+                "output_tokens_details": {"reasoning_tokens": 64}
+            }
+        });
+        let usage = TokenUsage::from_codex_response_auto(&responses).unwrap();
+        assert_eq!(usage.reasoning_tokens, 64);
+        assert_eq!(usage.service_tier.as_deref(), Some("priority"));
+    }
+
+    #[test]
+    fn test_prompt_tokens_handles_both_cache_conventions() {
+        // OpenAI 口径：input 已包含缓存命中
+        let openai = TokenUsage {
+            input_tokens: 1000,
+            cache_read_tokens: 800,
+            ..Default::default()
+        };
+        assert_eq!(openai.prompt_tokens(), 1000);
+
+        // Anthropic 口径：input 不含缓存命中与缓存写入
+        let anthropic = TokenUsage {
+            input_tokens: 10,
+            cache_read_tokens: 800,
+            cache_creation_tokens: 200,
+            ..Default::default()
+        };
+        assert_eq!(anthropic.prompt_tokens(), 1010);
+    }
 }
@@ -5,10 +5,13 @@ pub mod env_checker;
 pub mod env_manager;
 pub mod mcp;
 pub mod model_fetch;
+pub mod notification;
 pub mod omo;
+pub mod pricing_io;
 pub mod prompt;
 pub mod provider;
 pub mod proxy;
+pub mod session_search;
 pub mod session_usage;
 pub mod session_usage_codex;
 pub mod session_usage_gemini;
@@ -16,6 +19,9 @@ pub mod skill;
 pub mod speedtest;
 pub mod stream_check;
 pub mod subscription;
+pub mod usage_attribution;
+pub mod usage_export;
+pub mod usage_forecast;
 pub mod usage_stats;
 pub mod webdav;
 pub mod webdav_auto_sync;
@@ -0,0 +1,912 @@
+//! 通知子系统
+//!
+//! 故障转移切换、熔断器打开、健康检查失败、订阅额度将耗尽、消费限额等事件
+//! 通过 `notify()` 投递到后台 worker，按规则过滤后发送到：
+//! - 系统通知（macOS `osascript` / Linux `notify-send` / Windows PowerShell Toast）
+//! - Webhook（JSON POST，支持通用 / Slack / 飞书 / 钉钉消息格式）
+//!
+//! 规则按顺序匹配，第一条命中事件类型、应用与供应商的规则决定是否发送
+//! （阈值、免打扰时段、去重窗口）。未启动 worker 时（如单元测试）`notify()` 为空操作。
+
+use crate::database::Database;
+use crate::error::AppError;
+use chrono::{DateTime, Local, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 事件队列容量（队列满时丢弃新事件，避免阻塞代理请求路径）
+const EVENT_QUEUE_CAPACITY: usize = 64;
+/// 默认去重窗口（分钟）
+const DEFAULT_DEDUPE_MINUTES: u32 = 30;
+/// 去重窗口上限（分钟）
+const MAX_DEDUPE_MINUTES: u32 = 7 * 24 * 60;
+/// 订阅额度事件未设置阈值时使用的默认阈值（使用百分比）
+const DEFAULT_QUOTA_THRESHOLD: f64 = 90.0;
+/// 订阅额度检查间隔（秒）
+const QUOTA_CHECK_INTERVAL_SECS: u64 = 10 * 60;
+/// 检查订阅额度的 CLI 工具
+const QUOTA_TOOLS: [&str; 3] = ["claude", "codex", "gemini"];
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+static EVENT_TX: OnceLock<Sender<NotificationEvent>> = OnceLock::new();
+
+/// 通知事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEventKind {
+    FailoverSwitch,
+    CircuitOpen,
+    StreamCheckFailed,
+    QuotaLow,
+    BudgetWarning,
+    BudgetExceeded,
+    BudgetForecast,
+}
+
+impl NotificationEventKind {
+    pub const ALL: [NotificationEventKind; 7] = [
+        NotificationEventKind::FailoverSwitch,
+        NotificationEventKind::CircuitOpen,
+        NotificationEventKind::StreamCheckFailed,
+        NotificationEventKind::QuotaLow,
+        NotificationEventKind::BudgetWarning,
+        NotificationEventKind::BudgetExceeded,
+        NotificationEventKind::BudgetForecast,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationEventKind::FailoverSwitch => "failover_switch",
+            NotificationEventKind::CircuitOpen => "circuit_open",
+            NotificationEventKind::StreamCheckFailed => "stream_check_failed",
+            NotificationEventKind::QuotaLow => "quota_low",
+            NotificationEventKind::BudgetWarning => "budget_warning",
+            NotificationEventKind::BudgetExceeded => "budget_exceeded",
+            NotificationEventKind::BudgetForecast => "budget_forecast",
+        }
+    }
+
+    fn title(&self, language: &str) -> &'static str {
+        match (language, self) {
+            ("en", NotificationEventKind::FailoverSwitch) => "Provider switched by failover",
+            ("en", NotificationEventKind::CircuitOpen) => "Circuit breaker opened",
+            ("en", NotificationEventKind::StreamCheckFailed) => "Health check failed",
+            ("en", NotificationEventKind::QuotaLow) => "Subscription quota running low",
+            ("en", NotificationEventKind::BudgetWarning) => "Spending near limit",
+            ("en", NotificationEventKind::BudgetExceeded) => "Spending limit exceeded",
+            ("en", NotificationEventKind::BudgetForecast) => "Spending limit projected to be hit",
+            ("ja", NotificationEventKind::FailoverSwitch) => "フェイルオーバーで切り替えました",
+            ("ja", NotificationEventKind::CircuitOpen) => "サーキットブレーカーが開きました",
+            ("ja", NotificationEventKind::StreamCheckFailed) => "ヘルスチェック失敗",
+            ("ja", NotificationEventKind::QuotaLow) => "サブスクリプション残量わずか",
+            ("ja", NotificationEventKind::BudgetWarning) => "利用額が上限に近づいています",
+            ("ja", NotificationEventKind::BudgetExceeded) => "利用額が上限を超えました",
+            ("ja", NotificationEventKind::BudgetForecast) => "利用額が上限を超える見込みです",
+            (_, NotificationEventKind::FailoverSwitch) => "已自动切换供应商",
+            (_, NotificationEventKind::CircuitOpen) => "供应商已熔断",
+            (_, NotificationEventKind::StreamCheckFailed) => "健康检查失败",
+            (_, NotificationEventKind::QuotaLow) => "订阅额度即将耗尽",
+            (_, NotificationEventKind::BudgetWarning) => "消费接近限额",
+            (_, NotificationEventKind::BudgetExceeded) => "消费已超出限额",
+            (_, NotificationEventKind::BudgetForecast) => "预计将超出消费限额",
+        }
+    }
+}
+
+/// Webhook 消息格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    #[default]
+    Generic,
+    Slack,
+    Feishu,
+    Dingtalk,
+}
+
+/// Webhook 目标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookTarget {
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 免打扰时段（本地时间 `HH:MM`，可跨午夜，如 22:00-08:00）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl QuietHours {
+    fn parse(value: &str) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        let (Some(start), Some(end)) = (Self::parse(&self.start), Self::parse(&self.end)) else {
+            return false;
+        };
+        let time = time.with_second(0).unwrap_or(time);
+        if start <= end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        }
+    }
+}
+
+/// 通知规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRule {
+    pub id: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub event: NotificationEventKind,
+    /// 为空时匹配所有应用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_type: Option<String>,
+    /// 为空时匹配所有供应商
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_id: Option<String>,
+    /// 使用百分比阈值，仅对带数值的事件（订阅额度、消费限额）生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+    /// 同一规则下相同事件的最短通知间隔（分钟），0 表示不去重
+    #[serde(default = "default_dedupe_minutes")]
+    pub dedupe_minutes: u32,
+}
+
+impl NotificationRule {
+    fn matches(&self, event: &NotificationEvent) -> bool {
+        let field_matches = |filter: &Option<String>, value: &Option<String>| {
+            filter
+                .as_deref()
+                .map(|f| value.as_deref() == Some(f))
+                .unwrap_or(true)
+        };
+        self.enabled
+            && self.event == event.kind
+            && field_matches(&self.app_type, &event.app_type)
+            && field_matches(&self.provider_id, &event.provider_id)
+    }
+
+    fn effective_threshold(&self) -> Option<f64> {
+        self.threshold
+            .or((self.event == NotificationEventKind::QuotaLow).then_some(DEFAULT_QUOTA_THRESHOLD))
+    }
+}
+
+/// 通知配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 是否发送系统通知
+    #[serde(default = "default_true")]
+    pub desktop: bool,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookTarget>,
+    #[serde(default)]
+    pub rules: Vec<NotificationRule>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            desktop: true,
+            webhooks: Vec::new(),
+            rules: NotificationEventKind::ALL
+                .into_iter()
+                .map(|kind| NotificationRule {
+                    id: kind.as_str().to_string(),
+                    enabled: true,
+                    event: kind,
+                    app_type: None,
+                    provider_id: None,
+                    threshold: None,
+                    quiet_hours: None,
+                    dedupe_minutes: DEFAULT_DEDUPE_MINUTES,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl NotificationConfig {
+    /// 校验配置（保存前调用）
+    pub fn validate(&self) -> Result<(), AppError> {
+        for webhook in &self.webhooks {
+            let url = webhook.url.trim();
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(AppError::InvalidInput(format!(
+                    "Webhook 地址必须以 http:// 或 https:// 开头: {url}"
+                )));
+            }
+        }
+        for rule in &self.rules {
+            if rule.id.trim().is_empty() {
+                return Err(AppError::InvalidInput("通知规则 ID 不能为空".to_string()));
+            }
+            if let Some(threshold) = rule.threshold {
+                if !(0.0..=100.0).contains(&threshold) {
+                    return Err(AppError::InvalidInput(format!(
+                        "通知规则 {} 的阈值必须在 0-100 之间",
+                        rule.id
+                    )));
+                }
+            }
+            if rule.dedupe_minutes > MAX_DEDUPE_MINUTES {
+                return Err(AppError::InvalidInput(format!(
+                    "通知规则 {} 的去重窗口不能超过 {MAX_DEDUPE_MINUTES} 分钟",
+                    rule.id
+                )));
+            }
+            if let Some(quiet) = &rule.quiet_hours {
+                if QuietHours::parse(&quiet.start).is_none()
+                    || QuietHours::parse(&quiet.end).is_none()
+                {
+                    return Err(AppError::InvalidInput(format!(
+                        "通知规则 {} 的免打扰时段格式应为 HH:MM",
+                        rule.id
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_dedupe_minutes() -> u32 {
+    DEFAULT_DEDUPE_MINUTES
+}
+
+/// 通知事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationEvent {
+    pub kind: NotificationEventKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider_name: Option<String>,
+    pub detail: String,
+    /// 数值指标（使用百分比），用于与规则阈值比较
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    /// 附加去重键（如额度窗口名、限额周期），区分同一供应商的不同事件
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedupe_key: Option<String>,
+    pub timestamp: i64,
+}
+
+impl NotificationEvent {
+    pub fn new(kind: NotificationEventKind, detail: impl Into<String>) -> Self {
+        Self {
+            kind,
+            app_type: None,
+            provider_id: None,
+            provider_name: None,
+            detail: detail.into(),
+            value: None,
+            dedupe_key: None,
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    pub fn app(mut self, app_type: &str) -> Self {
+        self.app_type = Some(app_type.to_string());
+        self
+    }
+
+    pub fn provider(mut self, provider_id: &str, provider_name: Option<&str>) -> Self {
+        self.provider_id = Some(provider_id.to_string());
+        self.provider_name = provider_name.map(str::to_string);
+        self
+    }
+
+    pub fn value(mut self, value: f64) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    pub fn dedupe_key(mut self, key: impl Into<String>) -> Self {
+        self.dedupe_key = Some(key.into());
+        self
+    }
+
+    fn render(&self, language: &str) -> (String, String) {
+        let mut parts = Vec::new();
+        if let Some(app) = &self.app_type {
+            parts.push(format!("[{app}]"));
+        }
+        let subject = self.provider_name.as_ref().or(self.provider_id.as_ref());
+        match (subject, self.detail.is_empty()) {
+            (Some(name), true) => parts.push(name.clone()),
+            (Some(name), false) => parts.push(format!("{name}: {}", self.detail)),
+            (None, _) => parts.push(self.detail.clone()),
+        }
+        (self.kind.title(language).to_string(), parts.join(" "))
+    }
+}
+
+/// 单个通道的投递结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationDelivery {
+    /// `desktop` 或 Webhook 地址
+    pub channel: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 投递通知事件（非阻塞；队列满或 worker 未启动时丢弃）
+pub fn notify(event: NotificationEvent) {
+    let Some(tx) = EVENT_TX.get() else {
+        return;
+    };
+    match tx.try_send(event) {
+        Ok(()) => {}
+        Err(TrySendError::Full(event)) => {
+            log::warn!("[Notify] 事件队列已满，丢弃 {} 通知", event.kind.as_str());
+        }
+        Err(TrySendError::Closed(_)) => {}
+    }
+}
+
+/// 启动通知 worker 与订阅额度检查任务
+pub fn start_worker(db: Arc<Database>) {
+    if EVENT_TX.get().is_some() {
+        return;
+    }
+
+    let (tx, rx) = channel::<NotificationEvent>(EVENT_QUEUE_CAPACITY);
+    if EVENT_TX.set(tx).is_err() {
+        return;
+    }
+
+    let db_for_quota = db.clone();
+    tauri::async_runtime::spawn(async move {
+        run_worker_loop(db, rx).await;
+    });
+    tauri::async_runtime::spawn(async move {
+        run_quota_watch(db_for_quota).await;
+    });
+}
+
+async fn run_worker_loop(db: Arc<Database>, mut rx: Receiver<NotificationEvent>) {
+    let mut last_sent: HashMap<String, i64> = HashMap::new();
+
+    while let Some(mut event) = rx.recv().await {
+        let config = match db.get_notification_config() {
+            Ok(config) => config,
+            Err(e) => {
+                log::warn!("[Notify] 读取通知配置失败: {e}");
+                continue;
+            }
+        };
+        if !config.enabled || !should_deliver(&config.rules, &event, Local::now(), &mut last_sent) {
+            continue;
+        }
+
+        if event.provider_name.is_none() {
+            if let (Some(id), Some(app)) = (&event.provider_id, &event.app_type) {
+                if let Ok(Some(provider)) = db.get_provider_by_id(id, app) {
+                    event.provider_name = Some(provider.name);
+                }
+            }
+        }
+
+        let language = crate::settings::get_settings().language;
+        let (title, body) = event.render(language.as_deref().unwrap_or("zh"));
+        for delivery in deliver(&config, &title, &body, &event).await {
+            if !delivery.success {
+                log::warn!(
+                    "[Notify] {} 通知发送到 {} 失败: {}",
+                    event.kind.as_str(),
+                    delivery.channel,
+                    delivery.error.unwrap_or_default()
+                );
+            }
+        }
+
+        // 清理超出最大去重窗口的记录
+        let horizon = event.timestamp - MAX_DEDUPE_MINUTES as i64 * 60;
+        last_sent.retain(|_, sent_at| *sent_at >= horizon);
+    }
+}
+
+/// 定期检查订阅额度（仅在存在启用的额度规则时查询，避免无谓的网络请求）
+async fn run_quota_watch(db: Arc<Database>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(QUOTA_CHECK_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        let watching = db
+            .get_notification_config()
+            .map(|config| {
+                config.enabled
+                    && config
+                        .rules
+                        .iter()
+                        .any(|r| r.enabled && r.event == NotificationEventKind::QuotaLow)
+            })
+            .unwrap_or(false);
+        if !watching {
+            continue;
+        }
+
+        for tool in QUOTA_TOOLS {
+            let quota = match crate::services::subscription::get_subscription_quota(tool).await {
+                Ok(quota) if quota.success => quota,
+                Ok(_) => continue,
+                Err(e) => {
+                    log::debug!("[Notify] 查询 {tool} 订阅额度失败: {e}");
+                    continue;
+                }
+            };
+            for tier in quota.tiers {
+                let mut detail = format!("{} {:.0}%", tier.name, tier.utilization);
+                if let Some(resets_at) = &tier.resets_at {
+                    detail.push_str(&format!(" (resets {resets_at})"));
+                }
+                notify(
+                    NotificationEvent::new(NotificationEventKind::QuotaLow, detail)
+                        .app(tool)
+                        .value(tier.utilization)
+                        .dedupe_key(tier.name),
+                );
+            }
+        }
+    }
+}
+
+/// 按规则判断事件是否应发送；发送时记录去重时间
+fn should_deliver(
+    rules: &[NotificationRule],
+    event: &NotificationEvent,
+    now: DateTime<Local>,
+    last_sent: &mut HashMap<String, i64>,
+) -> bool {
+    let Some(rule) = rules.iter().find(|rule| rule.matches(event)) else {
+        return false;
+    };
+
+    if let Some(threshold) = rule.effective_threshold() {
+        if event.value.is_some_and(|value| value < threshold) {
+            return false;
+        }
+    }
+    if rule
+        .quiet_hours
+        .as_ref()
+        .is_some_and(|quiet| quiet.contains(now.time()))
+    {
+        return false;
+    }
+
+    let key = format!(
+        "{}:{}:{}:{}",
+        rule.id,
+        event.app_type.as_deref().unwrap_or_default(),
+        event.provider_id.as_deref().unwrap_or_default(),
+        event.dedupe_key.as_deref().unwrap_or_default()
+    );
+    let now_ts = now.timestamp();
+    if let Some(sent_at) = last_sent.get(&key) {
+        if now_ts - sent_at < rule.dedupe_minutes as i64 * 60 {
+            return false;
+        }
+    }
+    last_sent.insert(key, now_ts);
+    true
+}
+
+/// 发送到所有已启用的通道
+async fn deliver(
+    config: &NotificationConfig,
+    title: &str,
+    body: &str,
+    event: &NotificationEvent,
+) -> Vec<NotificationDelivery> {
+    let mut deliveries = Vec::new();
+
+    if config.desktop {
+        let (title_owned, body_owned) = (title.to_string(), body.to_string());
+        let result = tokio::task::spawn_blocking(move || {
+            show_desktop_notification(&title_owned, &body_owned)
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+        deliveries.push(NotificationDelivery {
+            channel: "desktop".to_string(),
+            success: result.is_ok(),
+            error: result.err(),
+        });
+    }
+
+    for webhook in config.webhooks.iter().filter(|w| w.enabled) {
+        let payload = webhook_payload(webhook.format, title, body, event);
+        let result = send_webhook(webhook.url.trim(), &payload).await;
+        deliveries.push(NotificationDelivery {
+            channel: webhook.url.clone(),
+            success: result.is_ok(),
+            error: result.err(),
+        });
+    }
+
+    deliveries
+}
+
+/// 发送测试通知（忽略规则与启用开关，返回每个通道的结果）
+pub async fn send_test_notification(config: &NotificationConfig) -> Vec<NotificationDelivery> {
+    let language = crate::settings::get_settings().language;
+    let (title, body) = match language.as_deref().unwrap_or("zh") {
+        "en" => ("CC Switch test notification", "Notifications are working."),
+        "ja" => ("CC Switch テスト通知", "通知は正常に動作しています。"),
+        _ => ("CC Switch 测试通知", "通知配置正常。"),
+    };
+    let event = NotificationEvent::new(NotificationEventKind::FailoverSwitch, body);
+    deliver(config, title, body, &event).await
+}
+
+/// 构造 Webhook 请求体
+fn webhook_payload(
+    format: WebhookFormat,
+    title: &str,
+    body: &str,
+    event: &NotificationEvent,
+) -> Value {
+    let text = format!("{title}\n{body}");
+    match format {
+        WebhookFormat::Generic => json!({
+            "event": event.kind.as_str(),
+            "title": title,
+            "message": body,
+            "appType": event.app_type,
+            "providerId": event.provider_id,
+            "providerName": event.provider_name,
+            "value": event.value,
+            "timestamp": event.timestamp,
+        }),
+        WebhookFormat::Slack => json!({ "text": format!("*{title}*\n{body}") }),
+        WebhookFormat::Feishu => json!({
+            "msg_type": "text",
+            "content": { "text": text },
+        }),
+        WebhookFormat::Dingtalk => json!({
+            "msgtype": "text",
+            "text": { "content": text },
+        }),
+    }
+}
+
+async fn send_webhook(url: &str, payload: &Value) -> Result<(), String> {
+    let response = crate::proxy::http_client::get()
+        .post(url)
+        .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+        .json(payload)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {status}"));
+    }
+    Ok(())
+}
+
+/// 系统通知命令
+struct DesktopCommand {
+    program: &'static str,
+    args: Vec<String>,
+    envs: Vec<(&'static str, String)>,
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_command(title: &str, body: &str) -> Option<DesktopCommand> {
+    Some(DesktopCommand {
+        program: "osascript",
+        args: vec![
+            "-e".to_string(),
+            format!(
+                "display notification {} with title {}",
+                applescript_string(body),
+                applescript_string(title)
+            ),
+        ],
+        envs: Vec::new(),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn desktop_command(title: &str, body: &str) -> Option<DesktopCommand> {
+    Some(DesktopCommand {
+        program: "notify-send",
+        args: vec![
+            "--app-name=CC Switch".to_string(),
+            "--".to_string(),
+            title.to_string(),
+            body.to_string(),
+        ],
+        envs: Vec::new(),
+    })
+}
+
+/// Windows 通过 PowerShell 调用 WinRT Toast；标题与正文经环境变量传入，避免脚本转义问题
+#[cfg(target_os = "windows")]
+fn desktop_command(title: &str, body: &str) -> Option<DesktopCommand> {
+    const SCRIPT: &str = "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+        $template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+        $texts = $template.GetElementsByTagName('text'); \
+        $texts.Item(0).AppendChild($template.CreateTextNode($env:CC_SWITCH_NOTIFY_TITLE)) > $null; \
+        $texts.Item(1).AppendChild($template.CreateTextNode($env:CC_SWITCH_NOTIFY_BODY)) > $null; \
+        $toast = [Windows.UI.Notifications.ToastNotification]::new($template); \
+        [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe').Show($toast)";
+    Some(DesktopCommand {
+        program: "powershell",
+        args: vec![
+            "-NoProfile".to_string(),
+            "-NonInteractive".to_string(),
+            "-Command".to_string(),
+            SCRIPT.to_string(),
+        ],
+        envs: vec![
+            ("CC_SWITCH_NOTIFY_TITLE", title.to_string()),
+            ("CC_SWITCH_NOTIFY_BODY", body.to_string()),
+        ],
+    })
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn desktop_command(_title: &str, _body: &str) -> Option<DesktopCommand> {
+    None
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn show_desktop_notification(title: &str, body: &str) -> Result<(), String> {
+    let command = desktop_command(title, body).ok_or("当前平台不支持系统通知")?;
+    let mut process = std::process::Command::new(command.program);
+    process.args(&command.args).envs(command.envs);
+    #[cfg(target_os = "windows")]
+    process.creation_flags(CREATE_NO_WINDOW);
+
+    let output = process
+        .output()
+        .map_err(|e| format!("无法执行 {}: {e}", command.program))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} 退出码 {}: {}",
+            command.program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 3, 2, hour, minute, 0)
+            .earliest()
+            .expect("valid local datetime")
+    }
+
+    fn rule(id: &str, event: NotificationEventKind) -> NotificationRule {
+        NotificationRule {
+            id: id.to_string(),
+            enabled: true,
+            event,
+            app_type: None,
+            provider_id: None,
+            threshold: None,
+            quiet_hours: None,
+            dedupe_minutes: 30,
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_decides_with_dedupe_window() {
+        let rules = vec![
+            NotificationRule {
+                app_type: Some("codex".to_string()),
+                enabled: false,
+                ..rule("codex-off", NotificationEventKind::CircuitOpen)
+            },
+            NotificationRule {
+                provider_id: Some("p2".to_string()),
+                dedupe_minutes: 0,
+                ..rule("p2", NotificationEventKind::CircuitOpen)
+            },
+            rule("all", NotificationEventKind::CircuitOpen),
+        ];
+        let mut sent = HashMap::new();
+        let event = |provider: &str| {
+            NotificationEvent::new(NotificationEventKind::CircuitOpen, "open")
+                .app("codex")
+                .provider(provider, None)
+        };
+
+        // 禁用的规则被跳过，落到通用规则
+        assert!(should_deliver(&rules, &event("p1"), at(10, 0), &mut sent));
+        assert!(!should_deliver(&rules, &event("p1"), at(10, 29), &mut sent));
+        assert!(should_deliver(&rules, &event("p1"), at(10, 30), &mut sent));
+
+        // 去重窗口为 0 的规则每次都发送
+        assert!(should_deliver(&rules, &event("p2"), at(10, 0), &mut sent));
+        assert!(should_deliver(&rules, &event("p2"), at(10, 0), &mut sent));
+
+        // 没有规则匹配的事件类型不发送
+        let other = NotificationEvent::new(NotificationEventKind::QuotaLow, "x").value(99.0);
+        assert!(!should_deliver(&rules, &other, at(10, 0), &mut sent));
+    }
+
+    #[test]
+    fn applies_thresholds_and_quiet_hours() {
+        let rules = vec![NotificationRule {
+            quiet_hours: Some(QuietHours {
+                start: "22:00".to_string(),
+                end: "08:00".to_string(),
+            }),
+            ..rule("quota", NotificationEventKind::QuotaLow)
+        }];
+        let quota = |value: f64, tier: &str| {
+            NotificationEvent::new(NotificationEventKind::QuotaLow, "q")
+                .app("claude")
+                .value(value)
+                .dedupe_key(tier)
+        };
+        let mut sent = HashMap::new();
+
+        // 未设置阈值时额度事件使用默认 90%
+        assert!(!should_deliver(
+            &rules,
+            &quota(80.0, "five_hour"),
+            at(12, 0),
+            &mut sent
+        ));
+        assert!(should_deliver(
+            &rules,
+            &quota(95.0, "five_hour"),
+            at(12, 0),
+            &mut sent
+        ));
+        // 不同额度窗口分别去重
+        assert!(should_deliver(
+            &rules,
+            &quota(95.0, "seven_day"),
+            at(12, 0),
+            &mut sent
+        ));
+
+        // 跨午夜的免打扰时段
+        assert!(!should_deliver(
+            &rules,
+            &quota(99.0, "opus"),
+            at(23, 30),
+            &mut sent
+        ));
+        assert!(!should_deliver(
+            &rules,
+            &quota(99.0, "opus"),
+            at(7, 59),
+            &mut sent
+        ));
+        assert!(should_deliver(
+            &rules,
+            &quota(99.0, "opus"),
+            at(8, 0),
+            &mut sent
+        ));
+
+        let custom = vec![NotificationRule {
+            threshold: Some(50.0),
+            ..rule("budget", NotificationEventKind::BudgetWarning)
+        }];
+        let budget = |value: f64| {
+            NotificationEvent::new(NotificationEventKind::BudgetWarning, "b").value(value)
+        };
+        assert!(!should_deliver(
+            &custom,
+            &budget(40.0),
+            at(12, 0),
+            &mut sent
+        ));
+        assert!(should_deliver(&custom, &budget(60.0), at(12, 0), &mut sent));
+    }
+
+    #[test]
+    fn builds_webhook_payload_shapes() {
+        let event = NotificationEvent::new(NotificationEventKind::FailoverSwitch, "→ Backup")
+            .app("claude")
+            .provider("p1", Some("Backup"));
+        let (title, body) = event.render("en");
+        assert_eq!(title, "Provider switched by failover");
+        assert_eq!(body, "[claude] Backup: → Backup");
+
+        let generic = webhook_payload(WebhookFormat::Generic, &title, &body, &event);
+        assert_eq!(generic["event"], "failover_switch");
+        assert_eq!(generic["providerId"], "p1");
+        assert_eq!(generic["message"], body.as_str());
+
+        let slack = webhook_payload(WebhookFormat::Slack, &title, &body, &event);
+        assert_eq!(
+            slack["text"],
+            format!("*Provider switched by failover*\n{body}")
+        );
+
+        let feishu = webhook_payload(WebhookFormat::Feishu, &title, &body, &event);
+        assert_eq!(feishu["msg_type"], "text");
+        assert_eq!(feishu["content"]["text"], format!("{title}\n{body}"));
+
+        let dingtalk = webhook_payload(WebhookFormat::Dingtalk, &title, &body, &event);
+        assert_eq!(dingtalk["msgtype"], "text");
+        assert_eq!(dingtalk["text"]["content"], format!("{title}\n{body}"));
+    }
+
+    #[test]
+    fn validates_config() {
+        let mut config = NotificationConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.rules.len(), NotificationEventKind::ALL.len());
+
+        config.webhooks.push(WebhookTarget {
+            url: "ftp://example.com".to_string(),
+            format: WebhookFormat::Generic,
+            enabled: true,
+        });
+        assert!(config.validate().is_err());
+        config.webhooks[0].url = "https://hooks.example.com/x".to_string();
+        assert!(config.validate().is_ok());
+
+        config.rules[0].quiet_hours = Some(QuietHours {
+            start: "25:00".to_string(),
+            end: "08:00".to_string(),
+        });
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn quotes_applescript_strings() {
+        assert_eq!(
+            applescript_string(r#"say "hi" \ bye"#),
+            r#""say \"hi\" \\ bye""#
+        );
+    }
+}
@@ -0,0 +1,1123 @@
+//! 模型定价批量导入/导出
+//!
+//! 支持两种文件格式：
+//! - LiteLLM `model_prices_and_context_window.json`：按 token 计价（USD/token），导入时换算为每百万 token
+//! - CSV：列名与 `model_pricing` 表一致，分段价格与服务等级倍率以 JSON 存放在单元格中
+//!
+//! 导入分两步：先与现有数据对比生成预览（新增 / 变更 / 未变），确认后在单个事务中写入，
+//! 写入后按新价格重算历史请求日志成本。文件中未提供的可选字段沿用现有值。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::usage::calculator::{ModelPricing, PricingTier};
+use crate::services::usage_stats::clean_model_id;
+use regex::Regex;
+use rusqlite::Connection;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// 模型定价信息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricingInfo {
+    pub model_id: String,
+    pub display_name: String,
+    pub input_cost_per_million: String,
+    pub output_cost_per_million: String,
+    pub cache_read_cost_per_million: String,
+    pub cache_creation_cost_per_million: String,
+    /// 1 小时缓存写入价格（为空时按缓存写入价格计费）
+    #[serde(default)]
+    pub cache_creation_1h_cost_per_million: Option<String>,
+    /// 推理 token 价格（为空时按输出价格计费）
+    #[serde(default)]
+    pub reasoning_cost_per_million: Option<String>,
+    /// 长上下文分段价格
+    #[serde(default)]
+    pub tiers: Vec<PricingTier>,
+    /// 服务等级倍率（如 priority、batch）
+    #[serde(default)]
+    pub service_tier_multipliers: HashMap<String, String>,
+}
+
+/// 定价文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PricingFileFormat {
+    /// LiteLLM model_prices_and_context_window.json
+    Litellm,
+    Csv,
+}
+
+impl PricingFileFormat {
+    /// 根据扩展名判断格式，无法判断时按内容（JSON 对象）推断
+    pub fn detect(path: &Path, content: &str) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("csv") => PricingFileFormat::Csv,
+            Some("json") => PricingFileFormat::Litellm,
+            _ if content.trim_start().starts_with('{') => PricingFileFormat::Litellm,
+            _ => PricingFileFormat::Csv,
+        }
+    }
+}
+
+/// 导入文件中的一行定价（None 表示文件未提供，沿用现有值）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PricingImportRow {
+    pub model_id: String,
+    pub display_name: Option<String>,
+    pub input_cost_per_million: String,
+    pub output_cost_per_million: String,
+    pub cache_read_cost_per_million: Option<String>,
+    pub cache_creation_cost_per_million: Option<String>,
+    pub cache_creation_1h_cost_per_million: Option<String>,
+    pub reasoning_cost_per_million: Option<String>,
+    pub tiers: Option<Vec<PricingTier>>,
+    pub service_tier_multipliers: Option<HashMap<String, String>>,
+}
+
+/// 单个模型的定价变更
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingChange {
+    pub before: ModelPricingInfo,
+    pub after: ModelPricingInfo,
+}
+
+/// 导入预览
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingImportPreview {
+    pub format: PricingFileFormat,
+    pub added: Vec<ModelPricingInfo>,
+    pub changed: Vec<PricingChange>,
+    pub unchanged: Vec<String>,
+    /// 无法解析而被跳过的条目（含原因）
+    pub skipped: Vec<String>,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingImportResult {
+    pub added: usize,
+    pub updated: usize,
+    /// 按新价格重算成本的请求日志数
+    pub recomputed_logs: usize,
+}
+
+const CSV_COLUMNS: [&str; 10] = [
+    "model_id",
+    "display_name",
+    "input_cost_per_million",
+    "output_cost_per_million",
+    "cache_read_cost_per_million",
+    "cache_creation_cost_per_million",
+    "cache_creation_1h_cost_per_million",
+    "reasoning_cost_per_million",
+    "tiers",
+    "service_tier_multipliers",
+];
+
+/// LiteLLM 服务等级后缀 → service_tier
+const LITELLM_SERVICE_TIERS: [(&str, &str); 3] = [
+    ("priority", "priority"),
+    ("flex", "flex"),
+    ("batches", "batch"),
+];
+
+/// LiteLLM 中非对话类模型（embedding、图像、语音等）不导入
+const LITELLM_CHAT_MODES: [&str; 3] = ["chat", "completion", "responses"];
+
+static LITELLM_TIER_KEY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+)_above_(\d+)k_tokens$").expect("valid regex"));
+
+impl Database {
+    /// 获取所有模型定价（按显示名称排序）
+    pub fn list_model_pricing(&self) -> Result<Vec<ModelPricingInfo>, AppError> {
+        let conn = lock_conn!(self.conn);
+        load_model_pricing(&conn)
+    }
+
+    /// 解析定价文件并与现有定价对比，不写入数据库
+    pub fn preview_pricing_import(
+        &self,
+        content: &str,
+        format: PricingFileFormat,
+    ) -> Result<PricingImportPreview, AppError> {
+        let (rows, skipped) = parse_pricing_file(content, format)?;
+        let existing = self.list_model_pricing()?;
+        Ok(diff_pricing(&existing, rows, skipped, format))
+    }
+
+    /// 在单个事务中应用定价导入
+    ///
+    /// `model_ids` 为 None 时应用全部新增与变更，否则只应用其中列出的模型。
+    pub async fn apply_pricing_import(
+        &self,
+        content: &str,
+        format: PricingFileFormat,
+        model_ids: Option<&[String]>,
+    ) -> Result<PricingImportResult, AppError> {
+        let (rows, skipped) = parse_pricing_file(content, format)?;
+        let selected = model_ids.map(|ids| ids.iter().cloned().collect::<HashSet<_>>());
+        let is_selected = |model_id: &str| {
+            selected
+                .as_ref()
+                .is_none_or(|selected| selected.contains(model_id))
+        };
+
+        let mut result = PricingImportResult::default();
+        {
+            let mut conn = lock_conn!(self.conn);
+            let tx = conn
+                .transaction()
+                .map_err(|e| AppError::Database(format!("开启事务失败: {e}")))?;
+
+            let existing = load_model_pricing(&tx)?;
+            let preview = diff_pricing(&existing, rows, skipped, format);
+            let added = preview
+                .added
+                .into_iter()
+                .filter(|info| is_selected(&info.model_id));
+            let changed = preview
+                .changed
+                .into_iter()
+                .map(|change| change.after)
+                .filter(|info| is_selected(&info.model_id));
+
+            for info in added {
+                upsert_model_pricing(&tx, &info)?;
+                result.added += 1;
+            }
+            for info in changed {
+                upsert_model_pricing(&tx, &info)?;
+                result.updated += 1;
+            }
+
+            tx.commit()
+                .map_err(|e| AppError::Database(format!("提交事务失败: {e}")))?;
+        }
+
+        if result.added + result.updated > 0 {
+            result.recomputed_logs = self.recompute_request_costs(None).await?;
+        }
+        log::info!(
+            "定价导入完成：新增 {} 条，更新 {} 条，重算 {} 条请求日志",
+            result.added,
+            result.updated,
+            result.recomputed_logs
+        );
+        Ok(result)
+    }
+
+    /// 导出全部模型定价
+    pub fn export_model_pricing(&self, format: PricingFileFormat) -> Result<String, AppError> {
+        let pricing = self.list_model_pricing()?;
+        match format {
+            PricingFileFormat::Litellm => export_litellm(&pricing),
+            PricingFileFormat::Csv => Ok(export_csv(&pricing)),
+        }
+    }
+}
+
+fn load_model_pricing(conn: &Connection) -> Result<Vec<ModelPricingInfo>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT model_id, display_name, input_cost_per_million, output_cost_per_million,
+                cache_read_cost_per_million, cache_creation_cost_per_million,
+                cache_creation_1h_cost_per_million, reasoning_cost_per_million,
+                tiers, service_tier_multipliers
+         FROM model_pricing
+         ORDER BY display_name",
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok(ModelPricingInfo {
+            model_id: row.get(0)?,
+            display_name: row.get(1)?,
+            input_cost_per_million: row.get(2)?,
+            output_cost_per_million: row.get(3)?,
+            cache_read_cost_per_million: row.get(4)?,
+            cache_creation_cost_per_million: row.get(5)?,
+            cache_creation_1h_cost_per_million: row.get(6)?,
+            reasoning_cost_per_million: row.get(7)?,
+            tiers: row
+                .get::<_, Option<String>>(8)?
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+            service_tier_multipliers: row
+                .get::<_, Option<String>>(9)?
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+        })
+    })?;
+
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+fn upsert_model_pricing(conn: &Connection, info: &ModelPricingInfo) -> Result<(), AppError> {
+    let tiers = (!info.tiers.is_empty())
+        .then(|| serde_json::to_string(&info.tiers))
+        .transpose()
+        .map_err(|e| AppError::Database(format!("分段价格序列化失败: {e}")))?;
+    let multipliers = (!info.service_tier_multipliers.is_empty())
+        .then(|| serde_json::to_string(&info.service_tier_multipliers))
+        .transpose()
+        .map_err(|e| AppError::Database(format!("服务等级倍率序列化失败: {e}")))?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO model_pricing (
+            model_id, display_name, input_cost_per_million, output_cost_per_million,
+            cache_read_cost_per_million, cache_creation_cost_per_million,
+            cache_creation_1h_cost_per_million, reasoning_cost_per_million,
+            tiers, service_tier_multipliers
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        rusqlite::params![
+            info.model_id,
+            info.display_name,
+            info.input_cost_per_million,
+            info.output_cost_per_million,
+            info.cache_read_cost_per_million,
+            info.cache_creation_cost_per_million,
+            info.cache_creation_1h_cost_per_million,
+            info.reasoning_cost_per_million,
+            tiers,
+            multipliers
+        ],
+    )
+    .map_err(|e| AppError::Database(format!("写入模型定价失败: {e}")))?;
+    Ok(())
+}
+
+/// 解析定价文件，返回可导入的行与被跳过的条目
+pub fn parse_pricing_file(
+    content: &str,
+    format: PricingFileFormat,
+) -> Result<(Vec<PricingImportRow>, Vec<String>), AppError> {
+    match format {
+        PricingFileFormat::Litellm => parse_litellm(content),
+        PricingFileFormat::Csv => parse_csv(content),
+    }
+}
+
+/// 与现有定价对比，生成导入预览
+pub fn diff_pricing(
+    existing: &[ModelPricingInfo],
+    rows: Vec<PricingImportRow>,
+    mut skipped: Vec<String>,
+    format: PricingFileFormat,
+) -> PricingImportPreview {
+    let existing: HashMap<&str, &ModelPricingInfo> = existing
+        .iter()
+        .map(|info| (info.model_id.as_str(), info))
+        .collect();
+    let mut preview = PricingImportPreview {
+        format,
+        added: Vec::new(),
+        changed: Vec::new(),
+        unchanged: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    for row in rows {
+        let before = existing.get(row.model_id.as_str()).copied();
+        let after = match merge_row(before, row) {
+            Ok(after) => after,
+            Err(reason) => {
+                skipped.push(reason);
+                continue;
+            }
+        };
+        match before {
+            None => preview.added.push(after),
+            Some(before) if normalize_info(before) == after => {
+                preview.unchanged.push(after.model_id)
+            }
+            Some(before) => preview.changed.push(PricingChange {
+                before: before.clone(),
+                after,
+            }),
+        }
+    }
+
+    preview.skipped = skipped;
+    preview
+}
+
+/// 合并导入行与现有定价，并校验价格可用于计费
+fn merge_row(
+    existing: Option<&ModelPricingInfo>,
+    row: PricingImportRow,
+) -> Result<ModelPricingInfo, String> {
+    let model_id = row.model_id;
+    let keep = |value: Option<String>, current: Option<&String>| {
+        value
+            .or_else(|| current.cloned())
+            .unwrap_or_else(|| "0".to_string())
+    };
+    let info = ModelPricingInfo {
+        display_name: row
+            .display_name
+            .or_else(|| existing.map(|e| e.display_name.clone()))
+            .unwrap_or_else(|| model_id.clone()),
+        input_cost_per_million: row.input_cost_per_million,
+        output_cost_per_million: row.output_cost_per_million,
+        cache_read_cost_per_million: keep(
+            row.cache_read_cost_per_million,
+            existing.map(|e| &e.cache_read_cost_per_million),
+        ),
+        cache_creation_cost_per_million: keep(
+            row.cache_creation_cost_per_million,
+            existing.map(|e| &e.cache_creation_cost_per_million),
+        ),
+        cache_creation_1h_cost_per_million: row
+            .cache_creation_1h_cost_per_million
+            .or_else(|| existing.and_then(|e| e.cache_creation_1h_cost_per_million.clone())),
+        reasoning_cost_per_million: row
+            .reasoning_cost_per_million
+            .or_else(|| existing.and_then(|e| e.reasoning_cost_per_million.clone())),
+        tiers: row
+            .tiers
+            .or_else(|| existing.map(|e| e.tiers.clone()))
+            .unwrap_or_default(),
+        service_tier_multipliers: row
+            .service_tier_multipliers
+            .or_else(|| existing.map(|e| e.service_tier_multipliers.clone()))
+            .unwrap_or_default(),
+        model_id,
+    };
+
+    let tiers = serde_json::to_string(&info.tiers).map_err(|e| e.to_string());
+    let multipliers = serde_json::to_string(&info.service_tier_multipliers);
+    ModelPricing::from_strings(
+        &info.input_cost_per_million,
+        &info.output_cost_per_million,
+        &info.cache_read_cost_per_million,
+        &info.cache_creation_cost_per_million,
+    )
+    .map_err(|e| e.to_string())
+    .and_then(|pricing| {
+        pricing.with_modifiers(
+            info.cache_creation_1h_cost_per_million.as_deref(),
+            info.reasoning_cost_per_million.as_deref(),
+            tiers.as_deref().ok(),
+            multipliers.as_deref().ok(),
+        )
+    })
+    .map_err(|e| format!("{}: {e}", info.model_id))?;
+
+    Ok(normalize_info(&info))
+}
+
+/// 规范化价格字符串（3.0 与 3 视为相同），便于对比
+fn normalize_info(info: &ModelPricingInfo) -> ModelPricingInfo {
+    ModelPricingInfo {
+        model_id: info.model_id.clone(),
+        display_name: info.display_name.clone(),
+        input_cost_per_million: normalize_decimal(&info.input_cost_per_million),
+        output_cost_per_million: normalize_decimal(&info.output_cost_per_million),
+        cache_read_cost_per_million: normalize_decimal(&info.cache_read_cost_per_million),
+        cache_creation_cost_per_million: normalize_decimal(&info.cache_creation_cost_per_million),
+        cache_creation_1h_cost_per_million: info
+            .cache_creation_1h_cost_per_million
+            .as_deref()
+            .map(normalize_decimal),
+        reasoning_cost_per_million: info
+            .reasoning_cost_per_million
+            .as_deref()
+            .map(normalize_decimal),
+        tiers: info
+            .tiers
+            .iter()
+            .map(|tier| PricingTier {
+                above_input_tokens: tier.above_input_tokens,
+                input_cost_per_million: normalize_decimal(&tier.input_cost_per_million),
+                output_cost_per_million: normalize_decimal(&tier.output_cost_per_million),
+                cache_read_cost_per_million: normalize_decimal(&tier.cache_read_cost_per_million),
+                cache_creation_cost_per_million: normalize_decimal(
+                    &tier.cache_creation_cost_per_million,
+                ),
+                cache_creation_1h_cost_per_million: tier
+                    .cache_creation_1h_cost_per_million
+                    .as_deref()
+                    .map(normalize_decimal),
+            })
+            .collect(),
+        service_tier_multipliers: info
+            .service_tier_multipliers
+            .iter()
+            .map(|(tier, value)| (tier.trim().to_lowercase(), normalize_decimal(value)))
+            .collect(),
+    }
+}
+
+fn normalize_decimal(value: &str) -> String {
+    Decimal::from_str(value.trim())
+        .map(|d| d.normalize().to_string())
+        .unwrap_or_else(|_| value.trim().to_string())
+}
+
+// --- LiteLLM ---
+
+fn parse_litellm(content: &str) -> Result<(Vec<PricingImportRow>, Vec<String>), AppError> {
+    let root: Map<String, Value> = serde_json::from_str(content)
+        .map_err(|e| AppError::InvalidInput(format!("LiteLLM 定价文件解析失败: {e}")))?;
+
+    let mut rows = Vec::new();
+    let mut skipped = Vec::new();
+    let mut seen = HashSet::new();
+
+    // 先导入不带供应商前缀的条目，带前缀的同名模型（如 gemini/gemini-2.5-pro）仅在缺失时补充
+    let (plain, prefixed): (Vec<_>, Vec<_>) = root.iter().partition(|(key, _)| !key.contains('/'));
+    for (key, entry) in plain.into_iter().chain(prefixed) {
+        if key == "sample_spec" {
+            continue;
+        }
+        let Some(entry) = entry.as_object() else {
+            continue;
+        };
+        if entry
+            .get("mode")
+            .and_then(|m| m.as_str())
+            .is_some_and(|mode| !LITELLM_CHAT_MODES.contains(&mode))
+        {
+            continue;
+        }
+
+        let model_id = clean_model_id(key);
+        if model_id.is_empty() || !seen.insert(model_id.clone()) {
+            continue;
+        }
+        match litellm_entry_to_row(model_id, entry) {
+            Ok(Some(row)) => rows.push(row),
+            Ok(None) => {}
+            Err(reason) => skipped.push(format!("{key}: {reason}")),
+        }
+    }
+
+    Ok((rows, skipped))
+}
+
+fn litellm_entry_to_row(
+    model_id: String,
+    entry: &Map<String, Value>,
+) -> Result<Option<PricingImportRow>, String> {
+    let per_token = |key: &str| -> Result<Option<Decimal>, String> {
+        match entry.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => json_decimal(value)
+                .map(Some)
+                .ok_or_else(|| format!("{key} 不是有效数字")),
+        }
+    };
+    let per_million = |key: &str| -> Result<Option<String>, String> {
+        per_token(key)?.map(to_per_million).transpose()
+    };
+
+    // 没有输入/输出价格的条目（如免费或按请求计费的模型）无法用于 token 计费
+    let (Some(input), Some(output)) = (
+        per_token("input_cost_per_token")?,
+        per_token("output_cost_per_token")?,
+    ) else {
+        return Ok(None);
+    };
+
+    // 分段价格：xxx_above_200k_tokens
+    let mut tiers: HashMap<u64, HashMap<String, Decimal>> = HashMap::new();
+    for (key, value) in entry {
+        if let Some(caps) = LITELLM_TIER_KEY.captures(key) {
+            let threshold = caps[2]
+                .parse::<u64>()
+                .ok()
+                .and_then(|thousands| thousands.checked_mul(1000))
+                .ok_or_else(|| format!("{key} 阈值无效"))?;
+            let decimal = json_decimal(value).ok_or_else(|| format!("{key} 不是有效数字"))?;
+            tiers
+                .entry(threshold)
+                .or_default()
+                .insert(caps[1].to_string(), decimal);
+        }
+    }
+    let cache_read = per_token("cache_read_input_token_cost")?;
+    let cache_creation = per_token("cache_creation_input_token_cost")?;
+    let cache_creation_1h = per_token("cache_creation_input_token_cost_above_1hr")?;
+    let mut tiers: Vec<PricingTier> = tiers
+        .into_iter()
+        .map(|(threshold, fields)| {
+            let field = |name: &str, base: Option<Decimal>| {
+                fields
+                    .get(name)
+                    .copied()
+                    .or(base)
+                    .map(to_per_million)
+                    .unwrap_or_else(|| Ok("0".to_string()))
+            };
+            Ok(PricingTier {
+                above_input_tokens: threshold,
+                input_cost_per_million: field("input_cost_per_token", Some(input))?,
+                output_cost_per_million: field("output_cost_per_token", Some(output))?,
+                cache_read_cost_per_million: field("cache_read_input_token_cost", cache_read)?,
+                cache_creation_cost_per_million: field(
+                    "cache_creation_input_token_cost",
+                    cache_creation,
+                )?,
+                cache_creation_1h_cost_per_million: fields
+                    .get("cache_creation_input_token_cost_above_1hr")
+                    .copied()
+                    .map(to_per_million)
+                    .transpose()?,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    tiers.sort_by_key(|tier| tier.above_input_tokens);
+
+    // 服务等级：input_cost_per_token_priority 等与基础输入价格的比值
+    let mut multipliers = HashMap::new();
+    if !input.is_zero() {
+        for (suffix, service_tier) in LITELLM_SERVICE_TIERS {
+            if let Some(price) = per_token(&format!("input_cost_per_token_{suffix}"))? {
+                let ratio = price
+                    .checked_div(input)
+                    .ok_or_else(|| format!("input_cost_per_token_{suffix} 超出可表示范围"))?;
+                multipliers.insert(
+                    service_tier.to_string(),
+                    ratio.round_dp(4).normalize().to_string(),
+                );
+            }
+        }
+    }
+
+    Ok(Some(PricingImportRow {
+        model_id,
+        display_name: None,
+        input_cost_per_million: to_per_million(input)?,
+        output_cost_per_million: to_per_million(output)?,
+        cache_read_cost_per_million: cache_read.map(to_per_million).transpose()?,
+        cache_creation_cost_per_million: cache_creation.map(to_per_million).transpose()?,
+        cache_creation_1h_cost_per_million: cache_creation_1h.map(to_per_million).transpose()?,
+        reasoning_cost_per_million: per_million("output_cost_per_reasoning_token")?,
+        tiers: (!tiers.is_empty()).then_some(tiers),
+        service_tier_multipliers: (!multipliers.is_empty()).then_some(multipliers),
+    }))
+}
+
+fn export_litellm(pricing: &[ModelPricingInfo]) -> Result<String, AppError> {
+    let mut root = Map::new();
+    for info in pricing {
+        let mut entry = Map::new();
+        let mut put = |key: String, per_million: &str| {
+            if let Some(value) = per_million_to_json(per_million) {
+                entry.insert(key, value);
+            }
+        };
+
+        put("input_cost_per_token".into(), &info.input_cost_per_million);
+        put(
+            "output_cost_per_token".into(),
+            &info.output_cost_per_million,
+        );
+        put(
+            "cache_read_input_token_cost".into(),
+            &info.cache_read_cost_per_million,
+        );
+        put(
+            "cache_creation_input_token_cost".into(),
+            &info.cache_creation_cost_per_million,
+        );
+        if let Some(price) = &info.cache_creation_1h_cost_per_million {
+            put("cache_creation_input_token_cost_above_1hr".into(), price);
+        }
+        if let Some(price) = &info.reasoning_cost_per_million {
+            put("output_cost_per_reasoning_token".into(), price);
+        }
+        for tier in &info.tiers {
+            if tier.above_input_tokens % 1000 != 0 {
+                log::warn!(
+                    "模型 {} 的分段阈值 {} 无法用 LiteLLM 格式表示，已跳过",
+                    info.model_id,
+                    tier.above_input_tokens
+                );
+                continue;
+            }
+            let suffix = format!("_above_{}k_tokens", tier.above_input_tokens / 1000);
+            put(
+                format!("input_cost_per_token{suffix}"),
+                &tier.input_cost_per_million,
+            );
+            put(
+                format!("output_cost_per_token{suffix}"),
+                &tier.output_cost_per_million,
+            );
+            put(
+                format!("cache_read_input_token_cost{suffix}"),
+                &tier.cache_read_cost_per_million,
+            );
+            put(
+                format!("cache_creation_input_token_cost{suffix}"),
+                &tier.cache_creation_cost_per_million,
+            );
+            if let Some(price) = &tier.cache_creation_1h_cost_per_million {
+                put(
+                    format!("cache_creation_input_token_cost_above_1hr{suffix}"),
+                    price,
+                );
+            }
+        }
+        for (suffix, service_tier) in LITELLM_SERVICE_TIERS {
+            let Some(multiplier) = info
+                .service_tier_multipliers
+                .get(service_tier)
+                .and_then(|m| Decimal::from_str(m).ok())
+            else {
+                continue;
+            };
+            for (key, base) in [
+                ("input_cost_per_token", &info.input_cost_per_million),
+                ("output_cost_per_token", &info.output_cost_per_million),
+            ] {
+                if let Some(price) = Decimal::from_str(base)
+                    .ok()
+                    .and_then(|base| base.checked_mul(multiplier))
+                {
+                    put(format!("{key}_{suffix}"), &price.normalize().to_string());
+                }
+            }
+        }
+        entry.insert("mode".into(), Value::String("chat".into()));
+        root.insert(info.model_id.clone(), Value::Object(entry));
+    }
+
+    serde_json::to_string_pretty(&root)
+        .map_err(|e| AppError::Database(format!("定价导出失败: {e}")))
+}
+
+fn json_decimal(value: &Value) -> Option<Decimal> {
+    let text = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.trim().to_string(),
+        _ => return None,
+    };
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .ok()
+}
+
+fn to_per_million(per_token: Decimal) -> Result<String, String> {
+    per_token
+        .checked_mul(Decimal::from(1_000_000))
+        .map(|price| price.round_dp(6).normalize().to_string())
+        .ok_or_else(|| format!("价格 {per_token} 超出可表示范围"))
+}
+
+fn per_million_to_json(per_million: &str) -> Option<Value> {
+    let per_token = Decimal::from_str(per_million.trim())
+        .ok()?
+        .checked_div(Decimal::from(1_000_000))?;
+    Number::from_f64(per_token.to_f64()?).map(Value::Number)
+}
+
+// --- CSV ---
+
+fn parse_csv(content: &str) -> Result<(Vec<PricingImportRow>, Vec<String>), AppError> {
+    let records = parse_csv_records(content)?;
+    let mut records = records.into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| AppError::InvalidInput("CSV 文件为空".to_string()))?;
+    let columns: HashMap<String, usize> = header
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.trim().to_lowercase(), index))
+        .collect();
+    for required in [
+        "model_id",
+        "input_cost_per_million",
+        "output_cost_per_million",
+    ] {
+        if !columns.contains_key(required) {
+            return Err(AppError::InvalidInput(format!(
+                "CSV 缺少必需列: {required}"
+            )));
+        }
+    }
+
+    let mut rows = Vec::new();
+    let mut skipped = Vec::new();
+    for (line, record) in records.enumerate() {
+        let cell = |name: &str| {
+            columns
+                .get(name)
+                .and_then(|&index| record.get(index))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(String::from)
+        };
+        // 表头占第 1 行
+        let line = line + 2;
+
+        let Some(model_id) = cell("model_id") else {
+            if record.iter().any(|value| !value.trim().is_empty()) {
+                skipped.push(format!("第 {line} 行: 缺少 model_id"));
+            }
+            continue;
+        };
+        let (Some(input), Some(output)) = (
+            cell("input_cost_per_million"),
+            cell("output_cost_per_million"),
+        ) else {
+            skipped.push(format!("第 {line} 行 {model_id}: 缺少输入或输出价格"));
+            continue;
+        };
+        let tiers = match cell("tiers")
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+        {
+            Ok(tiers) => tiers,
+            Err(e) => {
+                skipped.push(format!("第 {line} 行 {model_id}: 分段价格格式错误: {e}"));
+                continue;
+            }
+        };
+        let multipliers = match cell("service_tier_multipliers")
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+        {
+            Ok(multipliers) => multipliers,
+            Err(e) => {
+                skipped.push(format!(
+                    "第 {line} 行 {model_id}: 服务等级倍率格式错误: {e}"
+                ));
+                continue;
+            }
+        };
+
+        rows.push(PricingImportRow {
+            display_name: cell("display_name"),
+            input_cost_per_million: input,
+            output_cost_per_million: output,
+            cache_read_cost_per_million: cell("cache_read_cost_per_million"),
+            cache_creation_cost_per_million: cell("cache_creation_cost_per_million"),
+            cache_creation_1h_cost_per_million: cell("cache_creation_1h_cost_per_million"),
+            reasoning_cost_per_million: cell("reasoning_cost_per_million"),
+            tiers,
+            service_tier_multipliers: multipliers,
+            model_id,
+        });
+    }
+
+    Ok((rows, skipped))
+}
+
+fn export_csv(pricing: &[ModelPricingInfo]) -> String {
+    let mut out = CSV_COLUMNS.join(",");
+    out.push('\n');
+    for info in pricing {
+        let tiers = if info.tiers.is_empty() {
+            String::new()
+        } else {
+            serde_json::to_string(&info.tiers).unwrap_or_default()
+        };
+        let multipliers = if info.service_tier_multipliers.is_empty() {
+            String::new()
+        } else {
+            // 按键排序，保证导出结果稳定
+            let sorted: std::collections::BTreeMap<_, _> =
+                info.service_tier_multipliers.iter().collect();
+            serde_json::to_string(&sorted).unwrap_or_default()
+        };
+        let fields = [
+            info.model_id.as_str(),
+            info.display_name.as_str(),
+            info.input_cost_per_million.as_str(),
+            info.output_cost_per_million.as_str(),
+            info.cache_read_cost_per_million.as_str(),
+            info.cache_creation_cost_per_million.as_str(),
+            info.cache_creation_1h_cost_per_million
+                .as_deref()
+                .unwrap_or(""),
+            info.reasoning_cost_per_million.as_deref().unwrap_or(""),
+            tiers.as_str(),
+            multipliers.as_str(),
+        ];
+        let line = fields
+            .iter()
+            .map(|field| csv_escape(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+pub(crate) fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 解析 RFC 4180 CSV（支持引号转义与单元格内换行）
+fn parse_csv_records(content: &str) -> Result<Vec<Vec<String>>, AppError> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(AppError::InvalidInput("CSV 引号未闭合".to_string()));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LITELLM_SAMPLE: &str = r#"{
+        "sample_spec": {"input_cost_per_token": 0, "output_cost_per_token": 0},
+        "claude-sonnet-4-5-20250929": {
+            "input_cost_per_token": 3e-06,
+            "output_cost_per_token": 1.5e-05,
+            "cache_read_input_token_cost": 3e-07,
+            "cache_creation_input_token_cost": 3.75e-06,
+            "cache_creation_input_token_cost_above_1hr": 6e-06,
+            "input_cost_per_token_above_200k_tokens": 6e-06,
+            "output_cost_per_token_above_200k_tokens": 2.25e-05,
+            "cache_read_input_token_cost_above_200k_tokens": 6e-07,
+            "cache_creation_input_token_cost_above_200k_tokens": 7.5e-06,
+            "cache_creation_input_token_cost_above_1hr_above_200k_tokens": 1.2e-05,
+            "mode": "chat"
+        },
+        "gpt-5": {
+            "input_cost_per_token": 1.25e-06,
+            "output_cost_per_token": 1e-05,
+            "cache_read_input_token_cost": 1.25e-07,
+            "input_cost_per_token_priority": 2.5e-06,
+            "input_cost_per_token_flex": 6.25e-07,
+            "mode": "responses"
+        },
+        "openai/gpt-5": {"input_cost_per_token": 9e-06, "output_cost_per_token": 9e-06},
+        "brand-new-model": {"input_cost_per_token": 1e-06, "output_cost_per_token": 2e-06, "mode": "chat"},
+        "text-embedding-3-small": {"input_cost_per_token": 2e-08, "output_cost_per_token": 0, "mode": "embedding"},
+        "broken-model": {"input_cost_per_token": "abc", "output_cost_per_token": 1e-06}
+    }"#;
+
+    #[test]
+    fn litellm_overflowing_prices_are_skipped() {
+        let content = r#"{
+            "huge-price": {"input_cost_per_token": 1e25, "output_cost_per_token": 1e-06},
+            "huge-tier": {
+                "input_cost_per_token": 1e-06,
+                "output_cost_per_token": 1e-06,
+                "input_cost_per_token_above_99999999999999999k_tokens": 2e-06
+            },
+            "huge-ratio": {
+                "input_cost_per_token": 1e-28,
+                "output_cost_per_token": 1e-06,
+                "input_cost_per_token_priority": 1e25
+            },
+            "ok-model": {"input_cost_per_token": 1e-06, "output_cost_per_token": 2e-06}
+        }"#;
+        let (rows, skipped) = parse_litellm(content).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].model_id, "ok-model");
+        assert_eq!(skipped.len(), 3, "{skipped:?}");
+    }
+
+    #[test]
+    fn litellm_entries_are_converted_to_per_million() {
+        let (rows, skipped) = parse_litellm(LITELLM_SAMPLE).unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("broken-model"));
+        // embedding 与重复的带前缀条目被忽略
+        assert_eq!(rows.len(), 3);
+
+        let sonnet = rows
+            .iter()
+            .find(|r| r.model_id == "claude-sonnet-4-5-20250929")
+            .unwrap();
+        assert_eq!(sonnet.input_cost_per_million, "3");
+        assert_eq!(sonnet.output_cost_per_million, "15");
+        assert_eq!(sonnet.cache_read_cost_per_million.as_deref(), Some("0.3"));
+        assert_eq!(
+            sonnet.cache_creation_1h_cost_per_million.as_deref(),
+            Some("6")
+        );
+        let tier = &sonnet.tiers.as_ref().unwrap()[0];
+        assert_eq!(tier.above_input_tokens, 200_000);
+        assert_eq!(tier.input_cost_per_million, "6");
+        assert_eq!(tier.output_cost_per_million, "22.5");
+        assert_eq!(tier.cache_read_cost_per_million, "0.6");
+        assert_eq!(
+            tier.cache_creation_1h_cost_per_million.as_deref(),
+            Some("12")
+        );
+
+        let gpt5 = rows.iter().find(|r| r.model_id == "gpt-5").unwrap();
+        assert_eq!(gpt5.input_cost_per_million, "1.25");
+        let multipliers = gpt5.service_tier_multipliers.as_ref().unwrap();
+        assert_eq!(multipliers["priority"], "2");
+        assert_eq!(multipliers["flex"], "0.5");
+    }
+
+    #[test]
+    fn diff_classifies_new_changed_and_unchanged() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let preview = db.preview_pricing_import(LITELLM_SAMPLE, PricingFileFormat::Litellm)?;
+
+        assert_eq!(
+            preview
+                .added
+                .iter()
+                .map(|i| i.model_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["brand-new-model"]
+        );
+        // 种子数据中的 claude-sonnet-4-5 与文件一致（0.30 与 0.3 视为相同）
+        assert!(preview
+            .unchanged
+            .contains(&"claude-sonnet-4-5-20250929".to_string()));
+        // 文件中的服务等级倍率整体替换现有配置（种子数据还包含 batch）
+        assert_eq!(preview.changed.len(), 1);
+        let change = &preview.changed[0];
+        assert_eq!(change.after.model_id, "gpt-5");
+        assert_eq!(change.after.display_name, "GPT-5");
+        assert!(change.before.service_tier_multipliers.contains_key("batch"));
+        assert!(!change.after.service_tier_multipliers.contains_key("batch"));
+        assert_eq!(preview.skipped.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn apply_writes_selected_rows_and_recomputes_costs() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, input_tokens, output_tokens,
+                    total_cost_usd, latency_ms, status_code, created_at
+                ) VALUES ('req-1', 'p1', 'claude', 'brand-new-model', 1000000, 0, '0', 100, 200, 1000)",
+                [],
+            )?;
+        }
+
+        let csv = "model_id,display_name,input_cost_per_million,output_cost_per_million\n\
+                   brand-new-model,\"Brand, New\",1.5,3\n\
+                   other-model,Other,1,2\n";
+        let result = db
+            .apply_pricing_import(
+                csv,
+                PricingFileFormat::Csv,
+                Some(&["brand-new-model".to_string()]),
+            )
+            .await?;
+        assert_eq!(result.added, 1);
+        assert_eq!(result.updated, 0);
+        assert_eq!(result.recomputed_logs, 1);
+
+        let pricing = db.list_model_pricing()?;
+        let added = pricing
+            .iter()
+            .find(|i| i.model_id == "brand-new-model")
+            .unwrap();
+        assert_eq!(added.display_name, "Brand, New");
+        assert!(pricing.iter().all(|i| i.model_id != "other-model"));
+
+        let conn = lock_conn!(db.conn);
+        let cost: String = conn.query_row(
+            "SELECT total_cost_usd FROM proxy_request_logs WHERE request_id = 'req-1'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(
+            Decimal::from_str(&cost).unwrap(),
+            Decimal::from_str("1.5").unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn csv_export_round_trips() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let csv = db.export_model_pricing(PricingFileFormat::Csv)?;
+        assert!(csv.starts_with("model_id,display_name,"));
+
+        let preview = db.preview_pricing_import(&csv, PricingFileFormat::Csv)?;
+        assert!(preview.added.is_empty());
+        assert!(preview.changed.is_empty(), "{:?}", preview.changed);
+        assert!(preview.skipped.is_empty(), "{:?}", preview.skipped);
+        assert_eq!(preview.unchanged.len(), db.list_model_pricing()?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn litellm_export_round_trips() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let json = db.export_model_pricing(PricingFileFormat::Litellm)?;
+        let preview = db.preview_pricing_import(&json, PricingFileFormat::Litellm)?;
+        assert!(preview.added.is_empty());
+        assert!(preview.changed.is_empty(), "{:?}", preview.changed);
+        Ok(())
+    }
+
+    #[test]
+    fn csv_parser_handles_quotes_and_newlines() {
+        let records = parse_csv_records("a,\"b \"\"x\"\"\",\"multi\nline\"\r\n1,2,3").unwrap();
+        assert_eq!(
+            records,
+            vec![
+                vec![
+                    "a".to_string(),
+                    "b \"x\"".to_string(),
+                    "multi\nline".to_string()
+                ],
+                vec!["1".to_string(), "2".to_string(), "3".to_string()],
+            ]
+        );
+        assert!(parse_csv_records("a,\"b").is_err());
+    }
+}
@@ -6,6 +6,7 @@ use crate::app_config::AppType;
 use crate::config::{get_claude_settings_path, read_json_file, write_json_file};
 use crate::database::Database;
 use crate::provider::Provider;
+use crate::proxy::inbound_auth;
 use crate::proxy::server::ProxyServer;
 use crate::proxy::switch_lock::SwitchLockManager;
 use crate::proxy::types::*;
@@ -1823,6 +1824,13 @@ impl ProxyService {
         // 保存到数据库（保持 live_takeover_active 状态不变）
         let mut new_config = config.clone();
         new_config.live_takeover_active = previous.live_takeover_active;
+        new_config.otlp_endpoint = new_config
+            .otlp_endpoint
+            .map(|endpoint| endpoint.trim().to_string())
+            .filter(|endpoint| !endpoint.is_empty());
+        if let Some(endpoint) = new_config.otlp_endpoint.as_deref() {
+            crate::proxy::otel::normalize_endpoint(endpoint)?;
+        }
 
         self.db
             .update_proxy_config(new_config.clone())
@@ -1892,6 +1900,102 @@ impl ProxyService {
         Ok(())
     }
 
+    /// 获取入站访问控制配置
+    pub async fn get_access_config(&self) -> Result<ProxyAccessConfig, String> {
+        self.db
+            .get_proxy_access_config()
+            .await
+            .map_err(|e| format!("获取代理访问控制配置失败: {e}"))
+    }
+
+    /// 更新入站访问控制配置
+    ///
+    /// 令牌与来源白名单可热更新；局域网模式开关会改变监听地址，需要重启服务器。
+    pub async fn update_access_config(
+        &self,
+        config: &ProxyAccessConfig,
+    ) -> Result<ProxyAccessConfig, String> {
+        let mut new_config = config.clone();
+        new_config.access_token = new_config
+            .access_token
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty());
+        new_config.allowed_cidrs = new_config
+            .allowed_cidrs
+            .iter()
+            .map(|cidr| cidr.trim().to_string())
+            .filter(|cidr| !cidr.is_empty())
+            .collect();
+        if new_config.lan_mode && new_config.access_token.is_none() {
+            new_config.access_token = Some(inbound_auth::generate_access_token());
+        }
+        inbound_auth::validate_access_config(&new_config)?;
+
+        let previous = self
+            .db
+            .get_proxy_access_config()
+            .await
+            .map_err(|e| format!("获取代理访问控制配置失败: {e}"))?;
+        self.db
+            .update_proxy_access_config(&new_config)
+            .await
+            .map_err(|e| format!("保存代理访问控制配置失败: {e}"))?;
+
+        let mut server_guard = self.server.write().await;
+        let Some(server) = server_guard.as_ref() else {
+            return Ok(new_config);
+        };
+
+        if server.is_lan_mode().await == new_config.lan_mode {
+            server.apply_access_config(&new_config).await;
+            log::info!("代理访问控制配置已实时应用");
+            return Ok(new_config);
+        }
+
+        // 原地重新绑定监听地址：保留熔断器、会话亲和等运行时状态
+        let restarted = match server.stop().await {
+            Ok(()) => server
+                .start()
+                .await
+                .map(|_| ())
+                .map_err(|e| format!("重启代理服务器失败: {e}")),
+            Err(e) => Err(format!("重启前停止代理服务器失败: {e}")),
+        };
+        if let Err(e) = restarted {
+            log::error!("切换局域网共享模式失败，恢复原访问控制配置: {e}");
+            if let Err(db_err) = self.db.update_proxy_access_config(&previous).await {
+                *server_guard = None;
+                return Err(format!("{e}；恢复原访问控制配置失败，代理已停止: {db_err}"));
+            }
+            return match server.start().await {
+                Ok(_) => Err(format!("{e}，已恢复原访问控制配置")),
+                Err(restore_err) => {
+                    *server_guard = None;
+                    Err(format!(
+                        "{e}；以原配置恢复代理失败，代理已停止: {restore_err}"
+                    ))
+                }
+            };
+        }
+        log::info!(
+            "局域网共享模式已{}，代理服务器已重启",
+            if new_config.lan_mode {
+                "开启"
+            } else {
+                "关闭"
+            }
+        );
+
+        Ok(new_config)
+    }
+
+    /// 重新生成入站访问令牌（旧令牌立即失效）
+    pub async fn regenerate_access_token(&self) -> Result<ProxyAccessConfig, String> {
+        let mut config = self.get_access_config().await?;
+        config.access_token = Some(inbound_auth::generate_access_token());
+        self.update_access_config(&config).await
+    }
+
     /// 检查服务器是否正在运行
     pub async fn is_running(&self) -> bool {
         self.server.read().await.is_some()
@@ -1913,6 +2017,20 @@ impl ProxyService {
         Ok(())
     }
 
+    /// 让运行中的代理重新加载模型路由规则
+    pub async fn reload_model_routing_rules(&self) {
+        if let Some(server) = self.server.read().await.as_ref() {
+            server.reload_model_routing_rules().await;
+        }
+    }
+
+    /// 让运行中的代理重新加载请求抓包规则
+    pub async fn reload_capture_rules(&self) {
+        if let Some(server) = self.server.read().await.as_ref() {
+            server.reload_capture_rules().await;
+        }
+    }
+
     /// 重置指定 Provider 的熔断器
     ///
     /// 如果代理服务器正在运行，立即重置内存中的熔断器状态
@@ -2783,4 +2901,46 @@ command = "latest-command"
             "new MCP entries should remain in the restore backup"
         );
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn lan_mode_toggle_rebinds_the_running_server_in_place() {
+        let _home = TempHome::new();
+        let db = Arc::new(Database::memory().expect("init db"));
+        let service = ProxyService::new(db.clone());
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("pick free port")
+            .port();
+        let mut config = db.get_proxy_config().await.expect("get proxy config");
+        config.listen_address = "127.0.0.1".to_string();
+        config.listen_port = port;
+        db.update_proxy_config(config)
+            .await
+            .expect("update proxy config");
+
+        service.start().await.expect("start proxy");
+        if let Some(server) = service.server.read().await.as_ref() {
+            server.set_active_target("claude", "p1", "P1").await;
+        }
+
+        let updated = service
+            .update_access_config(&ProxyAccessConfig {
+                lan_mode: true,
+                ..Default::default()
+            })
+            .await
+            .expect("enable lan mode");
+        assert!(updated.access_token.is_some());
+
+        // 同一实例重新绑定，运行时状态保留
+        let status = service.get_status().await.expect("status");
+        assert!(status.running);
+        assert!(status.lan_mode);
+        assert_eq!(status.address, "0.0.0.0");
+        assert_eq!(status.active_targets.len(), 1);
+
+        service.stop().await.expect("stop proxy");
+    }
 }
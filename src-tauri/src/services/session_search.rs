@@ -0,0 +1,692 @@
+//! 会话全文搜索
+//!
+//! 对六个会话来源（Claude / Codex / Gemini / OpenCode / OpenClaw / Hermes）的消息建立
+//! SQLite FTS5 索引，支持按关键词检索并跳转到具体消息。
+//!
+//! ## 数据流
+//! ```text
+//! scan_sessions → 比对 session_search_files 指纹 → load_messages（仅变更的会话）
+//!               → 重建该会话的 session_search_fts 行 → search_sessions 查询
+//! ```
+//!
+//! 同步由会话列表刷新在后台触发（[`Database::refresh_session_search_index`]）；搜索只查询
+//! 现有索引，仅在从未同步时同步一次，索引超过 [`AUTO_SYNC_INTERVAL_SECS`] 未更新时转入后台同步。
+//!
+//! 指纹取源文件 mtime（毫秒）与 `last_active_at` 的较大值；SQLite 来源
+//! （`sqlite:` 前缀）多个会话共享同一数据库文件，只使用 `last_active_at`。
+//! 索引行同时记录 [`INDEX_VERSION`]，版本不一致的会话即使指纹未变也会重建。
+
+use crate::database::{escape_like, lock_conn, Database};
+use crate::error::AppError;
+use crate::session_manager::{self, SessionMessage, SessionMeta};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Mutex, TryLockError};
+use std::time::SystemTime;
+
+/// 消息投影版本：`load_messages` 的扁平化结果（内容或 message_index 对应关系）
+/// 变化时递增，使已有索引在下次同步时全部重建
+const INDEX_VERSION: i64 = 1;
+
+/// 搜索时索引被视为过期、需要后台同步的间隔（秒）
+pub const AUTO_SYNC_INTERVAL_SECS: i64 = 5 * 60;
+
+/// 上次完成索引同步的时间（秒级时间戳，0 表示本次运行尚未同步）
+static LAST_SYNC_AT: AtomicI64 = AtomicI64::new(0);
+
+/// 串行化索引同步，避免会话列表刷新与搜索同时扫描
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+/// 索引新鲜度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexFreshness {
+    /// 本次运行尚未同步
+    Never,
+    /// 超过 [`AUTO_SYNC_INTERVAL_SECS`] 未同步
+    Stale,
+    Fresh,
+}
+
+/// 当前索引的新鲜度
+pub fn index_freshness() -> IndexFreshness {
+    let last = LAST_SYNC_AT.load(Ordering::Relaxed);
+    if last == 0 {
+        IndexFreshness::Never
+    } else if unix_secs() - last >= AUTO_SYNC_INTERVAL_SECS {
+        IndexFreshness::Stale
+    } else {
+        IndexFreshness::Fresh
+    }
+}
+
+fn unix_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// 默认返回的命中数量
+pub const DEFAULT_SEARCH_LIMIT: u32 = 50;
+
+/// 单次查询返回的命中数量上限
+const MAX_SEARCH_LIMIT: u32 = 500;
+
+/// trigram 分词器可用 MATCH 检索的最短关键词长度（字符数）
+const MIN_MATCH_TERM_CHARS: usize = 3;
+
+/// 摘要片段在命中位置两侧保留的字符数
+const SNIPPET_RADIUS_CHARS: usize = 60;
+
+/// 搜索条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchQuery {
+    /// 关键词，空白分隔，多个关键词需同时命中
+    pub query: String,
+    #[serde(default)]
+    pub provider_id: Option<String>,
+    /// 项目目录（子串匹配）
+    #[serde(default)]
+    pub project_dir: Option<String>,
+    /// 起始时间（毫秒时间戳，按消息时间，缺失时按会话最后活跃时间）
+    #[serde(default)]
+    pub start_date: Option<i64>,
+    /// 结束时间（毫秒时间戳）
+    #[serde(default)]
+    pub end_date: Option<i64>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// 单条搜索命中
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchHit {
+    pub provider_id: String,
+    pub session_id: String,
+    pub source_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    /// 命中消息在 `load_messages` 结果中的下标
+    pub message_index: u32,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts: Option<i64>,
+    pub snippet: String,
+    /// 相关度（越大越相关；仅含短关键词时为 0，按时间倒序）
+    pub score: f64,
+}
+
+/// 索引同步结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionIndexSyncResult {
+    pub indexed: u32,
+    pub unchanged: u32,
+    pub removed: u32,
+    pub errors: Vec<String>,
+}
+
+impl Database {
+    /// 用最新扫描到的会话刷新索引并记录同步时间
+    ///
+    /// 同一时间只运行一个同步：`wait = false` 时若已有同步在进行则直接跳过并返回 `None`。
+    pub fn refresh_session_search_index(
+        &self,
+        sessions: &[SessionMeta],
+        wait: bool,
+    ) -> Result<Option<SessionIndexSyncResult>, AppError> {
+        let _guard = match SYNC_LOCK.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) if wait => {
+                SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+            }
+            Err(TryLockError::WouldBlock) => return Ok(None),
+        };
+
+        let sync = self.sync_session_search_index(sessions)?;
+        LAST_SYNC_AT.store(unix_secs(), Ordering::Relaxed);
+        if sync.indexed > 0 || sync.removed > 0 {
+            log::debug!(
+                "[SESSION-SEARCH] 索引已更新: indexed={}, removed={}, errors={}",
+                sync.indexed,
+                sync.removed,
+                sync.errors.len()
+            );
+        }
+        Ok(Some(sync))
+    }
+
+    /// 增量同步会话全文索引：只重新加载指纹变化的会话，并清理已不存在的会话
+    pub fn sync_session_search_index(
+        &self,
+        sessions: &[SessionMeta],
+    ) -> Result<SessionIndexSyncResult, AppError> {
+        self.sync_session_search_index_with(sessions, |meta, source_path| {
+            session_manager::load_messages(&meta.provider_id, source_path)
+        })
+    }
+
+    pub(crate) fn sync_session_search_index_with<F>(
+        &self,
+        sessions: &[SessionMeta],
+        loader: F,
+    ) -> Result<SessionIndexSyncResult, AppError>
+    where
+        F: Fn(&SessionMeta, &str) -> Result<Vec<SessionMessage>, String>,
+    {
+        let known: HashMap<String, (i64, i64)> = {
+            let conn = lock_conn!(self.conn);
+            let mut stmt = conn
+                .prepare(
+                    "SELECT source_path, last_modified, index_version FROM session_search_files",
+                )
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            rows.collect::<Result<_, _>>()
+                .map_err(|e| AppError::Database(e.to_string()))?
+        };
+
+        let mut result = SessionIndexSyncResult::default();
+        let mut seen = HashSet::new();
+
+        for meta in sessions {
+            let Some(source_path) = meta.source_path.as_deref() else {
+                continue;
+            };
+            if !seen.insert(source_path.to_string()) {
+                continue;
+            }
+
+            let fingerprint = session_fingerprint(meta, source_path);
+            if known.get(source_path) == Some(&(fingerprint, INDEX_VERSION)) {
+                result.unchanged += 1;
+                continue;
+            }
+
+            // 在锁外解析会话文件，避免长时间占用数据库连接
+            let messages = match loader(meta, source_path) {
+                Ok(messages) => messages,
+                Err(e) => {
+                    log::warn!("[SESSION-SEARCH] 加载会话失败 ({source_path}): {e}");
+                    result.errors.push(format!("{source_path}: {e}"));
+                    continue;
+                }
+            };
+
+            self.replace_session_index(meta, source_path, fingerprint, &messages)?;
+            result.indexed += 1;
+        }
+
+        let stale: Vec<&String> = known.keys().filter(|p| !seen.contains(*p)).collect();
+        if !stale.is_empty() {
+            let mut conn = lock_conn!(self.conn);
+            let tx = conn
+                .transaction()
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            for source_path in stale {
+                delete_session_rows(&tx, source_path)?;
+                result.removed += 1;
+            }
+            tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        Ok(result)
+    }
+
+    /// 全文检索会话消息，返回按相关度排序的命中
+    pub fn search_sessions(
+        &self,
+        query: &SessionSearchQuery,
+    ) -> Result<Vec<SessionSearchHit>, AppError> {
+        let terms = parse_query_terms(&query.query);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conditions = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        let match_expr = build_match_expression(&terms);
+        if let Some(ref expr) = match_expr {
+            conditions.push("session_search_fts MATCH ?".to_string());
+            params.push(Box::new(expr.clone()));
+        }
+        // 短关键词无法走 trigram 索引，退化为 LIKE 过滤
+        for term in terms
+            .iter()
+            .filter(|t| t.chars().count() < MIN_MATCH_TERM_CHARS)
+        {
+            conditions.push("session_search_fts.content LIKE ? ESCAPE '\\'".to_string());
+            params.push(Box::new(format!("%{}%", escape_like(term))));
+        }
+        if let Some(ref provider_id) = query.provider_id {
+            conditions.push("f.provider_id = ?".to_string());
+            params.push(Box::new(provider_id.clone()));
+        }
+        if let Some(ref project_dir) = query.project_dir {
+            conditions.push("f.project_dir LIKE ? ESCAPE '\\'".to_string());
+            params.push(Box::new(format!("%{}%", escape_like(project_dir))));
+        }
+        if let Some(start) = query.start_date {
+            conditions.push("COALESCE(session_search_fts.ts, f.last_active_at) >= ?".to_string());
+            params.push(Box::new(start));
+        }
+        if let Some(end) = query.end_date {
+            conditions.push("COALESCE(session_search_fts.ts, f.last_active_at) <= ?".to_string());
+            params.push(Box::new(end));
+        }
+
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT);
+        params.push(Box::new(limit as i64));
+
+        // bm25() 越小越相关，取负值作为对外的 score
+        let (score_expr, order_by) = if match_expr.is_some() {
+            ("-bm25(session_search_fts)", "score DESC")
+        } else {
+            (
+                "0.0",
+                "COALESCE(session_search_fts.ts, f.last_active_at) DESC",
+            )
+        };
+        let sql = format!(
+            "SELECT f.provider_id, f.session_id, f.source_path, f.title, f.project_dir,
+                    session_search_fts.message_index, session_search_fts.role,
+                    session_search_fts.ts, session_search_fts.content, {score_expr} AS score
+             FROM session_search_fts
+             JOIN session_search_files f ON f.source_path = session_search_fts.source_path
+             WHERE {}
+             ORDER BY {order_by}
+             LIMIT ?",
+            conditions.join(" AND ")
+        );
+
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let rows = stmt
+            .query_map(param_refs.as_slice(), |row| {
+                let content: String = row.get(8)?;
+                Ok(SessionSearchHit {
+                    provider_id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    source_path: row.get(2)?,
+                    title: row.get(3)?,
+                    project_dir: row.get(4)?,
+                    message_index: row.get(5)?,
+                    role: row.get(6)?,
+                    ts: row.get(7)?,
+                    snippet: build_snippet(&content, &terms),
+                    score: row.get(9)?,
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 用最新消息替换单个会话的索引行
+    fn replace_session_index(
+        &self,
+        meta: &SessionMeta,
+        source_path: &str,
+        fingerprint: i64,
+        messages: &[SessionMessage],
+    ) -> Result<(), AppError> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        delete_session_rows(&tx, source_path)?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO session_search_fts (content, source_path, message_index, role, ts)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            for (index, message) in messages.iter().enumerate() {
+                if message.content.trim().is_empty() {
+                    continue;
+                }
+                stmt.execute(rusqlite::params![
+                    message.content,
+                    source_path,
+                    index as i64,
+                    message.role,
+                    message.ts
+                ])
+                .map_err(|e| AppError::Database(format!("写入会话索引失败: {e}")))?;
+            }
+        }
+        tx.execute(
+            "INSERT INTO session_search_files (
+                source_path, provider_id, session_id, title, project_dir,
+                last_active_at, last_modified, message_count, indexed_at, index_version
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            rusqlite::params![
+                source_path,
+                meta.provider_id,
+                meta.session_id,
+                meta.title,
+                meta.project_dir,
+                meta.last_active_at.or(meta.created_at),
+                fingerprint,
+                messages.len() as i64,
+                now,
+                INDEX_VERSION
+            ],
+        )
+        .map_err(|e| AppError::Database(format!("更新会话索引状态失败: {e}")))?;
+
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))
+    }
+}
+
+fn delete_session_rows(conn: &rusqlite::Connection, source_path: &str) -> Result<(), AppError> {
+    conn.execute(
+        "DELETE FROM session_search_fts WHERE source_path = ?1",
+        [source_path],
+    )
+    .map_err(|e| AppError::Database(format!("删除会话索引失败: {e}")))?;
+    conn.execute(
+        "DELETE FROM session_search_files WHERE source_path = ?1",
+        [source_path],
+    )
+    .map_err(|e| AppError::Database(format!("删除会话索引失败: {e}")))?;
+    Ok(())
+}
+
+/// 计算会话变更指纹
+fn session_fingerprint(meta: &SessionMeta, source_path: &str) -> i64 {
+    let active = meta.last_active_at.or(meta.created_at).unwrap_or(0);
+    if source_path.starts_with("sqlite:") {
+        return active;
+    }
+
+    let modified = std::fs::metadata(source_path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    modified.max(active)
+}
+
+/// 拆分关键词：按空白分隔并去掉引号，保持顺序去重
+fn parse_query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for raw in query.split_whitespace() {
+        let term = raw.replace('"', "");
+        if !term.is_empty() && !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+/// 生成 FTS5 MATCH 表达式：每个关键词作为短语检索，全部需要命中
+fn build_match_expression(terms: &[String]) -> Option<String> {
+    let phrases: Vec<String> = terms
+        .iter()
+        .filter(|t| t.chars().count() >= MIN_MATCH_TERM_CHARS)
+        .map(|t| format!("\"{t}\""))
+        .collect();
+
+    if phrases.is_empty() {
+        None
+    } else {
+        Some(phrases.join(" AND "))
+    }
+}
+
+/// 截取首个关键词命中位置附近的文本作为摘要（大小写不敏感）
+fn build_snippet(content: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let folded: Vec<char> = chars.iter().map(|c| fold_char(*c)).collect();
+
+    let hit = terms
+        .iter()
+        .filter_map(|term| {
+            let needle: Vec<char> = term.chars().map(fold_char).collect();
+            if needle.is_empty() || needle.len() > folded.len() {
+                return None;
+            }
+            folded
+                .windows(needle.len())
+                .position(|window| window == needle.as_slice())
+                .map(|pos| (pos, needle.len()))
+        })
+        .min_by_key(|(pos, _)| *pos);
+
+    let (start, end) = match hit {
+        Some((pos, len)) => (
+            pos.saturating_sub(SNIPPET_RADIUS_CHARS),
+            (pos + len + SNIPPET_RADIUS_CHARS).min(chars.len()),
+        ),
+        None => (0, (SNIPPET_RADIUS_CHARS * 2).min(chars.len())),
+    };
+
+    let body: String = chars[start..end].iter().collect();
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < chars.len() { "…" } else { "" };
+    format!("{prefix}{body}{suffix}")
+}
+
+fn fold_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(provider_id: &str, session_id: &str, source_path: &str, active: i64) -> SessionMeta {
+        SessionMeta {
+            provider_id: provider_id.to_string(),
+            session_id: session_id.to_string(),
+            title: Some(format!("{session_id} title")),
+            summary: None,
+            project_dir: Some(format!("/work/{provider_id}")),
+            created_at: None,
+            last_active_at: Some(active),
+            source_path: Some(source_path.to_string()),
+            resume_command: None,
+        }
+    }
+
+    fn message(role: &str, content: &str, ts: i64) -> SessionMessage {
+        SessionMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            ts: Some(ts),
+        }
+    }
+
+    fn fixtures() -> HashMap<String, Vec<SessionMessage>> {
+        HashMap::from([
+            (
+                "sqlite:/tmp/opencode.db:s1".to_string(),
+                vec![
+                    message("user", "Please look at the failing schema migration", 1_000),
+                    message("assistant", "", 1_001),
+                    message("assistant", "Fixed the migration bug in v18 to v19", 1_002),
+                ],
+            ),
+            (
+                "sqlite:/tmp/hermes.db:s2".to_string(),
+                vec![message("user", "修复数据库迁移问题 migration", 5_000)],
+            ),
+        ])
+    }
+
+    fn query(text: &str) -> SessionSearchQuery {
+        SessionSearchQuery {
+            query: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sync_indexes_incrementally_and_removes_stale_sessions() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let data = fixtures();
+        let loader = |_: &SessionMeta, path: &str| Ok(data.get(path).cloned().unwrap_or_default());
+
+        let sessions = vec![
+            meta("opencode", "s1", "sqlite:/tmp/opencode.db:s1", 10),
+            meta("hermes", "s2", "sqlite:/tmp/hermes.db:s2", 20),
+        ];
+        let first = db.sync_session_search_index_with(&sessions, loader)?;
+        assert_eq!((first.indexed, first.unchanged, first.removed), (2, 0, 0));
+
+        let second = db.sync_session_search_index_with(&sessions, loader)?;
+        assert_eq!(
+            (second.indexed, second.unchanged, second.removed),
+            (0, 2, 0)
+        );
+
+        // 指纹变化的会话重新索引，缺失的会话被清理
+        let updated = vec![meta("opencode", "s1", "sqlite:/tmp/opencode.db:s1", 11)];
+        let third = db.sync_session_search_index_with(&updated, loader)?;
+        assert_eq!((third.indexed, third.unchanged, third.removed), (1, 0, 1));
+
+        let hits = db.search_sessions(&query("migration"))?;
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|h| h.provider_id == "opencode"));
+        Ok(())
+    }
+
+    #[test]
+    fn sync_rebuilds_rows_from_older_index_version() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let data = fixtures();
+        let loader = |_: &SessionMeta, path: &str| Ok(data.get(path).cloned().unwrap_or_default());
+
+        let sessions = vec![meta("opencode", "s1", "sqlite:/tmp/opencode.db:s1", 10)];
+        db.sync_session_search_index_with(&sessions, loader)?;
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute("UPDATE session_search_files SET index_version = 0", [])
+                .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        let resync = db.sync_session_search_index_with(&sessions, loader)?;
+        assert_eq!((resync.indexed, resync.unchanged), (1, 0));
+        let again = db.sync_session_search_index_with(&sessions, loader)?;
+        assert_eq!((again.indexed, again.unchanged), (0, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn search_ranks_hits_and_reports_message_offset() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let data = fixtures();
+        db.sync_session_search_index_with(
+            &[
+                meta("opencode", "s1", "sqlite:/tmp/opencode.db:s1", 10),
+                meta("hermes", "s2", "sqlite:/tmp/hermes.db:s2", 20),
+            ],
+            |_, path| Ok(data.get(path).cloned().unwrap_or_default()),
+        )?;
+
+        let hits = db.search_sessions(&query("migration bug"))?;
+        assert_eq!(hits.len(), 1);
+        let hit = &hits[0];
+        assert_eq!(hit.session_id, "s1");
+        assert_eq!(
+            hit.message_index, 2,
+            "empty messages keep their original offset"
+        );
+        assert_eq!(hit.role, "assistant");
+        assert_eq!(hit.title.as_deref(), Some("s1 title"));
+        assert!(hit.snippet.contains("migration bug"));
+        assert!(hit.score > 0.0);
+
+        // 过滤条件
+        let mut filtered = query("migration");
+        filtered.provider_id = Some("hermes".to_string());
+        let hits = db.search_sessions(&filtered)?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "s2");
+
+        let mut filtered = query("migration");
+        filtered.project_dir = Some("opencode".to_string());
+        filtered.end_date = Some(1_000);
+        let hits = db.search_sessions(&filtered)?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_index, 0);
+
+        // 短中文关键词退化为 LIKE 匹配
+        let hits = db.search_sessions(&query("迁移"))?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].provider_id, "hermes");
+
+        // LIKE 通配符按字面匹配
+        assert!(db.search_sessions(&query("v_"))?.is_empty());
+        let mut filtered = query("migration");
+        filtered.project_dir = Some("%".to_string());
+        assert!(db.search_sessions(&filtered)?.is_empty());
+
+        assert!(db.search_sessions(&query("   "))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_records_sync_time_and_skips_when_busy() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let _busy = SYNC_LOCK.lock().unwrap();
+            assert!(db.refresh_session_search_index(&[], false)?.is_none());
+        }
+        assert!(db.refresh_session_search_index(&[], false)?.is_some());
+        assert_eq!(index_freshness(), IndexFreshness::Fresh);
+
+        LAST_SYNC_AT.store(unix_secs() - AUTO_SYNC_INTERVAL_SECS, Ordering::Relaxed);
+        assert_eq!(index_freshness(), IndexFreshness::Stale);
+        Ok(())
+    }
+
+    #[test]
+    fn match_expression_quotes_terms_and_skips_short_ones() {
+        let terms = parse_query_terms("schema \"mig\"ration db schema");
+        assert_eq!(terms, vec!["schema", "migration", "db"]);
+        assert_eq!(
+            build_match_expression(&terms).as_deref(),
+            Some("\"schema\" AND \"migration\"")
+        );
+        assert_eq!(build_match_expression(&parse_query_terms("db 迁移")), None);
+    }
+
+    #[test]
+    fn snippet_centers_on_first_hit() {
+        let content = format!("{}Found The Bug\nhere{}", "a".repeat(100), "b".repeat(100));
+        let snippet = build_snippet(&content, &["the bug".to_string()]);
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("Found The Bug here"));
+        assert_eq!(
+            build_snippet("short text", &["zzz".to_string()]),
+            "short text"
+        );
+    }
+}
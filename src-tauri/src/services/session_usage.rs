@@ -384,6 +384,7 @@ fn insert_session_log_entry(
         cache_creation_tokens: msg.cache_creation_tokens,
         model: Some(msg.model.clone()),
         message_id: None,
+        ..Default::default()
     };
 
     let pricing = find_model_pricing_for_session(&conn, &msg.model);
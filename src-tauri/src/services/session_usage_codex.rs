@@ -473,6 +473,7 @@ fn insert_codex_session_entry(
         cache_creation_tokens: 0,
         model: Some(model.to_string()),
         message_id: None,
+        ..Default::default()
     };
 
     let pricing = find_codex_pricing(&conn, model);
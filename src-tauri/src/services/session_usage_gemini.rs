@@ -262,6 +262,7 @@ fn insert_gemini_session_entry(
         cache_creation_tokens: 0,
         model: Some(model.to_string()),
         message_id: None,
+        ..Default::default()
     };
 
     let pricing = find_gemini_pricing(&conn, model);
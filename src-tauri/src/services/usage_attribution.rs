@@ -0,0 +1,486 @@
+//! 按项目 / 会话归因使用量
+//!
+//! `proxy_request_logs.session_id` 同时来自代理请求（`data_source = 'proxy'`）与会话日志同步
+//! （`session_log` / `codex_session` / `gemini_session`）。这里先按会话聚合明细，
+//! 再与 `session_manager::scan_sessions` 扫描出的 `SessionMeta` 关联，得到会话标题与项目目录，
+//! 最后按项目目录汇总。
+//!
+//! 日汇总表 `usage_daily_rollups` 不含会话信息，已归档的历史明细不参与归因。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::session_manager::SessionMeta;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// 默认返回的高成本会话数量
+pub const DEFAULT_TOP_SESSIONS: usize = 10;
+
+/// Codex 代理请求的 session_id 前缀（见 `proxy::session::extract_codex_session`）
+const CODEX_PROXY_SESSION_PREFIX: &str = "codex_";
+
+/// 单个会话的使用量
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUsage {
+    pub app_type: String,
+    pub session_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    pub request_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub total_cost: String,
+    pub first_request_at: i64,
+    pub last_request_at: i64,
+    /// 该会话明细涉及的数据来源（proxy / session_log / codex_session / gemini_session）
+    pub data_sources: Vec<String>,
+}
+
+/// 单个项目目录的使用量（`project_dir` 为空表示无法关联到本地会话）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectUsage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    pub session_count: u64,
+    pub request_count: u64,
+    pub total_tokens: u64,
+    pub total_cost: String,
+    pub last_request_at: i64,
+}
+
+/// 项目 / 会话归因结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageAttribution {
+    /// 按成本降序
+    pub projects: Vec<ProjectUsage>,
+    /// 按最近请求时间降序
+    pub sessions: Vec<SessionUsage>,
+    /// 成本最高的前 N 个会话
+    pub top_sessions: Vec<SessionUsage>,
+    /// 没有 session_id 的请求（无法归因）
+    pub unattributed_requests: u64,
+    pub unattributed_cost: String,
+}
+
+/// 按 (app_type, session_id) 聚合的明细行
+#[derive(Debug, Clone)]
+struct SessionUsageRow {
+    app_type: String,
+    session_id: String,
+    request_count: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_tokens: u64,
+    cache_creation_tokens: u64,
+    total_cost: f64,
+    first_request_at: i64,
+    last_request_at: i64,
+    data_sources: Vec<String>,
+}
+
+/// 未归因明细汇总
+#[derive(Debug, Clone, Default)]
+struct UnattributedUsage {
+    request_count: u64,
+    total_cost: f64,
+}
+
+impl Database {
+    /// 按会话聚合请求明细
+    ///
+    /// 返回 (会话聚合行, 无 session_id 的请求汇总)
+    fn query_session_usage(
+        &self,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+        app_type: Option<&str>,
+    ) -> Result<(Vec<SessionUsageRow>, UnattributedUsage), AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut conditions = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        if let Some(start) = start_date {
+            conditions.push("created_at >= ?");
+            params.push(Box::new(start));
+        }
+        if let Some(end) = end_date {
+            conditions.push("created_at <= ?");
+            params.push(Box::new(end));
+        }
+        if let Some(at) = app_type {
+            conditions.push("app_type = ?");
+            params.push(Box::new(at.to_string()));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("AND {}", conditions.join(" AND "))
+        };
+        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+        let sql = format!(
+            "SELECT app_type, session_id,
+                COUNT(*),
+                COALESCE(SUM(input_tokens), 0),
+                COALESCE(SUM(output_tokens), 0),
+                COALESCE(SUM(cache_read_tokens), 0),
+                COALESCE(SUM(cache_creation_tokens), 0),
+                COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0),
+                MIN(created_at),
+                MAX(created_at),
+                GROUP_CONCAT(DISTINCT COALESCE(data_source, 'proxy'))
+            FROM proxy_request_logs
+            WHERE session_id IS NOT NULL AND session_id != '' {where_clause}
+            GROUP BY app_type, session_id"
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(param_refs.as_slice(), |row| {
+            let sources: Option<String> = row.get(10)?;
+            let mut data_sources: Vec<String> = sources
+                .unwrap_or_default()
+                .split(',')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect();
+            data_sources.sort();
+            Ok(SessionUsageRow {
+                app_type: row.get(0)?,
+                session_id: row.get(1)?,
+                request_count: row.get::<_, i64>(2)? as u64,
+                input_tokens: row.get::<_, i64>(3)? as u64,
+                output_tokens: row.get::<_, i64>(4)? as u64,
+                cache_read_tokens: row.get::<_, i64>(5)? as u64,
+                cache_creation_tokens: row.get::<_, i64>(6)? as u64,
+                total_cost: row.get(7)?,
+                first_request_at: row.get(8)?,
+                last_request_at: row.get(9)?,
+                data_sources,
+            })
+        })?;
+        let mut sessions = Vec::new();
+        for row in rows {
+            sessions.push(row?);
+        }
+
+        let unattributed = conn.query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0)
+                FROM proxy_request_logs
+                WHERE (session_id IS NULL OR session_id = '') {where_clause}"
+            ),
+            param_refs.as_slice(),
+            |row| {
+                Ok(UnattributedUsage {
+                    request_count: row.get::<_, i64>(0)? as u64,
+                    total_cost: row.get(1)?,
+                })
+            },
+        )?;
+
+        Ok((sessions, unattributed))
+    }
+
+    /// 获取按项目 / 会话归因的使用量
+    ///
+    /// `sessions` 通常来自 `session_manager::scan_sessions()`，用于补全标题与项目目录
+    pub fn get_usage_attribution(
+        &self,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+        app_type: Option<&str>,
+        sessions: &[SessionMeta],
+        top_n: usize,
+    ) -> Result<UsageAttribution, AppError> {
+        let (rows, unattributed) = self.query_session_usage(start_date, end_date, app_type)?;
+        Ok(attribute_usage(rows, unattributed, sessions, top_n))
+    }
+}
+
+/// 统一不同来源的 session_id，使代理日志与会话文件中的 ID 一致
+fn normalize_session_id(app_type: &str, session_id: &str) -> String {
+    if app_type == "codex" {
+        if let Some(stripped) = session_id.strip_prefix(CODEX_PROXY_SESSION_PREFIX) {
+            return stripped.to_string();
+        }
+    }
+    session_id.to_string()
+}
+
+/// 规范化项目目录（去掉末尾路径分隔符），便于同一项目的会话合并
+fn normalize_project_dir(dir: &str) -> Option<String> {
+    let trimmed = dir.trim();
+    let normalized = trimmed.trim_end_matches(['/', '\\']);
+    if normalized.is_empty() {
+        // 根目录本身
+        return (!trimmed.is_empty()).then(|| trimmed.to_string());
+    }
+    Some(normalized.to_string())
+}
+
+fn attribute_usage(
+    rows: Vec<SessionUsageRow>,
+    unattributed: UnattributedUsage,
+    metas: &[SessionMeta],
+    top_n: usize,
+) -> UsageAttribution {
+    // 优先匹配同一应用的会话，其次按 ID 匹配（如 OpenCode 经代理时 app_type 不同）
+    let mut by_app: HashMap<(&str, &str), &SessionMeta> = HashMap::new();
+    let mut by_id: HashMap<&str, &SessionMeta> = HashMap::new();
+    for meta in metas {
+        by_app.insert((meta.provider_id.as_str(), meta.session_id.as_str()), meta);
+        by_id.entry(meta.session_id.as_str()).or_insert(meta);
+    }
+
+    // 合并同一会话的不同 ID 形式（如 Codex 代理日志的 `codex_` 前缀）
+    let mut merged: HashMap<(String, String), (SessionUsageRow, BTreeSet<String>)> = HashMap::new();
+    for row in rows {
+        let session_id = normalize_session_id(&row.app_type, &row.session_id);
+        let key = (row.app_type.clone(), session_id.clone());
+        match merged.get_mut(&key) {
+            Some((acc, sources)) => {
+                acc.request_count += row.request_count;
+                acc.input_tokens += row.input_tokens;
+                acc.output_tokens += row.output_tokens;
+                acc.cache_read_tokens += row.cache_read_tokens;
+                acc.cache_creation_tokens += row.cache_creation_tokens;
+                acc.total_cost += row.total_cost;
+                acc.first_request_at = acc.first_request_at.min(row.first_request_at);
+                acc.last_request_at = acc.last_request_at.max(row.last_request_at);
+                sources.extend(row.data_sources);
+            }
+            None => {
+                let sources = row.data_sources.iter().cloned().collect();
+                merged.insert(key, (SessionUsageRow { session_id, ..row }, sources));
+            }
+        }
+    }
+
+    let mut sessions_with_cost: Vec<(SessionUsage, f64)> = merged
+        .into_values()
+        .map(|(row, sources)| {
+            let meta = by_app
+                .get(&(row.app_type.as_str(), row.session_id.as_str()))
+                .or_else(|| by_id.get(row.session_id.as_str()))
+                .copied();
+            let usage = SessionUsage {
+                title: meta.and_then(|m| m.title.clone()),
+                project_dir: meta
+                    .and_then(|m| m.project_dir.as_deref())
+                    .and_then(normalize_project_dir),
+                app_type: row.app_type,
+                session_id: row.session_id,
+                request_count: row.request_count,
+                input_tokens: row.input_tokens,
+                output_tokens: row.output_tokens,
+                cache_read_tokens: row.cache_read_tokens,
+                cache_creation_tokens: row.cache_creation_tokens,
+                total_cost: format!("{:.6}", row.total_cost),
+                first_request_at: row.first_request_at,
+                last_request_at: row.last_request_at,
+                data_sources: sources.into_iter().collect(),
+            };
+            (usage, row.total_cost)
+        })
+        .collect();
+
+    // 按项目目录汇总
+    let mut projects: HashMap<Option<String>, (ProjectUsage, f64)> = HashMap::new();
+    for (session, cost) in &sessions_with_cost {
+        let (project, project_cost) =
+            projects
+                .entry(session.project_dir.clone())
+                .or_insert_with(|| {
+                    (
+                        ProjectUsage {
+                            project_dir: session.project_dir.clone(),
+                            session_count: 0,
+                            request_count: 0,
+                            total_tokens: 0,
+                            total_cost: String::new(),
+                            last_request_at: 0,
+                        },
+                        0.0,
+                    )
+                });
+        project.session_count += 1;
+        project.request_count += session.request_count;
+        project.total_tokens += session.input_tokens + session.output_tokens;
+        project.last_request_at = project.last_request_at.max(session.last_request_at);
+        *project_cost += cost;
+    }
+    let mut projects: Vec<(ProjectUsage, f64)> = projects.into_values().collect();
+    projects.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then_with(|| a.0.project_dir.cmp(&b.0.project_dir))
+    });
+    let projects = projects
+        .into_iter()
+        .map(|(mut project, cost)| {
+            project.total_cost = format!("{cost:.6}");
+            project
+        })
+        .collect();
+
+    sessions_with_cost.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then_with(|| b.0.last_request_at.cmp(&a.0.last_request_at))
+    });
+    let top_sessions = sessions_with_cost
+        .iter()
+        .take(top_n)
+        .map(|(session, _)| session.clone())
+        .collect();
+
+    let mut sessions: Vec<SessionUsage> = sessions_with_cost.into_iter().map(|(s, _)| s).collect();
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.last_request_at));
+
+    UsageAttribution {
+        projects,
+        sessions,
+        top_sessions,
+        unattributed_requests: unattributed.request_count,
+        unattributed_cost: format!("{:.6}", unattributed.total_cost),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn meta(provider_id: &str, session_id: &str, title: &str, project_dir: &str) -> SessionMeta {
+        SessionMeta {
+            provider_id: provider_id.to_string(),
+            session_id: session_id.to_string(),
+            title: Some(title.to_string()),
+            summary: None,
+            project_dir: Some(project_dir.to_string()),
+            created_at: None,
+            last_active_at: None,
+            source_path: None,
+            resume_command: None,
+        }
+    }
+
+    fn insert_log(
+        db: &Database,
+        request_id: &str,
+        app_type: &str,
+        session_id: Option<&str>,
+        cost: &str,
+        created_at: i64,
+        data_source: &str,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model,
+                input_tokens, output_tokens, total_cost_usd,
+                latency_ms, status_code, session_id, created_at, data_source
+            ) VALUES (?, 'p1', ?, 'm', 100, 50, ?, 10, 200, ?, ?, ?)",
+            params![
+                request_id,
+                app_type,
+                cost,
+                session_id,
+                created_at,
+                data_source
+            ],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn attributes_proxy_and_session_log_usage_to_projects() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        insert_log(&db, "r1", "claude", Some("s-a"), "0.5", 100, "proxy")?;
+        insert_log(&db, "r2", "claude", Some("s-a"), "0.25", 200, "session_log")?;
+        insert_log(&db, "r3", "codex", Some("codex_s-b"), "1.0", 300, "proxy")?;
+        insert_log(&db, "r4", "codex", Some("s-b"), "0.5", 400, "codex_session")?;
+        insert_log(&db, "r5", "claude", Some("s-c"), "0.1", 500, "proxy")?;
+        insert_log(&db, "r6", "claude", Some("s-orphan"), "0.05", 600, "proxy")?;
+        insert_log(&db, "r7", "claude", None, "0.3", 700, "proxy")?;
+
+        let metas = vec![
+            meta("claude", "s-a", "Fix login", "/work/repo-x/"),
+            meta("codex", "s-b", "Add tests", "/work/repo-y"),
+            meta("claude", "s-c", "Docs", "/work/repo-x"),
+        ];
+        let result = db.get_usage_attribution(None, None, None, &metas, 2)?;
+
+        let session_ids: Vec<&str> = result
+            .sessions
+            .iter()
+            .map(|s| s.session_id.as_str())
+            .collect();
+        assert_eq!(session_ids, vec!["s-orphan", "s-c", "s-b", "s-a"]);
+
+        let codex = result
+            .sessions
+            .iter()
+            .find(|s| s.session_id == "s-b")
+            .unwrap();
+        assert_eq!(codex.request_count, 2);
+        assert_eq!(codex.total_cost, "1.500000");
+        assert_eq!(codex.title.as_deref(), Some("Add tests"));
+        assert_eq!(codex.data_sources, vec!["codex_session", "proxy"]);
+        assert_eq!(codex.first_request_at, 300);
+        assert_eq!(codex.last_request_at, 400);
+
+        let projects: Vec<(Option<&str>, u64, &str)> = result
+            .projects
+            .iter()
+            .map(|p| {
+                (
+                    p.project_dir.as_deref(),
+                    p.session_count,
+                    p.total_cost.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            projects,
+            vec![
+                (Some("/work/repo-y"), 1, "1.500000"),
+                (Some("/work/repo-x"), 2, "0.850000"),
+                (None, 1, "0.050000"),
+            ]
+        );
+
+        let top: Vec<&str> = result
+            .top_sessions
+            .iter()
+            .map(|s| s.session_id.as_str())
+            .collect();
+        assert_eq!(top, vec!["s-b", "s-a"]);
+
+        assert_eq!(result.unattributed_requests, 1);
+        assert_eq!(result.unattributed_cost, "0.300000");
+        Ok(())
+    }
+
+    #[test]
+    fn respects_date_and_app_filters() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        insert_log(&db, "r1", "claude", Some("s-a"), "0.5", 100, "proxy")?;
+        insert_log(&db, "r2", "claude", Some("s-a"), "0.25", 200, "session_log")?;
+        insert_log(&db, "r3", "codex", Some("s-b"), "1.0", 200, "codex_session")?;
+        insert_log(&db, "r4", "claude", None, "0.3", 50, "proxy")?;
+
+        let result = db.get_usage_attribution(Some(150), None, Some("claude"), &[], 10)?;
+        assert_eq!(result.sessions.len(), 1);
+        assert_eq!(result.sessions[0].request_count, 1);
+        assert_eq!(result.sessions[0].total_cost, "0.250000");
+        assert_eq!(result.projects.len(), 1);
+        assert!(result.projects[0].project_dir.is_none());
+        assert_eq!(result.unattributed_requests, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,670 @@
+//! 使用量数据导出
+//!
+//! 按 `LogFilters` 导出 `proxy_request_logs` 明细与 `usage_daily_rollups` 日汇总，
+//! 供财务按供应商 / 模型 / 日期做成本核算。
+//!
+//! - CSV / JSONL：明细与日汇总写入同一文件，以 `record_type` 区分（`request` / `daily_rollup`），
+//!   逐页从数据库读取并写出，不在内存中缓存完整结果集
+//! - SQLite：通过单独的连接写入独立文件的 `request_logs` 与 `daily_rollups` 表
+//!
+//! 数据按页读取，每页单独持有数据库锁，导出期间代理仍可写入请求日志。
+//!
+//! 日汇总只记录按天聚合的数据：仅导出完整落在时间范围内的日期，且指定状态码过滤时不导出。
+
+use crate::config::get_app_config_dir;
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::pricing_io::csv_escape;
+use crate::services::usage_stats::{
+    compute_rollup_date_bounds, log_filter_clause, provider_name_coalesce,
+    push_rollup_date_filters, LogFilters,
+};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageExportFormat {
+    Csv,
+    Jsonl,
+    Sqlite,
+}
+
+impl UsageExportFormat {
+    /// 根据文件扩展名推断导出格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("csv") => Some(UsageExportFormat::Csv),
+            Some("jsonl" | "ndjson") => Some(UsageExportFormat::Jsonl),
+            Some("db" | "sqlite" | "sqlite3") => Some(UsageExportFormat::Sqlite),
+            _ => None,
+        }
+    }
+}
+
+/// 导出结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageExportSummary {
+    pub format: UsageExportFormat,
+    pub file_path: String,
+    pub request_logs: u64,
+    pub daily_rollups: u64,
+}
+
+/// 导出列（明细与日汇总共用，日汇总中不适用的列为空）
+const EXPORT_COLUMNS: [&str; 32] = [
+    "record_type",
+    "date",
+    "created_at",
+    "request_id",
+    "app_type",
+    "provider_id",
+    "provider_name",
+    "model",
+    "request_model",
+    "status_code",
+    "request_count",
+    "success_count",
+    "input_tokens",
+    "output_tokens",
+    "cache_read_tokens",
+    "cache_creation_tokens",
+    "cache_creation_1h_tokens",
+    "reasoning_tokens",
+    "input_cost_usd",
+    "output_cost_usd",
+    "cache_read_cost_usd",
+    "cache_creation_cost_usd",
+    "total_cost_usd",
+    "cost_multiplier",
+    "latency_ms",
+    "first_token_ms",
+    "duration_ms",
+    "is_streaming",
+    "service_tier",
+    "session_id",
+    "data_source",
+    "error_message",
+];
+
+/// 每页读取的行数；每页单独获取数据库锁，导出大量数据时不会长时间阻塞代理写日志
+const PAGE_SIZE: usize = 1000;
+
+/// 一段带参数的导出查询，按分页键做 keyset 分页
+struct ExportQuery {
+    /// 导出列表达式（与 `EXPORT_COLUMNS` 一一对应）
+    columns: String,
+    /// FROM 与 JOIN 子句
+    from: String,
+    /// WHERE 子句（无条件时为空）
+    where_clause: String,
+    params: Vec<Box<dyn rusqlite::ToSql>>,
+    /// 分页键：组合唯一，同时决定导出顺序
+    keys: &'static [&'static str],
+}
+
+/// 一页导出行，以及最后一行的分页键（用于读取下一页）
+type ExportPage = (Vec<Vec<Value>>, Option<Vec<SqlValue>>);
+
+impl ExportQuery {
+    /// 读取 `after` 之后的一页
+    fn page(&self, conn: &Connection, after: Option<&[SqlValue]>) -> Result<ExportPage, AppError> {
+        let keys = self.keys.join(", ");
+        let mut where_clause = self.where_clause.clone();
+        if after.is_some() {
+            let placeholders = vec!["?"; self.keys.len()].join(", ");
+            let keyset = format!("({keys}) > ({placeholders})");
+            where_clause = if where_clause.is_empty() {
+                format!("WHERE {keyset}")
+            } else {
+                format!("{where_clause} AND {keyset}")
+            };
+        }
+        let sql = format!(
+            "SELECT {}, {keys} {} {where_clause} ORDER BY {keys} LIMIT {PAGE_SIZE}",
+            self.columns, self.from
+        );
+
+        let mut params: Vec<&dyn rusqlite::ToSql> =
+            self.params.iter().map(|p| p.as_ref()).collect();
+        if let Some(after) = after {
+            params.extend(after.iter().map(|v| v as &dyn rusqlite::ToSql));
+        }
+
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(params.as_slice())?;
+        let mut page = Vec::new();
+        let mut last_key = None;
+        while let Some(row) = rows.next()? {
+            page.push(
+                (0..EXPORT_COLUMNS.len())
+                    .map(|index| row.get_ref(index).map(value_to_json))
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
+            last_key = Some(
+                (0..self.keys.len())
+                    .map(|index| row.get::<_, SqlValue>(EXPORT_COLUMNS.len() + index))
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
+        }
+        Ok((page, last_key))
+    }
+}
+
+impl Database {
+    /// 按过滤条件导出请求日志与日汇总到文件
+    pub fn export_usage(
+        &self,
+        filters: &LogFilters,
+        format: UsageExportFormat,
+        path: &Path,
+    ) -> Result<UsageExportSummary, AppError> {
+        ensure_not_live_database(path, &get_app_config_dir().join("cc-switch.db"))?;
+        let logs = request_log_query(filters);
+        let rollups = rollup_query(filters)?;
+
+        // 先写入同目录下的临时文件，成功后再替换目标文件，失败时不破坏已有文件
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| AppError::InvalidInput(format!("无效的导出路径: {}", path.display())))?;
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            file_name.to_string_lossy(),
+            uuid::Uuid::new_v4()
+        ));
+        let result = match format {
+            UsageExportFormat::Csv | UsageExportFormat::Jsonl => {
+                self.export_lines(&logs, rollups.as_ref(), format, &temp_path)
+            }
+            UsageExportFormat::Sqlite => self.export_sqlite(&logs, rollups.as_ref(), &temp_path),
+        }
+        .and_then(|counts| {
+            std::fs::rename(&temp_path, path).map_err(|e| AppError::io(path, e))?;
+            Ok(counts)
+        });
+        let (request_logs, daily_rollups) = result.inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp_path);
+        })?;
+
+        log::info!(
+            "已导出使用量数据到 {}：明细 {request_logs} 条，日汇总 {daily_rollups} 条",
+            path.display()
+        );
+        Ok(UsageExportSummary {
+            format,
+            file_path: path.display().to_string(),
+            request_logs,
+            daily_rollups,
+        })
+    }
+
+    /// 导出为 CSV / JSONL
+    fn export_lines(
+        &self,
+        logs: &ExportQuery,
+        rollups: Option<&ExportQuery>,
+        format: UsageExportFormat,
+        path: &Path,
+    ) -> Result<(u64, u64), AppError> {
+        let file = File::create(path).map_err(|e| AppError::io(path, e))?;
+        let mut writer = BufWriter::new(file);
+        if format == UsageExportFormat::Csv {
+            writeln!(writer, "{}", EXPORT_COLUMNS.join(",")).map_err(|e| AppError::io(path, e))?;
+        }
+        let mut write_line = |values: Vec<Value>| -> Result<(), AppError> {
+            let line = format_line(values, format)?;
+            writeln!(writer, "{line}").map_err(|e| AppError::io(path, e))
+        };
+        let request_logs = self.for_each_export_row(logs, &mut write_line)?;
+        let daily_rollups = match rollups {
+            Some(query) => self.for_each_export_row(query, &mut write_line)?,
+            None => 0,
+        };
+        writer.flush().map_err(|e| AppError::io(path, e))?;
+        Ok((request_logs, daily_rollups))
+    }
+
+    /// 分页读取查询结果并逐行交给 `emit`，返回行数
+    fn for_each_export_row(
+        &self,
+        query: &ExportQuery,
+        emit: &mut impl FnMut(Vec<Value>) -> Result<(), AppError>,
+    ) -> Result<u64, AppError> {
+        let mut after: Option<Vec<SqlValue>> = None;
+        let mut count = 0;
+        loop {
+            let (rows, last_key) = {
+                let conn = lock_conn!(self.conn);
+                query.page(&conn, after.as_deref())?
+            };
+            let done = rows.len() < PAGE_SIZE;
+            for row in rows {
+                emit(row)?;
+                count += 1;
+            }
+            if done {
+                return Ok(count);
+            }
+            after = last_key;
+        }
+    }
+
+    /// 导出为独立 SQLite 文件
+    ///
+    /// 通过单独的连接写入导出文件，从主数据库分页读取，不在主连接上 ATTACH。
+    fn export_sqlite(
+        &self,
+        logs: &ExportQuery,
+        rollups: Option<&ExportQuery>,
+        path: &Path,
+    ) -> Result<(u64, u64), AppError> {
+        let mut export = Connection::open(path)
+            .map_err(|e| AppError::Database(format!("创建导出数据库失败: {e}")))?;
+        let tx = export.transaction()?;
+
+        let columns = EXPORT_COLUMNS.join(", ");
+        let placeholders = vec!["?"; EXPORT_COLUMNS.len()].join(", ");
+        let copy = |table: &str, query: Option<&ExportQuery>| -> Result<u64, AppError> {
+            tx.execute(&format!("CREATE TABLE {table} ({columns})"), [])?;
+            let Some(query) = query else {
+                return Ok(0);
+            };
+            let mut insert = tx.prepare(&format!("INSERT INTO {table} VALUES ({placeholders})"))?;
+            self.for_each_export_row(query, &mut |values| {
+                insert.execute(rusqlite::params_from_iter(values.iter().map(json_to_sql)))?;
+                Ok(())
+            })
+        };
+        let request_logs = copy("request_logs", Some(logs))?;
+        let daily_rollups = copy("daily_rollups", rollups)?;
+
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("提交导出数据库失败: {e}")))?;
+        Ok((request_logs, daily_rollups))
+    }
+}
+
+/// 拒绝指向正在使用的数据库（及其 WAL / 日志文件）的导出路径
+fn ensure_not_live_database(path: &Path, live_db: &Path) -> Result<(), AppError> {
+    let (Some(target), Some(live)) = (normalize_path(path), normalize_path(live_db)) else {
+        return Ok(());
+    };
+    let live = live.to_string_lossy().into_owned();
+    let target = target.to_string_lossy();
+    let is_live = ["", "-wal", "-shm", "-journal"]
+        .iter()
+        .any(|suffix| target == format!("{live}{suffix}"));
+    if is_live {
+        return Err(AppError::InvalidInput(format!(
+            "不能导出到正在使用的数据库文件: {}",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// 规范化路径；文件不存在时规范化其父目录
+fn normalize_path(path: &Path) -> Option<PathBuf> {
+    path.canonicalize().ok().or_else(|| {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        Some(parent.canonicalize().ok()?.join(path.file_name()?))
+    })
+}
+
+fn request_log_query(filters: &LogFilters) -> ExportQuery {
+    let (where_clause, params) = log_filter_clause(filters);
+    let provider_name = provider_name_coalesce("l", "p");
+    ExportQuery {
+        columns: format!(
+            "'request', date(l.created_at, 'unixepoch', 'localtime'), l.created_at,
+             l.request_id, l.app_type, l.provider_id, {provider_name}, l.model,
+             l.request_model, l.status_code, 1,
+             CASE WHEN l.status_code >= 200 AND l.status_code < 300 THEN 1 ELSE 0 END,
+             l.input_tokens, l.output_tokens, l.cache_read_tokens, l.cache_creation_tokens,
+             l.cache_creation_1h_tokens, l.reasoning_tokens,
+             l.input_cost_usd, l.output_cost_usd, l.cache_read_cost_usd,
+             l.cache_creation_cost_usd, l.total_cost_usd, l.cost_multiplier,
+             l.latency_ms, l.first_token_ms, l.duration_ms, l.is_streaming,
+             l.service_tier, l.session_id, l.data_source, l.error_message"
+        ),
+        from: "FROM proxy_request_logs l
+             LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type"
+            .to_string(),
+        where_clause,
+        params,
+        keys: &["l.created_at", "l.request_id"],
+    }
+}
+
+/// 日汇总查询；日汇总不含状态码，指定状态码过滤时返回 None
+fn rollup_query(filters: &LogFilters) -> Result<Option<ExportQuery>, AppError> {
+    if filters.status_code.is_some() {
+        return Ok(None);
+    }
+
+    let mut conditions = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    if let Some(ref app_type) = filters.app_type {
+        conditions.push("r.app_type = ?".to_string());
+        params.push(Box::new(app_type.clone()));
+    }
+    if let Some(ref provider_name) = filters.provider_name {
+        conditions.push("p.name LIKE ?".to_string());
+        params.push(Box::new(format!("%{provider_name}%")));
+    }
+    if let Some(ref model) = filters.model {
+        conditions.push("r.model LIKE ?".to_string());
+        params.push(Box::new(format!("%{model}%")));
+    }
+    let bounds = compute_rollup_date_bounds(filters.start_date, filters.end_date)?;
+    push_rollup_date_filters(&mut conditions, &mut params, "r.date", &bounds);
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let provider_name = provider_name_coalesce("r", "p");
+    Ok(Some(ExportQuery {
+        columns: format!(
+            "'daily_rollup', r.date, NULL, NULL, r.app_type, r.provider_id,
+             {provider_name}, r.model, NULL, NULL, r.request_count, r.success_count,
+             r.input_tokens, r.output_tokens, r.cache_read_tokens, r.cache_creation_tokens,
+             NULL, NULL, NULL, NULL, NULL, NULL, r.total_cost_usd, NULL,
+             r.avg_latency_ms, NULL, NULL, NULL, NULL, NULL, NULL, NULL"
+        ),
+        from: "FROM usage_daily_rollups r
+             LEFT JOIN providers p ON r.provider_id = p.id AND r.app_type = p.app_type"
+            .to_string(),
+        where_clause,
+        params,
+        keys: &["r.date", "r.app_type", "r.provider_id", "r.model"],
+    }))
+}
+
+/// 把一行导出值格式化为 CSV 行或 JSON 对象
+fn format_line(values: Vec<Value>, format: UsageExportFormat) -> Result<String, AppError> {
+    Ok(match format {
+        UsageExportFormat::Csv => values
+            .iter()
+            .map(|value| match value {
+                Value::Null => String::new(),
+                Value::String(s) => csv_escape(s),
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(","),
+        _ => {
+            let object: Map<String, Value> = EXPORT_COLUMNS
+                .iter()
+                .zip(values)
+                .filter(|(_, value)| !value.is_null())
+                .map(|(column, value)| (column.to_string(), value))
+                .collect();
+            serde_json::to_string(&object)
+                .map_err(|e| AppError::Message(format!("序列化导出数据失败: {e}")))?
+        }
+    })
+}
+
+fn value_to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) | ValueRef::Blob(t) => {
+            Value::String(String::from_utf8_lossy(t).into_owned())
+        }
+    }
+}
+
+/// `value_to_json` 的逆转换，写入导出数据库时保留列的原始类型
+fn json_to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Number(n) => n
+            .as_i64()
+            .map(SqlValue::Integer)
+            .or_else(|| n.as_f64().map(SqlValue::Real))
+            .unwrap_or(SqlValue::Null),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        _ => SqlValue::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn seed(db: &Database) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        for (request_id, app_type, model, status, cost, created_at) in [
+            (
+                "req-1",
+                "claude",
+                "claude-sonnet-4-5",
+                200,
+                "0.01",
+                1_700_000_000i64,
+            ),
+            (
+                "req-2",
+                "claude",
+                "claude-haiku-4-5",
+                500,
+                "0",
+                1_700_000_100,
+            ),
+            ("req-3", "codex", "gpt-5", 200, "0.02", 1_700_000_200),
+        ] {
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, input_tokens, output_tokens,
+                    total_cost_usd, latency_ms, status_code, error_message, created_at
+                ) VALUES (?1, 'p1', ?2, ?3, 100, 50, ?4, 120, ?5, ?6, ?7)",
+                params![
+                    request_id,
+                    app_type,
+                    model,
+                    cost,
+                    status,
+                    (status != 200).then_some("upstream, \"error\""),
+                    created_at
+                ],
+            )?;
+        }
+        conn.execute(
+            "INSERT INTO usage_daily_rollups (
+                date, app_type, provider_id, model, request_count, success_count,
+                input_tokens, output_tokens, total_cost_usd, avg_latency_ms
+            ) VALUES ('2023-10-01', 'claude', 'p1', 'claude-sonnet-4-5', 10, 9, 1000, 500, '0.5', 200)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn exports_csv_with_requests_and_rollups() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        seed(&db)?;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.csv");
+
+        let filters = LogFilters {
+            app_type: Some("claude".to_string()),
+            ..Default::default()
+        };
+        let summary = db.export_usage(&filters, UsageExportFormat::Csv, &path)?;
+        assert_eq!(summary.request_logs, 2);
+        assert_eq!(summary.daily_rollups, 1);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], EXPORT_COLUMNS.join(","));
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("request,"));
+        assert!(lines[2].contains("\"upstream, \"\"error\"\"\""));
+        assert!(lines[3].starts_with("daily_rollup,2023-10-01,"));
+        Ok(())
+    }
+
+    #[test]
+    fn exports_jsonl_and_skips_rollups_for_status_filter() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        seed(&db)?;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.jsonl");
+
+        let filters = LogFilters {
+            status_code: Some(200),
+            ..Default::default()
+        };
+        let summary = db.export_usage(&filters, UsageExportFormat::Jsonl, &path)?;
+        assert_eq!(summary.request_logs, 2);
+        assert_eq!(summary.daily_rollups, 0);
+
+        let records: Vec<Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["record_type"], "request");
+        assert_eq!(records[0]["request_id"], "req-1");
+        assert_eq!(records[0]["total_cost_usd"], "0.01");
+        assert_eq!(records[0]["success_count"], 1);
+        assert!(records[0].get("error_message").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn exports_standalone_sqlite_file() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        seed(&db)?;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.db");
+        std::fs::write(&path, b"stale").unwrap();
+
+        let filters = LogFilters {
+            end_date: Some(1_700_000_150),
+            ..Default::default()
+        };
+        let summary = db.export_usage(&filters, UsageExportFormat::Sqlite, &path)?;
+        assert_eq!(summary.request_logs, 2);
+        assert_eq!(summary.daily_rollups, 1);
+
+        let exported = Connection::open(&path).unwrap();
+        let count: i64 = exported
+            .query_row("SELECT COUNT(*) FROM request_logs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+        let cost: String = exported
+            .query_row("SELECT total_cost_usd FROM daily_rollups", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(cost, "0.5");
+
+        // 已存在的导出文件可被覆盖
+        db.export_usage(&filters, UsageExportFormat::Sqlite, &path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn exports_across_multiple_pages_in_order() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let mut conn = lock_conn!(db.conn);
+            let tx = conn.transaction()?;
+            // 同一时间戳的多行按 request_id 排序，验证分页键不会遗漏或重复
+            for index in 0..(PAGE_SIZE * 2 + 5) {
+                tx.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, input_tokens, output_tokens,
+                        total_cost_usd, latency_ms, status_code, created_at
+                    ) VALUES (?1, 'p1', 'claude', 'm', 1, 1, '0', 1, 200, ?2)",
+                    params![
+                        format!("req-{index:05}"),
+                        1_700_000_000i64 + (index / 7) as i64
+                    ],
+                )?;
+            }
+            tx.commit()?;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.jsonl");
+
+        let summary = db.export_usage(&LogFilters::default(), UsageExportFormat::Jsonl, &path)?;
+        assert_eq!(summary.request_logs as usize, PAGE_SIZE * 2 + 5);
+
+        let ids: Vec<String> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["request_id"].to_string())
+            .collect();
+        let mut sorted = ids.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(ids, sorted);
+        Ok(())
+    }
+
+    #[test]
+    fn refuses_live_database_and_keeps_target_on_failure() -> Result<(), AppError> {
+        let dir = tempfile::tempdir().unwrap();
+        let live = dir.path().join("cc-switch.db");
+        std::fs::write(&live, b"live").unwrap();
+
+        let aliased = dir.path().join(".").join("cc-switch.db");
+        assert!(ensure_not_live_database(&aliased, &live).is_err());
+        assert!(ensure_not_live_database(&dir.path().join("cc-switch.db-wal"), &live).is_err());
+        assert!(ensure_not_live_database(&dir.path().join("usage.db"), &live).is_ok());
+
+        // 导出失败（目标为目录）时不留下临时文件
+        let db = Database::memory()?;
+        let target = dir.path().join("occupied.csv");
+        std::fs::create_dir(&target).unwrap();
+        assert!(db
+            .export_usage(&LogFilters::default(), UsageExportFormat::Csv, &target)
+            .is_err());
+        let leftovers = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .count();
+        assert_eq!(leftovers, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(
+            UsageExportFormat::from_path(Path::new("a.CSV")),
+            Some(UsageExportFormat::Csv)
+        );
+        assert_eq!(
+            UsageExportFormat::from_path(Path::new("a.ndjson")),
+            Some(UsageExportFormat::Jsonl)
+        );
+        assert_eq!(
+            UsageExportFormat::from_path(Path::new("a.sqlite3")),
+            Some(UsageExportFormat::Sqlite)
+        );
+        assert_eq!(UsageExportFormat::from_path(Path::new("a.txt")), None);
+    }
+}
@@ -0,0 +1,624 @@
+//! 消费速率与预算预测
+//!
+//! 基于近 30 天的请求明细与日汇总，按 (应用, 供应商) 计算：
+//! - 小时速率：最近 3 小时的平均每小时消费
+//! - 日速率：今天之前 7 天的平均每日消费
+//! - 今日与月末预计消费：今日剩余时间按小时速率、之后每天按日速率外推
+//! - 按上述外推触达 `ProviderMeta` 日 / 月限额的时间
+//! - 异常日：单日消费达到此前 7 天中位数的 3 倍
+//!
+//! 后台定期检查，预计将超出（但尚未超出）限额时发射 `provider-budget-forecast` 事件，
+//! 每个供应商在同一限额周期内只提醒一次。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::budget::{self, BudgetPeriod};
+use crate::services::notification::{self, NotificationEvent, NotificationEventKind};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tauri::{AppHandle, Emitter};
+
+/// 后台检查间隔（秒）
+pub const FORECAST_CHECK_INTERVAL_SECS: u64 = 10 * 60;
+
+/// 小时速率统计窗口
+const HOURLY_WINDOW_HOURS: i64 = 3;
+/// 日速率统计窗口（不含今天）
+const DAILY_WINDOW_DAYS: usize = 7;
+/// 历史数据天数（另外至少覆盖本月）
+const HISTORY_DAYS: i64 = 30;
+/// 异常判定倍数
+const ANOMALY_FACTOR: f64 = 3.0;
+/// 异常判定至少需要的基线天数
+const ANOMALY_MIN_BASELINE_DAYS: usize = 3;
+
+/// 消费异常日
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendAnomaly {
+    pub date: String,
+    pub cost_usd: String,
+    pub trailing_median_usd: String,
+    pub ratio: f64,
+}
+
+/// 单个供应商的消费预测
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BurnForecast {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub hourly_burn_usd: String,
+    pub daily_burn_usd: String,
+    pub today_usd: String,
+    pub month_to_date_usd: String,
+    pub projected_today_usd: String,
+    pub projected_month_end_usd: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_limit_usd: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_limit_usd: Option<String>,
+    /// 预计触达日限额的时间（Unix 秒）；已超限或今日内不会触达时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_limit_hit_at: Option<i64>,
+    /// 预计触达月限额的时间（Unix 秒）；已超限或本月内不会触达时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_limit_hit_at: Option<i64>,
+    pub anomalies: Vec<SpendAnomaly>,
+}
+
+/// 预计超限事件载荷
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetForecastPayload {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub period: BudgetPeriod,
+    pub usage_usd: String,
+    pub projected_usd: String,
+    pub limit_usd: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_hit_at: Option<i64>,
+}
+
+/// 数值形式的预测结果
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Projection {
+    hourly_burn: f64,
+    daily_burn: f64,
+    today: f64,
+    month_to_date: f64,
+    projected_today: f64,
+    projected_month_end: f64,
+    daily_limit_hit_at: Option<i64>,
+    monthly_limit_hit_at: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+struct ForecastEntry {
+    forecast: BurnForecast,
+    projection: Projection,
+    daily_limit: Option<f64>,
+    monthly_limit: Option<f64>,
+}
+
+impl Database {
+    /// 获取各供应商的消费速率与预算预测（按月末预计消费降序）
+    pub fn get_usage_forecast(
+        &self,
+        app_type: Option<&str>,
+    ) -> Result<Vec<BurnForecast>, AppError> {
+        Ok(self
+            .compute_forecasts(app_type, Local::now())?
+            .into_iter()
+            .map(|entry| entry.forecast)
+            .collect())
+    }
+
+    fn compute_forecasts(
+        &self,
+        app_type: Option<&str>,
+        now: DateTime<Local>,
+    ) -> Result<Vec<ForecastEntry>, AppError> {
+        let today = now.date_naive();
+        let month_start = today.with_day(1).unwrap_or(today);
+        let window_start = month_start.min(today - Duration::days(HISTORY_DAYS));
+        let (daily, hourly) = self.query_spend_series(app_type, window_start, now)?;
+
+        let mut app_types: Vec<&str> = daily.keys().map(|(app, _)| app.as_str()).collect();
+        app_types.sort();
+        app_types.dedup();
+        let mut providers = HashMap::new();
+        for app in app_types {
+            for (id, provider) in self.get_all_providers(app)? {
+                providers.insert((app.to_string(), id), provider);
+            }
+        }
+
+        let mut entries: Vec<ForecastEntry> = daily
+            .into_iter()
+            .map(|(key, by_day)| {
+                let series = dense_series(&by_day, window_start, today);
+                let provider = providers.get(&key);
+                let (daily_limit, monthly_limit) =
+                    provider.map(budget::spend_limits).unwrap_or((None, None));
+                let hourly_burn = hourly.get(&key).copied().unwrap_or(0.0);
+                let projection = project(&series, hourly_burn, daily_limit, monthly_limit, now);
+                let anomalies = detect_anomalies(&series)
+                    .into_iter()
+                    .map(|(index, cost, median)| SpendAnomaly {
+                        date: (window_start + Duration::days(index as i64))
+                            .format("%Y-%m-%d")
+                            .to_string(),
+                        cost_usd: format!("{cost:.6}"),
+                        trailing_median_usd: format!("{median:.6}"),
+                        ratio: cost / median,
+                    })
+                    .collect();
+                let (app_type, provider_id) = key;
+                let forecast = BurnForecast {
+                    provider_name: provider
+                        .map(|p| p.name.clone())
+                        .unwrap_or_else(|| provider_id.clone()),
+                    app_type,
+                    provider_id,
+                    hourly_burn_usd: format!("{:.6}", projection.hourly_burn),
+                    daily_burn_usd: format!("{:.6}", projection.daily_burn),
+                    today_usd: format!("{:.6}", projection.today),
+                    month_to_date_usd: format!("{:.6}", projection.month_to_date),
+                    projected_today_usd: format!("{:.6}", projection.projected_today),
+                    projected_month_end_usd: format!("{:.6}", projection.projected_month_end),
+                    daily_limit_usd: daily_limit.map(|l| format!("{l:.2}")),
+                    monthly_limit_usd: monthly_limit.map(|l| format!("{l:.2}")),
+                    daily_limit_hit_at: projection.daily_limit_hit_at,
+                    monthly_limit_hit_at: projection.monthly_limit_hit_at,
+                    anomalies,
+                };
+                ForecastEntry {
+                    forecast,
+                    projection,
+                    daily_limit,
+                    monthly_limit,
+                }
+            })
+            .collect();
+
+        entries.sort_by(|a, b| {
+            b.projection
+                .projected_month_end
+                .total_cmp(&a.projection.projected_month_end)
+        });
+        Ok(entries)
+    }
+
+    /// 查询每日消费（明细 + 日汇总）与最近小时窗口的消费
+    #[allow(clippy::type_complexity)]
+    fn query_spend_series(
+        &self,
+        app_type: Option<&str>,
+        window_start: NaiveDate,
+        now: DateTime<Local>,
+    ) -> Result<
+        (
+            HashMap<(String, String), BTreeMap<NaiveDate, f64>>,
+            HashMap<(String, String), f64>,
+        ),
+        AppError,
+    > {
+        let conn = lock_conn!(self.conn);
+        let start_ts = local_midnight(window_start).timestamp();
+        let start_date = window_start.format("%Y-%m-%d").to_string();
+
+        let mut stmt = conn.prepare(
+            "SELECT app_type, provider_id, day, SUM(cost) FROM (
+                SELECT app_type, provider_id,
+                    date(created_at, 'unixepoch', 'localtime') AS day,
+                    CAST(total_cost_usd AS REAL) AS cost
+                FROM proxy_request_logs
+                WHERE created_at >= ?1 AND (?3 IS NULL OR app_type = ?3)
+                UNION ALL
+                SELECT app_type, provider_id, date, CAST(total_cost_usd AS REAL)
+                FROM usage_daily_rollups
+                WHERE date >= ?2 AND (?3 IS NULL OR app_type = ?3)
+            )
+            GROUP BY app_type, provider_id, day",
+        )?;
+        let rows = stmt.query_map(params![start_ts, start_date, app_type], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<f64>>(3)?.unwrap_or(0.0),
+            ))
+        })?;
+        let mut daily: HashMap<(String, String), BTreeMap<NaiveDate, f64>> = HashMap::new();
+        for row in rows {
+            let (app, provider_id, day, cost) = row?;
+            let Ok(day) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") else {
+                continue;
+            };
+            *daily
+                .entry((app, provider_id))
+                .or_default()
+                .entry(day)
+                .or_default() += cost;
+        }
+
+        let since = now.timestamp() - HOURLY_WINDOW_HOURS * 3600;
+        let mut stmt = conn.prepare(
+            "SELECT app_type, provider_id, COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0)
+            FROM proxy_request_logs
+            WHERE created_at >= ?1 AND (?2 IS NULL OR app_type = ?2)
+            GROUP BY app_type, provider_id",
+        )?;
+        let rows = stmt.query_map(params![since, app_type], |row| {
+            Ok((
+                (row.get::<_, String>(0)?, row.get::<_, String>(1)?),
+                row.get::<_, f64>(2)? / HOURLY_WINDOW_HOURS as f64,
+            ))
+        })?;
+        let hourly = rows.collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok((daily, hourly))
+    }
+}
+
+/// 预计超限提醒（后台定期调用）
+#[derive(Debug, Default)]
+pub struct ForecastMonitor {
+    notified: HashSet<String>,
+}
+
+impl ForecastMonitor {
+    /// 重新计算预测，对新出现的预计超限发射事件
+    pub fn check(&mut self, db: &Database, app: &AppHandle) -> Result<(), AppError> {
+        let entries = db.compute_forecasts(None, Local::now())?;
+
+        // 清理过期周期的记录，防止长期运行时无限增长
+        let daily_key = BudgetPeriod::Daily.current_key();
+        let monthly_key = BudgetPeriod::Monthly.current_key();
+        self.notified
+            .retain(|k| k.ends_with(&daily_key) || k.ends_with(&monthly_key));
+
+        for payload in entries.iter().flat_map(forecast_alerts) {
+            let key = format!(
+                "{}:{}:{}:{}",
+                payload.app_type,
+                payload.provider_id,
+                payload.period.as_str(),
+                payload.period.current_key()
+            );
+            if !self.notified.insert(key) {
+                continue;
+            }
+            log::info!(
+                "[Budget] {} {} 预计消费 ${} 将超出限额 ${}",
+                payload.provider_name,
+                payload.period.as_str(),
+                payload.projected_usd,
+                payload.limit_usd
+            );
+            let limit: f64 = payload.limit_usd.parse().unwrap_or_default();
+            let projected: f64 = payload.projected_usd.parse().unwrap_or_default();
+            let mut event = NotificationEvent::new(
+                NotificationEventKind::BudgetForecast,
+                format!(
+                    "{} ${} → ${} / ${}",
+                    payload.period.as_str(),
+                    payload.usage_usd,
+                    payload.projected_usd,
+                    payload.limit_usd
+                ),
+            )
+            .app(&payload.app_type)
+            .provider(&payload.provider_id, Some(&payload.provider_name))
+            .dedupe_key(payload.period.as_str());
+            if limit > 0.0 {
+                event = event.value(projected / limit * 100.0);
+            }
+            notification::notify(event);
+
+            if let Err(e) = app.emit(budget::BUDGET_FORECAST_EVENT, &payload) {
+                log::error!("[Budget] 发射预计超限事件失败: {e}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 预计将超出但当前尚未超出的限额
+fn forecast_alerts(entry: &ForecastEntry) -> Vec<BudgetForecastPayload> {
+    let p = &entry.projection;
+    let periods = [
+        (
+            BudgetPeriod::Daily,
+            entry.daily_limit,
+            p.today,
+            p.projected_today,
+            p.daily_limit_hit_at,
+        ),
+        (
+            BudgetPeriod::Monthly,
+            entry.monthly_limit,
+            p.month_to_date,
+            p.projected_month_end,
+            p.monthly_limit_hit_at,
+        ),
+    ];
+
+    periods
+        .into_iter()
+        .filter_map(|(period, limit, usage, projected, hit_at)| {
+            let limit = limit?;
+            (usage < limit && projected >= limit).then(|| BudgetForecastPayload {
+                app_type: entry.forecast.app_type.clone(),
+                provider_id: entry.forecast.provider_id.clone(),
+                provider_name: entry.forecast.provider_name.clone(),
+                period,
+                usage_usd: format!("{usage:.6}"),
+                projected_usd: format!("{projected:.6}"),
+                limit_usd: format!("{limit:.2}"),
+                limit_hit_at: hit_at,
+            })
+        })
+        .collect()
+}
+
+/// 把按日消费补齐为从 `start` 到 `today`（含）的连续序列，缺失日期记为 0
+fn dense_series(by_day: &BTreeMap<NaiveDate, f64>, start: NaiveDate, today: NaiveDate) -> Vec<f64> {
+    start
+        .iter_days()
+        .take_while(|day| *day <= today)
+        .map(|day| by_day.get(&day).copied().unwrap_or(0.0))
+        .collect()
+}
+
+/// 根据连续日序列（最后一项为今天）与小时速率推算今日 / 月末消费及触达限额的时间
+fn project(
+    series: &[f64],
+    hourly_burn: f64,
+    daily_limit: Option<f64>,
+    monthly_limit: Option<f64>,
+    now: DateTime<Local>,
+) -> Projection {
+    let today_date = now.date_naive();
+    let today = series.last().copied().unwrap_or(0.0);
+    let history = &series[..series.len().saturating_sub(1)];
+    let baseline = &history[history.len().saturating_sub(DAILY_WINDOW_DAYS)..];
+    let daily_burn = if baseline.is_empty() {
+        0.0
+    } else {
+        baseline.iter().sum::<f64>() / baseline.len() as f64
+    };
+    let month_days = today_date.day() as usize;
+    let month_to_date: f64 = series[series.len().saturating_sub(month_days)..]
+        .iter()
+        .sum();
+
+    let end_of_today = local_midnight(today_date + Duration::days(1));
+    let hours_left_today = (end_of_today - now).num_seconds().max(0) as f64 / 3600.0;
+    let next_month = next_month_start(today_date);
+    let full_days_left = (next_month - today_date)
+        .num_days()
+        .saturating_sub(1)
+        .max(0) as f64;
+
+    let projected_today = today + hourly_burn * hours_left_today;
+    let projected_month_end = month_to_date - today + projected_today + daily_burn * full_days_left;
+
+    // 今日剩余时间按小时速率消耗
+    let hit_within_today = |remaining: f64| {
+        (hourly_burn > 0.0 && remaining <= hourly_burn * hours_left_today)
+            .then(|| now.timestamp() + (remaining / hourly_burn * 3600.0) as i64)
+    };
+
+    let daily_limit_hit_at = daily_limit
+        .filter(|limit| today < *limit)
+        .and_then(|limit| hit_within_today(limit - today));
+
+    let monthly_limit_hit_at = monthly_limit
+        .filter(|limit| month_to_date < *limit)
+        .and_then(|limit| {
+            let remaining = limit - month_to_date;
+            hit_within_today(remaining).or_else(|| {
+                if daily_burn <= 0.0 {
+                    return None;
+                }
+                let after_today = remaining - (projected_today - today);
+                let at = end_of_today.timestamp() + (after_today / daily_burn * 86_400.0) as i64;
+                (at < local_midnight(next_month).timestamp()).then_some(at)
+            })
+        });
+
+    Projection {
+        hourly_burn,
+        daily_burn,
+        today,
+        month_to_date,
+        projected_today,
+        projected_month_end,
+        daily_limit_hit_at,
+        monthly_limit_hit_at,
+    }
+}
+
+/// 找出消费达到此前 7 天中位数 3 倍的日期，返回 (序号, 当日消费, 基线中位数)
+fn detect_anomalies(series: &[f64]) -> Vec<(usize, f64, f64)> {
+    series
+        .iter()
+        .enumerate()
+        .filter_map(|(index, cost)| {
+            let baseline = &series[index.saturating_sub(DAILY_WINDOW_DAYS)..index];
+            if baseline.len() < ANOMALY_MIN_BASELINE_DAYS {
+                return None;
+            }
+            let median = median(baseline);
+            (median > 0.0 && *cost >= median * ANOMALY_FACTOR).then_some((index, *cost, median))
+        })
+        .collect()
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+fn next_month_start(day: NaiveDate) -> NaiveDate {
+    let (year, month) = if day.month() == 12 {
+        (day.year() + 1, 1)
+    } else {
+        (day.year(), day.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(day)
+}
+
+/// 本地时间某日零点（夏令时跳变时取最早的有效时刻）
+fn local_midnight(day: NaiveDate) -> DateTime<Local> {
+    let naive = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&naive))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn local(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(year, month, day, hour, 0, 0)
+            .earliest()
+            .expect("valid local datetime")
+    }
+
+    #[test]
+    fn projects_month_end_and_limit_hit_times() {
+        // 6 月 10 日 12:00：此前每天 $2，今天已花 $3，最近每小时 $0.5
+        let now = local(2026, 6, 10, 12);
+        let mut series = vec![2.0; 9];
+        series.push(3.0);
+
+        let projection = project(&series, 0.5, Some(8.0), Some(60.0), now);
+        assert!((projection.daily_burn - 2.0).abs() < 1e-9);
+        assert!((projection.month_to_date - 21.0).abs() < 1e-9);
+        assert!((projection.projected_today - 9.0).abs() < 1e-9);
+        // 剩余 20 个整天 * $2
+        assert!((projection.projected_month_end - 67.0).abs() < 1e-9);
+
+        // 日限额 $8：还差 $5，按 $0.5/h 需 10 小时
+        assert_eq!(
+            projection.daily_limit_hit_at,
+            Some(now.timestamp() + 10 * 3600)
+        );
+        // 月限额 $60：今日结束时 $27，还差 $33，按 $2/天 需 16.5 天
+        let expected = local(2026, 6, 11, 0).timestamp() + (16.5 * 86_400.0) as i64;
+        assert_eq!(projection.monthly_limit_hit_at, Some(expected));
+
+        // 不会在本月内触达的限额没有时间
+        let projection = project(&series, 0.5, Some(100.0), Some(1000.0), now);
+        assert_eq!(projection.daily_limit_hit_at, None);
+        assert_eq!(projection.monthly_limit_hit_at, None);
+    }
+
+    #[test]
+    fn flags_days_at_three_times_trailing_median() {
+        let series = [0.0, 1.0, 1.0, 1.2, 0.8, 3.5, 1.0, 0.0, 9.0];
+        let anomalies = detect_anomalies(&series);
+        assert_eq!(anomalies.len(), 2);
+        assert_eq!(anomalies[0].0, 5);
+        assert!((anomalies[0].2 - 1.0).abs() < 1e-9);
+        assert_eq!(anomalies[1].0, 8);
+
+        // 基线不足或中位数为 0 时不判定
+        assert!(detect_anomalies(&[0.0, 0.0, 0.0, 5.0]).is_empty());
+        assert!(detect_anomalies(&[1.0, 5.0]).is_empty());
+    }
+
+    #[test]
+    fn forecasts_merge_detail_logs_and_rollups() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let today = Local::now().date_naive();
+        let now = local(today.year(), today.month(), today.day(), 12);
+        let yesterday = today - Duration::days(1);
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model,
+                    input_tokens, output_tokens, total_cost_usd,
+                    latency_ms, status_code, created_at
+                ) VALUES ('r1', 'p1', 'claude', 'm', 1, 1, '0.6', 10, 200, ?1)",
+                params![now.timestamp() - 60],
+            )?;
+            conn.execute(
+                "INSERT INTO usage_daily_rollups (
+                    date, app_type, provider_id, model,
+                    request_count, success_count, input_tokens, output_tokens,
+                    cache_read_tokens, cache_creation_tokens, total_cost_usd, avg_latency_ms
+                ) VALUES (?1, 'claude', 'p1', 'm', 1, 1, 1, 1, 0, 0, '7.0', 10)",
+                params![yesterday.format("%Y-%m-%d").to_string()],
+            )?;
+        }
+
+        let entries = db.compute_forecasts(Some("claude"), now)?;
+        assert_eq!(entries.len(), 1);
+        let projection = &entries[0].projection;
+        assert!((projection.today - 0.6).abs() < 1e-9);
+        assert!((projection.hourly_burn - 0.2).abs() < 1e-9);
+        assert!((projection.daily_burn - 1.0).abs() < 1e-9);
+        assert_eq!(entries[0].forecast.provider_name, "p1");
+
+        assert!(db.compute_forecasts(Some("codex"), now)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn alerts_only_when_projection_newly_crosses_limit() {
+        let entry = |today: f64, projected_today: f64| ForecastEntry {
+            forecast: BurnForecast {
+                app_type: "claude".to_string(),
+                provider_id: "p1".to_string(),
+                provider_name: "P1".to_string(),
+                hourly_burn_usd: String::new(),
+                daily_burn_usd: String::new(),
+                today_usd: String::new(),
+                month_to_date_usd: String::new(),
+                projected_today_usd: String::new(),
+                projected_month_end_usd: String::new(),
+                daily_limit_usd: None,
+                monthly_limit_usd: None,
+                daily_limit_hit_at: None,
+                monthly_limit_hit_at: None,
+                anomalies: Vec::new(),
+            },
+            projection: Projection {
+                today,
+                projected_today,
+                ..Default::default()
+            },
+            daily_limit: Some(10.0),
+            monthly_limit: None,
+        };
+
+        let alerts = forecast_alerts(&entry(4.0, 12.0));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].period, BudgetPeriod::Daily);
+        assert_eq!(alerts[0].projected_usd, "12.000000");
+
+        assert!(forecast_alerts(&entry(4.0, 9.0)).is_empty());
+        // 已超限由代理路径的 provider-budget-exceeded 事件负责
+        assert!(forecast_alerts(&entry(11.0, 15.0)).is_empty());
+    }
+}
@@ -2,8 +2,11 @@
 //!
 //! 提供使用量数据的聚合查询功能
 
-use crate::database::{lock_conn, Database};
+use crate::database::{escape_like, lock_conn, Database};
 use crate::error::AppError;
+use crate::proxy::usage::calculator::{CostCalculator, ModelPricing};
+use crate::proxy::usage::logger::UsageLogger;
+use crate::proxy::usage::parser::TokenUsage;
 use chrono::{Local, NaiveDate, TimeZone, Timelike};
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
@@ -101,6 +104,12 @@ pub struct RequestLogDetail {
     pub output_tokens: u32,
     pub cache_read_tokens: u32,
     pub cache_creation_tokens: u32,
+    #[serde(default)]
+    pub cache_creation_1h_tokens: u32,
+    #[serde(default)]
+    pub reasoning_tokens: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<String>,
     pub input_cost_usd: String,
     pub output_cost_usd: String,
     pub cache_read_cost_usd: String,
@@ -115,12 +124,15 @@ pub struct RequestLogDetail {
     pub created_at: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data_source: Option<String>,
+    /// 关联的调试抓包 ID（开启请求抓包时存在）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_id: Option<String>,
 }
 
 /// SQL fragment: resolve provider_name with fallback for session-based entries.
 /// Session logs use placeholder provider_ids (_session, _codex_session, _gemini_session)
 /// that don't exist in the providers table — this COALESCE gives them readable names.
-fn provider_name_coalesce(log_alias: &str, provider_alias: &str) -> String {
+pub(crate) fn provider_name_coalesce(log_alias: &str, provider_alias: &str) -> String {
     format!(
         "COALESCE({provider_alias}.name, CASE {log_alias}.provider_id \
          WHEN '_session' THEN 'Claude (Session)' \
@@ -130,8 +142,46 @@ fn provider_name_coalesce(log_alias: &str, provider_alias: &str) -> String {
     )
 }
 
+/// 根据过滤器生成请求日志查询的 WHERE 子句（日志表别名 `l`，供应商表别名 `p`）
+pub(crate) fn log_filter_clause(filters: &LogFilters) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+    let mut conditions = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(ref app_type) = filters.app_type {
+        conditions.push("l.app_type = ?");
+        params.push(Box::new(app_type.clone()));
+    }
+    if let Some(ref provider_name) = filters.provider_name {
+        conditions.push("p.name LIKE ?");
+        params.push(Box::new(format!("%{provider_name}%")));
+    }
+    if let Some(ref model) = filters.model {
+        conditions.push("l.model LIKE ?");
+        params.push(Box::new(format!("%{model}%")));
+    }
+    if let Some(status) = filters.status_code {
+        conditions.push("l.status_code = ?");
+        params.push(Box::new(status as i64));
+    }
+    if let Some(start) = filters.start_date {
+        conditions.push("l.created_at >= ?");
+        params.push(Box::new(start));
+    }
+    if let Some(end) = filters.end_date {
+        conditions.push("l.created_at <= ?");
+        params.push(Box::new(end));
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    (where_clause, params)
+}
+
 #[derive(Debug, Clone, Default)]
-struct RollupDateBounds {
+pub(crate) struct RollupDateBounds {
     start: Option<String>,
     end: Option<String>,
     is_empty: bool,
@@ -144,7 +194,7 @@ fn local_datetime_from_timestamp(ts: i64) -> Result<chrono::DateTime<Local>, App
         .ok_or_else(|| AppError::Database(format!("无法解析本地时间戳: {ts}")))
 }
 
-fn compute_rollup_date_bounds(
+pub(crate) fn compute_rollup_date_bounds(
     start_ts: Option<i64>,
     end_ts: Option<i64>,
 ) -> Result<RollupDateBounds, AppError> {
@@ -185,7 +235,7 @@ fn compute_rollup_date_bounds(
     })
 }
 
-fn push_rollup_date_filters(
+pub(crate) fn push_rollup_date_filters(
     conditions: &mut Vec<String>,
     params: &mut Vec<Box<dyn rusqlite::ToSql>>,
     column: &str,
@@ -855,39 +905,7 @@ impl Database {
     ) -> Result<PaginatedLogs, AppError> {
         let conn = lock_conn!(self.conn);
 
-        let mut conditions = Vec::new();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-        if let Some(ref app_type) = filters.app_type {
-            conditions.push("l.app_type = ?");
-            params.push(Box::new(app_type.clone()));
-        }
-        if let Some(ref provider_name) = filters.provider_name {
-            conditions.push("p.name LIKE ?");
-            params.push(Box::new(format!("%{provider_name}%")));
-        }
-        if let Some(ref model) = filters.model {
-            conditions.push("l.model LIKE ?");
-            params.push(Box::new(format!("%{model}%")));
-        }
-        if let Some(status) = filters.status_code {
-            conditions.push("l.status_code = ?");
-            params.push(Box::new(status as i64));
-        }
-        if let Some(start) = filters.start_date {
-            conditions.push("l.created_at >= ?");
-            params.push(Box::new(start));
-        }
-        if let Some(end) = filters.end_date {
-            conditions.push("l.created_at <= ?");
-            params.push(Box::new(end));
-        }
-
-        let where_clause = if conditions.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", conditions.join(" AND "))
-        };
+        let (where_clause, mut params) = log_filter_clause(filters);
 
         // 获取总数
         let count_sql = format!(
@@ -912,7 +930,8 @@ impl Database {
                     l.input_tokens, l.output_tokens, l.cache_read_tokens, l.cache_creation_tokens,
                     l.input_cost_usd, l.output_cost_usd, l.cache_read_cost_usd, l.cache_creation_cost_usd, l.total_cost_usd,
                     l.is_streaming, l.latency_ms, l.first_token_ms, l.duration_ms,
-                    l.status_code, l.error_message, l.created_at, l.data_source
+                    l.status_code, l.error_message, l.created_at, l.data_source,
+                    l.cache_creation_1h_tokens, l.reasoning_tokens, l.service_tier, l.capture_id
              FROM proxy_request_logs l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
              {where_clause}
@@ -937,6 +956,9 @@ impl Database {
                 output_tokens: row.get::<_, i64>(8)? as u32,
                 cache_read_tokens: row.get::<_, i64>(9)? as u32,
                 cache_creation_tokens: row.get::<_, i64>(10)? as u32,
+                cache_creation_1h_tokens: row.get::<_, i64>(24)? as u32,
+                reasoning_tokens: row.get::<_, i64>(25)? as u32,
+                service_tier: row.get(26)?,
                 input_cost_usd: row.get(11)?,
                 output_cost_usd: row.get(12)?,
                 cache_read_cost_usd: row.get(13)?,
@@ -950,6 +972,7 @@ impl Database {
                 error_message: row.get(21)?,
                 created_at: row.get(22)?,
                 data_source: row.get(23)?,
+                capture_id: row.get(27)?,
             })
         })?;
 
@@ -990,7 +1013,8 @@ impl Database {
                     input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
                     input_cost_usd, output_cost_usd, cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
                     is_streaming, latency_ms, first_token_ms, duration_ms,
-                    status_code, error_message, created_at, l.data_source
+                    status_code, error_message, created_at, l.data_source,
+                    l.cache_creation_1h_tokens, l.reasoning_tokens, l.service_tier, l.capture_id
              FROM proxy_request_logs l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
              WHERE l.request_id = ?"
@@ -1010,6 +1034,9 @@ impl Database {
                 output_tokens: row.get::<_, i64>(8)? as u32,
                 cache_read_tokens: row.get::<_, i64>(9)? as u32,
                 cache_creation_tokens: row.get::<_, i64>(10)? as u32,
+                cache_creation_1h_tokens: row.get::<_, i64>(24)? as u32,
+                reasoning_tokens: row.get::<_, i64>(25)? as u32,
+                service_tier: row.get(26)?,
                 input_cost_usd: row.get(11)?,
                 output_cost_usd: row.get(12)?,
                 cache_read_cost_usd: row.get(13)?,
@@ -1023,6 +1050,7 @@ impl Database {
                 error_message: row.get(21)?,
                 created_at: row.get(22)?,
                 data_source: row.get(23)?,
+                capture_id: row.get(27)?,
             })
         });
 
@@ -1131,6 +1159,50 @@ impl Database {
             monthly_exceeded,
         })
     }
+
+    /// 统计近期各供应商的延迟与单位成本（供路由策略排序）
+    ///
+    /// 仅统计 `since` 之后的成功请求；流式请求取首字延迟，非流式取总延迟。
+    pub fn get_provider_routing_stats(
+        &self,
+        app_type: &str,
+        since: i64,
+    ) -> Result<HashMap<String, ProviderRoutingStats>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT provider_id, COUNT(*),
+                        AVG(CASE WHEN is_streaming = 1 AND first_token_ms IS NOT NULL
+                                 THEN first_token_ms ELSE latency_ms END),
+                        COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0),
+                        COALESCE(SUM(input_tokens + output_tokens + cache_read_tokens + cache_creation_tokens), 0)
+                 FROM proxy_request_logs
+                 WHERE app_type = ?1 AND created_at >= ?2
+                   AND status_code >= 200 AND status_code < 300
+                 GROUP BY provider_id",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![app_type, since], |row| {
+                let provider_id: String = row.get(0)?;
+                let total_cost: f64 = row.get(3)?;
+                let total_tokens: i64 = row.get(4)?;
+                Ok((
+                    provider_id,
+                    ProviderRoutingStats {
+                        samples: row.get::<_, i64>(1)? as u64,
+                        avg_latency_ms: row.get(2)?,
+                        cost_per_million_tokens: (total_tokens > 0)
+                            .then(|| total_cost * 1_000_000.0 / total_tokens as f64),
+                    },
+                ))
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        rows.collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
 }
 
 /// Provider 限额状态
@@ -1146,12 +1218,15 @@ pub struct ProviderLimitStatus {
     pub monthly_exceeded: bool,
 }
 
-#[derive(Clone)]
-struct PricingInfo {
-    input: rust_decimal::Decimal,
-    output: rust_decimal::Decimal,
-    cache_read: rust_decimal::Decimal,
-    cache_creation: rust_decimal::Decimal,
+/// 供应商近期路由统计
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProviderRoutingStats {
+    /// 成功请求数
+    pub samples: u64,
+    /// 平均延迟（毫秒）
+    pub avg_latency_ms: Option<f64>,
+    /// 每百万 token 的实际成本（USD，已含成本倍数）
+    pub cost_per_million_tokens: Option<f64>,
 }
 
 impl Database {
@@ -1159,7 +1234,7 @@ impl Database {
         conn: &Connection,
         log: &mut RequestLogDetail,
         provider_cache: &mut HashMap<(String, String), rust_decimal::Decimal>,
-        pricing_cache: &mut HashMap<String, PricingInfo>,
+        pricing_cache: &mut HashMap<String, Option<ModelPricing>>,
     ) -> Result<(), AppError> {
         let total_cost = rust_decimal::Decimal::from_str(&log.total_cost_usd)
             .unwrap_or(rust_decimal::Decimal::ZERO);
@@ -1184,33 +1259,23 @@ impl Database {
             &log.app_type,
         )?;
 
-        let million = rust_decimal::Decimal::from(1_000_000u64);
-
-        // 与 CostCalculator::calculate 保持一致的计算逻辑：
-        // 1. input_cost 需要扣除 cache_read_tokens（避免缓存部分被重复计费）
-        // 2. 各项成本是基础成本（不含倍率）
-        // 3. 倍率只作用于最终总价
-        let billable_input_tokens =
-            (log.input_tokens as u64).saturating_sub(log.cache_read_tokens as u64);
-        let input_cost =
-            rust_decimal::Decimal::from(billable_input_tokens) * pricing.input / million;
-        let output_cost =
-            rust_decimal::Decimal::from(log.output_tokens as u64) * pricing.output / million;
-        let cache_read_cost = rust_decimal::Decimal::from(log.cache_read_tokens as u64)
-            * pricing.cache_read
-            / million;
-        let cache_creation_cost = rust_decimal::Decimal::from(log.cache_creation_tokens as u64)
-            * pricing.cache_creation
-            / million;
-        // 总成本 = 基础成本之和 × 倍率
-        let base_total = input_cost + output_cost + cache_read_cost + cache_creation_cost;
-        let total_cost = base_total * multiplier;
-
-        log.input_cost_usd = format!("{input_cost:.6}");
-        log.output_cost_usd = format!("{output_cost:.6}");
-        log.cache_read_cost_usd = format!("{cache_read_cost:.6}");
-        log.cache_creation_cost_usd = format!("{cache_creation_cost:.6}");
-        log.total_cost_usd = format!("{total_cost:.6}");
+        let usage = TokenUsage {
+            input_tokens: log.input_tokens,
+            output_tokens: log.output_tokens,
+            cache_read_tokens: log.cache_read_tokens,
+            cache_creation_tokens: log.cache_creation_tokens,
+            cache_creation_1h_tokens: log.cache_creation_1h_tokens,
+            reasoning_tokens: log.reasoning_tokens,
+            service_tier: log.service_tier.clone(),
+            ..Default::default()
+        };
+        let cost = CostCalculator::calculate(&usage, &pricing, multiplier);
+
+        log.input_cost_usd = format!("{:.6}", cost.input_cost);
+        log.output_cost_usd = format!("{:.6}", cost.output_cost);
+        log.cache_read_cost_usd = format!("{:.6}", cost.cache_read_cost);
+        log.cache_creation_cost_usd = format!("{:.6}", cost.cache_creation_cost);
+        log.total_cost_usd = format!("{:.6}", cost.total_cost);
 
         conn.execute(
             "UPDATE proxy_request_logs
@@ -1269,54 +1334,181 @@ impl Database {
 
     fn get_model_pricing_cached(
         conn: &Connection,
-        cache: &mut HashMap<String, PricingInfo>,
+        cache: &mut HashMap<String, Option<ModelPricing>>,
         model: &str,
-    ) -> Result<Option<PricingInfo>, AppError> {
-        if let Some(info) = cache.get(model) {
-            return Ok(Some(info.clone()));
+    ) -> Result<Option<ModelPricing>, AppError> {
+        if let Some(pricing) = cache.get(model) {
+            return Ok(pricing.clone());
         }
 
-        let row = find_model_pricing_row(conn, model)?;
-        let Some((input, output, cache_read, cache_creation)) = row else {
-            return Ok(None);
+        let pricing = find_model_pricing(conn, model)?;
+        cache.insert(model.to_string(), pricing.clone());
+        Ok(pricing)
+    }
+
+    /// 按当前定价重新计算历史请求日志的成本
+    ///
+    /// `model_id` 为 None 时重算全部日志，否则只重算清洗后模型名（或请求模型名）与之相同的日志。
+    /// 计费模型按供应商的计费模式来源选择（与记录日志时的 `UsageLogger::resolve_pricing_config`
+    /// 一致），找不到定价时回退另一个模型名；两者均无定价的日志保持不变。
+    /// 倍率沿用记录时保存的 `cost_multiplier`。已汇总进日统计表的旧数据不受影响。
+    pub async fn recompute_request_costs(&self, model_id: Option<&str>) -> Result<usize, AppError> {
+        // 按模型过滤：先用 LIKE 在 SQL 中筛出候选行，再按清洗规则精确比对
+        let model_filter = model_id.map(|target| format!("%{}%", escape_like(target)));
+        const FILTER: &str = "?1 IS NULL
+             OR REPLACE(model, '@', '-') LIKE ?1 ESCAPE '\\'
+             OR REPLACE(request_model, '@', '-') LIKE ?1 ESCAPE '\\'";
+        let matches_model = |model: &str, request_model: Option<&str>| {
+            model_id.is_none_or(|target| {
+                std::iter::once(model)
+                    .chain(request_model)
+                    .any(|m| clean_model_id(m) == target)
+            })
         };
 
-        let pricing = PricingInfo {
-            input: rust_decimal::Decimal::from_str(&input)
-                .map_err(|e| AppError::Database(format!("解析输入价格失败: {e}")))?,
-            output: rust_decimal::Decimal::from_str(&output)
-                .map_err(|e| AppError::Database(format!("解析输出价格失败: {e}")))?,
-            cache_read: rust_decimal::Decimal::from_str(&cache_read)
-                .map_err(|e| AppError::Database(format!("解析缓存读取价格失败: {e}")))?,
-            cache_creation: rust_decimal::Decimal::from_str(&cache_creation)
-                .map_err(|e| AppError::Database(format!("解析缓存写入价格失败: {e}")))?,
+        // 计费模式来源的解析会读取数据库，需在持有连接锁之前完成
+        let provider_keys = {
+            let conn = lock_conn!(self.conn);
+            let mut stmt = conn.prepare(&format!(
+                "SELECT DISTINCT provider_id, app_type FROM proxy_request_logs WHERE {FILTER}"
+            ))?;
+            let rows = stmt.query_map(params![model_filter], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
         };
+        let logger = UsageLogger::new(self);
+        let mut pricing_sources = HashMap::new();
+        for key in provider_keys {
+            let (_, source) = logger.resolve_pricing_config(&key.0, &key.1).await;
+            pricing_sources.insert(key, source);
+        }
 
-        cache.insert(model.to_string(), pricing.clone());
-        Ok(Some(pricing))
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(format!("开启事务失败: {e}")))?;
+
+        let rows = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT request_id, provider_id, app_type, model, request_model, cost_multiplier,
+                        input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
+                        cache_creation_1h_tokens, reasoning_tokens, service_tier
+                 FROM proxy_request_logs
+                 WHERE {FILTER}"
+            ))?;
+            let rows = stmt.query_map(params![model_filter], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    (row.get::<_, String>(1)?, row.get::<_, String>(2)?),
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    TokenUsage {
+                        input_tokens: row.get::<_, i64>(6)? as u32,
+                        output_tokens: row.get::<_, i64>(7)? as u32,
+                        cache_read_tokens: row.get::<_, i64>(8)? as u32,
+                        cache_creation_tokens: row.get::<_, i64>(9)? as u32,
+                        cache_creation_1h_tokens: row.get::<_, i64>(10)? as u32,
+                        reasoning_tokens: row.get::<_, i64>(11)? as u32,
+                        service_tier: row.get(12)?,
+                        ..Default::default()
+                    },
+                ))
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        let mut pricing_cache = HashMap::new();
+        let mut updated = 0;
+        for (request_id, provider_key, model, request_model, multiplier, usage) in rows {
+            if !matches_model(&model, request_model.as_deref()) {
+                continue;
+            }
+
+            let by_request = pricing_sources
+                .get(&provider_key)
+                .is_some_and(|source| source == "request");
+            let candidates: Vec<&str> = if by_request {
+                request_model
+                    .as_deref()
+                    .into_iter()
+                    .chain([model.as_str()])
+                    .collect()
+            } else {
+                std::iter::once(model.as_str())
+                    .chain(request_model.as_deref())
+                    .collect()
+            };
+
+            let mut pricing = None;
+            for candidate in candidates {
+                pricing = Self::get_model_pricing_cached(&tx, &mut pricing_cache, candidate)?;
+                if pricing.is_some() {
+                    break;
+                }
+            }
+            let Some(pricing) = pricing else {
+                continue;
+            };
+
+            let multiplier = multiplier
+                .and_then(|m| rust_decimal::Decimal::from_str(&m).ok())
+                .unwrap_or(rust_decimal::Decimal::ONE);
+            let cost = CostCalculator::calculate(&usage, &pricing, multiplier);
+            updated += tx
+                .execute(
+                    "UPDATE proxy_request_logs
+                     SET input_cost_usd = ?1,
+                         output_cost_usd = ?2,
+                         cache_read_cost_usd = ?3,
+                         cache_creation_cost_usd = ?4,
+                         total_cost_usd = ?5
+                     WHERE request_id = ?6",
+                    params![
+                        cost.input_cost.to_string(),
+                        cost.output_cost.to_string(),
+                        cost.cache_read_cost.to_string(),
+                        cost.cache_creation_cost.to_string(),
+                        cost.total_cost.to_string(),
+                        request_id
+                    ],
+                )
+                .map_err(|e| AppError::Database(format!("更新请求成本失败: {e}")))?;
+        }
+
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("提交事务失败: {e}")))?;
+        log::info!("已按当前定价重算 {updated} 条请求日志成本");
+        Ok(updated)
     }
 }
 
-pub(crate) fn find_model_pricing_row(
-    conn: &Connection,
-    model_id: &str,
-) -> Result<Option<(String, String, String, String)>, AppError> {
-    // 清洗模型名称：去前缀(/)、去后缀(:)、@ 替换为 -
-    // 例如 moonshotai/gpt-5.2-codex@low:v2 → gpt-5.2-codex-low
-    let cleaned = model_id
+/// 清洗模型名称：去前缀(/)、去后缀(:)、@ 替换为 -
+/// 例如 moonshotai/gpt-5.2-codex@low:v2 → gpt-5.2-codex-low
+pub(crate) fn clean_model_id(model_id: &str) -> String {
+    model_id
         .rsplit_once('/')
         .map_or(model_id, |(_, r)| r)
         .split(':')
         .next()
         .unwrap_or(model_id)
         .trim()
-        .replace('@', "-");
+        .replace('@', "-")
+}
 
-    // 精确匹配清洗后的名称
-    let exact = conn
+/// 查找模型完整定价（含分段价格、缓存 TTL、推理与服务等级修饰项）
+pub(crate) fn find_model_pricing(
+    conn: &Connection,
+    model_id: &str,
+) -> Result<Option<ModelPricing>, AppError> {
+    let cleaned = clean_model_id(model_id);
+    let row = conn
         .query_row(
             "SELECT input_cost_per_million, output_cost_per_million,
-                    cache_read_cost_per_million, cache_creation_cost_per_million
+                    cache_read_cost_per_million, cache_creation_cost_per_million,
+                    cache_creation_1h_cost_per_million, reasoning_cost_per_million,
+                    tiers, service_tier_multipliers
              FROM model_pricing
              WHERE model_id = ?1",
             [&cleaned],
@@ -1326,17 +1518,39 @@ pub(crate) fn find_model_pricing_row(
                     row.get::<_, String>(1)?,
                     row.get::<_, String>(2)?,
                     row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
                 ))
             },
         )
         .optional()
         .map_err(|e| AppError::Database(format!("查询模型定价失败: {e}")))?;
 
-    if exact.is_none() {
+    let Some((input, output, cache_read, cache_creation, cache_1h, reasoning, tiers, multipliers)) =
+        row
+    else {
         log::warn!("模型 {model_id}（清洗后: {cleaned}）未找到定价信息，成本将记录为 0");
-    }
+        return Ok(None);
+    };
 
-    Ok(exact)
+    let pricing = ModelPricing::from_strings(&input, &output, &cache_read, &cache_creation)
+        .map_err(|e| AppError::Database(format!("解析定价数据失败: {e}")))?;
+    // 修饰项解析失败时退回基础价格，避免一条错误配置导致整条日志无成本
+    let pricing = match pricing.clone().with_modifiers(
+        cache_1h.as_deref(),
+        reasoning.as_deref(),
+        tiers.as_deref(),
+        multipliers.as_deref(),
+    ) {
+        Ok(pricing) => pricing,
+        Err(e) => {
+            log::warn!("模型 {cleaned} 的定价修饰项无效，按基础价格计费: {e}");
+            pricing
+        }
+    };
+    Ok(Some(pricing))
 }
 
 #[cfg(test)]
@@ -1896,35 +2110,129 @@ mod tests {
         )?;
 
         // 测试精确匹配（seed_model_pricing 已预置 claude-sonnet-4-5-20250929）
-        let result = find_model_pricing_row(&conn, "claude-sonnet-4-5-20250929")?;
+        let result = find_model_pricing(&conn, "claude-sonnet-4-5-20250929")?;
         assert!(
             result.is_some(),
             "应该能精确匹配 claude-sonnet-4-5-20250929"
         );
 
         // 清洗：去除前缀和冒号后缀
-        let result = find_model_pricing_row(&conn, "anthropic/claude-haiku-4.5")?;
+        let result = find_model_pricing(&conn, "anthropic/claude-haiku-4.5")?;
         assert!(
             result.is_some(),
             "带前缀的模型 anthropic/claude-haiku-4.5 应能匹配到 claude-haiku-4.5"
         );
-        let result = find_model_pricing_row(&conn, "moonshotai/kimi-k2-0905:exa")?;
+        let result = find_model_pricing(&conn, "moonshotai/kimi-k2-0905:exa")?;
         assert!(
             result.is_some(),
             "带前缀+冒号后缀的模型应清洗后匹配到 kimi-k2-0905"
         );
 
         // 清洗：@ 替换为 -（seed_model_pricing 已预置 gpt-5.2-codex-low）
-        let result = find_model_pricing_row(&conn, "gpt-5.2-codex@low")?;
+        let result = find_model_pricing(&conn, "gpt-5.2-codex@low")?;
         assert!(
             result.is_some(),
             "带 @ 分隔符的模型 gpt-5.2-codex@low 应能匹配到 gpt-5.2-codex-low"
         );
 
         // 测试不存在的模型
-        let result = find_model_pricing_row(&conn, "unknown-model-123")?;
+        let result = find_model_pricing(&conn, "unknown-model-123")?;
         assert!(result.is_none(), "不应该匹配不存在的模型");
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_recompute_request_costs_after_price_change() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            for (request_id, model, input) in [
+                ("req-1", "claude-sonnet-4-5-20250929", 1000),
+                ("req-2", "anthropic/claude-sonnet-4-5-20250929", 300_000),
+                ("req-3", "gpt-5", 1000),
+            ] {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, request_model,
+                        input_tokens, output_tokens, total_cost_usd, cost_multiplier,
+                        latency_ms, status_code, created_at
+                    ) VALUES (?1, 'p1', 'claude', ?2, ?2, ?3, 1000, '0', '2', 100, 200, 1000)",
+                    params![request_id, model, input],
+                )?;
+            }
+            conn.execute(
+                "UPDATE model_pricing SET input_cost_per_million = '4'
+                 WHERE model_id = 'claude-sonnet-4-5-20250929'",
+                [],
+            )?;
+        }
+
+        let updated = db
+            .recompute_request_costs(Some("claude-sonnet-4-5-20250929"))
+            .await?;
+        assert_eq!(updated, 2);
+
+        let conn = lock_conn!(db.conn);
+        let cost = |request_id: &str| -> Result<String, AppError> {
+            Ok(conn.query_row(
+                "SELECT total_cost_usd FROM proxy_request_logs WHERE request_id = ?",
+                [request_id],
+                |row| row.get(0),
+            )?)
+        };
+        // (1000 × 4 + 1000 × 15) / 1M × 2
+        assert_eq!(
+            rust_decimal::Decimal::from_str(&cost("req-1")?).unwrap(),
+            rust_decimal::Decimal::from_str("0.038").unwrap()
+        );
+        // 超过 200k 走长上下文价格：(300000 × 6 + 1000 × 22.5) / 1M × 2
+        assert_eq!(
+            rust_decimal::Decimal::from_str(&cost("req-2")?).unwrap(),
+            rust_decimal::Decimal::from_str("3.645").unwrap()
+        );
+        // 其他模型不受影响
+        assert_eq!(cost("req-3")?, "0");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recompute_request_costs_honors_request_pricing_source() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, request_model,
+                    input_tokens, output_tokens, total_cost_usd, cost_multiplier,
+                    latency_ms, status_code, created_at
+                ) VALUES ('req-1', 'p1', 'claude', 'claude-sonnet-4-5-20250929',
+                          'claude-haiku-4-5-20251001', 1000000, 0, '0', '1', 100, 200, 1000)",
+                [],
+            )?;
+        }
+        db.set_pricing_model_source("claude", "request").await?;
+
+        // 按请求模型过滤与计费：1M × 1 (haiku 输入价)
+        assert_eq!(
+            db.recompute_request_costs(Some("claude-haiku-4-5-20251001"))
+                .await?,
+            1
+        );
+        assert_eq!(db.recompute_request_costs(Some("claude_haiku%")).await?, 0);
+
+        let conn = lock_conn!(db.conn);
+        let cost: String = conn.query_row(
+            "SELECT total_cost_usd FROM proxy_request_logs WHERE request_id = 'req-1'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(
+            rust_decimal::Decimal::from_str(&cost).unwrap(),
+            rust_decimal::Decimal::ONE
+        );
+
+        Ok(())
+    }
 }
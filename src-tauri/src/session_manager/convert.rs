@@ -0,0 +1,1094 @@
+//! 会话格式转换
+//!
+//! 将任一来源的结构化会话（见 `load_structured_messages`）写入 Claude / Codex / Gemini
+//! 的原生存储布局，使目标 CLI 可以直接恢复该会话，并报告无法迁移的内容。
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::{load_structured_messages, provider_root, MessagePart, StructuredMessage, TurnUsage};
+use crate::config::{atomic_write, write_json_file};
+
+/// 每类损失最多保留的示例数
+const MAX_LOSS_SAMPLES: usize = 5;
+
+/// 转换目标格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertTarget {
+    Claude,
+    Codex,
+    Gemini,
+}
+
+impl ConvertTarget {
+    pub fn from_provider_id(provider_id: &str) -> Option<Self> {
+        match provider_id {
+            "claude" => Some(Self::Claude),
+            "codex" => Some(Self::Codex),
+            "gemini" => Some(Self::Gemini),
+            _ => None,
+        }
+    }
+
+    pub fn provider_id(self) -> &'static str {
+        match self {
+            Self::Claude => "claude",
+            Self::Codex => "codex",
+            Self::Gemini => "gemini",
+        }
+    }
+
+    fn resume_command(self, session_id: &str) -> String {
+        match self {
+            Self::Claude => format!("claude --resume {session_id}"),
+            Self::Codex => format!("codex resume {session_id}"),
+            Self::Gemini => format!("gemini --resume {session_id}"),
+        }
+    }
+
+    /// Claude / Codex 回放思考过程需要签名或加密内容，只有 Gemini 能保留摘要
+    fn keeps_thinking(self) -> bool {
+        matches!(self, Self::Gemini)
+    }
+}
+
+/// 待转换的会话
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionConvertRequest {
+    pub provider_id: String,
+    pub session_id: String,
+    pub source_path: String,
+    /// 目标 CLI 按项目目录组织会话，必须提供
+    #[serde(default)]
+    pub project_dir: Option<String>,
+}
+
+/// 无法原样迁移的内容类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConversionLossKind {
+    /// 思考过程（已丢弃）
+    Thinking,
+    /// 图片引用（会话中不含图片数据，已丢弃）
+    Image,
+    /// 找不到配对结果的工具调用（已转为文本）
+    ToolCallAsText,
+    /// 找不到配对调用的工具结果（已转为文本）
+    ToolResultAsText,
+    /// 目标格式无法表达的角色，如 system / developer（已丢弃）
+    UnsupportedRole,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionLoss {
+    pub kind: ConversionLossKind,
+    pub count: u32,
+    /// 示例（工具名、角色名或图片来源），去重后最多 5 条
+    pub samples: Vec<String>,
+}
+
+/// 转换结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionConvertReport {
+    pub provider_id: String,
+    pub session_id: String,
+    pub path: String,
+    pub resume_command: String,
+    pub messages_written: u32,
+    pub losses: Vec<ConversionLoss>,
+}
+
+/// 将会话转换为目标 CLI 的格式并写入其会话目录
+pub fn convert_session(
+    request: &SessionConvertRequest,
+    target: ConvertTarget,
+) -> Result<SessionConvertReport, String> {
+    if request.provider_id == target.provider_id() {
+        return Err(format!(
+            "Session is already in {} format",
+            target.provider_id()
+        ));
+    }
+    let project_dir = request
+        .project_dir
+        .as_deref()
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+        .ok_or_else(|| "Project directory is required to convert a session".to_string())?;
+
+    let messages = load_structured_messages(&request.provider_id, &request.source_path)?;
+    let root = provider_root(target.provider_id())?;
+    convert_messages(
+        messages,
+        target,
+        project_dir,
+        &root,
+        Local::now().timestamp_millis(),
+    )
+}
+
+pub(crate) fn convert_messages(
+    messages: Vec<StructuredMessage>,
+    target: ConvertTarget,
+    project_dir: &str,
+    root: &Path,
+    now_ms: i64,
+) -> Result<SessionConvertReport, String> {
+    let mut losses = LossTracker::default();
+    let mut turns = build_turns(messages, target, &mut losses);
+    pair_tool_calls(&mut turns, &mut losses);
+    if turns.is_empty() {
+        return Err("Session has no messages that can be converted".to_string());
+    }
+
+    let written = match target {
+        ConvertTarget::Claude => write_claude(&turns, project_dir, root, now_ms)?,
+        ConvertTarget::Codex => write_codex(&turns, project_dir, root, now_ms)?,
+        ConvertTarget::Gemini => write_gemini(&turns, project_dir, root, now_ms)?,
+    };
+
+    Ok(SessionConvertReport {
+        provider_id: target.provider_id().to_string(),
+        resume_command: target.resume_command(&written.session_id),
+        session_id: written.session_id,
+        path: written.path.to_string_lossy().to_string(),
+        messages_written: written.messages,
+        losses: losses.losses,
+    })
+}
+
+#[derive(Default)]
+struct LossTracker {
+    losses: Vec<ConversionLoss>,
+}
+
+impl LossTracker {
+    fn record(&mut self, kind: ConversionLossKind, sample: Option<&str>) {
+        let index = match self.losses.iter().position(|loss| loss.kind == kind) {
+            Some(index) => index,
+            None => {
+                self.losses.push(ConversionLoss {
+                    kind,
+                    count: 0,
+                    samples: Vec::new(),
+                });
+                self.losses.len() - 1
+            }
+        };
+
+        let loss = &mut self.losses[index];
+        loss.count += 1;
+        if let Some(sample) = sample.filter(|s| !s.is_empty()) {
+            if loss.samples.len() < MAX_LOSS_SAMPLES && !loss.samples.iter().any(|s| s == sample) {
+                loss.samples.push(sample.to_string());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TurnRole {
+    User,
+    Assistant,
+    /// 工具结果，写入时归属于用户一侧
+    Tool,
+}
+
+/// 按角色归并后的对话轮次：工具调用总在助手轮次、工具结果总在工具轮次
+#[derive(Debug)]
+struct Turn {
+    role: TurnRole,
+    ts: Option<i64>,
+    model: Option<String>,
+    usage: Option<TurnUsage>,
+    parts: Vec<MessagePart>,
+}
+
+fn build_turns(
+    messages: Vec<StructuredMessage>,
+    target: ConvertTarget,
+    losses: &mut LossTracker,
+) -> Vec<Turn> {
+    let mut turns: Vec<Turn> = Vec::new();
+
+    for message in messages {
+        let role = match message.role.as_str() {
+            "user" => TurnRole::User,
+            "assistant" | "gemini" | "model" => TurnRole::Assistant,
+            "tool" => TurnRole::Tool,
+            other => {
+                losses.record(ConversionLossKind::UnsupportedRole, Some(other));
+                continue;
+            }
+        };
+
+        let mut touched_assistant = false;
+        for part in message.parts {
+            let part_role = match &part {
+                MessagePart::ToolCall { .. } => TurnRole::Assistant,
+                MessagePart::ToolResult { .. } => TurnRole::Tool,
+                MessagePart::Thinking { .. } if !target.keeps_thinking() => {
+                    losses.record(ConversionLossKind::Thinking, None);
+                    continue;
+                }
+                MessagePart::Image { media_type, source } => {
+                    let sample = source.as_deref().or(media_type.as_deref());
+                    losses.record(ConversionLossKind::Image, sample);
+                    continue;
+                }
+                _ => role,
+            };
+            touched_assistant |= part_role == TurnRole::Assistant;
+
+            match turns.last_mut() {
+                Some(turn) if turn.role == part_role => turn.parts.push(part),
+                _ => turns.push(Turn {
+                    role: part_role,
+                    ts: message.ts,
+                    model: None,
+                    usage: None,
+                    parts: vec![part],
+                }),
+            }
+        }
+
+        if !touched_assistant {
+            continue;
+        }
+        if let Some(turn) = turns
+            .iter_mut()
+            .rev()
+            .find(|turn| turn.role == TurnRole::Assistant)
+        {
+            if turn.model.is_none() {
+                turn.model = message.model;
+            }
+            if let Some(usage) = message.usage {
+                turn.usage = Some(add_usage(turn.usage.take(), &usage));
+            }
+        }
+    }
+
+    turns
+}
+
+fn add_usage(total: Option<TurnUsage>, usage: &TurnUsage) -> TurnUsage {
+    let mut total = total.unwrap_or_default();
+    total.input_tokens += usage.input_tokens;
+    total.output_tokens += usage.output_tokens;
+    total.cache_read_tokens += usage.cache_read_tokens;
+    total.cache_creation_tokens += usage.cache_creation_tokens;
+    if let Some(reasoning) = usage.reasoning_tokens {
+        total.reasoning_tokens = Some(total.reasoning_tokens.unwrap_or(0) + reasoning);
+    }
+    total
+}
+
+/// 目标 API 要求每个工具调用的结果紧跟在下一条消息中：
+/// 保留能配对的调用与结果，其余转为文本
+fn pair_tool_calls(turns: &mut [Turn], losses: &mut LossTracker) {
+    for index in 0..turns.len() {
+        if turns[index].role != TurnRole::Assistant {
+            continue;
+        }
+        let result_ids: HashSet<String> = match turns.get(index + 1) {
+            Some(next) if next.role == TurnRole::Tool => next
+                .parts
+                .iter()
+                .filter_map(|part| match part {
+                    MessagePart::ToolResult {
+                        tool_call_id: Some(id),
+                        ..
+                    } => Some(id.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => HashSet::new(),
+        };
+
+        let mut seen = HashSet::new();
+        for part in &mut turns[index].parts {
+            let MessagePart::ToolCall { id, name, input } = part else {
+                continue;
+            };
+            let paired = id
+                .as_ref()
+                .is_some_and(|id| result_ids.contains(id) && seen.insert(id.clone()));
+            if !paired {
+                losses.record(ConversionLossKind::ToolCallAsText, Some(name));
+                *part = tool_call_as_text(name, input);
+            }
+        }
+    }
+
+    for index in 0..turns.len() {
+        if turns[index].role != TurnRole::Tool {
+            continue;
+        }
+        let mut call_ids: HashSet<String> = match index.checked_sub(1).map(|i| &turns[i]) {
+            Some(prev) if prev.role == TurnRole::Assistant => prev
+                .parts
+                .iter()
+                .filter_map(|part| match part {
+                    MessagePart::ToolCall { id: Some(id), .. } => Some(id.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => HashSet::new(),
+        };
+
+        for part in &mut turns[index].parts {
+            let MessagePart::ToolResult {
+                tool_call_id,
+                output,
+                ..
+            } = part
+            else {
+                continue;
+            };
+            let paired = tool_call_id.as_ref().is_some_and(|id| call_ids.remove(id));
+            if !paired {
+                losses.record(ConversionLossKind::ToolResultAsText, None);
+                *part = MessagePart::Text {
+                    text: format!("[Tool result]\n{output}"),
+                };
+            }
+        }
+    }
+}
+
+fn tool_call_as_text(name: &str, input: &Value) -> MessagePart {
+    let text = match input {
+        Value::Null => format!("[Tool: {name}]"),
+        Value::String(raw) => format!("[Tool: {name}]\n{raw}"),
+        other => format!("[Tool: {name}]\n{other}"),
+    };
+    MessagePart::Text { text }
+}
+
+struct WrittenSession {
+    session_id: String,
+    path: PathBuf,
+    messages: u32,
+}
+
+fn texts(turn: &Turn) -> Vec<&str> {
+    turn.parts
+        .iter()
+        .filter_map(|part| match part {
+            MessagePart::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn tool_results(turn: &Turn) -> impl Iterator<Item = (&str, &str, bool)> {
+    turn.parts.iter().filter_map(|part| match part {
+        MessagePart::ToolResult {
+            tool_call_id: Some(id),
+            output,
+            is_error,
+        } => Some((id.as_str(), output.as_str(), *is_error)),
+        _ => None,
+    })
+}
+
+/// 工具参数需为对象；其他形式包装为 `{"input": ...}`
+fn object_input(input: &Value) -> Value {
+    match input {
+        Value::Object(_) => input.clone(),
+        Value::Null => json!({}),
+        other => json!({ "input": other }),
+    }
+}
+
+fn iso_ts(ts_ms: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(ts_ms)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn write_jsonl(path: &Path, lines: &[Value]) -> Result<(), String> {
+    let mut content = String::new();
+    for line in lines {
+        content.push_str(&line.to_string());
+        content.push('\n');
+    }
+    atomic_write(path, content.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// Claude Code 以项目路径命名会话目录，非字母数字字符替换为 `-`
+fn claude_project_dir_name(project_dir: &str) -> String {
+    project_dir
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Claude 的 tool_use id 仅允许 `[A-Za-z0-9_-]`
+fn claude_tool_id(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// 写入 `~/.claude/projects/<project>/<id>.jsonl`
+fn write_claude(
+    turns: &[Turn],
+    project_dir: &str,
+    root: &Path,
+    now_ms: i64,
+) -> Result<WrittenSession, String> {
+    let session_id = Uuid::new_v4().to_string();
+    let path = root
+        .join(claude_project_dir_name(project_dir))
+        .join(format!("{session_id}.jsonl"));
+
+    let mut lines = Vec::new();
+    let mut parent: Option<String> = None;
+    let mut ts = turns.first().and_then(|t| t.ts).unwrap_or(now_ms);
+    for turn in turns {
+        ts = turn.ts.unwrap_or(ts);
+        let (kind, message) = match turn.role {
+            TurnRole::Assistant => ("assistant", claude_assistant_message(turn)),
+            TurnRole::User | TurnRole::Tool => ("user", claude_user_message(turn)),
+        };
+        let uuid = Uuid::new_v4().to_string();
+        lines.push(json!({
+            "parentUuid": parent,
+            "isSidechain": false,
+            "userType": "external",
+            "cwd": project_dir,
+            "sessionId": session_id,
+            "type": kind,
+            "message": message,
+            "uuid": uuid,
+            "timestamp": iso_ts(ts),
+        }));
+        parent = Some(uuid);
+    }
+
+    write_jsonl(&path, &lines)?;
+    Ok(WrittenSession {
+        session_id,
+        path,
+        messages: lines.len() as u32,
+    })
+}
+
+fn claude_assistant_message(turn: &Turn) -> Value {
+    let content: Vec<Value> = turn
+        .parts
+        .iter()
+        .filter_map(|part| match part {
+            MessagePart::Text { text } => Some(json!({ "type": "text", "text": text })),
+            MessagePart::ToolCall {
+                id: Some(id),
+                name,
+                input,
+            } => Some(json!({
+                "type": "tool_use",
+                "id": claude_tool_id(id),
+                "name": name,
+                "input": object_input(input),
+            })),
+            _ => None,
+        })
+        .collect();
+
+    let mut message = json!({
+        "id": format!("msg_{}", Uuid::new_v4().simple()),
+        "type": "message",
+        "role": "assistant",
+        "content": content,
+    });
+    if let Some(model) = &turn.model {
+        message["model"] = json!(model);
+    }
+    if let Some(usage) = &turn.usage {
+        message["usage"] = json!({
+            "input_tokens": usage.input_tokens,
+            "output_tokens": usage.output_tokens,
+            "cache_read_input_tokens": usage.cache_read_tokens,
+            "cache_creation_input_tokens": usage.cache_creation_tokens,
+        });
+    }
+    message
+}
+
+/// 工具结果块必须位于用户消息开头
+fn claude_user_message(turn: &Turn) -> Value {
+    let texts = texts(turn);
+    let mut content: Vec<Value> = tool_results(turn)
+        .map(|(id, output, is_error)| {
+            json!({
+                "type": "tool_result",
+                "tool_use_id": claude_tool_id(id),
+                "content": output,
+                "is_error": is_error,
+            })
+        })
+        .collect();
+
+    if content.is_empty() && texts.len() == 1 {
+        return json!({ "role": "user", "content": texts[0] });
+    }
+    content.extend(
+        texts
+            .into_iter()
+            .map(|text| json!({ "type": "text", "text": text })),
+    );
+    json!({ "role": "user", "content": content })
+}
+
+/// 写入 `~/.codex/sessions/YYYY/MM/DD/rollout-<time>-<id>.jsonl`
+fn write_codex(
+    turns: &[Turn],
+    project_dir: &str,
+    root: &Path,
+    now_ms: i64,
+) -> Result<WrittenSession, String> {
+    let session_id = Uuid::new_v4().to_string();
+    let start_ms = turns.first().and_then(|t| t.ts).unwrap_or(now_ms);
+    let start = Local
+        .timestamp_millis_opt(start_ms)
+        .single()
+        .unwrap_or_else(Local::now);
+    let path = root
+        .join(start.format("%Y").to_string())
+        .join(start.format("%m").to_string())
+        .join(start.format("%d").to_string())
+        .join(format!(
+            "rollout-{}-{session_id}.jsonl",
+            start.format("%Y-%m-%dT%H-%M-%S")
+        ));
+
+    let line = |ts: i64, kind: &str, payload: Value| json!({ "timestamp": iso_ts(ts), "type": kind, "payload": payload });
+
+    let mut lines = vec![line(
+        start_ms,
+        "session_meta",
+        json!({
+            "id": session_id,
+            "timestamp": iso_ts(start_ms),
+            "cwd": project_dir,
+            "originator": "cc-switch",
+            "cli_version": env!("CARGO_PKG_VERSION"),
+            "instructions": null,
+        }),
+    )];
+    let mut messages = 0;
+    let mut current_model: Option<&str> = None;
+    let mut total_usage: Option<TurnUsage> = None;
+    let mut ts = start_ms;
+
+    for turn in turns {
+        ts = turn.ts.unwrap_or(ts);
+        match turn.role {
+            TurnRole::Assistant => {
+                if let Some(model) = turn.model.as_deref() {
+                    if current_model != Some(model) {
+                        current_model = Some(model);
+                        lines.push(line(
+                            ts,
+                            "turn_context",
+                            json!({
+                                "cwd": project_dir,
+                                "approval_policy": "on-request",
+                                "sandbox_policy": { "mode": "workspace-write" },
+                                "model": model,
+                                "summary": "auto",
+                            }),
+                        ));
+                    }
+                }
+                for part in &turn.parts {
+                    match part {
+                        MessagePart::Text { text } => {
+                            lines.push(line(
+                                ts,
+                                "response_item",
+                                json!({
+                                    "type": "message",
+                                    "role": "assistant",
+                                    "content": [{ "type": "output_text", "text": text }],
+                                }),
+                            ));
+                            lines.push(line(
+                                ts,
+                                "event_msg",
+                                json!({ "type": "agent_message", "message": text }),
+                            ));
+                        }
+                        MessagePart::ToolCall {
+                            id: Some(id),
+                            name,
+                            input,
+                        } => {
+                            let arguments = match input {
+                                Value::String(raw) => raw.clone(),
+                                other => object_input(other).to_string(),
+                            };
+                            lines.push(line(
+                                ts,
+                                "response_item",
+                                json!({
+                                    "type": "function_call",
+                                    "name": name,
+                                    "arguments": arguments,
+                                    "call_id": id,
+                                }),
+                            ));
+                        }
+                        _ => continue,
+                    }
+                    messages += 1;
+                }
+                if let Some(usage) = &turn.usage {
+                    let total = add_usage(total_usage.take(), usage);
+                    lines.push(line(
+                        ts,
+                        "event_msg",
+                        json!({
+                            "type": "token_count",
+                            "info": {
+                                "total_token_usage": codex_usage(&total),
+                                "last_token_usage": codex_usage(usage),
+                            },
+                        }),
+                    ));
+                    total_usage = Some(total);
+                }
+            }
+            TurnRole::User | TurnRole::Tool => {
+                for (id, output, _) in tool_results(turn) {
+                    lines.push(line(
+                        ts,
+                        "response_item",
+                        json!({ "type": "function_call_output", "call_id": id, "output": output }),
+                    ));
+                    messages += 1;
+                }
+                let texts = texts(turn);
+                if texts.is_empty() {
+                    continue;
+                }
+                let content: Vec<Value> = texts
+                    .iter()
+                    .map(|text| json!({ "type": "input_text", "text": text }))
+                    .collect();
+                lines.push(line(
+                    ts,
+                    "response_item",
+                    json!({ "type": "message", "role": "user", "content": content }),
+                ));
+                lines.push(line(
+                    ts,
+                    "event_msg",
+                    json!({ "type": "user_message", "message": texts.join("\n\n") }),
+                ));
+                messages += 1;
+            }
+        }
+    }
+
+    write_jsonl(&path, &lines)?;
+    Ok(WrittenSession {
+        session_id,
+        path,
+        messages,
+    })
+}
+
+fn codex_usage(usage: &TurnUsage) -> Value {
+    json!({
+        "input_tokens": usage.input_tokens,
+        "cached_input_tokens": usage.cache_read_tokens,
+        "output_tokens": usage.output_tokens,
+        "reasoning_output_tokens": usage.reasoning_tokens.unwrap_or(0),
+        "total_tokens": usage.input_tokens + usage.output_tokens,
+    })
+}
+
+/// 写入 `~/.gemini/tmp/<sha256(project)>/chats/session-<time>-<id>.json`
+fn write_gemini(
+    turns: &[Turn],
+    project_dir: &str,
+    root: &Path,
+    now_ms: i64,
+) -> Result<WrittenSession, String> {
+    let session_id = Uuid::new_v4().to_string();
+    let project_hash = format!("{:x}", Sha256::digest(project_dir.as_bytes()));
+    let project_root = root.join(&project_hash);
+    let start_ms = turns.first().and_then(|t| t.ts).unwrap_or(now_ms);
+    let start = DateTime::<Utc>::from_timestamp_millis(start_ms).unwrap_or_default();
+    let path = project_root.join("chats").join(format!(
+        "session-{}-{}.json",
+        start.format("%Y-%m-%dT%H-%M"),
+        &session_id[..8]
+    ));
+
+    let mut messages = Vec::new();
+    let mut ts = start_ms;
+    for (index, turn) in turns.iter().enumerate() {
+        ts = turn.ts.unwrap_or(ts);
+        let texts = texts(turn);
+        match turn.role {
+            TurnRole::Assistant => {
+                // 工具结果内嵌在调用中，取自紧随其后的工具轮次
+                let results: HashMap<&str, (&str, bool)> = turns
+                    .get(index + 1)
+                    .filter(|next| next.role == TurnRole::Tool)
+                    .map(|next| {
+                        tool_results(next)
+                            .map(|(id, output, is_error)| (id, (output, is_error)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                let mut message = json!({
+                    "id": Uuid::new_v4().to_string(),
+                    "timestamp": iso_ts(ts),
+                    "type": "gemini",
+                    "content": texts.join("\n\n"),
+                });
+                let thoughts: Vec<Value> = turn
+                    .parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        MessagePart::Thinking { text } => Some(json!({
+                            "subject": "",
+                            "description": text,
+                            "timestamp": iso_ts(ts),
+                        })),
+                        _ => None,
+                    })
+                    .collect();
+                if !thoughts.is_empty() {
+                    message["thoughts"] = json!(thoughts);
+                }
+                let tool_calls: Vec<Value> = turn
+                    .parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        MessagePart::ToolCall {
+                            id: Some(id),
+                            name,
+                            input,
+                        } => {
+                            let (output, is_error) =
+                                results.get(id.as_str()).copied().unwrap_or(("", false));
+                            let response = if is_error {
+                                json!({ "error": output })
+                            } else {
+                                json!({ "output": output })
+                            };
+                            Some(json!({
+                                "id": id,
+                                "name": name,
+                                "args": object_input(input),
+                                "result": [{
+                                    "functionResponse": { "id": id, "name": name, "response": response },
+                                }],
+                                "status": if is_error { "error" } else { "success" },
+                                "timestamp": iso_ts(ts),
+                            }))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                if !tool_calls.is_empty() {
+                    message["toolCalls"] = json!(tool_calls);
+                }
+                if let Some(model) = &turn.model {
+                    message["model"] = json!(model);
+                }
+                if let Some(usage) = &turn.usage {
+                    message["tokens"] = json!({
+                        "input": usage.input_tokens,
+                        "output": usage.output_tokens,
+                        "cached": usage.cache_read_tokens,
+                        "thoughts": usage.reasoning_tokens.unwrap_or(0),
+                        "tool": 0,
+                        "total": usage.input_tokens + usage.output_tokens,
+                    });
+                }
+                messages.push(message);
+            }
+            TurnRole::User | TurnRole::Tool => {
+                if texts.is_empty() {
+                    continue;
+                }
+                messages.push(json!({
+                    "id": Uuid::new_v4().to_string(),
+                    "timestamp": iso_ts(ts),
+                    "type": "user",
+                    "content": texts.join("\n\n"),
+                }));
+            }
+        }
+    }
+
+    let marker = project_root.join(".project_root");
+    if !marker.exists() {
+        atomic_write(&marker, project_dir.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let session = json!({
+        "sessionId": session_id,
+        "projectHash": project_hash,
+        "startTime": iso_ts(start_ms),
+        "lastUpdated": iso_ts(ts),
+        "messages": messages,
+    });
+    write_json_file(&path, &session).map_err(|e| e.to_string())?;
+
+    Ok(WrittenSession {
+        session_id,
+        path,
+        messages: messages.len() as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_manager::providers::{claude, codex, gemini};
+    use tempfile::tempdir;
+
+    fn text(text: &str) -> MessagePart {
+        MessagePart::Text {
+            text: text.to_string(),
+        }
+    }
+
+    fn call(id: &str, name: &str) -> MessagePart {
+        MessagePart::ToolCall {
+            id: Some(id.to_string()),
+            name: name.to_string(),
+            input: json!({ "cmd": ["ls"] }),
+        }
+    }
+
+    fn result(id: &str, output: &str) -> MessagePart {
+        MessagePart::ToolResult {
+            tool_call_id: Some(id.to_string()),
+            output: output.to_string(),
+            is_error: false,
+        }
+    }
+
+    /// Codex 风格的会话：调用与结果分属两条消息
+    fn codex_like_messages() -> Vec<StructuredMessage> {
+        let mut reply = StructuredMessage::new(
+            "assistant",
+            vec![
+                MessagePart::Thinking {
+                    text: "List the directory.".to_string(),
+                },
+                call("call_1", "shell"),
+            ],
+            Some(2_000),
+        );
+        reply.model = Some("gpt-5-codex".to_string());
+        reply.usage = Some(TurnUsage {
+            input_tokens: 100,
+            output_tokens: 20,
+            cache_read_tokens: 50,
+            ..Default::default()
+        });
+
+        vec![
+            StructuredMessage::new("developer", vec![text("sandbox rules")], Some(900)),
+            StructuredMessage::new("user", vec![text("list files")], Some(1_000)),
+            reply,
+            StructuredMessage::new("tool", vec![result("call_1", "a.txt")], Some(3_000)),
+            StructuredMessage::new("assistant", vec![text("Found a.txt.")], Some(4_000)),
+        ]
+    }
+
+    #[test]
+    fn converts_codex_session_to_claude_jsonl() {
+        let temp = tempdir().expect("tempdir");
+        let report = convert_messages(
+            codex_like_messages(),
+            ConvertTarget::Claude,
+            "/work/my.app",
+            temp.path(),
+            0,
+        )
+        .expect("convert");
+
+        let path = PathBuf::from(&report.path);
+        assert_eq!(path.parent().unwrap(), temp.path().join("-work-my-app"));
+        assert_eq!(
+            report.resume_command,
+            format!("claude --resume {}", report.session_id)
+        );
+        assert_eq!(report.messages_written, 4);
+
+        let msgs = claude::load_structured_messages(&path).expect("load");
+        assert_eq!(msgs.len(), 4);
+        assert_eq!(msgs[1].parts, vec![call("call_1", "shell")]);
+        assert_eq!(msgs[1].model.as_deref(), Some("gpt-5-codex"));
+        assert_eq!(
+            msgs[1].usage.as_ref().map(|u| u.cache_read_tokens),
+            Some(50)
+        );
+        assert_eq!(msgs[2].role, "tool");
+        assert_eq!(msgs[2].parts, vec![result("call_1", "a.txt")]);
+
+        let kinds: Vec<_> = report.losses.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ConversionLossKind::UnsupportedRole,
+                ConversionLossKind::Thinking
+            ]
+        );
+        assert_eq!(report.losses[0].samples, vec!["developer".to_string()]);
+    }
+
+    #[test]
+    fn splits_inline_results_and_degrades_unpaired_tool_calls() {
+        // OpenCode 风格：调用与结果位于同一条助手消息
+        let messages = vec![
+            StructuredMessage::new("user", vec![text("run it")], Some(1_000)),
+            StructuredMessage::new(
+                "assistant",
+                vec![
+                    call("c1", "bash"),
+                    result("c1", "ok"),
+                    call("c2", "read"),
+                    text("Done."),
+                ],
+                Some(2_000),
+            ),
+            StructuredMessage::new("tool", vec![result("c9", "stray")], Some(3_000)),
+        ];
+
+        let mut losses = LossTracker::default();
+        let mut turns = build_turns(messages, ConvertTarget::Claude, &mut losses);
+        pair_tool_calls(&mut turns, &mut losses);
+
+        let roles: Vec<_> = turns.iter().map(|t| t.role).collect();
+        assert_eq!(
+            roles,
+            vec![
+                TurnRole::User,
+                TurnRole::Assistant,
+                TurnRole::Tool,
+                TurnRole::Assistant,
+                TurnRole::Tool,
+            ]
+        );
+        assert_eq!(turns[1].parts, vec![call("c1", "bash")]);
+        assert_eq!(turns[2].parts, vec![result("c1", "ok")]);
+        assert_eq!(
+            turns[3].parts,
+            vec![text("[Tool: read]\n{\"cmd\":[\"ls\"]}"), text("Done.")]
+        );
+        assert_eq!(turns[4].parts, vec![text("[Tool result]\nstray")]);
+
+        let counts: Vec<_> = losses.losses.iter().map(|l| (l.kind, l.count)).collect();
+        assert_eq!(
+            counts,
+            vec![
+                (ConversionLossKind::ToolCallAsText, 1),
+                (ConversionLossKind::ToolResultAsText, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_session_to_codex_rollout() {
+        let temp = tempdir().expect("tempdir");
+        let report = convert_messages(
+            codex_like_messages(),
+            ConvertTarget::Codex,
+            "/work/app",
+            temp.path(),
+            0,
+        )
+        .expect("convert");
+
+        let path = PathBuf::from(&report.path);
+        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+        assert!(file_name.starts_with("rollout-"));
+        assert!(file_name.ends_with(&format!("-{}.jsonl", report.session_id)));
+        assert_eq!(path.ancestors().nth(4).unwrap(), temp.path());
+
+        let msgs = codex::load_structured_messages(&path).expect("load");
+        let roles: Vec<_> = msgs.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "tool", "assistant"]);
+        assert_eq!(msgs[1].parts, vec![call("call_1", "shell")]);
+        assert_eq!(msgs[1].model.as_deref(), Some("gpt-5-codex"));
+        assert_eq!(msgs[1].usage.as_ref().map(|u| u.input_tokens), Some(100));
+        assert_eq!(msgs[2].parts, vec![result("call_1", "a.txt")]);
+    }
+
+    #[test]
+    fn converts_session_to_gemini_chat_with_thoughts() {
+        let temp = tempdir().expect("tempdir");
+        let report = convert_messages(
+            codex_like_messages(),
+            ConvertTarget::Gemini,
+            "/work/app",
+            temp.path(),
+            0,
+        )
+        .expect("convert");
+
+        let path = PathBuf::from(&report.path);
+        let project_root = path.parent().unwrap().parent().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(project_root.join(".project_root")).unwrap(),
+            "/work/app"
+        );
+
+        let msgs = gemini::load_structured_messages(&path).expect("load");
+        assert_eq!(msgs.len(), 3);
+        assert_eq!(
+            msgs[1].parts,
+            vec![
+                MessagePart::Thinking {
+                    text: "List the directory.".to_string()
+                },
+                call("call_1", "shell"),
+                result("call_1", "a.txt"),
+            ]
+        );
+        assert_eq!(msgs[1].model.as_deref(), Some("gpt-5-codex"));
+        // Gemini 保留思考摘要，只丢弃 developer 消息
+        assert_eq!(report.losses.len(), 1);
+    }
+
+    #[test]
+    fn rejects_same_format_and_missing_project_dir() {
+        let mut request = SessionConvertRequest {
+            provider_id: "claude".to_string(),
+            session_id: "s1".to_string(),
+            source_path: "/tmp/s1.jsonl".to_string(),
+            project_dir: Some("/work/app".to_string()),
+        };
+        assert!(convert_session(&request, ConvertTarget::Claude).is_err());
+
+        request.project_dir = Some("  ".to_string());
+        let err = convert_session(&request, ConvertTarget::Codex).unwrap_err();
+        assert!(err.contains("Project directory"), "{err}");
+    }
+}
@@ -0,0 +1,820 @@
+//! 会话导出
+//!
+//! 基于 `load_structured_messages` 将会话渲染为 Markdown、自包含 HTML 或规范化 JSON，
+//! 保留工具调用参数、思考过程与每轮用量；可选遮蔽路径与凭据，并支持将多个会话批量打包为 zip。
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{Local, TimeZone};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+
+use serde_json::Value;
+
+use super::{load_structured_messages, MessagePart, StructuredMessage, TurnUsage};
+
+/// 用户主目录（macOS / Linux / Windows）
+static HOME_PATH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:/Users|/home)/[^/\s]+|[A-Za-z]:\\Users\\[^\\\s]+").expect("valid regex")
+});
+
+/// 常见凭据格式，整体替换
+static SECRET_TOKENS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"sk-ant-[A-Za-z0-9_\-]{10,}",
+        r"|sk-[A-Za-z0-9_\-]{16,}",
+        r"|github_pat_[A-Za-z0-9_]{20,}",
+        r"|gh[pousr]_[A-Za-z0-9]{20,}",
+        r"|AKIA[0-9A-Z]{16}",
+        r"|AIza[0-9A-Za-z_\-]{35}",
+        r"|xox[abprs]-[A-Za-z0-9\-]{10,}",
+        r"|eyJ[A-Za-z0-9_\-]{10,}\.[A-Za-z0-9_\-]{10,}\.[A-Za-z0-9_\-]{10,}",
+    ))
+    .expect("valid regex")
+});
+
+/// `Bearer xxx` 与 `api_key=xxx` / `"token": "xxx"` 形式的赋值，保留前缀
+static SECRET_ASSIGNMENTS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)(bearer\s+|(?:api[_-]?key|secret|token|password|passwd)["']?\s*[:=]\s*["']?)[A-Za-z0-9._~+/\-]{8,}"#,
+    )
+    .expect("valid regex")
+});
+
+/// 工具参数中表示凭据的对象键（如 `api_key`、`ANTHROPIC_AUTH_TOKEN`），其字符串值整体替换
+static SECRET_KEY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(?:api[_-]?key|secret|token|password|passwd)(?:$|[^a-z])")
+        .expect("valid regex")
+});
+
+const REDACTED: &str = "[REDACTED]";
+const PROJECT_PLACEHOLDER: &str = "<project>";
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// 导出选项
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// 将项目目录替换为 `<project>`、用户主目录替换为 `~`
+    #[serde(default)]
+    pub redact_paths: bool,
+    /// 遮蔽 API Key、Token 等凭据
+    #[serde(default)]
+    pub redact_secrets: bool,
+}
+
+/// 待导出的会话
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExportRequest {
+    pub provider_id: String,
+    pub session_id: String,
+    pub source_path: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub project_dir: Option<String>,
+}
+
+/// 规范化的会话记录（JSON 导出格式）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTranscript {
+    pub provider_id: String,
+    pub session_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    pub exported_at: i64,
+    pub messages: Vec<StructuredMessage>,
+}
+
+/// 批量导出中单个会话的失败信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExportFailure {
+    pub provider_id: String,
+    pub session_id: String,
+    pub error: String,
+}
+
+/// 批量导出结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExportReport {
+    pub path: String,
+    pub exported: u32,
+    pub failed: Vec<SessionExportFailure>,
+}
+
+/// 导出单个会话到文件
+pub fn export_session(
+    request: &SessionExportRequest,
+    options: &SessionExportOptions,
+    target_path: &Path,
+) -> Result<(), String> {
+    let content = render_session(request, options)?;
+    fs::write(target_path, content)
+        .map_err(|e| format!("Failed to write {}: {e}", target_path.display()))
+}
+
+/// 批量导出多个会话为 zip；单个会话失败不会中断整体导出
+pub fn export_sessions_zip(
+    requests: &[SessionExportRequest],
+    options: &SessionExportOptions,
+    target_path: &Path,
+) -> Result<SessionExportReport, String> {
+    let file = fs::File::create(target_path)
+        .map_err(|e| format!("Failed to create {}: {e}", target_path.display()))?;
+    let mut writer = zip::ZipWriter::new(file);
+    let zip_options =
+        SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let mut used_names = HashSet::new();
+    let mut exported = 0;
+    let mut failed = Vec::new();
+
+    for request in requests {
+        let content = match render_session(request, options) {
+            Ok(content) => content,
+            Err(error) => {
+                failed.push(SessionExportFailure {
+                    provider_id: request.provider_id.clone(),
+                    session_id: request.session_id.clone(),
+                    error,
+                });
+                continue;
+            }
+        };
+
+        let name = unique_entry_name(request, options.format, &mut used_names);
+        writer
+            .start_file(name.as_str(), zip_options)
+            .and_then(|_| writer.write_all(content.as_bytes()).map_err(Into::into))
+            .map_err(|e| format!("Failed to write {name} into zip: {e}"))?;
+        exported += 1;
+    }
+
+    writer
+        .finish()
+        .map_err(|e| format!("Failed to finish zip: {e}"))?;
+
+    Ok(SessionExportReport {
+        path: target_path.to_string_lossy().to_string(),
+        exported,
+        failed,
+    })
+}
+
+fn render_session(
+    request: &SessionExportRequest,
+    options: &SessionExportOptions,
+) -> Result<String, String> {
+    let messages = load_structured_messages(&request.provider_id, &request.source_path)?;
+    let transcript = build_transcript(request, messages, options, Local::now().timestamp_millis());
+    render_transcript(&transcript, options.format)
+}
+
+/// 构建规范化会话记录，并按选项遮蔽敏感信息
+pub(crate) fn build_transcript(
+    request: &SessionExportRequest,
+    messages: Vec<StructuredMessage>,
+    options: &SessionExportOptions,
+    exported_at: i64,
+) -> SessionTranscript {
+    let redactor = Redactor::new(options, request.project_dir.as_deref());
+
+    SessionTranscript {
+        provider_id: request.provider_id.clone(),
+        session_id: request.session_id.clone(),
+        title: request.title.as_deref().map(|t| redactor.apply(t)),
+        project_dir: request.project_dir.as_deref().map(|d| redactor.apply(d)),
+        exported_at,
+        messages: messages
+            .into_iter()
+            .map(|mut message| {
+                message.parts = message
+                    .parts
+                    .into_iter()
+                    .map(|part| redactor.apply_part(part))
+                    .collect();
+                message
+            })
+            .collect(),
+    }
+}
+
+struct Redactor<'a> {
+    paths: bool,
+    secrets: bool,
+    project_dir: Option<&'a str>,
+}
+
+impl<'a> Redactor<'a> {
+    fn new(options: &SessionExportOptions, project_dir: Option<&'a str>) -> Self {
+        Self {
+            paths: options.redact_paths,
+            secrets: options.redact_secrets,
+            project_dir: project_dir.filter(|d| !d.trim().is_empty()),
+        }
+    }
+
+    fn apply(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        if self.secrets {
+            out = SECRET_TOKENS.replace_all(&out, REDACTED).into_owned();
+            out = SECRET_ASSIGNMENTS
+                .replace_all(&out, format!("${{1}}{REDACTED}"))
+                .into_owned();
+        }
+        if self.paths {
+            if let Some(dir) = self.project_dir {
+                out = out.replace(dir, PROJECT_PLACEHOLDER);
+            }
+            out = HOME_PATH.replace_all(&out, "~").into_owned();
+        }
+        out
+    }
+
+    fn apply_part(&self, part: MessagePart) -> MessagePart {
+        match part {
+            MessagePart::Text { text } => MessagePart::Text {
+                text: self.apply(&text),
+            },
+            MessagePart::Thinking { text } => MessagePart::Thinking {
+                text: self.apply(&text),
+            },
+            MessagePart::ToolCall { id, name, input } => MessagePart::ToolCall {
+                id,
+                name,
+                input: self.apply_value(input),
+            },
+            MessagePart::ToolResult {
+                tool_call_id,
+                output,
+                is_error,
+            } => MessagePart::ToolResult {
+                tool_call_id,
+                output: self.apply(&output),
+                is_error,
+            },
+            MessagePart::Image { media_type, source } => MessagePart::Image {
+                media_type,
+                source: source.map(|s| self.apply(&s)),
+            },
+        }
+    }
+
+    /// 递归遮蔽工具参数中的字符串值
+    fn apply_value(&self, value: Value) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.apply(&text)),
+            Value::Array(items) => {
+                Value::Array(items.into_iter().map(|v| self.apply_value(v)).collect())
+            }
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(k, v)| {
+                        let v = if self.secrets && SECRET_KEY.is_match(&k) {
+                            mask_strings(v)
+                        } else {
+                            self.apply_value(v)
+                        };
+                        (k, v)
+                    })
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+}
+
+/// 将值中的所有字符串替换为 [`REDACTED`]
+fn mask_strings(value: Value) -> Value {
+    match value {
+        Value::String(_) => Value::String(REDACTED.to_string()),
+        Value::Array(items) => Value::Array(items.into_iter().map(mask_strings).collect()),
+        Value::Object(map) => {
+            Value::Object(map.into_iter().map(|(k, v)| (k, mask_strings(v))).collect())
+        }
+        other => other,
+    }
+}
+
+pub(crate) fn render_transcript(
+    transcript: &SessionTranscript,
+    format: ExportFormat,
+) -> Result<String, String> {
+    match format {
+        ExportFormat::Markdown => Ok(render_markdown(transcript)),
+        ExportFormat::Html => Ok(render_html(transcript)),
+        ExportFormat::Json => serde_json::to_string_pretty(transcript)
+            .map_err(|e| format!("Failed to serialize session: {e}")),
+    }
+}
+
+fn render_markdown(transcript: &SessionTranscript) -> String {
+    let mut out = format!("# {}\n\n", transcript_title(transcript));
+    out.push_str(&format!("- Provider: {}\n", transcript.provider_id));
+    out.push_str(&format!("- Session: `{}`\n", transcript.session_id));
+    if let Some(dir) = &transcript.project_dir {
+        out.push_str(&format!("- Project: `{dir}`\n"));
+    }
+    out.push_str(&format!(
+        "- Exported: {}\n",
+        format_ts(transcript.exported_at)
+    ));
+
+    for message in &transcript.messages {
+        out.push_str(&format!("\n## {}", role_label(&message.role)));
+        if let Some(ts) = message.ts {
+            out.push_str(&format!(" · {}", format_ts(ts)));
+        }
+        if let Some(model) = &message.model {
+            out.push_str(&format!(" · `{model}`"));
+        }
+        out.push_str("\n\n");
+
+        for part in &message.parts {
+            match part {
+                MessagePart::Text { text } => {
+                    out.push_str(text);
+                    out.push_str("\n\n");
+                }
+                MessagePart::Thinking { text } => {
+                    out.push_str("> **Thinking:**\n>\n");
+                    for line in text.lines() {
+                        if line.trim().is_empty() {
+                            out.push_str(">\n");
+                        } else {
+                            out.push_str(&format!("> {line}\n"));
+                        }
+                    }
+                    out.push('\n');
+                }
+                MessagePart::ToolCall { name, input, .. } => {
+                    out.push_str(&format!("**Tool call:** `{name}`\n\n"));
+                    if let Some(args) = tool_input_text(input) {
+                        let fence = markdown_fence(&args);
+                        out.push_str(&format!("{fence}json\n{args}\n{fence}\n\n"));
+                    }
+                }
+                MessagePart::ToolResult {
+                    output, is_error, ..
+                } => {
+                    let label = if *is_error {
+                        "Tool error"
+                    } else {
+                        "Tool result"
+                    };
+                    let fence = markdown_fence(output);
+                    out.push_str(&format!("**{label}:**\n\n{fence}\n{output}\n{fence}\n\n"));
+                }
+                MessagePart::Image { media_type, source } => {
+                    out.push_str(&format!(
+                        "*[Image: {}]*\n\n",
+                        image_label(media_type.as_deref(), source.as_deref())
+                    ));
+                }
+            }
+        }
+
+        if let Some(usage) = &message.usage {
+            out.push_str(&format!("*{}*\n\n", usage_label(usage)));
+        }
+    }
+
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;\
+max-width:860px;margin:2rem auto;padding:0 1rem;color:#1f2328;line-height:1.6}\
+header{border-bottom:1px solid #d0d7de;margin-bottom:1.5rem}\
+header dl{display:grid;grid-template-columns:max-content 1fr;gap:.25rem 1rem;font-size:.9rem}\
+header dt{color:#656d76}\
+.message{border:1px solid #d0d7de;border-radius:8px;padding:.75rem 1rem;margin:1rem 0}\
+.message.user{background:#f6f8fa}.message.tool{background:#fbfbf3}\
+.meta{font-size:.8rem;color:#656d76;margin-bottom:.5rem}.role{font-weight:600;color:#1f2328}\
+.text{white-space:pre-wrap;word-break:break-word}\
+.tool-call{font-family:ui-monospace,monospace;font-size:.85rem;color:#8250df}\
+.thinking{border-left:3px solid #d0d7de;padding-left:.75rem;color:#656d76;white-space:pre-wrap}\
+.error summary{color:#cf222e}.usage,.image{font-size:.8rem;color:#656d76}\
+pre{background:#f6f8fa;padding:.75rem;border-radius:6px;overflow-x:auto;font-size:.85rem}";
+
+fn render_html(transcript: &SessionTranscript) -> String {
+    let title = escape_html(&transcript_title(transcript));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>{HTML_STYLE}</style>\n</head>\n<body>\n<header>\n<h1>{title}</h1>\n<dl>\n"
+    );
+    let mut meta_row = |label: &str, value: &str| {
+        out.push_str(&format!(
+            "<dt>{label}</dt><dd>{}</dd>\n",
+            escape_html(value)
+        ));
+    };
+    meta_row("Provider", &transcript.provider_id);
+    meta_row("Session", &transcript.session_id);
+    if let Some(dir) = &transcript.project_dir {
+        meta_row("Project", dir);
+    }
+    meta_row("Exported", &format_ts(transcript.exported_at));
+    out.push_str("</dl>\n</header>\n<main>\n");
+
+    for message in &transcript.messages {
+        let role_class: String = message
+            .role
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+        out.push_str(&format!(
+            "<section class=\"message {role_class}\">\n<div class=\"meta\"><span class=\"role\">{}</span>",
+            escape_html(&role_label(&message.role))
+        ));
+        if let Some(ts) = message.ts {
+            out.push_str(&format!(" · <time>{}</time>", format_ts(ts)));
+        }
+        if let Some(model) = &message.model {
+            out.push_str(&format!(" · <code>{}</code>", escape_html(model)));
+        }
+        out.push_str("</div>\n");
+
+        for part in &message.parts {
+            match part {
+                MessagePart::Text { text } => {
+                    out.push_str(&format!(
+                        "<div class=\"text\">{}</div>\n",
+                        escape_html(text)
+                    ));
+                }
+                MessagePart::Thinking { text } => {
+                    out.push_str(&format!(
+                        "<details class=\"thinking\"><summary>Thinking</summary>{}</details>\n",
+                        escape_html(text)
+                    ));
+                }
+                MessagePart::ToolCall { name, input, .. } => {
+                    out.push_str(&format!(
+                        "<div class=\"tool-call\">Tool call: {}</div>\n",
+                        escape_html(name)
+                    ));
+                    if let Some(args) = tool_input_text(input) {
+                        out.push_str(&format!("<pre>{}</pre>\n", escape_html(&args)));
+                    }
+                }
+                MessagePart::ToolResult {
+                    output, is_error, ..
+                } => {
+                    let (class, label) = if *is_error {
+                        (" class=\"error\"", "Tool error")
+                    } else {
+                        ("", "Tool result")
+                    };
+                    out.push_str(&format!(
+                        "<details{class} open><summary>{label}</summary><pre>{}</pre></details>\n",
+                        escape_html(output)
+                    ));
+                }
+                MessagePart::Image { media_type, source } => {
+                    out.push_str(&format!(
+                        "<div class=\"image\">[Image: {}]</div>\n",
+                        escape_html(&image_label(media_type.as_deref(), source.as_deref()))
+                    ));
+                }
+            }
+        }
+        if let Some(usage) = &message.usage {
+            out.push_str(&format!(
+                "<div class=\"usage\">{}</div>\n",
+                escape_html(&usage_label(usage))
+            ));
+        }
+        out.push_str("</section>\n");
+    }
+
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+fn transcript_title(transcript: &SessionTranscript) -> String {
+    transcript
+        .title
+        .clone()
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| {
+            format!(
+                "{} session {}",
+                transcript.provider_id, transcript.session_id
+            )
+        })
+}
+
+fn role_label(role: &str) -> String {
+    match role {
+        "user" => "User".to_string(),
+        "assistant" => "Assistant".to_string(),
+        "tool" => "Tool".to_string(),
+        "system" => "System".to_string(),
+        other => {
+            let mut chars = other.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => "Unknown".to_string(),
+            }
+        }
+    }
+}
+
+/// 工具参数格式化为 JSON；无参数时返回 `None`
+fn tool_input_text(input: &Value) -> Option<String> {
+    match input {
+        Value::Null => None,
+        Value::Object(map) if map.is_empty() => None,
+        Value::String(text) => Some(text.clone()),
+        other => serde_json::to_string_pretty(other).ok(),
+    }
+}
+
+fn image_label(media_type: Option<&str>, source: Option<&str>) -> String {
+    source.or(media_type).unwrap_or("inline").to_string()
+}
+
+fn usage_label(usage: &TurnUsage) -> String {
+    let mut label = format!(
+        "Tokens: {} in · {} out",
+        usage.input_tokens, usage.output_tokens
+    );
+    if usage.cache_read_tokens > 0 {
+        label.push_str(&format!(" · {} cache read", usage.cache_read_tokens));
+    }
+    if usage.cache_creation_tokens > 0 {
+        label.push_str(&format!(" · {} cache write", usage.cache_creation_tokens));
+    }
+    if let Some(reasoning) = usage.reasoning_tokens.filter(|n| *n > 0) {
+        label.push_str(&format!(" · {reasoning} reasoning"));
+    }
+    label
+}
+
+fn format_ts(ts_ms: i64) -> String {
+    Local
+        .timestamp_millis_opt(ts_ms)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| ts_ms.to_string())
+}
+
+/// 代码块围栏需比内容中最长的连续反引号更长
+fn markdown_fence(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unique_entry_name(
+    request: &SessionExportRequest,
+    format: ExportFormat,
+    used: &mut HashSet<String>,
+) -> String {
+    let stem: String = format!("{}-{}", request.provider_id, request.session_id)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(96)
+        .collect();
+
+    let ext = format.extension();
+    let mut name = format!("{stem}.{ext}");
+    let mut n = 2;
+    while !used.insert(name.clone()) {
+        name = format!("{stem}-{n}.{ext}");
+        n += 1;
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> SessionExportRequest {
+        SessionExportRequest {
+            provider_id: "claude".to_string(),
+            session_id: "abc-123".to_string(),
+            source_path: "/tmp/abc-123.jsonl".to_string(),
+            title: Some("Fix <migration> bug".to_string()),
+            project_dir: Some("/home/alice/work/app".to_string()),
+        }
+    }
+
+    fn text(text: &str) -> MessagePart {
+        MessagePart::Text {
+            text: text.to_string(),
+        }
+    }
+
+    fn sample_messages() -> Vec<StructuredMessage> {
+        let mut assistant = StructuredMessage::new(
+            "assistant",
+            vec![
+                MessagePart::Thinking {
+                    text: "Look at the db module first.".to_string(),
+                },
+                text("Let me check."),
+                MessagePart::ToolCall {
+                    id: Some("toolu_1".to_string()),
+                    name: "Read".to_string(),
+                    input: serde_json::json!({ "file_path": "/home/alice/work/app/db.rs" }),
+                },
+                text("Found it, key sk-ant-REDACTED."),
+            ],
+            Some(2_000),
+        );
+        assistant.model = Some("claude-sonnet-4".to_string());
+        assistant.usage = Some(TurnUsage {
+            input_tokens: 120,
+            output_tokens: 45,
+            cache_read_tokens: 300,
+            ..Default::default()
+        });
+
+        vec![
+            StructuredMessage::new(
+                "user",
+                vec![text("Why does /home/alice/work/app/db.rs fail?")],
+                Some(1_000),
+            ),
+            assistant,
+            StructuredMessage::new(
+                "tool",
+                vec![MessagePart::ToolResult {
+                    tool_call_id: Some("toolu_1".to_string()),
+                    output: "```rust\nfn main() {}\n```".to_string(),
+                    is_error: false,
+                }],
+                None,
+            ),
+        ]
+    }
+
+    #[test]
+    fn transcript_keeps_structured_parts() {
+        let transcript = build_transcript(
+            &request(),
+            sample_messages(),
+            &SessionExportOptions::default(),
+            0,
+        );
+
+        assert_eq!(transcript.messages, sample_messages());
+
+        let json = render_transcript(&transcript, ExportFormat::Json).unwrap();
+        assert!(json.contains("\"type\": \"toolCall\""), "{json}");
+        assert!(json.contains("\"toolCallId\": \"toolu_1\""), "{json}");
+        assert!(json.contains("\"cacheReadTokens\": 300"), "{json}");
+    }
+
+    #[test]
+    fn redaction_masks_paths_and_secrets() {
+        let options = SessionExportOptions {
+            format: ExportFormat::Json,
+            redact_paths: true,
+            redact_secrets: true,
+        };
+        let transcript = build_transcript(&request(), sample_messages(), &options, 0);
+        let json = render_transcript(&transcript, ExportFormat::Json).unwrap();
+
+        assert!(!json.contains("alice"), "{json}");
+        assert!(!json.contains("abcdefghijklmnop"), "{json}");
+        assert!(json.contains("<project>/db.rs"));
+        assert!(json.contains("[REDACTED]"));
+        assert_eq!(transcript.project_dir.as_deref(), Some("<project>"));
+        // 工具参数中的路径同样被遮蔽
+        assert!(matches!(
+            &transcript.messages[1].parts[2],
+            MessagePart::ToolCall { input, .. } if input["file_path"] == "<project>/db.rs"
+        ));
+
+        let redactor = Redactor::new(&options, None);
+        assert_eq!(
+            redactor.apply("Authorization: Bearer abcdef0123456789 api_key=\"supersecret1\""),
+            "Authorization: Bearer [REDACTED] api_key=\"[REDACTED]\""
+        );
+        assert_eq!(redactor.apply(r"C:\Users\bob\repo"), r"~\repo");
+    }
+
+    #[test]
+    fn redaction_masks_secret_keys_in_tool_input() {
+        let options = SessionExportOptions {
+            format: ExportFormat::Json,
+            redact_paths: false,
+            redact_secrets: true,
+        };
+        let redactor = Redactor::new(&options, None);
+        let input = serde_json::json!({
+            "api_key": "abcd1234efgh5678",
+            "env": { "ANTHROPIC_AUTH_TOKEN": "short", "HOME": "/tmp" },
+            "clientSecret": ["a", "b"],
+            "max_tokens": 1024,
+            "tokenizer": "cl100k",
+        });
+        assert_eq!(
+            redactor.apply_value(input.clone()),
+            serde_json::json!({
+                "api_key": "[REDACTED]",
+                "env": { "ANTHROPIC_AUTH_TOKEN": "[REDACTED]", "HOME": "/tmp" },
+                "clientSecret": ["[REDACTED]", "[REDACTED]"],
+                "max_tokens": 1024,
+                "tokenizer": "cl100k",
+            })
+        );
+
+        // 未开启凭据遮蔽时原样保留
+        let plain = Redactor::new(&SessionExportOptions::default(), None);
+        assert_eq!(plain.apply_value(input.clone()), input);
+    }
+
+    #[test]
+    fn markdown_and_html_render_all_parts() {
+        let transcript = build_transcript(
+            &request(),
+            sample_messages(),
+            &SessionExportOptions::default(),
+            0,
+        );
+
+        let md = render_transcript(&transcript, ExportFormat::Markdown).unwrap();
+        assert!(md.starts_with("# Fix <migration> bug\n"));
+        assert!(md.contains("**Tool call:** `Read`"));
+        assert!(md.contains("```json\n{\n  \"file_path\": \"/home/alice/work/app/db.rs\"\n}\n```"));
+        assert!(md.contains("> **Thinking:**\n>\n> Look at the db module first."));
+        assert!(md.contains("*Tokens: 120 in · 45 out · 300 cache read*"));
+        assert!(md.contains(" · `claude-sonnet-4`"));
+        // 工具结果内含 ``` 时使用更长的围栏
+        assert!(md.contains("````\n```rust\nfn main() {}\n```\n````"));
+        assert!(md.contains(&format!("## User · {}", format_ts(1_000))));
+
+        let html = render_transcript(&transcript, ExportFormat::Html).unwrap();
+        assert!(html.contains("<title>Fix &lt;migration&gt; bug</title>"));
+        assert!(html.contains("<section class=\"message tool\">"));
+        assert!(html.contains("Tool call: Read"));
+        assert!(html.contains("<summary>Thinking</summary>"));
+        assert!(!html.contains("<migration>"));
+    }
+
+    #[test]
+    fn zip_entry_names_are_sanitized_and_unique() {
+        let mut used = HashSet::new();
+        let mut req = request();
+        req.session_id = "a/b:c".to_string();
+        assert_eq!(
+            unique_entry_name(&req, ExportFormat::Markdown, &mut used),
+            "claude-a_b_c.md"
+        );
+        assert_eq!(
+            unique_entry_name(&req, ExportFormat::Markdown, &mut used),
+            "claude-a_b_c-2.md"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+pub mod convert;
+pub mod export;
 pub mod providers;
 pub mod terminal;
 
@@ -36,6 +38,124 @@ pub struct SessionMessage {
     pub ts: Option<i64>,
 }
 
+/// 结构化会话消息：保留工具调用、思考过程、图片引用以及每轮的模型与用量。
+///
+/// `SessionMessage` 是它的扁平投影（见 [`StructuredMessage::to_flat`]）。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructuredMessage {
+    pub role: String,
+    pub parts: Vec<MessagePart>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TurnUsage>,
+}
+
+/// 消息内容片段
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum MessagePart {
+    Text {
+        text: String,
+    },
+    Thinking {
+        text: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ToolCall {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        name: String,
+        /// 调用参数（原样保留，字符串形式的参数会尝试解析为 JSON）
+        input: serde_json::Value,
+    },
+    #[serde(rename_all = "camelCase")]
+    ToolResult {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tool_call_id: Option<String>,
+        output: String,
+        is_error: bool,
+    },
+    /// 图片引用：只保留 URL / 路径与类型，不内嵌图片数据
+    #[serde(rename_all = "camelCase")]
+    Image {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        media_type: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+    },
+}
+
+/// 单轮 token 用量
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<u64>,
+}
+
+impl StructuredMessage {
+    pub fn new(role: impl Into<String>, parts: Vec<MessagePart>, ts: Option<i64>) -> Self {
+        Self {
+            role: role.into(),
+            parts,
+            ts,
+            model: None,
+            usage: None,
+        }
+    }
+
+    /// 投影为扁平消息：文本、`[Tool: name]` 标记与工具输出按行拼接，
+    /// 思考过程与图片不计入；内容为空时返回 `None`
+    pub fn to_flat(&self) -> Option<SessionMessage> {
+        let content = self
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                MessagePart::Text { text } => Some(text.clone()),
+                MessagePart::ToolCall { name, .. } => Some(format!("[Tool: {name}]")),
+                MessagePart::ToolResult { output, .. } => Some(output.clone()),
+                MessagePart::Thinking { .. } | MessagePart::Image { .. } => None,
+            })
+            .filter(|text| !text.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if content.trim().is_empty() {
+            return None;
+        }
+
+        Some(SessionMessage {
+            role: self.role.clone(),
+            content,
+            ts: self.ts,
+        })
+    }
+
+    /// 是否没有任何可展示的内容
+    pub fn is_empty(&self) -> bool {
+        self.parts.iter().all(|part| match part {
+            MessagePart::Text { text } | MessagePart::Thinking { text } => text.trim().is_empty(),
+            MessagePart::ToolResult { output, .. } => output.trim().is_empty(),
+            MessagePart::ToolCall { .. } | MessagePart::Image { .. } => false,
+        })
+    }
+}
+
+pub(crate) fn flatten_messages(messages: Vec<StructuredMessage>) -> Vec<SessionMessage> {
+    messages
+        .iter()
+        .filter_map(StructuredMessage::to_flat)
+        .collect()
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DeleteSessionRequest {
@@ -111,6 +231,30 @@ pub fn load_messages(provider_id: &str, source_path: &str) -> Result<Vec<Session
     }
 }
 
+/// 加载结构化会话消息（含工具调用、思考过程、图片引用与每轮用量）
+pub fn load_structured_messages(
+    provider_id: &str,
+    source_path: &str,
+) -> Result<Vec<StructuredMessage>, String> {
+    if provider_id == "opencode" && source_path.starts_with("sqlite:") {
+        return opencode::load_structured_messages_sqlite(source_path);
+    }
+    if provider_id == "hermes" && source_path.starts_with("sqlite:") {
+        return hermes::load_structured_messages_sqlite(source_path);
+    }
+
+    let path = Path::new(source_path);
+    match provider_id {
+        "codex" => codex::load_structured_messages(path),
+        "claude" => claude::load_structured_messages(path),
+        "opencode" => opencode::load_structured_messages(path),
+        "openclaw" => openclaw::load_structured_messages(path),
+        "gemini" => gemini::load_structured_messages(path),
+        "hermes" => hermes::load_structured_messages(path),
+        _ => Err(format!("Unsupported provider: {provider_id}")),
+    }
+}
+
 pub fn delete_session(
     provider_id: &str,
     session_id: &str,
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
@@ -5,11 +6,11 @@ use std::path::{Path, PathBuf};
 use serde_json::Value;
 
 use crate::config::get_claude_config_dir;
-use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::session_manager::{flatten_messages, SessionMessage, SessionMeta, StructuredMessage};
 
 use super::utils::{
-    extract_text, parse_timestamp_to_ms, path_basename, read_head_tail_lines, truncate_summary,
-    TITLE_MAX_CHARS,
+    extract_parts, extract_text, parse_timestamp_to_ms, parse_usage, path_basename,
+    read_head_tail_lines, truncate_summary, TITLE_MAX_CHARS,
 };
 
 const PROVIDER_ID: &str = "claude";
@@ -30,9 +31,15 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
 }
 
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    load_structured_messages(path).map(flatten_messages)
+}
+
+pub fn load_structured_messages(path: &Path) -> Result<Vec<StructuredMessage>, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
     let reader = BufReader::new(file);
     let mut messages = Vec::new();
+    // Claude Code writes one line per content block, repeating the API message's usage
+    let mut seen_message_ids = HashSet::new();
 
     for line in reader.lines() {
         let line = match line {
@@ -72,14 +79,31 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
             }
         }
 
-        let content = message.get("content").map(extract_text).unwrap_or_default();
-        if content.trim().is_empty() {
+        let parts = message
+            .get("content")
+            .map(extract_parts)
+            .unwrap_or_default();
+        let ts = value.get("timestamp").and_then(parse_timestamp_to_ms);
+        let mut structured = StructuredMessage::new(role, parts, ts);
+        if structured.is_empty() {
             continue;
         }
 
-        let ts = value.get("timestamp").and_then(parse_timestamp_to_ms);
+        // "<synthetic>" marks messages generated locally by the CLI
+        structured.model = message
+            .get("model")
+            .and_then(Value::as_str)
+            .filter(|model| !model.starts_with('<'))
+            .map(str::to_string);
+        let first_occurrence = match message.get("id").and_then(Value::as_str) {
+            Some(id) => seen_message_ids.insert(id.to_string()),
+            None => true,
+        };
+        if first_occurrence {
+            structured.usage = message.get("usage").and_then(parse_usage);
+        }
 
-        messages.push(SessionMessage { role, content, ts });
+        messages.push(structured);
     }
 
     Ok(messages)
@@ -302,6 +326,7 @@ fn remove_path_if_exists(path: &Path) -> std::io::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::session_manager::MessagePart;
     use tempfile::tempdir;
 
     #[test]
@@ -352,6 +377,49 @@ mod tests {
         assert_eq!(msgs[1].content, "File written");
     }
 
+    #[test]
+    fn load_structured_messages_keeps_tool_input_and_dedupes_usage() {
+        let temp = tempdir().expect("tempdir");
+        let path = temp.path().join("session.jsonl");
+        std::fs::write(
+            &path,
+            concat!(
+                "{\"message\":{\"id\":\"msg_1\",\"model\":\"claude-sonnet-4\",\"role\":\"assistant\",\"content\":[{\"type\":\"thinking\",\"thinking\":\"Need the file.\"}],\"usage\":{\"input_tokens\":10,\"output_tokens\":5,\"cache_read_input_tokens\":100}},\"timestamp\":\"2026-03-06T10:00:00Z\"}\n",
+                "{\"message\":{\"id\":\"msg_1\",\"model\":\"claude-sonnet-4\",\"role\":\"assistant\",\"content\":[{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"Read\",\"input\":{\"file_path\":\"a.txt\"}}],\"usage\":{\"input_tokens\":10,\"output_tokens\":5,\"cache_read_input_tokens\":100}},\"timestamp\":\"2026-03-06T10:00:01Z\"}\n",
+                "{\"message\":{\"role\":\"user\",\"content\":[{\"type\":\"tool_result\",\"tool_use_id\":\"toolu_1\",\"content\":\"hello\",\"is_error\":true}]},\"timestamp\":\"2026-03-06T10:00:02Z\"}\n",
+            ),
+        )
+        .expect("write");
+
+        let msgs = load_structured_messages(&path).expect("load");
+        assert_eq!(msgs.len(), 3);
+        assert_eq!(msgs[0].model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(
+            msgs[0].usage.as_ref().map(|u| u.cache_read_tokens),
+            Some(100)
+        );
+        // Usage repeated across lines of the same API message is counted once
+        assert!(msgs[1].usage.is_none());
+        assert_eq!(
+            msgs[1].parts,
+            vec![MessagePart::ToolCall {
+                id: Some("toolu_1".to_string()),
+                name: "Read".to_string(),
+                input: serde_json::json!({ "file_path": "a.txt" }),
+            }]
+        );
+        assert_eq!(msgs[2].role, "tool");
+        assert!(matches!(
+            &msgs[2].parts[0],
+            MessagePart::ToolResult { tool_call_id: Some(id), is_error: true, .. } if id == "toolu_1"
+        ));
+
+        // Thinking-only messages are dropped from the flat view
+        let flat = load_messages(&path).expect("load flat");
+        assert_eq!(flat.len(), 2);
+        assert_eq!(flat[0].content, "[Tool: Read]");
+    }
+
     #[test]
     fn load_messages_mixed_text_and_tool_use() {
         let temp = tempdir().expect("tempdir");
@@ -7,11 +7,13 @@ use regex::Regex;
 use serde_json::Value;
 
 use crate::codex_config::get_codex_config_dir;
-use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::session_manager::{
+    flatten_messages, MessagePart, SessionMessage, SessionMeta, StructuredMessage,
+};
 
 use super::utils::{
-    extract_text, parse_timestamp_to_ms, path_basename, read_head_tail_lines, truncate_summary,
-    TITLE_MAX_CHARS,
+    extract_parts, extract_text, parse_timestamp_to_ms, parse_usage, path_basename,
+    read_head_tail_lines, thinking_part, tool_call_part, truncate_summary, TITLE_MAX_CHARS,
 };
 
 const PROVIDER_ID: &str = "codex";
@@ -37,9 +39,16 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
 }
 
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    load_structured_messages(path).map(flatten_messages)
+}
+
+pub fn load_structured_messages(path: &Path) -> Result<Vec<StructuredMessage>, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
     let reader = BufReader::new(file);
-    let mut messages = Vec::new();
+    let mut messages: Vec<StructuredMessage> = Vec::new();
+    let mut current_model: Option<String> = None;
+    // Index of the first message in the current turn; usage never crosses turn boundaries
+    let mut turn_start = 0;
 
     for line in reader.lines() {
         let line = match line {
@@ -51,53 +60,110 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
             Err(_) => continue,
         };
 
-        if value.get("type").and_then(Value::as_str) != Some("response_item") {
-            continue;
-        }
-
         let payload = match value.get("payload") {
             Some(payload) => payload,
             None => continue,
         };
-
         let payload_type = payload.get("type").and_then(Value::as_str).unwrap_or("");
 
+        match value.get("type").and_then(Value::as_str) {
+            Some("response_item") => {}
+            Some("turn_context") => {
+                if let Some(model) = payload.get("model").and_then(Value::as_str) {
+                    current_model = Some(model.to_string());
+                }
+                turn_start = messages.len();
+                continue;
+            }
+            // Token usage is reported after each turn; attach it to the latest assistant item
+            // of the current turn, never to one from an earlier turn
+            Some("event_msg") if payload_type == "token_count" => {
+                let usage = payload
+                    .get("info")
+                    .and_then(|info| info.get("last_token_usage"))
+                    .and_then(parse_usage);
+                if let (Some(usage), Some(last)) = (
+                    usage,
+                    messages[turn_start..]
+                        .iter_mut()
+                        .rev()
+                        .find(|m| m.role == "assistant" && m.usage.is_none()),
+                ) {
+                    last.usage = Some(usage);
+                }
+                continue;
+            }
+            _ => continue,
+        }
+
         // Codex uses separate payload types for tool interactions
-        let (role, content) = match payload_type {
+        let (role, parts) = match payload_type {
             "message" => {
                 let role = payload
                     .get("role")
                     .and_then(Value::as_str)
                     .unwrap_or("unknown")
                     .to_string();
-                let content = payload.get("content").map(extract_text).unwrap_or_default();
-                (role, content)
+                let parts = payload
+                    .get("content")
+                    .map(extract_parts)
+                    .unwrap_or_default();
+                (role, parts)
             }
-            "function_call" => {
-                let name = payload
-                    .get("name")
-                    .and_then(Value::as_str)
-                    .unwrap_or("unknown");
-                ("assistant".to_string(), format!("[Tool: {name}]"))
+            "function_call" | "custom_tool_call" => {
+                let call = tool_call_part(
+                    payload.get("call_id").and_then(Value::as_str),
+                    payload.get("name").and_then(Value::as_str),
+                    payload.get("arguments").or_else(|| payload.get("input")),
+                );
+                ("assistant".to_string(), vec![call])
             }
-            "function_call_output" => {
+            "function_call_output" | "custom_tool_call_output" => {
                 let output = payload
                     .get("output")
                     .and_then(Value::as_str)
                     .unwrap_or("")
                     .to_string();
-                ("tool".to_string(), output)
+                let result = MessagePart::ToolResult {
+                    tool_call_id: payload
+                        .get("call_id")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    output,
+                    is_error: false,
+                };
+                ("tool".to_string(), vec![result])
+            }
+            "reasoning" => {
+                let summary = payload
+                    .get("summary")
+                    .and_then(Value::as_array)
+                    .map(|items| {
+                        items
+                            .iter()
+                            .filter_map(|item| item.get("text").and_then(Value::as_str))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    })
+                    .unwrap_or_default();
+                let parts = thinking_part(&summary).into_iter().collect();
+                ("assistant".to_string(), parts)
             }
             _ => continue,
         };
 
-        if content.trim().is_empty() {
+        let ts = value.get("timestamp").and_then(parse_timestamp_to_ms);
+        let mut structured = StructuredMessage::new(role, parts, ts);
+        if structured.is_empty() {
             continue;
         }
+        if structured.role == "assistant" {
+            structured.model = current_model.clone();
+        } else if structured.role == "user" {
+            turn_start = messages.len();
+        }
 
-        let ts = value.get("timestamp").and_then(parse_timestamp_to_ms);
-
-        messages.push(SessionMessage { role, content, ts });
+        messages.push(structured);
     }
 
     Ok(messages)
@@ -397,4 +463,60 @@ mod tests {
         assert_eq!(msgs[3].role, "assistant");
         assert_eq!(msgs[3].content, "Done.");
     }
+
+    #[test]
+    fn load_structured_messages_attaches_model_and_token_count() {
+        let temp = tempdir().expect("tempdir");
+        let path = temp.path().join("session.jsonl");
+        std::fs::write(
+            &path,
+            concat!(
+                "{\"timestamp\":\"2026-03-06T21:50:12Z\",\"type\":\"turn_context\",\"payload\":{\"cwd\":\"/tmp\",\"model\":\"gpt-5-codex\"}}\n",
+                "{\"timestamp\":\"2026-03-06T21:50:13Z\",\"type\":\"response_item\",\"payload\":{\"type\":\"message\",\"role\":\"user\",\"content\":\"list files\"}}\n",
+                "{\"timestamp\":\"2026-03-06T21:50:14Z\",\"type\":\"response_item\",\"payload\":{\"type\":\"function_call\",\"name\":\"shell\",\"arguments\":\"{\\\"cmd\\\":[\\\"ls\\\"]}\",\"call_id\":\"call_1\"}}\n",
+                "{\"timestamp\":\"2026-03-06T21:50:15Z\",\"type\":\"event_msg\",\"payload\":{\"type\":\"token_count\",\"info\":{\"last_token_usage\":{\"input_tokens\":200,\"cached_input_tokens\":150,\"output_tokens\":20,\"reasoning_output_tokens\":8}}}}\n",
+            ),
+        )
+        .expect("write");
+
+        let msgs = load_structured_messages(&path).expect("load");
+        assert_eq!(msgs.len(), 2);
+        assert!(msgs[0].model.is_none());
+
+        let call = &msgs[1];
+        assert_eq!(call.model.as_deref(), Some("gpt-5-codex"));
+        assert_eq!(
+            call.parts,
+            vec![MessagePart::ToolCall {
+                id: Some("call_1".to_string()),
+                name: "shell".to_string(),
+                input: serde_json::json!({ "cmd": ["ls"] }),
+            }]
+        );
+        let usage = call.usage.as_ref().expect("usage");
+        assert_eq!(usage.input_tokens, 200);
+        assert_eq!(usage.cache_read_tokens, 150);
+        assert_eq!(usage.reasoning_tokens, Some(8));
+    }
+
+    #[test]
+    fn load_structured_messages_keeps_token_count_within_turn() {
+        let temp = tempdir().expect("tempdir");
+        let path = temp.path().join("session.jsonl");
+        std::fs::write(
+            &path,
+            concat!(
+                "{\"timestamp\":\"2026-03-06T21:50:12Z\",\"type\":\"turn_context\",\"payload\":{\"cwd\":\"/tmp\",\"model\":\"gpt-5-codex\"}}\n",
+                "{\"timestamp\":\"2026-03-06T21:50:13Z\",\"type\":\"response_item\",\"payload\":{\"type\":\"message\",\"role\":\"user\",\"content\":\"hi\"}}\n",
+                "{\"timestamp\":\"2026-03-06T21:50:14Z\",\"type\":\"response_item\",\"payload\":{\"type\":\"message\",\"role\":\"assistant\",\"content\":\"hello\"}}\n",
+                "{\"timestamp\":\"2026-03-06T21:50:15Z\",\"type\":\"response_item\",\"payload\":{\"type\":\"message\",\"role\":\"user\",\"content\":\"again\"}}\n",
+                "{\"timestamp\":\"2026-03-06T21:50:16Z\",\"type\":\"event_msg\",\"payload\":{\"type\":\"token_count\",\"info\":{\"last_token_usage\":{\"input_tokens\":50,\"output_tokens\":5}}}}\n",
+            ),
+        )
+        .expect("write");
+
+        let msgs = load_structured_messages(&path).expect("load");
+        assert_eq!(msgs.len(), 3);
+        assert!(msgs.iter().all(|m| m.usage.is_none()));
+    }
 }
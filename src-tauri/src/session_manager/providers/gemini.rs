@@ -2,9 +2,13 @@ use std::path::Path;
 
 use serde_json::Value;
 
-use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::session_manager::{
+    flatten_messages, MessagePart, SessionMessage, SessionMeta, StructuredMessage,
+};
 
-use super::utils::{parse_timestamp_to_ms, truncate_summary};
+use super::utils::{
+    parse_timestamp_to_ms, parse_usage, thinking_part, tool_call_part, truncate_summary,
+};
 
 const PROVIDER_ID: &str = "gemini";
 
@@ -58,6 +62,10 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
 }
 
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    load_structured_messages(path).map(flatten_messages)
+}
+
+pub fn load_structured_messages(path: &Path) -> Result<Vec<StructuredMessage>, String> {
     let data = std::fs::read_to_string(path).map_err(|e| format!("Failed to read session: {e}"))?;
     let value: Value =
         serde_json::from_str(&data).map_err(|e| format!("Failed to parse session JSON: {e}"))?;
@@ -76,8 +84,30 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
             Some(_) | None => continue,
         };
 
+        let mut parts = Vec::new();
+
+        // Thoughts are stored as {subject, description} summaries
+        if let Some(Value::Array(thoughts)) = msg.get("thoughts") {
+            for thought in thoughts {
+                let field = |key: &str| {
+                    thought
+                        .get(key)
+                        .and_then(Value::as_str)
+                        .filter(|text| !text.trim().is_empty())
+                };
+                let subject = field("subject");
+                let description = field("description");
+                let text = match (subject, description) {
+                    (Some(subject), Some(description)) => format!("{subject}: {description}"),
+                    (Some(text), None) | (None, Some(text)) => text.to_string(),
+                    (None, None) => continue,
+                };
+                parts.extend(thinking_part(&text));
+            }
+        }
+
         // Gemini content may be a plain string or an array of {text: ...} objects
-        let mut content = match msg.get("content") {
+        let text = match msg.get("content") {
             Some(Value::String(s)) => s.to_string(),
             Some(Value::Array(items)) => items
                 .iter()
@@ -86,35 +116,61 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
                 .join("\n"),
             _ => String::new(),
         };
+        if !text.trim().is_empty() {
+            parts.push(MessagePart::Text { text });
+        }
 
-        // Append tool call names from the optional toolCalls array
+        // Tool calls carry their own results in the optional toolCalls array
         if let Some(Value::Array(calls)) = msg.get("toolCalls") {
             for call in calls {
-                if let Some(name) = call.get("name").and_then(Value::as_str) {
-                    if !content.is_empty() {
-                        content.push('\n');
-                    }
-                    content.push_str(&format!("[Tool: {name}]"));
+                let Some(name) = call.get("name").and_then(Value::as_str) else {
+                    continue;
+                };
+                let id = call.get("id").and_then(Value::as_str);
+                parts.push(tool_call_part(id, Some(name), call.get("args")));
+                if let Some(output) = gemini_tool_output(call) {
+                    parts.push(MessagePart::ToolResult {
+                        tool_call_id: id.map(str::to_string),
+                        output,
+                        is_error: call.get("status").and_then(Value::as_str) == Some("error"),
+                    });
                 }
             }
         }
 
-        if content.trim().is_empty() {
+        let ts = msg.get("timestamp").and_then(parse_timestamp_to_ms);
+        let mut structured = StructuredMessage::new(role, parts, ts);
+        if structured.is_empty() {
             continue;
         }
+        structured.model = msg.get("model").and_then(Value::as_str).map(str::to_string);
+        structured.usage = msg.get("tokens").and_then(parse_usage);
 
-        let ts = msg.get("timestamp").and_then(parse_timestamp_to_ms);
-
-        result.push(SessionMessage {
-            role: role.to_string(),
-            content,
-            ts,
-        });
+        result.push(structured);
     }
 
     Ok(result)
 }
 
+/// Extract the textual output of a Gemini tool call (`result[].functionResponse.response`)
+fn gemini_tool_output(call: &Value) -> Option<String> {
+    let outputs: Vec<String> = call
+        .get("result")
+        .and_then(Value::as_array)?
+        .iter()
+        .filter_map(|item| item.get("functionResponse")?.get("response"))
+        .filter_map(
+            |response| match response.get("output").or_else(|| response.get("error")) {
+                Some(Value::String(text)) => Some(text.clone()),
+                Some(other) => Some(other.to_string()),
+                None => None,
+            },
+        )
+        .collect();
+
+    (!outputs.is_empty()).then(|| outputs.join("\n"))
+}
+
 pub fn delete_session(_root: &Path, path: &Path, session_id: &str) -> Result<bool, String> {
     let meta = parse_session(path).ok_or_else(|| {
         format!(
@@ -6,10 +6,13 @@ use rusqlite::Connection;
 use serde_json::Value;
 
 use crate::hermes_config::get_hermes_dir;
-use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::session_manager::{
+    flatten_messages, MessagePart, SessionMessage, SessionMeta, StructuredMessage,
+};
 
 use super::utils::{
-    extract_text, parse_timestamp_to_ms, read_head_tail_lines, truncate_summary, TITLE_MAX_CHARS,
+    extract_parts, extract_text, parse_timestamp_to_ms, parse_usage, read_head_tail_lines,
+    text_to_tool_result, tool_call_part, truncate_summary, TITLE_MAX_CHARS,
 };
 
 const PROVIDER_ID: &str = "hermes";
@@ -186,6 +189,10 @@ fn row_to_json(row: &rusqlite::Row, columns: &[String]) -> Value {
 
 /// Load messages from the Hermes SQLite database.
 pub fn load_messages_sqlite(source: &str) -> Result<Vec<SessionMessage>, String> {
+    load_structured_messages_sqlite(source).map(flatten_messages)
+}
+
+pub fn load_structured_messages_sqlite(source: &str) -> Result<Vec<StructuredMessage>, String> {
     let (db_path, session_id) = parse_sqlite_source(source)
         .ok_or_else(|| format!("Invalid SQLite source reference: {source}"))?;
 
@@ -219,11 +226,16 @@ pub fn load_messages_sqlite(source: &str) -> Result<Vec<SessionMessage>, String>
             continue;
         }
         let ts_ms = ts.and_then(|v| parse_timestamp_to_ms(&Value::Number(v.into())));
-        messages.push(SessionMessage {
-            role,
-            content,
-            ts: ts_ms,
-        });
+        let part = if role == "tool" {
+            MessagePart::ToolResult {
+                tool_call_id: None,
+                output: content,
+                is_error: false,
+            }
+        } else {
+            MessagePart::Text { text: content }
+        };
+        messages.push(StructuredMessage::new(role, vec![part], ts_ms));
     }
 
     Ok(messages)
@@ -430,6 +442,10 @@ fn parse_jsonl_session(path: &Path) -> Option<SessionMeta> {
 
 /// Load messages from a Hermes JSONL transcript file.
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    load_structured_messages(path).map(flatten_messages)
+}
+
+pub fn load_structured_messages(path: &Path) -> Result<Vec<StructuredMessage>, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
     let reader = BufReader::new(file);
     let mut messages = Vec::new();
@@ -448,37 +464,45 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
         };
 
         // Support both flat messages and nested {type:"message", message:{...}} format
-        let (role_val, content_val, ts_val) =
-            if value.get("type").and_then(Value::as_str) == Some("message") {
-                let msg = match value.get("message") {
-                    Some(m) => m,
-                    None => continue,
-                };
-                (
-                    msg.get("role"),
-                    msg.get("content"),
-                    value.get("timestamp").or_else(|| msg.get("ts")),
-                )
-            } else {
-                (
-                    value.get("role"),
-                    value.get("content"),
-                    value.get("timestamp").or_else(|| value.get("ts")),
-                )
+        let (msg, ts_val) = if value.get("type").and_then(Value::as_str) == Some("message") {
+            let msg = match value.get("message") {
+                Some(m) => m,
+                None => continue,
             };
+            (msg, value.get("timestamp").or_else(|| msg.get("ts")))
+        } else {
+            (&value, value.get("timestamp").or_else(|| value.get("ts")))
+        };
 
-        let role = match role_val.and_then(Value::as_str) {
+        let role = match msg.get("role").and_then(Value::as_str) {
             Some(r) => r.to_string(),
             None => continue,
         };
 
-        let content = content_val.map(extract_text).unwrap_or_default();
-        if content.trim().is_empty() {
-            continue;
+        let mut parts = msg.get("content").map(extract_parts).unwrap_or_default();
+        if role == "tool" {
+            parts = text_to_tool_result(parts, msg.get("tool_call_id").and_then(Value::as_str));
+        }
+        // OpenAI-style assistant tool calls: [{id, function: {name, arguments}}]
+        if let Some(Value::Array(calls)) = msg.get("tool_calls") {
+            for call in calls {
+                let function = call.get("function").unwrap_or(call);
+                parts.push(tool_call_part(
+                    call.get("id").and_then(Value::as_str),
+                    function.get("name").and_then(Value::as_str),
+                    function.get("arguments"),
+                ));
+            }
         }
 
         let ts = ts_val.and_then(parse_timestamp_to_ms);
-        messages.push(SessionMessage { role, content, ts });
+        let mut structured = StructuredMessage::new(role, parts, ts);
+        if structured.is_empty() {
+            continue;
+        }
+        structured.model = msg.get("model").and_then(Value::as_str).map(str::to_string);
+        structured.usage = msg.get("usage").and_then(parse_usage);
+        messages.push(structured);
     }
 
     Ok(messages)
@@ -8,12 +8,12 @@ use serde_json::Value;
 use crate::openclaw_config::get_openclaw_dir;
 use crate::{
     config::write_json_file,
-    session_manager::{SessionMessage, SessionMeta},
+    session_manager::{flatten_messages, SessionMessage, SessionMeta, StructuredMessage},
 };
 
 use super::utils::{
-    extract_text, parse_timestamp_to_ms, path_basename, read_head_tail_lines, truncate_summary,
-    TITLE_MAX_CHARS,
+    extract_parts, extract_text, parse_timestamp_to_ms, parse_usage, path_basename,
+    read_head_tail_lines, text_to_tool_result, truncate_summary, TITLE_MAX_CHARS,
 };
 
 const PROVIDER_ID: &str = "openclaw";
@@ -75,6 +75,10 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
 }
 
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    load_structured_messages(path).map(flatten_messages)
+}
+
+pub fn load_structured_messages(path: &Path) -> Result<Vec<StructuredMessage>, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
     let reader = BufReader::new(file);
     let mut messages = Vec::new();
@@ -109,14 +113,27 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
             other => other.to_string(),
         };
 
-        let content = message.get("content").map(extract_text).unwrap_or_default();
-        if content.trim().is_empty() {
-            continue;
+        let mut parts = message
+            .get("content")
+            .map(extract_parts)
+            .unwrap_or_default();
+        if role == "tool" {
+            let tool_call_id = message.get("toolCallId").and_then(Value::as_str);
+            parts = text_to_tool_result(parts, tool_call_id);
         }
 
         let ts = value.get("timestamp").and_then(parse_timestamp_to_ms);
+        let mut structured = StructuredMessage::new(role, parts, ts);
+        if structured.is_empty() {
+            continue;
+        }
+        structured.model = message
+            .get("model")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        structured.usage = message.get("usage").and_then(parse_usage);
 
-        messages.push(SessionMessage { role, content, ts });
+        messages.push(structured);
     }
 
     Ok(messages)
@@ -3,9 +3,14 @@ use std::path::{Path, PathBuf};
 use rusqlite::Connection;
 use serde_json::Value;
 
-use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::session_manager::{
+    flatten_messages, MessagePart, SessionMessage, SessionMeta, StructuredMessage, TurnUsage,
+};
 
-use super::utils::{parse_timestamp_to_ms, path_basename, truncate_summary};
+use super::utils::{
+    parse_timestamp_to_ms, parse_usage, path_basename, thinking_part, tool_call_part,
+    truncate_summary,
+};
 
 const PROVIDER_ID: &str = "opencode";
 
@@ -156,6 +161,10 @@ fn scan_sessions_sqlite() -> Vec<SessionMeta> {
 }
 
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    load_structured_messages(path).map(flatten_messages)
+}
+
+pub fn load_structured_messages(path: &Path) -> Result<Vec<StructuredMessage>, String> {
     // `path` is the message directory: storage/message/{sessionID}/
     if !path.is_dir() {
         return Err(format!("Message directory not found: {}", path.display()));
@@ -169,8 +178,8 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
     let mut msg_files = Vec::new();
     collect_json_files(path, &mut msg_files);
 
-    // Parse all messages and collect (created_ts, message_id, role, parts_text)
-    let mut entries: Vec<(i64, String, String, String)> = Vec::new();
+    // Parse all messages and collect (created_ts, message)
+    let mut entries: Vec<(i64, StructuredMessage)> = Vec::new();
 
     for msg_path in &msg_files {
         let data = match std::fs::read_to_string(msg_path) {
@@ -199,27 +208,22 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
             .and_then(parse_timestamp_to_ms)
             .unwrap_or(0);
 
-        // Collect text parts from storage/part/{messageID}/
+        // Collect parts from storage/part/{messageID}/
         let part_dir = storage.join("part").join(&msg_id);
-        let text = collect_parts_text(&part_dir);
-        if text.trim().is_empty() {
+        let ts = (created_ts > 0).then_some(created_ts);
+        let mut message = StructuredMessage::new(role, collect_parts(&part_dir), ts);
+        if message.is_empty() {
             continue;
         }
+        apply_message_info(&mut message, &value);
 
-        entries.push((created_ts, msg_id, role, text));
+        entries.push((created_ts, message));
     }
 
     // Sort by created timestamp
-    entries.sort_by_key(|(ts, _, _, _)| *ts);
+    entries.sort_by_key(|(ts, _)| *ts);
 
-    let messages = entries
-        .into_iter()
-        .map(|(ts, _, role, content)| SessionMessage {
-            role,
-            content,
-            ts: if ts > 0 { Some(ts) } else { None },
-        })
-        .collect();
+    let messages = entries.into_iter().map(|(_, message)| message).collect();
 
     Ok(messages)
 }
@@ -227,6 +231,11 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
 /// Load messages from the OpenCode SQLite database for a given source reference.
 /// Joins the `message` and `part` tables in memory to reconstruct full messages.
 pub fn load_messages_sqlite(source: &str) -> Result<Vec<SessionMessage>, String> {
+    load_structured_messages_sqlite(source).map(flatten_messages)
+}
+
+/// Structured variant of [`load_messages_sqlite`].
+pub fn load_structured_messages_sqlite(source: &str) -> Result<Vec<StructuredMessage>, String> {
     let (db_path, session_id) = parse_sqlite_source(source)
         .ok_or_else(|| format!("Invalid SQLite source reference: {source}"))?;
 
@@ -285,29 +294,24 @@ pub fn load_messages_sqlite(source: &str) -> Result<Vec<SessionMessage>, String>
             .unwrap_or("unknown")
             .to_string();
 
-        let mut texts = Vec::new();
-        if let Some(parts) = parts_map.get(&msg_id) {
-            for part_data in parts {
+        let mut parts = Vec::new();
+        if let Some(part_rows) = parts_map.get(&msg_id) {
+            for part_data in part_rows {
                 let part_value: Value = match serde_json::from_str(part_data) {
                     Ok(v) => v,
                     Err(_) => continue,
                 };
-                if let Some(text) = extract_part_text(&part_value) {
-                    texts.push(text);
-                }
+                parts.extend(extract_part(&part_value));
             }
         }
 
-        let content = texts.join("\n");
-        if content.trim().is_empty() {
+        let mut message = StructuredMessage::new(role, parts, Some(ts));
+        if message.is_empty() {
             continue;
         }
+        apply_message_info(&mut message, &msg_value);
 
-        messages.push(SessionMessage {
-            role,
-            content,
-            ts: Some(ts),
-        });
+        messages.push(message);
     }
 
     Ok(messages)
@@ -522,42 +526,65 @@ fn get_first_user_summary(storage: &Path, session_id: &str) -> Option<String> {
     // Take first user message and get its parts
     let (_, first_id) = user_msgs.first()?;
     let part_dir = storage.join("part").join(first_id);
-    let text = collect_parts_text(&part_dir);
-    if text.trim().is_empty() {
-        return None;
-    }
+    let text = StructuredMessage::new("user", collect_parts(&part_dir), None)
+        .to_flat()?
+        .content;
     Some(truncate_summary(&text, 160))
 }
 
-/// Collect text content from all parts in a part directory.
-fn extract_part_text(part_value: &Value) -> Option<String> {
+/// Convert an OpenCode part into message parts. Tool parts carry both the call
+/// and, once finished, its output or error in `state`.
+fn extract_part(part_value: &Value) -> Vec<MessagePart> {
     match part_value.get("type").and_then(Value::as_str) {
         Some("text") => part_value
             .get("text")
             .and_then(Value::as_str)
             .filter(|t| !t.trim().is_empty())
-            .map(|t| t.to_string()),
+            .map(|t| MessagePart::Text {
+                text: t.to_string(),
+            })
+            .into_iter()
+            .collect(),
+        Some("reasoning") => part_value
+            .get("text")
+            .and_then(Value::as_str)
+            .and_then(thinking_part)
+            .into_iter()
+            .collect(),
         Some("tool") => {
-            let tool = part_value
-                .get("tool")
-                .and_then(Value::as_str)
-                .unwrap_or("unknown");
-            Some(format!("[Tool: {tool}]"))
+            let call_id = part_value.get("callID").and_then(Value::as_str);
+            let state = part_value.get("state");
+            let mut parts = vec![tool_call_part(
+                call_id,
+                part_value.get("tool").and_then(Value::as_str),
+                state.and_then(|s| s.get("input")),
+            )];
+            let error = state.and_then(|s| s.get("error")).and_then(Value::as_str);
+            let output = state.and_then(|s| s.get("output")).and_then(Value::as_str);
+            if let Some(output) = error.or(output) {
+                parts.push(MessagePart::ToolResult {
+                    tool_call_id: call_id.map(str::to_string),
+                    output: output.to_string(),
+                    is_error: error.is_some(),
+                });
+            }
+            parts
         }
-        _ => None,
+        _ => Vec::new(),
     }
 }
 
-fn collect_parts_text(part_dir: &Path) -> String {
+/// Collect parts from all part files in a part directory.
+fn collect_parts(part_dir: &Path) -> Vec<MessagePart> {
     if !part_dir.is_dir() {
-        return String::new();
+        return Vec::new();
     }
 
-    let mut parts = Vec::new();
-    collect_json_files(part_dir, &mut parts);
+    let mut part_files = Vec::new();
+    collect_json_files(part_dir, &mut part_files);
 
-    let mut texts = Vec::new();
-    for part_path in &parts {
+    let mut parts = Vec::new();
+    for part_path in &part_files {
         let data = match std::fs::read_to_string(part_path) {
             Ok(d) => d,
             Err(_) => continue,
@@ -567,12 +594,27 @@ fn collect_parts_text(part_dir: &Path) -> String {
             Err(_) => continue,
         };
 
-        if let Some(text) = extract_part_text(&value) {
-            texts.push(text);
-        }
+        parts.extend(extract_part(&value));
     }
 
-    texts.join("\n")
+    parts
+}
+
+/// Fill model and token usage from OpenCode message info
+/// (`modelID`, `tokens: {input, output, reasoning, cache: {read, write}}`).
+fn apply_message_info(message: &mut StructuredMessage, info: &Value) {
+    message.model = info
+        .get("modelID")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    message.usage = info.get("tokens").and_then(|tokens| {
+        let mut usage = parse_usage(tokens).unwrap_or_default();
+        if let Some(cache) = tokens.get("cache") {
+            usage.cache_read_tokens = cache.get("read").and_then(Value::as_u64).unwrap_or(0);
+            usage.cache_creation_tokens = cache.get("write").and_then(Value::as_u64).unwrap_or(0);
+        }
+        (usage != TurnUsage::default()).then_some(usage)
+    });
 }
 
 fn collect_json_files(root: &Path, files: &mut Vec<PathBuf>) {
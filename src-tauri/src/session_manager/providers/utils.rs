@@ -5,6 +5,8 @@ use std::path::Path;
 use chrono::{DateTime, FixedOffset};
 use serde_json::Value;
 
+use crate::session_manager::{MessagePart, TurnUsage};
+
 /// Maximum number of characters for session titles (shared across providers).
 pub const TITLE_MAX_CHARS: usize = 80;
 
@@ -127,6 +129,174 @@ fn extract_text_from_item(item: &Value) -> Option<String> {
     None
 }
 
+/// Structured counterpart of [`extract_text`]: keeps tool calls (with input),
+/// tool results, thinking blocks and image references as separate parts.
+/// Projecting the result with `StructuredMessage::to_flat` yields the same text
+/// as `extract_text`, except that `toolCall` / `function_call` items also get a
+/// `[Tool: name]` marker.
+pub fn extract_parts(content: &Value) -> Vec<MessagePart> {
+    match content {
+        Value::String(text) => text_part(text).into_iter().collect(),
+        Value::Array(items) => items.iter().flat_map(extract_parts_from_item).collect(),
+        Value::Object(map) => map
+            .get("text")
+            .and_then(Value::as_str)
+            .and_then(text_part)
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn extract_parts_from_item(item: &Value) -> Vec<MessagePart> {
+    let item_type = item.get("type").and_then(Value::as_str).unwrap_or("");
+
+    match item_type {
+        "tool_use" | "toolCall" | "tool_call" | "function_call" => {
+            return vec![tool_call_part(
+                item.get("id").and_then(Value::as_str),
+                item.get("name").and_then(Value::as_str),
+                item.get("input").or_else(|| item.get("arguments")),
+            )];
+        }
+        "tool_result" => {
+            return vec![MessagePart::ToolResult {
+                tool_call_id: item
+                    .get("tool_use_id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                output: item.get("content").map(extract_text).unwrap_or_default(),
+                is_error: item.get("is_error").and_then(Value::as_bool) == Some(true),
+            }];
+        }
+        "thinking" => {
+            let text = item
+                .get("thinking")
+                .or_else(|| item.get("text"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            return thinking_part(text).into_iter().collect();
+        }
+        "redacted_thinking" => return Vec::new(),
+        "image" | "input_image" | "image_url" => return vec![image_part(item)],
+        _ => {}
+    }
+
+    for key in ["text", "input_text", "output_text"] {
+        if let Some(text) = item.get(key).and_then(Value::as_str) {
+            return text_part(text).into_iter().collect();
+        }
+    }
+
+    item.get("content").map(extract_parts).unwrap_or_default()
+}
+
+fn text_part(text: &str) -> Option<MessagePart> {
+    (!text.trim().is_empty()).then(|| MessagePart::Text {
+        text: text.to_string(),
+    })
+}
+
+pub fn thinking_part(text: &str) -> Option<MessagePart> {
+    (!text.trim().is_empty()).then(|| MessagePart::Thinking {
+        text: text.to_string(),
+    })
+}
+
+/// Build a tool call part; string arguments are parsed as JSON when possible.
+pub fn tool_call_part(id: Option<&str>, name: Option<&str>, input: Option<&Value>) -> MessagePart {
+    let input = match input {
+        Some(Value::String(raw)) => {
+            serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.clone()))
+        }
+        Some(value) => value.clone(),
+        None => Value::Null,
+    };
+    MessagePart::ToolCall {
+        id: id.map(str::to_string),
+        name: name.unwrap_or("unknown").to_string(),
+        input,
+    }
+}
+
+/// Wrap the plain text of a `role: "tool"` message into a single tool result part
+pub fn text_to_tool_result(
+    parts: Vec<MessagePart>,
+    tool_call_id: Option<&str>,
+) -> Vec<MessagePart> {
+    let mut output = Vec::new();
+    let mut rest = Vec::new();
+    for part in parts {
+        match part {
+            MessagePart::Text { text } => output.push(text),
+            other => rest.push(other),
+        }
+    }
+    if !output.is_empty() {
+        rest.insert(
+            0,
+            MessagePart::ToolResult {
+                tool_call_id: tool_call_id.map(str::to_string),
+                output: output.join("\n"),
+                is_error: false,
+            },
+        );
+    }
+    rest
+}
+
+/// Image reference without inline data (`data:` URLs and base64 sources are dropped).
+fn image_part(item: &Value) -> MessagePart {
+    let source = item.get("source");
+    let media_type = source
+        .and_then(|s| s.get("media_type"))
+        .or_else(|| item.get("media_type"))
+        .or_else(|| item.get("mimeType"))
+        .or_else(|| item.get("mime_type"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let location = source
+        .and_then(|s| s.get("url").or_else(|| s.get("path")))
+        .or_else(|| item.get("image_url").and_then(|u| u.get("url").or(Some(u))))
+        .or_else(|| item.get("url"))
+        .or_else(|| item.get("path"))
+        .and_then(Value::as_str)
+        .filter(|url| !url.starts_with("data:"))
+        .map(str::to_string);
+
+    MessagePart::Image {
+        media_type,
+        source: location,
+    }
+}
+
+/// Parse a per-turn usage object, trying each provider's field names in turn.
+pub fn parse_usage(usage: &Value) -> Option<TurnUsage> {
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| usage.get(*key).and_then(Value::as_u64))
+            .unwrap_or(0)
+    };
+    let reasoning = ["reasoning_output_tokens", "thoughts", "reasoning"]
+        .iter()
+        .find_map(|key| usage.get(*key).and_then(Value::as_u64));
+
+    let parsed = TurnUsage {
+        input_tokens: field(&["input_tokens", "input", "prompt_tokens"]),
+        output_tokens: field(&["output_tokens", "output", "completion_tokens"]),
+        cache_read_tokens: field(&[
+            "cache_read_input_tokens",
+            "cached_input_tokens",
+            "cacheRead",
+            "cached",
+        ]),
+        cache_creation_tokens: field(&["cache_creation_input_tokens", "cacheWrite"]),
+        reasoning_tokens: reasoning,
+    };
+
+    (parsed != TurnUsage::default()).then_some(parsed)
+}
+
 pub fn truncate_summary(text: &str, max_chars: usize) -> String {
     let trimmed = text.trim();
     if trimmed.is_empty() {
@@ -174,4 +344,60 @@ mod tests {
             Some(1_000)
         );
     }
+
+    #[test]
+    fn extract_parts_projects_to_extract_text() {
+        let content = json!([
+            {"type": "thinking", "thinking": "plan"},
+            {"type": "text", "text": "Running it."},
+            {"type": "tool_use", "id": "toolu_1", "name": "Bash", "input": {"command": "ls"}},
+            {"type": "tool_result", "tool_use_id": "toolu_1", "content": [{"type": "text", "text": "a.txt"}]},
+            {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}},
+        ]);
+
+        let parts = extract_parts(&content);
+        assert_eq!(parts.len(), 5);
+        assert_eq!(
+            parts[4],
+            MessagePart::Image {
+                media_type: Some("image/png".to_string()),
+                source: None,
+            }
+        );
+
+        let flat = crate::session_manager::StructuredMessage::new("assistant", parts, None)
+            .to_flat()
+            .expect("flat");
+        assert_eq!(flat.content, extract_text(&content));
+
+        // String arguments are parsed as JSON
+        let call =
+            json!([{"type": "function_call", "name": "shell", "arguments": "{\"cmd\":\"pwd\"}"}]);
+        assert_eq!(
+            extract_parts(&call),
+            vec![MessagePart::ToolCall {
+                id: None,
+                name: "shell".to_string(),
+                input: json!({"cmd": "pwd"}),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_usage_reads_provider_specific_keys() {
+        let claude = parse_usage(&json!({
+            "input_tokens": 3, "output_tokens": 7,
+            "cache_read_input_tokens": 11, "cache_creation_input_tokens": 13
+        }))
+        .expect("claude usage");
+        assert_eq!(claude.cache_creation_tokens, 13);
+        assert_eq!(claude.reasoning_tokens, None);
+
+        let gemini = parse_usage(&json!({"input": 5, "output": 2, "cached": 1, "thoughts": 4}))
+            .expect("gemini usage");
+        assert_eq!(gemini.cache_read_tokens, 1);
+        assert_eq!(gemini.reasoning_tokens, Some(4));
+
+        assert!(parse_usage(&json!({})).is_none());
+    }
 }
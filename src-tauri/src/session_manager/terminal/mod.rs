@@ -1,4 +1,5 @@
-use std::process::Command;
+use std::path::Path;
+use std::process::{Command, Stdio};
 
 pub fn launch_terminal(
     target: &str,
@@ -10,8 +11,11 @@ pub fn launch_terminal(
         return Err("Resume command is empty".to_string());
     }
 
+    if cfg!(target_os = "windows") {
+        return launch_windows(target, command, cwd, custom_config);
+    }
     if !cfg!(target_os = "macos") {
-        return Err("Terminal resume is only supported on macOS".to_string());
+        return launch_linux(target, command, cwd, custom_config);
     }
 
     match target {
@@ -235,33 +239,379 @@ fn launch_custom(
     cwd: Option<&str>,
     custom_config: Option<&str>,
 ) -> Result<(), String> {
+    let final_cmd_line = render_custom_template(custom_config, command, cwd, shell_single_quote)?;
+
+    // Execute via sh -c
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(&final_cmd_line)
+        .status()
+        .map_err(|e| format!("Failed to execute custom terminal launcher: {e}"))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err("Custom terminal execution returned error code".to_string())
+    }
+}
+
+/// 渲染自定义终端命令模板。
+///
+/// `{command}` 与 `{cwd}` 按原文替换（模板自行负责引号，如 `bash -c "{command}"`）；
+/// `{command_q}` 与 `{cwd_q}` 替换为按平台 shell 规则引用后的单个参数。
+/// `{cwd}` 缺省为当前目录。
+fn render_custom_template(
+    custom_config: Option<&str>,
+    command: &str,
+    cwd: Option<&str>,
+    quote: fn(&str) -> String,
+) -> Result<String, String> {
     let template = custom_config.ok_or("No custom terminal config provided")?;
 
     if template.trim().is_empty() {
         return Err("Custom terminal command template is empty".to_string());
     }
 
-    let cmd_str = command;
-    let dir_str = cwd.unwrap_or(".");
+    let dir_str = cwd.filter(|dir| !dir.trim().is_empty()).unwrap_or(".");
 
-    let final_cmd_line = template
-        .replace("{command}", cmd_str)
-        .replace("{cwd}", dir_str);
+    Ok(template
+        .replace("{command_q}", &quote(command))
+        .replace("{cwd_q}", &quote(dir_str))
+        .replace("{command}", command)
+        .replace("{cwd}", dir_str))
+}
 
-    // Execute via sh -c
-    let status = Command::new("sh")
-        .arg("-c")
-        .arg(&final_cmd_line)
+// ===== Linux / 其他 Unix =====
+
+/// 未指定首选终端时依次尝试的终端（`$TERMINAL` 与 `x-terminal-emulator` 优先）
+const LINUX_FALLBACK_TERMINALS: &[&str] = &[
+    "x-terminal-emulator",
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "mate-terminal",
+    "alacritty",
+    "kitty",
+    "ghostty",
+    "wezterm",
+    "xterm",
+];
+
+fn launch_linux(
+    target: &str,
+    command: &str,
+    cwd: Option<&str>,
+    custom_config: Option<&str>,
+) -> Result<(), String> {
+    let cwd = cwd.filter(|dir| !dir.trim().is_empty());
+
+    if target == "custom" {
+        let cmd_line = render_custom_template(custom_config, command, cwd, shell_single_quote)?;
+        return spawn_detached(Command::new("sh").arg("-c").arg(cmd_line), cwd)
+            .map_err(|e| format!("Failed to execute custom terminal launcher: {e}"));
+    }
+
+    let shell = std::env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.trim().is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string());
+    let env_terminal = std::env::var("TERMINAL").ok();
+    let candidates = linux_terminal_candidates(target, env_terminal.as_deref());
+
+    let mut last_error = None;
+    for terminal in candidates.iter().filter(|t| find_in_path(t)) {
+        let (program, args) = linux_terminal_argv(terminal, command, cwd, &shell);
+        match spawn_detached(Command::new(&program).args(&args), cwd) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                log::warn!("启动终端 {program} 失败: {e}");
+                last_error = Some(format!("Failed to launch {program}: {e}"));
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+        format!(
+            "No supported terminal found (tried: {}). Install one or set $TERMINAL.",
+            candidates.join(", ")
+        )
+    }))
+}
+
+/// 生成候选终端列表：首选终端 → `$TERMINAL` → 内置回退列表（去重）。
+/// `target` 为 "terminal"（macOS 默认值）或空时视为未指定。
+fn linux_terminal_candidates(target: &str, env_terminal: Option<&str>) -> Vec<String> {
+    let preferred = Some(target.trim()).filter(|t| !t.is_empty() && *t != "terminal");
+    let env_terminal = env_terminal.map(str::trim).filter(|t| !t.is_empty());
+
+    let mut candidates: Vec<String> = Vec::new();
+    for terminal in preferred
+        .into_iter()
+        .chain(env_terminal)
+        .chain(LINUX_FALLBACK_TERMINALS.iter().copied())
+    {
+        if !candidates.iter().any(|c| c == terminal) {
+            candidates.push(terminal.to_string());
+        }
+    }
+    candidates
+}
+
+/// 构造 Linux 终端的启动参数，返回 `(程序, 参数)`。
+///
+/// 已知终端通过各自的工作目录参数传递 `cwd`；未知终端（`x-terminal-emulator`、
+/// `$TERMINAL`、自定义路径等）统一使用 `-e`，并在 shell 脚本中 `cd` 到目标目录。
+/// 恢复命令作为单个参数交给 `$SHELL -lc`，退出后保留一个交互 shell，便于查看输出。
+fn linux_terminal_argv(
+    terminal: &str,
+    command: &str,
+    cwd: Option<&str>,
+    shell: &str,
+) -> (String, Vec<String>) {
+    let kind = Path::new(terminal)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(terminal);
+    let script = |with_cd: bool| {
+        let body = if with_cd {
+            match cwd {
+                Some(dir) => format!("cd {} && {command}", shell_single_quote(dir)),
+                None => command.to_string(),
+            }
+        } else {
+            command.to_string()
+        };
+        format!("{body}; exec {} -l", shell_single_quote(shell))
+    };
+    let shell_args = |with_cd: bool| vec![shell.to_string(), "-lc".to_string(), script(with_cd)];
+
+    let mut args: Vec<String> = Vec::new();
+    match kind {
+        "gnome-terminal" | "mate-terminal" => {
+            if let Some(dir) = cwd {
+                args.push(format!("--working-directory={dir}"));
+            }
+            args.push("--".to_string());
+        }
+        "konsole" => {
+            if let Some(dir) = cwd {
+                args.extend(["--workdir".to_string(), dir.to_string()]);
+            }
+            args.push("-e".to_string());
+        }
+        "xfce4-terminal" => {
+            if let Some(dir) = cwd {
+                args.push(format!("--working-directory={dir}"));
+            }
+            args.push("-x".to_string());
+        }
+        "alacritty" => {
+            if let Some(dir) = cwd {
+                args.extend(["--working-directory".to_string(), dir.to_string()]);
+            }
+            args.push("-e".to_string());
+        }
+        "kitty" => {
+            if let Some(dir) = cwd {
+                args.extend(["--directory".to_string(), dir.to_string()]);
+            }
+        }
+        "ghostty" => {
+            if let Some(dir) = cwd {
+                args.push(format!("--working-directory={dir}"));
+            }
+            args.push("-e".to_string());
+        }
+        "wezterm" => {
+            args.push("start".to_string());
+            if let Some(dir) = cwd {
+                args.extend(["--cwd".to_string(), dir.to_string()]);
+            }
+            args.push("--".to_string());
+        }
+        _ => {
+            args.push("-e".to_string());
+            args.extend(shell_args(true));
+            return (terminal.to_string(), args);
+        }
+    }
+
+    args.extend(shell_args(false));
+    (terminal.to_string(), args)
+}
+
+/// 检查程序是否可执行：含路径分隔符时直接检查文件，否则在 `PATH` 中查找
+fn find_in_path(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// 在后台启动终端进程，不等待其退出
+fn spawn_detached(command: &mut Command, cwd: Option<&str>) -> std::io::Result<()> {
+    if let Some(dir) = cwd.filter(|dir| Path::new(dir).is_dir()) {
+        command.current_dir(dir);
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+// ===== Windows =====
+
+fn launch_windows(
+    target: &str,
+    command: &str,
+    cwd: Option<&str>,
+    custom_config: Option<&str>,
+) -> Result<(), String> {
+    let cwd = cwd.filter(|dir| !dir.trim().is_empty());
+
+    if target == "custom" {
+        let cmd_line = render_custom_template(custom_config, command, cwd, cmd_quote)?;
+        return run_windows_cmd(&format!("/C {cmd_line}"), "custom terminal");
+    }
+
+    // 自定义终端保存为可执行文件路径：直接启动该程序，失败时报告错误而不回退到 cmd
+    if is_windows_path(target) {
+        if !Path::new(target).is_file() {
+            return Err(format!("Terminal executable not found: {target}"));
+        }
+        return run_windows_cmd(&windows_cmd_line(target, command, cwd), target);
+    }
+
+    let result = run_windows_cmd(&windows_cmd_line(target, command, cwd), target);
+
+    // 首选终端启动失败时回退到 cmd
+    if result.is_err() && !matches!(target, "cmd" | "terminal") {
+        log::warn!(
+            "首选终端 {target} 启动失败，回退到 cmd: {:?}",
+            result.as_ref().err()
+        );
+        return run_windows_cmd(&windows_cmd_line("cmd", command, cwd), "cmd");
+    }
+    result
+}
+
+/// 构造传给 `cmd` 的命令行：通过 `start` 打开新窗口，`/D` 指定工作目录。
+///
+/// `target` 可以是终端名称或可执行文件路径，路径按文件名识别终端类型；
+/// 未知的可执行文件统一使用 `-e` 传入要运行的命令。
+/// 恢复命令交给新窗口中的 shell 解释，按原文放在命令行末尾，仅对引号外的
+/// cmd 元字符加 `^` 转义，避免被外层 `cmd /C` 提前解释。
+fn windows_cmd_line(target: &str, command: &str, cwd: Option<&str>) -> String {
+    let command = cmd_escape_metachars(command);
+    let start_dir = cwd
+        .map(|dir| format!(" /D {}", cmd_quote(dir)))
+        .unwrap_or_default();
+    let is_path = is_windows_path(target);
+    let program = |name: &str| {
+        if is_path {
+            cmd_quote(target)
+        } else {
+            name.to_string()
+        }
+    };
+
+    match windows_terminal_kind(target).as_str() {
+        // Windows Terminal 自行管理窗口目录，使用 -d 而非 start /D
+        "wt" | "windowsterminal" => {
+            let dir = cwd
+                .map(|dir| format!(" -d {}", cmd_quote(dir)))
+                .unwrap_or_default();
+            format!(r#"/C start "" {}{dir} cmd /K {command}"#, program("wt"))
+        }
+        "powershell" | "pwsh" => format!(
+            r#"/C start ""{start_dir} {} -NoExit -Command {command}"#,
+            program("powershell")
+        ),
+        "wezterm" | "wezterm-gui" => {
+            let dir = cwd
+                .map(|dir| format!(" --cwd {}", cmd_quote(dir)))
+                .unwrap_or_default();
+            format!(
+                r#"/C start "" {} start{dir} -- cmd /K {command}"#,
+                program("wezterm")
+            )
+        }
+        "cmd" => format!(r#"/C start ""{start_dir} {} /K {command}"#, program("cmd")),
+        _ if is_path => format!(
+            r#"/C start ""{start_dir} {} -e cmd /K {command}"#,
+            cmd_quote(target)
+        ),
+        _ => format!(r#"/C start ""{start_dir} cmd /K {command}"#),
+    }
+}
+
+/// 终端目标是否为可执行文件路径（自定义终端）
+fn is_windows_path(target: &str) -> bool {
+    target.contains(['\\', '/']) || target.to_ascii_lowercase().ends_with(".exe")
+}
+
+/// 终端类型：取路径中的文件名（小写、去掉 `.exe`）
+fn windows_terminal_kind(target: &str) -> String {
+    let name = target
+        .rsplit(['\\', '/'])
+        .next()
+        .unwrap_or(target)
+        .to_ascii_lowercase();
+    name.strip_suffix(".exe").unwrap_or(&name).to_string()
+}
+
+fn run_windows_cmd(cmd_line: &str, terminal_name: &str) -> Result<(), String> {
+    let mut cmd = Command::new("cmd");
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        // cmd.exe 不识别 MSVC 风格的 `\"` 转义，命令行按 cmd 规则拼接后原样传入
+        cmd.raw_arg(cmd_line);
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    #[cfg(not(target_os = "windows"))]
+    cmd.arg(cmd_line);
+
+    let status = cmd
         .status()
-        .map_err(|e| format!("Failed to execute custom terminal launcher: {e}"))?;
+        .map_err(|e| format!("Failed to launch {terminal_name}: {e}"))?;
 
     if status.success() {
         Ok(())
     } else {
-        Err("Custom terminal execution returned error code".to_string())
+        Err(format!(
+            "{terminal_name} exited with code {:?}",
+            status.code()
+        ))
     }
 }
 
+/// 按 cmd 规则把值引用为单个参数（cmd 中引号内的 `"` 写作 `""`，反斜杠保持原样）
+fn cmd_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// 转义引号外的 cmd 元字符
+fn cmd_escape_metachars(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let mut in_quotes = false;
+    for ch in value.chars() {
+        if ch == '"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes && matches!(ch, '&' | '|' | '<' | '>' | '^' | '(' | ')') {
+            escaped.push('^');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
 fn build_shell_command(command: &str, cwd: Option<&str>) -> String {
     match cwd {
         Some(dir) if !dir.trim().is_empty() => {
@@ -276,6 +626,10 @@ fn shell_escape(value: &str) -> String {
     format!("\"{escaped}\"")
 }
 
+fn shell_single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\"'\"'"))
+}
+
 fn escape_osascript(value: &str) -> String {
     value.replace('\\', "\\\\").replace('"', "\\\"")
 }
@@ -335,4 +689,178 @@ mod tests {
         // Verify shell_escape works correctly for paths with spaces
         assert_eq!(shell_escape(cwd), "\"/tmp/project dir\"");
     }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn shell_single_quote_escapes_embedded_quotes() {
+        assert_eq!(
+            shell_single_quote("/tmp/it's here"),
+            "'/tmp/it'\"'\"'s here'"
+        );
+    }
+
+    #[test]
+    fn linux_known_terminals_pass_cwd_via_flags() {
+        let cwd = Some("/tmp/project dir");
+        let command = "claude --resume abc-123";
+        let tail = [
+            "/bin/bash",
+            "-lc",
+            "claude --resume abc-123; exec '/bin/bash' -l",
+        ];
+
+        let cases: [(&str, &[&str]); 7] = [
+            (
+                "gnome-terminal",
+                &["--working-directory=/tmp/project dir", "--"],
+            ),
+            ("konsole", &["--workdir", "/tmp/project dir", "-e"]),
+            (
+                "xfce4-terminal",
+                &["--working-directory=/tmp/project dir", "-x"],
+            ),
+            (
+                "alacritty",
+                &["--working-directory", "/tmp/project dir", "-e"],
+            ),
+            ("kitty", &["--directory", "/tmp/project dir"]),
+            ("ghostty", &["--working-directory=/tmp/project dir", "-e"]),
+            ("wezterm", &["start", "--cwd", "/tmp/project dir", "--"]),
+        ];
+
+        for (terminal, head) in cases {
+            let (program, args) = linux_terminal_argv(terminal, command, cwd, "/bin/bash");
+            let mut expected = strings(head);
+            expected.extend(strings(&tail));
+            assert_eq!(program, terminal);
+            assert_eq!(args, expected, "argv mismatch for {terminal}");
+        }
+    }
+
+    #[test]
+    fn linux_terminal_argv_recognizes_absolute_paths() {
+        let (program, args) =
+            linux_terminal_argv("/usr/bin/kitty", "codex resume x", None, "/bin/sh");
+        assert_eq!(program, "/usr/bin/kitty");
+        assert_eq!(
+            args,
+            strings(&["/bin/sh", "-lc", "codex resume x; exec '/bin/sh' -l"])
+        );
+    }
+
+    #[test]
+    fn linux_generic_terminal_uses_e_and_quoted_cd() {
+        let (program, args) = linux_terminal_argv(
+            "x-terminal-emulator",
+            "claude --resume abc-123",
+            Some("/tmp/it's dir"),
+            "/bin/zsh",
+        );
+        assert_eq!(program, "x-terminal-emulator");
+        assert_eq!(
+            args,
+            strings(&[
+                "-e",
+                "/bin/zsh",
+                "-lc",
+                "cd '/tmp/it'\"'\"'s dir' && claude --resume abc-123; exec '/bin/zsh' -l",
+            ])
+        );
+    }
+
+    #[test]
+    fn linux_candidates_prefer_target_then_env_then_fallbacks() {
+        let candidates = linux_terminal_candidates("konsole", Some("foot"));
+        assert_eq!(
+            &candidates[..3],
+            &strings(&["konsole", "foot", "x-terminal-emulator"])[..]
+        );
+        assert_eq!(candidates.iter().filter(|c| *c == "konsole").count(), 1);
+
+        // macOS 默认值 "terminal" 视为未指定
+        let candidates = linux_terminal_candidates("terminal", None);
+        assert_eq!(candidates[0], "x-terminal-emulator");
+        assert_eq!(candidates.len(), LINUX_FALLBACK_TERMINALS.len());
+    }
+
+    #[test]
+    fn windows_cmd_line_uses_start_with_working_directory() {
+        let cwd = Some(r"C:\Users\me\my project");
+        assert_eq!(
+            windows_cmd_line("cmd", "claude --resume abc", cwd),
+            r#"/C start "" /D "C:\Users\me\my project" cmd /K claude --resume abc"#
+        );
+        assert_eq!(
+            windows_cmd_line("powershell", "claude --resume abc", None),
+            r#"/C start "" powershell -NoExit -Command claude --resume abc"#
+        );
+        assert_eq!(
+            windows_cmd_line("wt", "claude --resume abc", cwd),
+            r#"/C start "" wt -d "C:\Users\me\my project" cmd /K claude --resume abc"#
+        );
+    }
+
+    #[test]
+    fn windows_cmd_line_launches_custom_executable() {
+        let cwd = Some(r"C:\work");
+        assert_eq!(
+            windows_cmd_line(r"C:\Program Files\PowerShell\7\pwsh.exe", "claude", cwd),
+            r#"/C start "" /D "C:\work" "C:\Program Files\PowerShell\7\pwsh.exe" -NoExit -Command claude"#
+        );
+        assert_eq!(
+            windows_cmd_line(r"C:\Tools\Alacritty.exe", "claude", cwd),
+            r#"/C start "" /D "C:\work" "C:\Tools\Alacritty.exe" -e cmd /K claude"#
+        );
+        assert_eq!(
+            windows_cmd_line(r"C:\Tools\WezTerm\wezterm-gui.exe", "claude", cwd),
+            r#"/C start "" "C:\Tools\WezTerm\wezterm-gui.exe" start --cwd "C:\work" -- cmd /K claude"#
+        );
+        assert!(is_windows_path(r"D:\apps\term.exe"));
+        assert!(!is_windows_path("wt"));
+    }
+
+    #[test]
+    fn windows_cmd_line_escapes_metachars_outside_quotes() {
+        assert_eq!(
+            windows_cmd_line("cmd", r#"cd "a & b" && claude --resume x"#, None),
+            r#"/C start "" cmd /K cd "a & b" ^&^& claude --resume x"#
+        );
+        assert_eq!(cmd_quote(r#"C:\say "hi""#), r#""C:\say ""hi""""#);
+    }
+
+    #[test]
+    fn custom_template_keeps_raw_placeholders() {
+        let rendered = render_custom_template(
+            Some(r#"foot -D "{cwd}" bash -c "{command}""#),
+            "claude --resume abc",
+            Some("/tmp/a b"),
+            shell_single_quote,
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            r#"foot -D "/tmp/a b" bash -c "claude --resume abc""#
+        );
+    }
+
+    #[test]
+    fn custom_template_quotes_placeholders() {
+        let rendered = render_custom_template(
+            Some("foot --working-directory {cwd_q} sh -c {command_q}"),
+            "claude --resume 'x'",
+            Some("/tmp/a b"),
+            shell_single_quote,
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "foot --working-directory '/tmp/a b' sh -c 'claude --resume '\"'\"'x'\"'\"''"
+        );
+
+        assert!(render_custom_template(Some("  "), "claude", None, shell_single_quote).is_err());
+        assert!(render_custom_template(None, "claude", None, shell_single_quote).is_err());
+    }
 }
@@ -287,6 +287,7 @@ pub struct AppSettings {
     /// - macOS: "terminal" | "iterm2" | "warp" | "alacritty" | "kitty" | "ghostty" | "wezterm" | "kaku"
     /// - Windows: "cmd" | "powershell" | "wt" (Windows Terminal)
     /// - Linux: "gnome-terminal" | "konsole" | "xfce4-terminal" | "alacritty" | "kitty" | "ghostty"
+    ///   （未设置或不可用时依次回退到 `$TERMINAL`、`x-terminal-emulator` 等）
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub preferred_terminal: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -1,5 +1,12 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { SessionMessage, SessionMeta } from "@/types";
+import type {
+  SessionConvertReport,
+  SessionConvertRequest,
+  SessionConvertTarget,
+  SessionMessage,
+  SessionMeta,
+  StructuredMessage,
+} from "@/types";
 
 export interface DeleteSessionOptions {
   providerId: string;
@@ -24,6 +31,16 @@ export const sessionsApi = {
     return await invoke("get_session_messages", { providerId, sourcePath });
   },
 
+  async getStructuredMessages(
+    providerId: string,
+    sourcePath: string,
+  ): Promise<StructuredMessage[]> {
+    return await invoke("get_session_structured_messages", {
+      providerId,
+      sourcePath,
+    });
+  },
+
   async delete(options: DeleteSessionOptions): Promise<boolean> {
     const { providerId, sessionId, sourcePath } = options;
     return await invoke("delete_session", {
@@ -39,6 +56,13 @@ export const sessionsApi = {
     return await invoke("delete_sessions", { items });
   },
 
+  async convert(
+    request: SessionConvertRequest,
+    targetProviderId: SessionConvertTarget,
+  ): Promise<SessionConvertReport> {
+    return await invoke("convert_session", { request, targetProviderId });
+  },
+
   async launchTerminal(options: {
     command: string;
     cwd?: string | null;
@@ -338,6 +338,114 @@ export interface SessionMessage {
   ts?: number;
 }
 
+export type MessagePart =
+  | { type: "text"; text: string }
+  | { type: "thinking"; text: string }
+  | { type: "toolCall"; id?: string; name: string; input: unknown }
+  | {
+      type: "toolResult";
+      toolCallId?: string;
+      output: string;
+      isError: boolean;
+    }
+  | { type: "image"; mediaType?: string; source?: string };
+
+export interface TurnUsage {
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  reasoningTokens?: number;
+}
+
+export interface StructuredMessage {
+  role: string;
+  parts: MessagePart[];
+  ts?: number;
+  model?: string;
+  usage?: TurnUsage;
+}
+
+export interface SessionSearchQuery {
+  query: string;
+  providerId?: string;
+  /** 项目目录（子串匹配） */
+  projectDir?: string;
+  /** 毫秒时间戳 */
+  startDate?: number;
+  endDate?: number;
+  limit?: number;
+}
+
+export type SessionExportFormat = "markdown" | "html" | "json";
+
+export interface SessionExportOptions {
+  format: SessionExportFormat;
+  /** 将项目目录替换为 `<project>`、用户主目录替换为 `~` */
+  redactPaths?: boolean;
+  /** 遮蔽 API Key、Token 等凭据 */
+  redactSecrets?: boolean;
+}
+
+export interface SessionExportRequest {
+  providerId: string;
+  sessionId: string;
+  sourcePath: string;
+  title?: string;
+  projectDir?: string | null;
+}
+
+export interface SessionExportReport {
+  path: string;
+  exported: number;
+  failed: { providerId: string; sessionId: string; error: string }[];
+}
+
+export type SessionConvertTarget = "claude" | "codex" | "gemini";
+
+export interface SessionConvertRequest {
+  providerId: string;
+  sessionId: string;
+  sourcePath: string;
+  projectDir?: string | null;
+}
+
+export type ConversionLossKind =
+  | "thinking"
+  | "image"
+  | "toolCallAsText"
+  | "toolResultAsText"
+  | "unsupportedRole";
+
+export interface ConversionLoss {
+  kind: ConversionLossKind;
+  count: number;
+  samples: string[];
+}
+
+export interface SessionConvertReport {
+  providerId: string;
+  sessionId: string;
+  path: string;
+  resumeCommand: string;
+  messagesWritten: number;
+  losses: ConversionLoss[];
+}
+
+export interface SessionSearchHit {
+  providerId: string;
+  sessionId: string;
+  sourcePath: string;
+  title?: string;
+  projectDir?: string;
+  /** 命中消息在会话消息列表中的下标 */
+  messageIndex: number;
+  role: string;
+  ts?: number;
+  snippet: string;
+  score: number;
+}
+
 // MCP 服务器连接参数（宽松：允许扩展字段）
 export interface McpServerSpec {
   // 可选：社区常见 .mcp.json 中 stdio 配置可不写 type
@@ -0,0 +1,48 @@
+export type NotificationEventKind =
+  | "failover_switch"
+  | "circuit_open"
+  | "stream_check_failed"
+  | "quota_low"
+  | "budget_warning"
+  | "budget_exceeded"
+  | "budget_forecast";
+
+export type WebhookFormat = "generic" | "slack" | "feishu" | "dingtalk";
+
+export interface WebhookTarget {
+  url: string;
+  format: WebhookFormat;
+  enabled: boolean;
+}
+
+/** 本地时间 HH:MM，可跨午夜 */
+export interface QuietHours {
+  start: string;
+  end: string;
+}
+
+export interface NotificationRule {
+  id: string;
+  enabled: boolean;
+  event: NotificationEventKind;
+  appType?: string;
+  providerId?: string;
+  /** 使用百分比阈值（订阅额度 / 消费限额事件） */
+  threshold?: number;
+  quietHours?: QuietHours;
+  dedupeMinutes: number;
+}
+
+export interface NotificationConfig {
+  enabled: boolean;
+  desktop: boolean;
+  webhooks: WebhookTarget[];
+  rules: NotificationRule[];
+}
+
+export interface NotificationDelivery {
+  /** "desktop" 或 Webhook 地址 */
+  channel: string;
+  success: boolean;
+  error?: string;
+}
@@ -1,3 +1,5 @@
+import type { RequestLog } from "./usage";
+
 export interface ProxyConfig {
   listen_address: string;
   listen_port: number;
@@ -9,6 +11,46 @@ export interface ProxyConfig {
   streaming_first_byte_timeout: number;
   streaming_idle_timeout: number;
   non_streaming_timeout: number;
+  // 在 /metrics 暴露 Prometheus 指标
+  metrics_enabled?: boolean;
+  // OTLP/HTTP 链路导出端点（如 http://127.0.0.1:4318）
+  otlp_endpoint?: string | null;
+}
+
+// 请求抓包规则（到期自动失效）
+export interface CaptureRule {
+  appType: string;
+  providerId?: string;
+  expiresAt: number;
+}
+
+// 请求回放结果（每个目标供应商一项）
+export interface ReplayOutcome {
+  providerId: string;
+  providerName?: string | null;
+  statusCode: number;
+  latencyMs: number;
+  firstTokenMs?: number | null;
+  isStreaming: boolean;
+  usage?: {
+    inputTokens: number;
+    outputTokens: number;
+    cacheReadTokens: number;
+    cacheCreationTokens: number;
+    reasoningTokens: number;
+    model?: string | null;
+  } | null;
+  // Claude 请求为聚合后的 Anthropic message，其他格式为原始响应
+  output?: unknown;
+  error?: string | null;
+}
+
+export interface ReplayReport {
+  appType: string;
+  uri: string;
+  requestModel?: string | null;
+  original?: RequestLog | null;
+  results: ReplayOutcome[];
 }
 
 export interface ProxyStatus {
@@ -32,6 +32,22 @@ export interface RequestLog {
   errorMessage?: string;
   createdAt: number;
   dataSource?: string;
+  // 关联的调试抓包 ID
+  captureId?: string;
+}
+
+export interface CaptureAttempt {
+  attempt: number;
+  request?: unknown;
+  response?: unknown;
+  responseBody?: string;
+  responseBodyTruncated: boolean;
+}
+
+export interface RequestCaptureDetail {
+  captureId: string;
+  inbound?: unknown;
+  attempts: CaptureAttempt[];
 }
 
 export interface SessionSyncResult {
@@ -102,6 +118,74 @@ export interface ModelStats {
   avgCostPerRequest: string;
 }
 
+export interface SessionUsage {
+  appType: string;
+  sessionId: string;
+  title?: string;
+  projectDir?: string;
+  requestCount: number;
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  totalCost: string;
+  firstRequestAt: number;
+  lastRequestAt: number;
+  dataSources: string[];
+}
+
+export interface ProjectUsage {
+  projectDir?: string;
+  sessionCount: number;
+  requestCount: number;
+  totalTokens: number;
+  totalCost: string;
+  lastRequestAt: number;
+}
+
+export interface UsageAttribution {
+  projects: ProjectUsage[];
+  sessions: SessionUsage[];
+  topSessions: SessionUsage[];
+  unattributedRequests: number;
+  unattributedCost: string;
+}
+
+export interface SpendAnomaly {
+  date: string;
+  costUsd: string;
+  trailingMedianUsd: string;
+  ratio: number;
+}
+
+export interface BurnForecast {
+  appType: string;
+  providerId: string;
+  providerName: string;
+  hourlyBurnUsd: string;
+  dailyBurnUsd: string;
+  todayUsd: string;
+  monthToDateUsd: string;
+  projectedTodayUsd: string;
+  projectedMonthEndUsd: string;
+  dailyLimitUsd?: string;
+  monthlyLimitUsd?: string;
+  dailyLimitHitAt?: number;
+  monthlyLimitHitAt?: number;
+  anomalies: SpendAnomaly[];
+}
+
+export interface BudgetForecastEvent {
+  appType: string;
+  providerId: string;
+  providerName: string;
+  period: "daily" | "monthly";
+  usageUsd: string;
+  projectedUsd: string;
+  limitUsd: string;
+  limitHitAt?: number;
+}
+
 export interface LogFilters {
   appType?: string;
   providerName?: string;